@@ -27,7 +27,12 @@ pub mod support;
 mod tests {
     mod challenge;
     mod device_queries;
+    mod pmr;
+    mod provisioning;
+    mod recovery;
+    mod reset_config;
     mod spdm_device_queries;
+    mod unseal;
 }
 
 /// End-to-end tests for Manticore.
@@ -9,6 +9,16 @@ use std::convert::TryInto as _;
 use std::time::Duration;
 use std::time::Instant;
 
+use manticore::hardware::ConfigResetError;
+use manticore::hardware::ConfigResetKind;
+use manticore::hardware::HostState;
+use manticore::hardware::ResetKind;
+use manticore::seal;
+use manticore::session;
+use ring::agreement as ecdh;
+
+use crate::support::rot;
+
 /// A fake `Identity` that returns fixed values.
 pub struct Identity {
     firmware_version: Vec<u8>,
@@ -61,16 +71,24 @@ impl manticore::hardware::Identity for Identity {
 pub struct Reset {
     startup_time: Instant,
     resets_since_power_on: u32,
+    ports: Vec<rot::Port>,
 }
 
 impl Reset {
     /// Creates a new `Reset`.
-    pub fn new(resets_since_power_on: u32) -> Self {
+    ///
+    /// `ports` describes the devices on ports 1 and up.
+    pub fn new(resets_since_power_on: u32, ports: &[rot::Port]) -> Self {
         Self {
             startup_time: Instant::now(),
             resets_since_power_on,
+            ports: ports.to_vec(),
         }
     }
+
+    fn port(&self, port: u8) -> Option<&rot::Port> {
+        self.ports.get((port as usize).checked_sub(1)?)
+    }
 }
 
 impl manticore::hardware::Reset for Reset {
@@ -81,4 +99,91 @@ impl manticore::hardware::Reset for Reset {
     fn uptime(&self) -> Duration {
         self.startup_time.elapsed()
     }
+
+    fn port_resets_since_power_on(
+        &self,
+        port: u8,
+        ty: ResetKind,
+    ) -> Option<u32> {
+        let port = self.port(port)?;
+        match ty {
+            ResetKind::Local => Some(port.resets_since_power_on),
+            ResetKind::External => Some(port.external_resets_since_power_on),
+        }
+    }
+
+    fn port_uptime(&self, port: u8) -> Option<Duration> {
+        self.port(port)?;
+        Some(self.startup_time.elapsed())
+    }
+
+    fn host_state(&self, port: u8) -> Option<HostState> {
+        Some(self.port(port)?.host_state)
+    }
+}
+
+/// A fake `ConfigReset` that supports every kind of reset other than
+/// intrusion resets, and only logs the resets it performs.
+pub struct ConfigReset;
+
+impl manticore::hardware::ConfigReset for ConfigReset {
+    fn supports(&self, ty: ConfigResetKind) -> bool {
+        ty != ConfigResetKind::ResetIntrusion
+    }
+
+    fn reset(&mut self, ty: ConfigResetKind) -> Result<(), ConfigResetError> {
+        log::info!("performing configuration reset: {:?}", ty);
+        Ok(())
+    }
+}
+
+/// A fake `seal::KeyAgreement` with a fixed private scalar.
+///
+/// `ring` has no static ECDH, so the scalar is replayed through its test RNG
+/// to rebuild an ephemeral key for every agreement. This is only acceptable
+/// in a test harness.
+pub struct SealingKey {
+    scalar: [u8; 32],
+    public: Vec<u8>,
+}
+
+impl SealingKey {
+    /// Creates a new `SealingKey` from a big-endian P-256 scalar.
+    pub fn from_scalar(scalar: &[u8]) -> Self {
+        let scalar: [u8; 32] = scalar.try_into().unwrap();
+        let public = Self::private_key(&scalar)
+            .compute_public_key()
+            .unwrap()
+            .as_ref()
+            .to_vec();
+        Self { scalar, public }
+    }
+
+    /// Returns the public half of this key, as an uncompressed SEC1 point.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public
+    }
+
+    fn private_key(scalar: &[u8; 32]) -> ecdh::EphemeralPrivateKey {
+        let rand = ring::test::rand::FixedSliceRandom { bytes: scalar };
+        ecdh::EphemeralPrivateKey::generate(&ecdh::ECDH_P256, &rand).unwrap()
+    }
+}
+
+impl seal::KeyAgreement for SealingKey {
+    fn agree(
+        &mut self,
+        their_key: &[u8],
+        secret: &mut session::Key,
+    ) -> Result<(), seal::Error> {
+        ecdh::agree_ephemeral(
+            Self::private_key(&self.scalar),
+            &ecdh::UnparsedPublicKey::new(&ecdh::ECDH_P256, their_key),
+            seal::Error::Agreement,
+            |material| {
+                secret.copy_from_slice(material);
+                Ok(())
+            },
+        )
+    }
 }
@@ -18,16 +18,26 @@ use std::time::Duration;
 use manticore::cert;
 use manticore::cert::CertFormat;
 use manticore::crypto::ring;
+use manticore::crypto::sig;
+use manticore::hardware;
+use manticore::hardware::flash;
+use manticore::manifest::pfm::Pfm;
+use manticore::manifest::store;
 use manticore::mem::Arena;
 use manticore::mem::BumpArena;
 use manticore::net;
+use manticore::net::encrypted::EncryptedHost;
+use manticore::pmr;
+use manticore::pmr::Pmrs as _;
 use manticore::protocol;
 use manticore::protocol::capabilities;
 use manticore::protocol::cerberus;
 use manticore::protocol::device_id::DeviceIdentifier;
 use manticore::protocol::spdm;
+use manticore::recovery;
 use manticore::server;
 use manticore::server::pa_rot::PaRot;
+use manticore::session;
 use manticore::session::ring::Session;
 
 use crate::support::fakes;
@@ -52,6 +62,9 @@ pub struct Options {
     /// The number of resets to report since power on.
     pub resets_since_power_on: u32,
 
+    /// Reset information to report for the devices on ports 1 and up.
+    pub ports: Vec<Port>,
+
     /// The maximum message size to report as a capability
     /// (unused by the transport).
     pub max_message_size: u16,
@@ -78,8 +91,36 @@ pub struct Options {
     /// The keypair to use with the certificate chain.
     pub alias_keypair: Option<KeyPairFormat>,
 
-    /// The contents of PMR #0.
-    pub pmr0: Vec<u8>,
+    /// If set, the device starts out with an empty certificate chain that
+    /// must be provisioned with `ImportCertificate`, ignoring `cert_chain`.
+    ///
+    /// `alias_keypair` is used as the DeviceID key.
+    pub provisioning: Option<Provisioning>,
+
+    /// Measurements to extend PMR #0 with on startup.
+    pub pmr0_measurements: Vec<pmr::Measurement>,
+
+    /// The big-endian P-256 private scalar of the sealing key.
+    ///
+    /// If unset, unsealing is not supported.
+    pub sealing_key: Option<Vec<u8>>,
+
+    /// The keypair whose public half authorizes `ResetConfig` requests.
+    ///
+    /// If unset, `ResetConfig` is not supported.
+    pub reset_authority: Option<KeyPairFormat>,
+
+    /// If set, the device stores a recovery image for host port 0.
+    pub recovery: Option<Recovery>,
+
+    /// If set, the device has storage for a PFM, which starts out empty.
+    pub pfm_store: bool,
+
+    /// If set, the device decrypts session-encrypted requests.
+    pub session_encryption: bool,
+
+    /// Whether to advertise host firmware protection.
+    pub firmware_protection: bool,
 }
 
 /// See [`Options::protocol`].
@@ -90,11 +131,96 @@ pub enum Protocol {
     Spdm,
 }
 
+/// See [`Options::ports`].
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Port {
+    /// The number of local resets to report since power on.
+    pub resets_since_power_on: u32,
+    /// The number of external resets to report since power on.
+    pub external_resets_since_power_on: u32,
+    /// The host reset state to report.
+    pub host_state: hardware::HostState,
+}
+
+/// See [`Options::provisioning`].
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Provisioning {
+    /// The DER-encoded subject name to request in the DeviceID CSR.
+    pub subject: Vec<u8>,
+    /// The DER-encoded public key to request in the DeviceID CSR.
+    pub spki: Vec<u8>,
+}
+
+/// See [`Options::recovery`].
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Recovery {
+    /// The signed PFM that new recovery images are checked against.
+    pub pfm: Vec<u8>,
+    /// The keypair whose public half verifies the PFM and recovery images.
+    pub manifest_keypair: KeyPairFormat,
+    /// The size of each of the two recovery image slots.
+    pub region_size: usize,
+}
+
 /// See [`Options::alias_keypair`].
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub enum KeyPairFormat {
     /// An RSA PKCS#8-encoded key pair.
     RsaPkcs8(Vec<u8>),
+    /// An ECDSA P-256 PKCS#8-encoded key pair, producing DER-encoded
+    /// signatures.
+    EcdsaP256Pkcs8(Vec<u8>),
+}
+
+impl KeyPairFormat {
+    /// Parses this key pair into a signer, along with the algorithm of the
+    /// signatures it produces.
+    ///
+    /// If the key pair fails to parse, aborts the process.
+    fn signer(&self) -> (Box<dyn sig::Sign>, sig::Algo) {
+        let result = match self {
+            Self::RsaPkcs8(pk8) => {
+                ring::rsa::Sign256::from_pkcs8(pk8).map(|s| {
+                    (
+                        Box::new(s) as Box<dyn sig::Sign>,
+                        sig::Algo::RsaPkcs1Sha256,
+                    )
+                })
+            }
+            Self::EcdsaP256Pkcs8(pk8) => {
+                ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(pk8)
+                    .map(|s| (Box::new(s) as _, sig::Algo::EcdsaDerP256))
+            }
+        };
+        match result {
+            Ok(signer) => signer,
+            Err(e) => {
+                log::error!("could not parse keypair: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Parses the public half of this key pair into a verifier.
+    ///
+    /// If the key pair fails to parse, aborts the process.
+    fn verifier(&self) -> Box<dyn sig::Verify> {
+        let result = match self {
+            Self::RsaPkcs8(pk8) => ring::rsa::Sign256::from_pkcs8(pk8)
+                .map(|s| Box::new(s.verifier()) as Box<dyn sig::Verify>),
+            Self::EcdsaP256Pkcs8(pk8) => {
+                ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(pk8)
+                    .map(|s| Box::new(s.verifier()) as _)
+            }
+        };
+        match result {
+            Ok(verifier) => verifier,
+            Err(e) => {
+                log::error!("could not parse keypair: {:?}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 }
 
 impl Default for Options {
@@ -105,6 +231,7 @@ impl Default for Options {
             vendor_firmware_versions: vec![],
             unique_device_identity: b"<uid unspecified>".to_vec(),
             resets_since_power_on: 5,
+            ports: vec![],
             max_message_size: 1024,
             max_packet_size: 256,
             regular_timeout: Duration::from_millis(30),
@@ -118,7 +245,17 @@ impl Default for Options {
             cert_chain: vec![],
             cert_format: CertFormat::RiotX509,
             alias_keypair: None,
-            pmr0: b"<pmr0 unspecified>".to_vec(),
+            provisioning: None,
+            pmr0_measurements: vec![pmr::Measurement {
+                event_type: 0,
+                digest: [0x55; 32],
+            }],
+            sealing_key: None,
+            reset_authority: None,
+            recovery: None,
+            pfm_store: false,
+            session_encryption: false,
+            firmware_protection: false,
         }
     }
 }
@@ -227,6 +364,25 @@ impl Virtual {
         tcp::send_cerberus::<Cmd>(self.port, req, arena)
     }
 
+    /// Sends `req` to this virtal RoT, using Cerberus-over-TCP, encrypted with
+    /// the session key `key`.
+    ///
+    /// Blocks until a response comes back.
+    pub fn send_cerberus_encrypted<'a, Cmd>(
+        &self,
+        req: Cmd::Req,
+        key: session::Key,
+        arena: &'a dyn Arena,
+    ) -> Result<
+        Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+        server::Error<net::CerberusHeader>,
+    >
+    where
+        Cmd: protocol::Command<'a, CommandType = cerberus::CommandType>,
+    {
+        tcp::send_cerberus_encrypted::<Cmd>(self.port, req, key, arena)
+    }
+
     /// Sends `req` to this virtal RoT, using SPDM-over-TCP.
     ///
     /// Blocks until a response comes back.
@@ -267,7 +423,7 @@ pub fn serve(opts: Options) -> ! {
             .map(|(k, v)| (*k, v.as_slice())),
         &opts.unique_device_identity,
     );
-    let reset = fakes::Reset::new(opts.resets_since_power_on);
+    let reset = fakes::Reset::new(opts.resets_since_power_on, &opts.ports);
 
     let mut hasher = ring::hash::Engine::new();
     let mut csrng = ring::csrng::Csrng::new();
@@ -275,35 +431,102 @@ pub fn serve(opts: Options) -> ! {
 
     let trust_chain_bytes =
         opts.cert_chain.iter().map(Vec::as_ref).collect::<Vec<_>>();
-    let mut signer = opts.alias_keypair.as_ref().map(|kp| match kp {
-        KeyPairFormat::RsaPkcs8(pk8) => {
-            match ring::rsa::Sign256::from_pkcs8(pk8) {
-                Ok(rsa) => rsa,
-                Err(e) => {
-                    log::error!("could not parse alias keypair: {:?}", e);
-                    std::process::exit(1);
-                }
-            }
+    let mut signer = opts.alias_keypair.as_ref().map(KeyPairFormat::signer);
+    let mut storage = vec![0; 8 * 1024];
+    let mut trust_chain: Box<dyn cert::TrustChain> = match &opts.provisioning {
+        Some(p) => {
+            let (signer, algo) =
+                signer.as_mut().expect("provisioning requires a keypair");
+            Box::new(cert::WritableChain::<8>::new(
+                opts.cert_format,
+                &p.subject,
+                &p.spki,
+                *algo,
+                &mut **signer,
+                &mut storage,
+            ))
         }
-    });
-    let mut trust_chain = cert::SimpleChain::<8>::parse(
-        &trust_chain_bytes,
-        opts.cert_format,
-        &mut ciphers,
-        signer.as_mut().map(|s| s as _),
-    )
-    .unwrap();
+        None => Box::new(
+            cert::SimpleChain::<8>::parse(
+                &trust_chain_bytes,
+                opts.cert_format,
+                &mut ciphers,
+                signer.as_mut().map(|(s, _)| &mut **s as _),
+            )
+            .unwrap(),
+        ),
+    };
     let mut session = Session::new();
 
+    let mut pmrs = pmr::SimplePmrs::<4, 16>::new();
+    for m in &opts.pmr0_measurements {
+        pmrs.extend(0, m.event_type, &m.digest, &mut hasher)
+            .unwrap();
+    }
+
+    let mut sealing_key = opts
+        .sealing_key
+        .as_ref()
+        .map(|scalar| fakes::SealingKey::from_scalar(scalar));
+
+    let mut config_reset = fakes::ConfigReset;
+    let mut reset_authority =
+        opts.reset_authority.as_ref().map(KeyPairFormat::verifier);
+
+    let mut recovery_verifier = opts
+        .recovery
+        .as_ref()
+        .map(|r| r.manifest_keypair.verifier());
+    let mut recovery_flash = opts.recovery.as_ref().map(|r| {
+        (
+            flash::Ram(r.pfm.as_slice()),
+            flash::RamMut(vec![0xff; r.region_size]),
+            flash::RamMut(vec![0xff; r.region_size]),
+            flash::RamMut(vec![0xff; 64]),
+        )
+    });
+    let mut recovery_stores =
+        match (&mut recovery_verifier, &mut recovery_flash) {
+            (Some(verifier), Some((pfm, a, b, records))) => {
+                vec![recovery::Store::new(
+                    pfm,
+                    &mut **verifier,
+                    [a, b],
+                    records,
+                )
+                .unwrap()]
+            }
+            _ => vec![],
+        };
+
+    let mut pfm_flash = (
+        flash::RamMut(vec![0xff; 1024]),
+        flash::RamMut(vec![0xff; 1024]),
+        flash::RamMut(vec![0xff; 64]),
+    );
+    let pfm_store = if opts.pfm_store {
+        let (a, b, records) = &mut pfm_flash;
+        Some(store::Store::<Pfm>::new([a, b], records).unwrap())
+    } else {
+        None
+    };
+
     let mut server = PaRot::new(manticore::server::pa_rot::Options {
         identity: &identity,
         reset: &reset,
         hasher: &mut hasher,
         csrng: &mut csrng,
         ciphers: &mut ciphers,
-        trust_chain: &mut trust_chain,
+        trust_chain: &mut *trust_chain,
         session: &mut session,
-        pmr0: &opts.pmr0,
+        session_encryption: opts.session_encryption,
+        pmrs: &mut pmrs,
+        sealing_key: sealing_key.as_mut().map(|k| k as _),
+        config_reset: Some(&mut config_reset),
+        reset_authority: reset_authority.as_mut().map(|v| &mut **v as _),
+        recovery: &mut recovery_stores,
+        pfm: pfm_store.as_ref(),
+        firmware_protection: opts.firmware_protection,
         device_id: opts.device_id,
         networking,
         timeouts,
@@ -325,10 +548,26 @@ pub fn serve(opts: Options) -> ! {
             println!("listening@{}", port);
 
             let mut arena = BumpArena::new(vec![0; 1024]);
+            let mut aes = ring::aead::AesGcm::new();
+            let mut csrng = ring::csrng::Csrng::new();
+            let mut scratch = vec![0; 2048];
 
             log::info!("entering server loop");
             loop {
-                if let Err(e) = server.process_request(&mut host, &arena) {
+                let result = if opts.session_encryption {
+                    let key = server.session_key();
+                    let mut host = EncryptedHost::new(
+                        &mut host,
+                        &mut aes,
+                        &mut csrng,
+                        key,
+                        &mut scratch,
+                    );
+                    server.process_request(&mut host, &arena)
+                } else {
+                    server.process_request(&mut host, &arena)
+                };
+                if let Err(e) = result {
                     log::error!("failed to process request: {:?}", e);
                 }
                 arena.reset();
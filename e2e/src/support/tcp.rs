@@ -6,13 +6,15 @@
 //!
 //! This module defines an ad-hoc binding of Cerberus over TCP (termed
 //! "Cerberus over TCP"). This binding of Manticore implements the abstract
-//! Cerberus header as three bytes, described as a packed C struct:
+//! Cerberus header as four bytes, described as a packed C struct:
 //! ```text
 //! struct TcpCerberus {
 //!   command_type: u8,
+//!   crypt: u8,
 //!   payload_len: u16,
 //! }
 //! ```
+//! Here, `crypt` is either `0` or `1`, and records the Cerberus "crypt" bit.
 //!
 //! This module also provides a binding of SPDM, which uses a four-byte header:
 //! ```text
@@ -32,9 +34,14 @@ use std::io::Write as _;
 use std::net::TcpListener;
 use std::net::TcpStream;
 
+use manticore::crypto::ring;
 use manticore::io;
 use manticore::mem::Arena;
+use manticore::mem::ArenaExt as _;
 use manticore::net;
+use manticore::net::device::DevicePort;
+use manticore::net::device::DeviceResponse;
+use manticore::net::encrypted::EncryptedDevice;
 use manticore::net::host::HostPort;
 use manticore::net::host::HostRequest;
 use manticore::net::host::HostResponse;
@@ -47,6 +54,7 @@ use manticore::protocol::wire::WireEnum;
 use manticore::protocol::Command;
 use manticore::protocol::Message;
 use manticore::server;
+use manticore::session;
 
 /// Sends `req` to a virtual RoT listening on `localhost:{port}`, using
 /// Cerberus-over-TCP.
@@ -70,6 +78,7 @@ pub fn send_cerberus<
     })?;
     let mut writer = Writer::new(net::CerberusHeader {
         command: <Cmd::Req as Message>::TYPE,
+        crypt: false,
     });
     log::info!("serializing {}", type_name::<Cmd::Req>());
     req.to_wire(&mut writer)?;
@@ -79,7 +88,23 @@ pub fn send_cerberus<
     let (header, len) = net::CerberusHeader::from_tcp(&mut conn)?;
     let mut r = TcpReader { tcp: conn, len };
 
-    if header.command == <Cmd::Resp as Message>::TYPE {
+    if header.command == <Cmd::Resp as Message>::TYPE
+        && header.command == cerberus::CommandType::Error
+    {
+        // Responses that are just an `Ack` share a command type with errors,
+        // so we buffer the payload to be able to try parsing it as either.
+        let payload = arena
+            .alloc_slice::<u8>(len)
+            .map_err(protocol::wire::Error::from)?;
+        io::Read::read_bytes(&mut r, payload).map_err(net::Error::from)?;
+        let payload = &*payload;
+        if let Ok(resp) = FromWire::from_wire(&mut &*payload, arena) {
+            log::info!("deserialized {}", type_name::<Cmd::Resp>());
+            return Ok(Ok(resp));
+        }
+        log::info!("deserializing {}", type_name::<protocol::Error<'a, Cmd>>());
+        Ok(Err(FromWire::from_wire(&mut &*payload, arena)?))
+    } else if header.command == <Cmd::Resp as Message>::TYPE {
         log::info!("deserializing {}", type_name::<Cmd::Resp>());
         Ok(Ok(FromWire::from_wire(&mut r, arena)?))
     } else if header.command == cerberus::CommandType::Error {
@@ -90,6 +115,72 @@ pub fn send_cerberus<
     }
 }
 
+/// Sends `req` to a virtual RoT listening on `localhost:{port}`, using
+/// Cerberus-over-TCP, encrypted with the session key `key`.
+///
+/// Blocks until a response comes back.
+pub fn send_cerberus_encrypted<
+    'a,
+    Cmd: Command<'a, CommandType = cerberus::CommandType>,
+>(
+    port: u16,
+    req: Cmd::Req,
+    key: session::Key,
+    arena: &'a dyn Arena,
+) -> Result<
+    Result<Cmd::Resp, protocol::Error<'a, Cmd>>,
+    server::Error<net::CerberusHeader>,
+> {
+    let mut msg = Writer::new(());
+    log::info!("serializing {}", type_name::<Cmd::Req>());
+    req.to_wire(&mut msg)?;
+
+    let mut tcp = TcpDevicePort::new(port);
+    let mut aes = ring::aead::AesGcm::new();
+    let mut csrng = ring::csrng::Csrng::new();
+    let mut scratch = vec![0; 2048];
+    let mut device = EncryptedDevice::new(
+        &mut tcp,
+        &mut aes,
+        &mut csrng,
+        Some(key),
+        &mut scratch,
+    );
+    let header = net::CerberusHeader {
+        command: <Cmd::Req as Message>::TYPE,
+        crypt: true,
+    };
+    device.send(0, header, &msg.buf)?;
+
+    log::info!("waiting for response");
+    device.wait_for_response(0)?;
+    let resp = device.receive_response()?;
+    let header = resp.header()?;
+    if !header.crypt {
+        log::error!("got unencrypted response to encrypted request");
+        return Err(net::Error::BadHeader.into());
+    }
+
+    let r = resp.payload()?;
+    let payload = arena
+        .alloc_slice::<u8>(r.remaining_data())
+        .map_err(protocol::wire::Error::from)?;
+    r.read_bytes(payload).map_err(net::Error::from)?;
+    let payload = &*payload;
+
+    if header.command == <Cmd::Resp as Message>::TYPE {
+        if let Ok(resp) = FromWire::from_wire(&mut &*payload, arena) {
+            log::info!("deserialized {}", type_name::<Cmd::Resp>());
+            return Ok(Ok(resp));
+        }
+    }
+    if header.command == cerberus::CommandType::Error {
+        log::info!("deserializing {}", type_name::<protocol::Error<'a, Cmd>>());
+        return Ok(Err(FromWire::from_wire(&mut &*payload, arena)?));
+    }
+    Err(net::Error::BadHeader.into())
+}
+
 /// Sends `req` to a virtual RoT listening on `localhost:{port}`, using
 /// Spdm-over-TCP.
 ///
@@ -174,12 +265,12 @@ impl Header for net::CerberusHeader {
     fn from_tcp(
         mut r: impl std::io::Read,
     ) -> Result<(Self, usize), net::Error> {
-        let mut header_bytes = [0u8; 3];
+        let mut header_bytes = [0u8; 4];
         r.read_exact(&mut header_bytes).map_err(|e| {
             log::error!("{}", e);
             net::Error::Io(io::Error::Internal)
         })?;
-        let [cmd_byte, len_lo, len_hi] = header_bytes;
+        let [cmd_byte, crypt_byte, len_lo, len_hi] = header_bytes;
 
         let header = Self {
             command: cerberus::CommandType::from_wire_value(cmd_byte)
@@ -187,6 +278,14 @@ impl Header for net::CerberusHeader {
                     log::error!("bad command byte: {}", cmd_byte);
                     net::Error::BadHeader
                 })?,
+            crypt: match crypt_byte {
+                0 => false,
+                1 => true,
+                _ => {
+                    log::error!("bad crypt byte: {}", crypt_byte);
+                    return Err(net::Error::BadHeader);
+                }
+            },
         };
         let len = u16::from_le_bytes([len_lo, len_hi]);
         Ok((header, len as usize))
@@ -198,11 +297,17 @@ impl Header for net::CerberusHeader {
         mut w: impl std::io::Write,
     ) -> Result<(), net::Error> {
         let [len_lo, len_hi] = (msg.len() as u16).to_le_bytes();
-        w.write_all(&[self.command.to_wire_value(), len_lo, len_hi])
-            .map_err(|e| {
-                log::error!("{}", e);
-                io::Error::BufferExhausted
-            })?;
+        let crypt_byte = self.crypt as u8;
+        w.write_all(&[
+            self.command.to_wire_value(),
+            crypt_byte,
+            len_lo,
+            len_hi,
+        ])
+        .map_err(|e| {
+            log::error!("{}", e);
+            io::Error::BufferExhausted
+        })?;
         w.write_all(msg).map_err(|e| {
             log::error!("{}", e);
             io::Error::BufferExhausted
@@ -273,7 +378,7 @@ struct Writer<H> {
     buf: Vec<u8>,
 }
 
-impl<H: Header> Writer<H> {
+impl<H> Writer<H> {
     /// Creates a new `Writer` that will encode the given abstract `header`.
     pub fn new(header: H) -> Self {
         Self {
@@ -281,7 +386,9 @@ impl<H: Header> Writer<H> {
             buf: Vec::new(),
         }
     }
+}
 
+impl<H: Header> Writer<H> {
     /// Flushes the buffered data to the given [`std::io::Write`] (usually, a
     /// [`TcpStream`]).
     pub fn finish(self, w: impl std::io::Write) -> Result<(), net::Error> {
@@ -460,3 +567,81 @@ impl<H> io::Read for Inner<H> {
 }
 #[allow(unsafe_code)]
 unsafe impl<'a, H: 'a> io::ReadZero<'a> for Inner<H> {}
+
+/// A Cerberus-over-TCP implementation of [`DevicePort`].
+///
+/// This type sends each request to a virtual RoT listening on
+/// `localhost:{port}`, over a fresh connection; the `dest` passed to `send()`
+/// is ignored.
+pub struct TcpDevicePort<H = net::CerberusHeader> {
+    port: u16,
+    // State for the current request: the connection it was sent on, and the
+    // header and reader for its response, once it has been received.
+    conn: Option<TcpStream>,
+    response: Option<(H, TcpReader)>,
+}
+
+impl<H> TcpDevicePort<H> {
+    /// Creates a new `TcpDevicePort` for talking to `localhost:{port}`.
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            conn: None,
+            response: None,
+        }
+    }
+}
+
+impl<H: Header> DevicePort<H> for TcpDevicePort<H> {
+    fn send(
+        &mut self,
+        _dest: u8,
+        header: H,
+        msg: &[u8],
+    ) -> Result<(), net::Error> {
+        self.response = None;
+
+        log::info!("connecting to 127.0.0.1:{}", self.port);
+        let mut conn =
+            TcpStream::connect(("127.0.0.1", self.port)).map_err(|e| {
+                log::error!("{}", e);
+                net::Error::Io(io::Error::Internal)
+            })?;
+        header.to_tcp(msg, &mut conn)?;
+        self.conn = Some(conn);
+        Ok(())
+    }
+
+    fn wait_for_response(
+        &mut self,
+        _duration: usize,
+    ) -> Result<(), net::Error> {
+        // Reading the header blocks until the response arrives.
+        Ok(())
+    }
+
+    fn receive_response(
+        &mut self,
+    ) -> Result<&mut dyn DeviceResponse<H>, net::Error> {
+        let mut conn = self.conn.take().ok_or(net::Error::OutOfOrder)?;
+        let (header, len) = H::from_tcp(&mut conn)?;
+        self.response = Some((header, TcpReader { tcp: conn, len }));
+        Ok(self)
+    }
+}
+
+impl<H: Header> DeviceResponse<H> for TcpDevicePort<H> {
+    fn header(&self) -> Result<H, net::Error> {
+        self.response
+            .as_ref()
+            .map(|(h, _)| *h)
+            .ok_or(net::Error::OutOfOrder)
+    }
+
+    fn payload(&mut self) -> Result<&mut dyn io::Read, net::Error> {
+        self.response
+            .as_mut()
+            .map(|(_, r)| r as &mut dyn io::Read)
+            .ok_or(net::Error::OutOfOrder)
+    }
+}
@@ -26,6 +26,7 @@ use crate::support::rot;
 #[test]
 fn challenge() {
     use manticore::protocol::challenge::*;
+    use manticore::protocol::firmware_version::*;
     use manticore::protocol::get_cert::*;
     use manticore::protocol::get_digests::*;
     use manticore::protocol::key_exchange::*;
@@ -41,6 +42,8 @@ fn challenge() {
         alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
             keys::KEY3_RSA_KEYPAIR.to_vec(),
         )),
+        firmware_version: b"my cool firmware".to_vec(),
+        session_encryption: true,
         ..Default::default()
     });
 
@@ -167,4 +170,37 @@ fn challenge() {
     let mut hasher = h.new_hmac(hash::Algo::Sha256, hmac_key).unwrap();
     hasher.write(alias_cert.raw()).unwrap();
     hasher.expect(alias_hmac).unwrap();
+
+    // Now that a session is established, requests may be encrypted.
+    let key = *session.aes_key().unwrap();
+    let resp = virt
+        .send_cerberus_encrypted::<FirmwareVersion>(
+            FirmwareVersionRequest { index: 0 },
+            key,
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(&resp.version[..16], b"my cool firmware");
+
+    // A request encrypted with the wrong key is rejected without a reply.
+    let mut bad_key = key;
+    bad_key[0] ^= 1;
+    assert!(virt
+        .send_cerberus_encrypted::<FirmwareVersion>(
+            FirmwareVersionRequest { index: 0 },
+            bad_key,
+            &arena,
+        )
+        .is_err());
+
+    // Plaintext requests continue to work.
+    let resp = virt
+        .send_cerberus::<FirmwareVersion>(
+            FirmwareVersionRequest { index: 0 },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(&resp.version[..16], b"my cool firmware");
 }
@@ -96,3 +96,229 @@ fn device_id() {
         }
     );
 }
+
+/// Performs capabilities negotiation with `virt`, offering AES keys of the
+/// given strengths.
+fn negotiate(
+    virt: &rot::Virtual,
+    aes: &[manticore::protocol::capabilities::AesKeyStrength],
+) -> manticore::protocol::capabilities::Capabilities {
+    use manticore::protocol::capabilities::*;
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<DeviceCapabilities>(
+        DeviceCapabilitiesRequest {
+            capabilities: Capabilities {
+                networking: Networking {
+                    max_message_size: 1024,
+                    max_packet_size: 256,
+                    mode: RotMode::Platform,
+                    roles: BusRole::Host.into(),
+                },
+                security: Security::Authentication.into(),
+                has_pfm_support: false,
+                has_policy_support: false,
+                has_firmware_protection: false,
+                crypto: Crypto {
+                    has_ecdsa: false,
+                    has_ecc: false,
+                    has_rsa: true,
+                    has_aes: !aes.is_empty(),
+                    ecc_strength: Default::default(),
+                    rsa_strength: RsaKeyStrength::Bits2048.into(),
+                    aes_strength: aes
+                        .iter()
+                        .fold(Default::default(), |acc, &s| acc | s),
+                },
+            },
+        },
+        &arena,
+    );
+    resp.unwrap().unwrap().capabilities
+}
+
+#[test]
+fn capabilities() {
+    use manticore::protocol::capabilities::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    let caps = negotiate(&virt, &[AesKeyStrength::Bits256]);
+    assert_eq!(
+        caps.security,
+        Security::HashAndKdf | Security::Authentication
+    );
+    // The virtual RoT has no PFM storage, sealing key, firmware protection,
+    // or session encryption by default.
+    assert!(!caps.has_pfm_support);
+    assert!(!caps.has_policy_support);
+    assert!(!caps.has_firmware_protection);
+    assert!(caps.crypto.aes_strength.is_empty());
+}
+
+#[test]
+fn capabilities_all_enabled() {
+    use manticore::protocol::capabilities::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        pfm_store: true,
+        sealing_key: Some(vec![0x42; 32]),
+        firmware_protection: true,
+        session_encryption: true,
+        ..Default::default()
+    });
+
+    let caps =
+        negotiate(&virt, &[AesKeyStrength::Bits128, AesKeyStrength::Bits256]);
+    assert_eq!(
+        caps.security,
+        Security::HashAndKdf
+            | Security::Authentication
+            | Security::Confidentiality
+    );
+    assert!(caps.has_pfm_support);
+    assert!(caps.has_policy_support);
+    assert!(caps.has_firmware_protection);
+    // Only 256-bit session keys are supported.
+    assert!(caps.crypto.aes_strength == AesKeyStrength::Bits256);
+
+    // Session encryption is not offered to a requester that can't use it.
+    for &aes in &[&[][..], &[AesKeyStrength::Bits128]] {
+        let caps = negotiate(&virt, aes);
+        assert_eq!(
+            caps.security,
+            Security::HashAndKdf | Security::Authentication
+        );
+        assert!(caps.crypto.aes_strength.is_empty());
+    }
+}
+
+#[test]
+fn device_info() {
+    use manticore::protocol::device_info::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        unique_device_identity: b"my very unique identity".to_vec(),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<DeviceInfo>(
+        DeviceInfoRequest {
+            index: InfoIndex::UniqueChipIndex,
+        },
+        &arena,
+    );
+    assert_eq!(resp.unwrap().unwrap().info, b"my very unique identity");
+
+    // The fake identity doesn't count attestation failures.
+    let resp = virt.send_cerberus::<DeviceInfo>(
+        DeviceInfoRequest {
+            index: InfoIndex::AttestationFailures,
+        },
+        &arena,
+    );
+    resp.unwrap().expect_err("expected error from server");
+}
+
+fn ports() -> Vec<rot::Port> {
+    use manticore::hardware::HostState;
+
+    vec![
+        rot::Port {
+            resets_since_power_on: 3,
+            external_resets_since_power_on: 1,
+            host_state: HostState::Running,
+        },
+        rot::Port {
+            resets_since_power_on: 7,
+            external_resets_since_power_on: 70_000,
+            host_state: HostState::InReset,
+        },
+    ]
+}
+
+#[test]
+fn reset_counter() {
+    use manticore::protocol::reset_counter::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        resets_since_power_on: 5,
+        ports: ports(),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let expected = [
+        (ResetType::Local, 0, Some(5)),
+        (ResetType::External, 0, None),
+        (ResetType::Local, 1, Some(3)),
+        (ResetType::External, 1, Some(1)),
+        (ResetType::Local, 2, Some(7)),
+        // Counts too large for the response saturate.
+        (ResetType::External, 2, Some(u16::MAX)),
+        (ResetType::Local, 3, None),
+    ];
+    for &(reset_type, port_id, count) in &expected {
+        let resp = virt
+            .send_cerberus::<ResetCounter>(
+                ResetCounterRequest {
+                    reset_type,
+                    port_id,
+                },
+                &arena,
+            )
+            .unwrap();
+        assert_eq!(resp.ok().map(|r| r.count), count);
+    }
+}
+
+#[test]
+fn device_uptime() {
+    use manticore::protocol::device_uptime::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        ports: ports(),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    for port_id in 0..=2 {
+        let resp = virt.send_cerberus::<DeviceUptime>(
+            DeviceUptimeRequest { port_id },
+            &arena,
+        );
+        resp.unwrap().unwrap();
+    }
+
+    let resp = virt.send_cerberus::<DeviceUptime>(
+        DeviceUptimeRequest { port_id: 3 },
+        &arena,
+    );
+    resp.unwrap().expect_err("expected error from server");
+}
+
+#[test]
+fn host_state() {
+    use manticore::protocol::get_host_state::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        ports: ports(),
+        ..Default::default()
+    });
+
+    let arena = BumpArena::new([0; 64]);
+    let resp = virt.send_cerberus::<GetHostState>(
+        GetHostStateRequest { port_id: 2 },
+        &arena,
+    );
+    assert_eq!(
+        resp.unwrap().unwrap().host_reset_state,
+        HostResetState::HostInReset
+    );
+
+    let resp = virt.send_cerberus::<GetHostState>(
+        GetHostStateRequest { port_id: 0 },
+        &arena,
+    );
+    resp.unwrap().expect_err("expected error from server");
+}
@@ -0,0 +1,208 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for Platform Measurement Registers.
+
+use manticore::cert::CertFormat;
+use manticore::crypto::hash;
+use manticore::crypto::hash::EngineExt as _;
+use manticore::crypto::ring;
+use manticore::crypto::sig::Verify as _;
+use manticore::io::Cursor;
+use manticore::io::Write as _;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::pmr;
+use manticore::protocol::wire::ToWire;
+use testutil::data::keys;
+use testutil::data::x509;
+
+use crate::support::rot;
+
+const PMR0: &[pmr::Measurement] = &[
+    pmr::Measurement {
+        event_type: 0x8000_0001,
+        digest: [0xaa; 32],
+    },
+    pmr::Measurement {
+        event_type: 0x8000_0002,
+        digest: [0xbb; 32],
+    },
+];
+
+/// Computes `SHA-256(old || new)`.
+fn extend(old: &[u8; 32], new: &[u8; 32]) -> [u8; 32] {
+    let mut h = ring::hash::Engine::new();
+    let mut out = [0; 32];
+    let mut hasher = h.new_hash(hash::Algo::Sha256).unwrap();
+    hasher.write(old).unwrap();
+    hasher.write(new).unwrap();
+    hasher.finish(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn pmrs() {
+    use manticore::protocol::challenge::*;
+    use manticore::protocol::get_pmr::*;
+    use manticore::protocol::reset_pmr::*;
+    use manticore::protocol::update_pmr::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        cert_chain: vec![
+            x509::CHAIN1.to_vec(),
+            x509::CHAIN2.to_vec(),
+            x509::CHAIN3.to_vec(),
+        ],
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY3_RSA_KEYPAIR.to_vec(),
+        )),
+        pmr0_measurements: PMR0.to_vec(),
+        ..Default::default()
+    });
+    let mut verifier = ring::rsa::Sign256::from_pkcs8(keys::KEY3_RSA_KEYPAIR)
+        .unwrap()
+        .verifier();
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let req = GetPmrRequest {
+        index: 0,
+        nonce: &[99; 32],
+    };
+    let resp = virt.send_cerberus::<GetPmr>(req, &arena).unwrap().unwrap();
+    let pmr0 = extend(&extend(&[0; 32], &[0xaa; 32]), &[0xbb; 32]);
+    assert_eq!(resp.value, pmr0);
+
+    let mut buf = vec![0; 1024];
+    let mut cursor = Cursor::new(&mut buf);
+    ToWire::to_wire(&req, &mut cursor).unwrap();
+    cursor.write_bytes(resp.nonce).unwrap();
+    cursor.write_bytes(&[resp.value.len() as u8]).unwrap();
+    cursor.write_bytes(resp.value).unwrap();
+    verifier
+        .verify(&[cursor.consumed_bytes()], resp.signature)
+        .unwrap();
+    arena.reset();
+
+    // PMR #0 is off-limits to the host.
+    virt.send_cerberus::<UpdatePmr>(
+        UpdatePmrRequest {
+            index: 0,
+            measurement: &[0xcc; 32],
+        },
+        &arena,
+    )
+    .unwrap()
+    .expect_err("expected error from server");
+    virt.send_cerberus::<ResetPmr>(ResetPmrRequest { index: 0 }, &arena)
+        .unwrap()
+        .expect_err("expected error from server");
+
+    virt.send_cerberus::<UpdatePmr>(
+        UpdatePmrRequest {
+            index: 1,
+            measurement: &[0xcc; 32],
+        },
+        &arena,
+    )
+    .unwrap()
+    .unwrap();
+    let resp = virt
+        .send_cerberus::<GetPmr>(
+            GetPmrRequest {
+                index: 1,
+                nonce: &[99; 32],
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.value, extend(&[0; 32], &[0xcc; 32]));
+    arena.reset();
+
+    virt.send_cerberus::<ResetPmr>(ResetPmrRequest { index: 1 }, &arena)
+        .unwrap()
+        .unwrap();
+    let resp = virt
+        .send_cerberus::<GetPmr>(
+            GetPmrRequest {
+                index: 1,
+                nonce: &[99; 32],
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.value, [0; 32]);
+    arena.reset();
+
+    // The PMR #0 value also appears in challenges.
+    let resp = virt
+        .send_cerberus::<Challenge>(
+            ChallengeRequest {
+                slot: 0,
+                nonce: &[99; 32],
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.tbs.pmr0, pmr0);
+    assert_eq!(resp.tbs.pmr0_components, 2);
+}
+
+#[test]
+fn attestation_data() {
+    use manticore::protocol::get_attestation_data::*;
+    use manticore::protocol::update_pmr::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        pmr0_measurements: PMR0.to_vec(),
+        ..Default::default()
+    });
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let resp = virt
+        .send_cerberus::<GetAttestationData>(
+            GetAttestationDataRequest { index: 0 },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.entries, PMR0);
+    arena.reset();
+
+    virt.send_cerberus::<UpdatePmr>(
+        UpdatePmrRequest {
+            index: 2,
+            measurement: &[0xcc; 32],
+        },
+        &arena,
+    )
+    .unwrap()
+    .unwrap();
+    let resp = virt
+        .send_cerberus::<GetAttestationData>(
+            GetAttestationDataRequest { index: 2 },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        resp.entries,
+        &[pmr::Measurement {
+            event_type: pmr::HOST_EVENT_TYPE,
+            digest: [0xcc; 32],
+        }]
+    );
+    arena.reset();
+
+    virt.send_cerberus::<GetAttestationData>(
+        GetAttestationDataRequest { index: 100 },
+        &arena,
+    )
+    .unwrap()
+    .expect_err("expected error from server");
+}
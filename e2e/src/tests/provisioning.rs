@@ -0,0 +1,169 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for certificate provisioning.
+
+use manticore::cert::CertFormat;
+use manticore::crypto::ring;
+use manticore::crypto::sig;
+use manticore::crypto::sig::Ciphers as _;
+use manticore::io::Cursor;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::wire::ToWire;
+use testutil::data::keys;
+use testutil::data::x509;
+
+use crate::support::rot;
+
+/// `CN=manticore`.
+const SUBJECT: &[u8] = &[
+    0x30, 0x14, 0x31, 0x12, 0x30, 0x10, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0c,
+    0x09, b'm', b'a', b'n', b't', b'i', b'c', b'o', b'r', b'e',
+];
+
+/// Provisions a virtual RoT whose DeviceID key is `keypair`, with a chain
+/// ending in `device_id`, which must certify `spki`.
+fn provision(
+    keypair: rot::KeyPairFormat,
+    spki: &[u8],
+    device_id: &[u8],
+) -> rot::Virtual {
+    use manticore::protocol::export_csr::*;
+    use manticore::protocol::get_certificate_state::*;
+    use manticore::protocol::get_digests::*;
+    use manticore::protocol::import_certificate::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options {
+        cert_format: CertFormat::RiotX509,
+        alias_keypair: Some(keypair),
+        provisioning: Some(rot::Provisioning {
+            subject: SUBJECT.to_vec(),
+            spki: spki.to_vec(),
+        }),
+        ..Default::default()
+    });
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let resp = virt
+        .send_cerberus::<GetCertificateState>(
+            GetCertificateStateRequest {},
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.state, CertificateState::NotProvisioned);
+    arena.reset();
+
+    let resp = virt
+        .send_cerberus::<ExportCsr>(ExportCsrRequest { index: 0 }, &arena)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.csr[0], 0x30);
+    arena.reset();
+
+    // Importing the DeviceID cert twice exercises replacing a certificate.
+    for &(cert_type, cert) in &[
+        (CertificateType::RootCa, x509::CHAIN1),
+        (CertificateType::DeviceId, device_id),
+        (CertificateType::IntermediateCa, x509::CHAIN2),
+        (CertificateType::DeviceId, device_id),
+    ] {
+        virt.send_cerberus::<ImportCertificate>(
+            ImportCertificateRequest { cert_type, cert },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+        arena.reset();
+    }
+
+    let resp = virt
+        .send_cerberus::<GetCertificateState>(
+            GetCertificateStateRequest {},
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.state, CertificateState::Valid);
+    arena.reset();
+
+    let resp = virt
+        .send_cerberus::<GetDigests>(
+            GetDigestsRequest {
+                slot: 0,
+                key_exchange: KeyExchangeAlgo::None,
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.digests.len(), 3);
+    virt
+}
+
+#[test]
+fn provision_rsa() {
+    provision(
+        rot::KeyPairFormat::RsaPkcs8(keys::KEY3_RSA_KEYPAIR.to_vec()),
+        keys::KEY3_RSA_PUBLIC,
+        x509::CHAIN3,
+    );
+}
+
+#[test]
+fn provision_ecdsa() {
+    use manticore::protocol::challenge::*;
+
+    let virt = provision(
+        rot::KeyPairFormat::EcdsaP256Pkcs8(
+            keys::KEY1_ECDSA_P256_KEYPAIR.to_vec(),
+        ),
+        keys::KEY1_ECDSA_P256_PUBLIC,
+        x509::CHAIN3_ECDSA_P256,
+    );
+
+    // The provisioned DeviceID key answers challenges.
+    let arena = BumpArena::new(vec![0; 1024]);
+    let req = ChallengeRequest {
+        slot: 0,
+        nonce: &[99; 32],
+    };
+    let resp = virt
+        .send_cerberus::<Challenge>(req, &arena)
+        .unwrap()
+        .unwrap();
+
+    let mut buf = vec![0; 1024];
+    let mut cursor = Cursor::new(&mut buf);
+    ToWire::to_wire(&req, &mut cursor).unwrap();
+    ToWire::to_wire(&resp.tbs, &mut cursor).unwrap();
+
+    let key = sig::PublicKeyParams::Ecc {
+        curve: sig::Curve::NistP256,
+        x: keys::KEY1_ECDSA_P256_X,
+        y: keys::KEY1_ECDSA_P256_Y,
+    };
+    let mut ciphers = ring::sig::Ciphers::new();
+    ciphers
+        .verifier(sig::Algo::EcdsaDerP256, &key)
+        .unwrap()
+        .verify(&[cursor.consumed_bytes()], resp.signature)
+        .unwrap();
+}
+
+#[test]
+fn not_provisionable() {
+    use manticore::protocol::get_certificate_state::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+
+    let arena = BumpArena::new(vec![0; 1024]);
+    virt.send_cerberus::<GetCertificateState>(
+        GetCertificateStateRequest {},
+        &arena,
+    )
+    .unwrap()
+    .expect_err("expected error from server");
+}
@@ -0,0 +1,188 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for recovery image updates.
+
+use manticore::crypto::hash;
+use manticore::crypto::ring;
+use manticore::manifest::owned;
+use manticore::manifest::KeyType;
+use manticore::manifest::SigType;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::error::Error;
+use manticore::protocol::error::RecoveryImageError;
+use manticore::recovery;
+use testutil::data::keys;
+
+use crate::support::rot;
+
+const VERSION: &[u8; 32] = b"e2e recovery image, version 1.0!";
+
+fn options() -> rot::Options {
+    let mut hasher = ring::hash::Engine::new();
+    let mut signer =
+        ring::rsa::Sign256::from_pkcs8(keys::KEY1_RSA_KEYPAIR).unwrap();
+
+    let pfm: owned::Pfm = serde_json::from_str(
+        r#"{
+            "version_id": 1,
+            "elements": [
+                { "platform_id": "e2e platform" },
+                {
+                    "version_count": 1,
+                    "firmware_id": "host firmware",
+                    "flags": 0,
+                    "hashed": true,
+                    "children": [{
+                        "version_addr": "0x20",
+                        "version_str": "host-1.0",
+                        "rw_regions": [],
+                        "image_regions": []
+                    }]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let sig_type = SigType {
+        key_type: KeyType::Rsa2048,
+        hash_type: hash::Algo::Sha256,
+    };
+    rot::Options {
+        recovery: Some(rot::Recovery {
+            pfm: pfm.sign(0, sig_type, &mut hasher, &mut signer).unwrap(),
+            manifest_keypair: rot::KeyPairFormat::RsaPkcs8(
+                keys::KEY1_RSA_KEYPAIR.to_vec(),
+            ),
+            region_size: 4096,
+        }),
+        ..Default::default()
+    }
+}
+
+fn image(host_version: &[u8]) -> Vec<u8> {
+    let mut hasher = ring::hash::Engine::new();
+    let mut signer =
+        ring::rsa::Sign256::from_pkcs8(keys::KEY1_RSA_KEYPAIR).unwrap();
+
+    let mut host_flash = vec![0xff; 256];
+    host_flash[0x20..0x20 + host_version.len()].copy_from_slice(host_version);
+    recovery::sign(
+        VERSION,
+        b"e2e platform",
+        &host_flash,
+        SigType {
+            key_type: KeyType::Rsa2048,
+            hash_type: hash::Algo::Sha256,
+        },
+        &mut hasher,
+        &mut signer,
+    )
+    .unwrap()
+}
+
+fn update(
+    virt: &rot::Virtual,
+    image: &[u8],
+) -> Result<(), Error<RecoveryImageError>> {
+    use manticore::protocol::activate_recovery_image::*;
+    use manticore::protocol::prepare_recovery_image::*;
+    use manticore::protocol::update_recovery_image::*;
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    virt.send_cerberus::<PrepareRecoveryImage>(
+        PrepareRecoveryImageRequest {
+            port_id: 0,
+            size: image.len() as u32,
+        },
+        &arena,
+    )
+    .unwrap()?;
+    arena.reset();
+
+    for chunk in image.chunks(128) {
+        virt.send_cerberus::<UpdateRecoveryImage>(
+            UpdateRecoveryImageRequest {
+                port_id: 0,
+                data: chunk,
+            },
+            &arena,
+        )
+        .unwrap()?;
+        arena.reset();
+    }
+
+    virt.send_cerberus::<ActivateRecoveryImage>(
+        ActivateRecoveryImageRequest { port_id: 0 },
+        &arena,
+    )
+    .unwrap()?;
+    Ok(())
+}
+
+#[test]
+fn update_recovery_image() {
+    use manticore::protocol::get_recovery_image_version::*;
+
+    let virt = rot::Virtual::spawn(&options());
+    let arena = BumpArena::new(vec![0; 1024]);
+
+    // There is no image to begin with.
+    assert!(matches!(
+        virt.send_cerberus::<GetRecoveryImageVersion>(
+            GetRecoveryImageVersionRequest { port_id: 0 },
+            &arena,
+        )
+        .unwrap(),
+        Err(Error::Specific(RecoveryImageError::NoImage))
+    ));
+
+    update(&virt, &image(b"host-1.0")).unwrap();
+
+    let resp = virt
+        .send_cerberus::<GetRecoveryImageVersion>(
+            GetRecoveryImageVersionRequest { port_id: 0 },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.version, VERSION);
+}
+
+#[test]
+fn bad_recovery_image() {
+    let virt = rot::Virtual::spawn(&options());
+
+    assert!(matches!(
+        update(&virt, &image(b"host-6.6")),
+        Err(Error::Specific(RecoveryImageError::Incompatible))
+    ));
+
+    let mut tampered = image(b"host-1.0");
+    tampered[100] ^= 1;
+    assert!(matches!(
+        update(&virt, &tampered),
+        Err(Error::Specific(RecoveryImageError::BadImage))
+    ));
+}
+
+#[test]
+fn no_recovery_storage() {
+    use manticore::protocol::prepare_recovery_image::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    let arena = BumpArena::new(vec![0; 1024]);
+    let resp = virt
+        .send_cerberus::<PrepareRecoveryImage>(
+            PrepareRecoveryImageRequest {
+                port_id: 0,
+                size: 1024,
+            },
+            &arena,
+        )
+        .unwrap();
+    assert!(matches!(resp, Err(Error::OutOfRange)));
+}
@@ -0,0 +1,143 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for authorized configuration resets.
+
+use manticore::crypto::ring;
+use manticore::crypto::sig::Sign as _;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::protocol::error::AuthorizationError;
+use manticore::protocol::error::Error;
+use manticore::protocol::reset_config::*;
+use testutil::data::keys;
+
+use crate::support::rot;
+
+fn sign(token: &[u8], keypair: &[u8]) -> Vec<u8> {
+    let mut signer = ring::rsa::Sign256::from_pkcs8(keypair).unwrap();
+    let mut sig = vec![0; signer.sig_bytes()];
+    let len = signer.sign(&[token], &mut sig).unwrap();
+    [token, &sig[..len]].concat()
+}
+
+#[test]
+fn authorized_reset() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        reset_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    });
+    let mut arena = BumpArena::new(vec![0; 1024]);
+
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            ResetConfigRequest {
+                reset_type: ResetType::RestoreBypass,
+                authorization: &[],
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    let signed = sign(resp.authorization, keys::KEY1_RSA_KEYPAIR);
+    arena.reset();
+
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            ResetConfigRequest {
+                reset_type: ResetType::RestoreBypass,
+                authorization: &signed,
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    assert!(resp.authorization.is_empty());
+    arena.reset();
+
+    // The token cannot be replayed.
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            ResetConfigRequest {
+                reset_type: ResetType::RestoreBypass,
+                authorization: &signed,
+            },
+            &arena,
+        )
+        .unwrap();
+    assert!(matches!(
+        resp,
+        Err(Error::Specific(AuthorizationError::Unauthorized))
+    ));
+}
+
+#[test]
+fn unauthorized_reset() {
+    let virt = rot::Virtual::spawn(&rot::Options {
+        reset_authority: Some(rot::KeyPairFormat::RsaPkcs8(
+            keys::KEY1_RSA_KEYPAIR.to_vec(),
+        )),
+        ..Default::default()
+    });
+    let mut arena = BumpArena::new(vec![0; 1024]);
+
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            ResetConfigRequest {
+                reset_type: ResetType::RestoreDefaults,
+                authorization: &[],
+            },
+            &arena,
+        )
+        .unwrap()
+        .unwrap();
+    let signed = sign(resp.authorization, keys::KEY2_RSA_KEYPAIR);
+    arena.reset();
+
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            ResetConfigRequest {
+                reset_type: ResetType::RestoreDefaults,
+                authorization: &signed,
+            },
+            &arena,
+        )
+        .unwrap();
+    assert!(matches!(
+        resp,
+        Err(Error::Specific(AuthorizationError::Unauthorized))
+    ));
+    arena.reset();
+
+    // Unsupported resets are rejected before a token is issued.
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            ResetConfigRequest {
+                reset_type: ResetType::ResetIntrusion,
+                authorization: &[],
+            },
+            &arena,
+        )
+        .unwrap();
+    assert!(matches!(resp, Err(Error::OutOfRange)));
+}
+
+#[test]
+fn no_authority() {
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+    let arena = BumpArena::new(vec![0; 1024]);
+
+    let resp = virt
+        .send_cerberus::<ResetConfig>(
+            ResetConfigRequest {
+                reset_type: ResetType::RestoreBypass,
+                authorization: &[],
+            },
+            &arena,
+        )
+        .unwrap();
+    assert!(matches!(resp, Err(Error::OutOfRange)));
+}
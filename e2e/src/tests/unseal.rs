@@ -0,0 +1,139 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for unsealing.
+
+use manticore::crypto::ring;
+use manticore::mem::Arena as _;
+use manticore::mem::BumpArena;
+use manticore::pmr;
+use manticore::pmr::Pmrs as _;
+use manticore::seal;
+
+use crate::support::fakes;
+use crate::support::rot;
+
+/// The device's sealing key.
+const SEALING_KEY: &[u8; 32] = &[0x42; 32];
+
+const SECRET: &[u8] = b"super secret disk encryption key";
+
+/// Sends `sealing` to the device and returns the result of unsealing it.
+fn unseal(
+    virt: &rot::Virtual,
+    sealing: &seal::ring::Sealing,
+) -> (manticore::protocol::unseal_result::UnsealStatus, Vec<u8>) {
+    use manticore::protocol::unseal::*;
+    use manticore::protocol::unseal_result::*;
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    virt.send_cerberus::<Unseal>(
+        UnsealRequest {
+            hmac_type: HmacType::Sha256,
+            seed_type: SeedType::Ecdh,
+            seed_params: sealing.hash_seed as u8,
+            seed: &sealing.seed,
+            ciphertext: &sealing.ciphertext,
+            hmac: &sealing.hmac,
+            policy: &sealing.policy,
+        },
+        &arena,
+    )
+    .unwrap()
+    .unwrap();
+    arena.reset();
+
+    let resp = virt
+        .send_cerberus::<UnsealResult>(UnsealResultRequest {}, &arena)
+        .unwrap()
+        .unwrap();
+    (resp.status, resp.key.to_vec())
+}
+
+#[test]
+fn no_sealing_key() {
+    use manticore::protocol::unseal::*;
+    use manticore::protocol::unseal_result::*;
+
+    let virt = rot::Virtual::spawn(&rot::Options::default());
+
+    let mut arena = BumpArena::new(vec![0; 1024]);
+    let policy = [[0; 64]; seal::POLICY_PMRS];
+    virt.send_cerberus::<Unseal>(
+        UnsealRequest {
+            hmac_type: HmacType::Sha256,
+            seed_type: SeedType::Ecdh,
+            seed_params: 0,
+            seed: &[0x04; 65],
+            ciphertext: &[0; 32],
+            hmac: &[0; 32],
+            policy: &policy,
+        },
+        &arena,
+    )
+    .unwrap()
+    .expect_err("expected error from server");
+    arena.reset();
+
+    let resp = virt
+        .send_cerberus::<UnsealResult>(UnsealResultRequest {}, &arena)
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp.status, UnsealStatus::NotStarted);
+    assert!(resp.key.is_empty());
+}
+
+#[test]
+fn unseal_ok() {
+    use manticore::protocol::unseal_result::*;
+
+    let opts = rot::Options {
+        sealing_key: Some(SEALING_KEY.to_vec()),
+        ..Default::default()
+    };
+    let virt = rot::Virtual::spawn(&opts);
+
+    let mut h = ring::hash::Engine::new();
+    let mut pmrs = pmr::SimplePmrs::<1, 16>::new();
+    for m in &opts.pmr0_measurements {
+        pmrs.extend(0, m.event_type, &m.digest, &mut h).unwrap();
+    }
+    let mut policy = vec![[0; 64]; seal::POLICY_PMRS];
+    policy[0][..32].copy_from_slice(pmrs.value(0).unwrap());
+
+    let device_key = fakes::SealingKey::from_scalar(SEALING_KEY)
+        .public_key()
+        .to_vec();
+    for &hash_seed in &[false, true] {
+        let sealing = seal::ring::seal(
+            &device_key,
+            SECRET,
+            hash_seed,
+            policy.clone(),
+            &mut h,
+        )
+        .unwrap();
+        assert_eq!(
+            unseal(&virt, &sealing),
+            (UnsealStatus::Success, SECRET.to_vec())
+        );
+    }
+
+    // Changing the policy invalidates the HMAC.
+    let mut sealing =
+        seal::ring::seal(&device_key, SECRET, false, policy.clone(), &mut h)
+            .unwrap();
+    sealing.policy[0][0] ^= 1;
+    assert_eq!(unseal(&virt, &sealing), (UnsealStatus::BadHmac, vec![]));
+
+    // A correctly authenticated policy that doesn't match the device's PMRs
+    // is rejected.
+    policy[0][0] ^= 1;
+    let sealing =
+        seal::ring::seal(&device_key, SECRET, false, policy, &mut h).unwrap();
+    assert_eq!(
+        unseal(&virt, &sealing),
+        (UnsealStatus::PolicyMismatch, vec![])
+    );
+}
@@ -0,0 +1,741 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Attestation of AC-RoTs by a PA-RoT.
+//!
+//! A PA-RoT is responsible for checking that the AC-RoTs attached to it are
+//! running trusted firmware. It does so by running the following exchange
+//! with each device, over a [`DevicePort`]:
+//!
+//! 1. [`protocol::GetDigests`], to learn the digests of the device's
+//!    certificate chain. The first digest must be that of a trusted root.
+//! 2. [`protocol::GetCert`], to fetch every certificate not already present
+//!    in a [`CertCache`]. The chain is then verified.
+//! 3. [`protocol::Challenge`], whose signature is checked against the leaf
+//!    certificate, and whose PMR0 is compared against the expected values
+//!    for the device.
+//!
+//! The outcome for each device is recorded in [`Device::status`].
+
+use arrayvec::ArrayVec;
+
+use crate::cert;
+use crate::cert::CertFormat;
+use crate::cert::TrustChain as _;
+use crate::crypto::csrng;
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::crypto::sig;
+use crate::io::Cursor;
+use crate::io::Read as _;
+use crate::mem::Arena;
+use crate::mem::ArenaExt as _;
+use crate::mem::OutOfMemory;
+use crate::net;
+use crate::net::device::DevicePort;
+use crate::net::CerberusHeader;
+use crate::protocol;
+use crate::protocol::capabilities::Timeouts;
+use crate::protocol::wire;
+use crate::protocol::wire::FromWire;
+use crate::protocol::wire::ToWire;
+use crate::protocol::CommandType;
+use crate::protocol::Message;
+
+/// The digest of a certificate, as reported by [`protocol::GetDigests`].
+pub type Digest = [u8; hash::Algo::Sha256.bytes()];
+
+/// The maximum length of a device's certificate chain.
+pub const MAX_CHAIN_LEN: usize = 8;
+
+/// The maximum length of a single certificate fetched from a device.
+pub const MAX_CERT_LEN: usize = 2048;
+
+/// The number of bytes of a certificate requested by each
+/// [`protocol::GetCert`].
+const CERT_CHUNK_LEN: u16 = 128;
+
+/// An error returned while attesting a device.
+#[derive(Clone, Debug)]
+pub enum Error {
+    /// Indicates an error communicating with the device.
+    Network(net::Error),
+    /// Indicates that the device responded with an error, or with a
+    /// malformed or unexpected response.
+    Protocol,
+    /// Indicates that the device's certificate chain is not rooted in a
+    /// trusted certificate.
+    UntrustedRoot,
+    /// Indicates that a certificate did not match its digest, or was longer
+    /// than [`MAX_CERT_LEN`].
+    BadCert,
+    /// Indicates that the device's certificate chain failed to verify.
+    Cert(cert::Error),
+    /// Indicates that the challenge response's signature did not verify.
+    Signature(sig::Error),
+    /// Indicates that the device reported an unexpected PMR0 value.
+    Pmr0Mismatch,
+    /// Indicates that the arena ran out of memory.
+    OutOfMemory,
+    /// Indicates an error while hashing.
+    Hash(hash::Error),
+    /// Indicates that a challenge nonce could not be generated.
+    Csrng(csrng::Error),
+}
+
+impl From<net::Error> for Error {
+    fn from(e: net::Error) -> Self {
+        Self::Network(e)
+    }
+}
+
+impl From<wire::Error> for Error {
+    fn from(e: wire::Error) -> Self {
+        match e {
+            wire::Error::OutOfMemory => Self::OutOfMemory,
+            _ => Self::Protocol,
+        }
+    }
+}
+
+impl From<OutOfMemory> for Error {
+    fn from(_: OutOfMemory) -> Self {
+        Self::OutOfMemory
+    }
+}
+
+impl From<cert::Error> for Error {
+    fn from(e: cert::Error) -> Self {
+        Self::Cert(e)
+    }
+}
+
+impl From<sig::Error> for Error {
+    fn from(e: sig::Error) -> Self {
+        Self::Signature(e)
+    }
+}
+
+impl From<hash::Error> for Error {
+    fn from(e: hash::Error) -> Self {
+        Self::Hash(e)
+    }
+}
+
+impl From<csrng::Error> for Error {
+    fn from(e: csrng::Error) -> Self {
+        Self::Csrng(e)
+    }
+}
+
+/// A cache of certificates, keyed by their digests.
+///
+/// Because certificates are looked up by their digests, cached certificates
+/// need not be trusted: a device that presents the same digests is presenting
+/// the same certificates, which are verified anew on every attestation.
+pub trait CertCache {
+    /// Looks up the certificate with the given digest.
+    fn get(&self, digest: &Digest) -> Option<&[u8]>;
+
+    /// Inserts `cert`, whose digest is `digest`, into the cache.
+    ///
+    /// Implementations are free to evict other certificates, or to decline
+    /// to cache `cert` altogether.
+    fn insert(&mut self, digest: &Digest, cert: &[u8]);
+}
+impl dyn CertCache {} // Ensure object-safe.
+
+/// A [`CertCache`] that stores up to `N` certificates in a fixed buffer.
+///
+/// When the cache is full, it is emptied to make room for new certificates.
+pub struct SimpleCertCache<'a, const N: usize> {
+    storage: &'a mut [u8],
+    used: usize,
+    entries: ArrayVec<(Digest, usize, usize), N>,
+}
+
+impl<'a, const N: usize> SimpleCertCache<'a, N> {
+    /// Creates a new, empty cache backed by `storage`.
+    pub fn new(storage: &'a mut [u8]) -> Self {
+        Self {
+            storage,
+            used: 0,
+            entries: ArrayVec::new(),
+        }
+    }
+}
+
+impl<const N: usize> CertCache for SimpleCertCache<'_, N> {
+    fn get(&self, digest: &Digest) -> Option<&[u8]> {
+        let &(_, start, end) = self.entries.iter().find(|e| &e.0 == digest)?;
+        Some(&self.storage[start..end])
+    }
+
+    fn insert(&mut self, digest: &Digest, cert: &[u8]) {
+        if cert.len() > self.storage.len() || self.get(digest).is_some() {
+            return;
+        }
+        if self.entries.is_full() || self.storage.len() - self.used < cert.len()
+        {
+            self.entries.clear();
+            self.used = 0;
+        }
+
+        let start = self.used;
+        self.used += cert.len();
+        self.storage[start..self.used].copy_from_slice(cert);
+        self.entries.push((*digest, start, self.used));
+    }
+}
+
+/// The outcome of attesting a [`Device`].
+#[derive(Clone, Debug)]
+pub enum Status {
+    /// The device has not been attested yet.
+    Unattested,
+    /// The device passed attestation.
+    Passed,
+    /// The device failed attestation.
+    Failed(Error),
+}
+
+/// An AC-RoT to be attested.
+pub struct Device<'a> {
+    /// The device's address, as passed to [`DevicePort::send()`].
+    pub addr: u8,
+    /// The certificate chain slot to challenge.
+    pub slot: u8,
+    /// The format of the device's certificates.
+    pub cert_format: CertFormat,
+    /// Digests of the root certificates this device's chain may be rooted
+    /// in.
+    pub trusted_roots: &'a [Digest],
+    /// Acceptable values for the device's PMR0.
+    ///
+    /// If empty, PMR0 is not checked.
+    pub expected_pmr0: &'a [&'a [u8]],
+    /// The outcome of the most recent attestation of this device.
+    pub status: Status,
+}
+
+/// Options struct for initializing an [`Attestor`].
+pub struct Options<'a> {
+    /// The port through which devices are reached.
+    pub port: &'a mut dyn DevicePort<CerberusHeader>,
+    /// A handle to a hashing engine.
+    pub hasher: &'a mut dyn hash::Engine,
+    /// A handle to a signature verification engine.
+    pub ciphers: &'a mut dyn sig::Ciphers,
+    /// A random number generator for creating challenge nonces.
+    pub csrng: &'a mut dyn csrng::Csrng,
+    /// A cache for certificates fetched from devices.
+    pub cache: &'a mut dyn CertCache,
+    /// How long to wait for each response.
+    ///
+    /// `Challenge` requests use the cryptographic timeout; all others use the
+    /// regular one.
+    pub timeouts: Timeouts,
+}
+
+/// Attests AC-RoTs on behalf of a PA-RoT.
+///
+/// See the [module documentation][self] for more information.
+pub struct Attestor<'a> {
+    opts: Options<'a>,
+    failures: u32,
+}
+
+impl<'a> Attestor<'a> {
+    /// Creates a new `Attestor` with the given `Options`.
+    pub fn new(opts: Options<'a>) -> Self {
+        Self { opts, failures: 0 }
+    }
+
+    /// Returns the number of failed attestations so far.
+    ///
+    /// This value is suitable for reporting with
+    /// [`hardware::Identity::attestation_failures()`].
+    ///
+    /// [`hardware::Identity::attestation_failures()`]:
+    ///     crate::hardware::Identity::attestation_failures
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Attests each of `devices` in turn, recording the outcome in each
+    /// device's [`Device::status`].
+    ///
+    /// `arena` is reset between devices.
+    pub fn attest_all(
+        &mut self,
+        devices: &mut [Device],
+        arena: &mut dyn Arena,
+    ) {
+        for device in devices {
+            arena.reset();
+            self.attest(device, arena);
+        }
+    }
+
+    /// Attests `device`, recording the outcome in [`Device::status`].
+    ///
+    /// Returns whether the device passed.
+    ///
+    /// `arena` must be large enough to hold [`MAX_CERT_LEN`] bytes for each
+    /// certificate fetched, alongside the responses used to fetch them.
+    pub fn attest(&mut self, device: &mut Device, arena: &dyn Arena) -> bool {
+        device.status = match self.try_attest(device, arena) {
+            Ok(()) => Status::Passed,
+            Err(e) => {
+                self.failures = self.failures.saturating_add(1);
+                Status::Failed(e)
+            }
+        };
+        matches!(device.status, Status::Passed)
+    }
+
+    fn try_attest(
+        &mut self,
+        device: &Device,
+        arena: &dyn Arena,
+    ) -> Result<(), Error> {
+        use protocol::challenge::*;
+        use protocol::get_digests::*;
+
+        let (digests, _) = self.request::<GetDigests>(
+            device.addr,
+            GetDigestsRequest {
+                slot: device.slot,
+                key_exchange: KeyExchangeAlgo::None,
+            },
+            arena,
+        )?;
+        let root = digests.digests.first().ok_or(Error::Protocol)?;
+        if !device.trusted_roots.contains(root) {
+            return Err(Error::UntrustedRoot);
+        }
+        if digests.digests.len() > MAX_CHAIN_LEN {
+            return Err(Error::Cert(cert::Error::ChainTooLong));
+        }
+
+        let mut raw_chain = ArrayVec::<&[u8], MAX_CHAIN_LEN>::new();
+        for (i, digest) in digests.digests.iter().enumerate() {
+            let raw: &[u8] = match self.opts.cache.get(digest) {
+                Some(cached) => {
+                    let raw = arena.alloc_slice::<u8>(cached.len())?;
+                    raw.copy_from_slice(cached);
+                    raw
+                }
+                None => self.fetch_cert(device, i as u8, digest, arena)?,
+            };
+            raw_chain.push(raw);
+        }
+
+        let chain = cert::SimpleChain::<MAX_CHAIN_LEN>::parse(
+            &raw_chain,
+            device.cert_format,
+            self.opts.ciphers,
+            None,
+        )?;
+        let leaf = chain.cert(0, raw_chain.len() - 1).ok_or(Error::Protocol)?;
+
+        let nonce = arena.alloc::<[u8; 32]>()?;
+        self.opts.csrng.fill(nonce)?;
+        let (resp, req_bytes) = self.request::<Challenge>(
+            device.addr,
+            ChallengeRequest {
+                slot: device.slot,
+                nonce,
+            },
+            arena,
+        )?;
+        if resp.tbs.slot != device.slot {
+            return Err(Error::Protocol);
+        }
+
+        let key = leaf.subject_key();
+        let algo = match key {
+            sig::PublicKeyParams::Rsa { .. } => sig::Algo::RsaPkcs1Sha256,
+            sig::PublicKeyParams::Ecc {
+                curve: sig::Curve::NistP256,
+                ..
+            } => sig::Algo::EcdsaDerP256,
+            sig::PublicKeyParams::Ecc {
+                curve: sig::Curve::NistP384,
+                ..
+            } => sig::Algo::EcdsaDerP384,
+        };
+        let verifier = self
+            .opts
+            .ciphers
+            .verifier(algo, key)
+            .ok_or(Error::Signature(sig::Error::Unspecified))?;
+        resp.tbs.as_iovec_with(|[a, b, c, d]| {
+            verifier.verify(&[req_bytes, a, b, c, d], resp.signature)
+        })?;
+
+        if !device.expected_pmr0.is_empty()
+            && !device.expected_pmr0.contains(&resp.tbs.pmr0)
+        {
+            return Err(Error::Pmr0Mismatch);
+        }
+        Ok(())
+    }
+
+    /// Fetches the `index`th certificate of `device`'s chain, in chunks, and
+    /// caches it if it matches `digest`.
+    fn fetch_cert<'r>(
+        &mut self,
+        device: &Device,
+        index: u8,
+        digest: &Digest,
+        arena: &'r dyn Arena,
+    ) -> Result<&'r [u8], Error> {
+        use protocol::get_cert::*;
+
+        let buf = arena.alloc_slice::<u8>(MAX_CERT_LEN)?;
+        let mut len = 0;
+        loop {
+            let (resp, _) = self.request::<GetCert>(
+                device.addr,
+                GetCertRequest {
+                    slot: device.slot,
+                    cert_number: index,
+                    offset: len as u16,
+                    len: CERT_CHUNK_LEN,
+                },
+                arena,
+            )?;
+            if resp.slot != device.slot || resp.cert_number != index {
+                return Err(Error::Protocol);
+            }
+
+            let chunk = resp.data;
+            buf.get_mut(len..len + chunk.len())
+                .ok_or(Error::BadCert)?
+                .copy_from_slice(chunk);
+            len += chunk.len();
+            if chunk.len() < CERT_CHUNK_LEN as usize {
+                break;
+            }
+        }
+
+        let raw = &buf[..len];
+        let mut actual = Digest::default();
+        self.opts.hasher.contiguous_hash(
+            hash::Algo::Sha256,
+            raw,
+            &mut actual,
+        )?;
+        if &actual != digest {
+            return Err(Error::BadCert);
+        }
+        self.opts.cache.insert(digest, raw);
+        Ok(raw)
+    }
+
+    /// Sends `req` to the device at `addr` and waits for its response.
+    ///
+    /// Returns the response and the serialized request.
+    fn request<'r, C>(
+        &mut self,
+        addr: u8,
+        req: C::Req,
+        arena: &'r dyn Arena,
+    ) -> Result<(C::Resp, &'r [u8]), Error>
+    where
+        C: protocol::Command<'r, CommandType = CommandType>,
+    {
+        let req_type = <C::Req as Message>::TYPE;
+        let timeout = match req_type {
+            CommandType::Challenge => self.opts.timeouts.crypto,
+            _ => self.opts.timeouts.regular,
+        };
+
+        let buf = arena.alloc_slice::<u8>(64)?;
+        let mut cursor = Cursor::new(buf);
+        req.to_wire(&mut cursor)?;
+        let req_bytes = cursor.take_consumed_bytes();
+
+        let port = &mut *self.opts.port;
+        port.send(
+            addr,
+            CerberusHeader {
+                command: req_type,
+                crypt: false,
+            },
+            req_bytes,
+        )?;
+        port.wait_for_response(timeout.as_millis() as usize)?;
+
+        let resp = port.receive_response()?;
+        if resp.header()?.command != <C::Resp as Message>::TYPE {
+            return Err(Error::Protocol);
+        }
+        let payload = resp.payload()?;
+        let buf = arena.alloc_slice::<u8>(payload.remaining_data())?;
+        payload.read_bytes(buf).map_err(net::Error::from)?;
+
+        let mut buf = &*buf;
+        let resp = C::Resp::from_wire(&mut buf, arena)?;
+        if !buf.is_empty() {
+            return Err(Error::Protocol);
+        }
+        Ok((resp, req_bytes))
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    use super::*;
+
+    use core::time::Duration;
+
+    use testutil::data::keys;
+    use testutil::data::x509;
+
+    use crate::crypto::ring;
+    use crate::hardware;
+    use crate::io;
+    use crate::mem::BumpArena;
+    use crate::net::device::DeviceResponse;
+    use crate::net::host::InMemHost;
+    use crate::pmr;
+    use crate::pmr::Pmrs as _;
+    use crate::protocol::capabilities;
+    use crate::protocol::device_id;
+    use crate::server::ac_rot;
+
+    struct Identity;
+    impl hardware::Identity for Identity {
+        fn firmware_version(&self) -> &[u8; 32] {
+            b"ac-rot firmware, version 1.0.0!!"
+        }
+        fn unique_device_identity(&self) -> &[u8] {
+            b"ac-rot"
+        }
+    }
+
+    /// A [`DevicePort`] that feeds requests straight into an [`ac_rot::AcRot`].
+    struct Loopback<'a> {
+        rot: ac_rot::AcRot<'a>,
+        sent: Vec<CommandType>,
+        header: Option<CerberusHeader>,
+        resp: Vec<u8>,
+        cursor: usize,
+    }
+
+    impl DevicePort<CerberusHeader> for Loopback<'_> {
+        fn send(
+            &mut self,
+            _: u8,
+            header: CerberusHeader,
+            msg: &[u8],
+        ) -> Result<(), net::Error> {
+            self.sent.push(header.command);
+
+            let mut out = [0; 4096];
+            let mut host = InMemHost::new(&mut out);
+            host.request(header, msg);
+            let mut arena = [0; 4096];
+            self.rot
+                .process_request(&mut host, &BumpArena::new(&mut arena))
+                .map_err(|_| net::Error::Disconnected)?;
+
+            let (header, resp) = host.response().unwrap();
+            self.header = Some(header);
+            self.resp = resp.to_vec();
+            self.cursor = 0;
+            Ok(())
+        }
+
+        fn wait_for_response(&mut self, _: usize) -> Result<(), net::Error> {
+            Ok(())
+        }
+
+        fn receive_response(
+            &mut self,
+        ) -> Result<&mut dyn DeviceResponse<CerberusHeader>, net::Error>
+        {
+            Ok(self)
+        }
+    }
+
+    impl DeviceResponse<CerberusHeader> for Loopback<'_> {
+        fn header(&self) -> Result<CerberusHeader, net::Error> {
+            self.header.ok_or(net::Error::OutOfOrder)
+        }
+
+        fn payload(&mut self) -> Result<&mut dyn io::Read, net::Error> {
+            Ok(self)
+        }
+    }
+
+    impl io::Read for Loopback<'_> {
+        fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), io::Error> {
+            let end = self.cursor + out.len();
+            let data = self
+                .resp
+                .get(self.cursor..end)
+                .ok_or(io::Error::BufferExhausted)?;
+            out.copy_from_slice(data);
+            self.cursor = end;
+            Ok(())
+        }
+
+        fn remaining_data(&self) -> usize {
+            self.resp.len() - self.cursor
+        }
+    }
+
+    /// Builds an AC-RoT with the `CHAIN1..3` test chain and a single PMR,
+    /// and passes a loopback port to it into `f`, along with the digest of
+    /// its root certificate and its PMR0.
+    fn with_device(f: impl FnOnce(&mut Loopback, Digest, &[u8])) {
+        let mut hasher = ring::hash::Engine::new();
+        let mut csrng = ring::csrng::Csrng::new();
+        let mut ciphers = ring::sig::Ciphers::new();
+        let mut signer =
+            ring::rsa::Sign256::from_pkcs8(keys::KEY3_RSA_KEYPAIR).unwrap();
+        let mut chain = cert::SimpleChain::<3>::parse(
+            &[x509::CHAIN1, x509::CHAIN2, x509::CHAIN3],
+            CertFormat::RiotX509,
+            &mut ciphers,
+            Some(&mut signer),
+        )
+        .unwrap();
+        let mut pmrs = pmr::SimplePmrs::<1, 4>::new();
+        pmrs.extend(0, 0, &[0x55; 32], &mut hasher).unwrap();
+        let pmr0 = *pmrs.value(0).unwrap();
+
+        let mut root = [0; 32];
+        hasher
+            .contiguous_hash(hash::Algo::Sha256, x509::CHAIN1, &mut root)
+            .unwrap();
+
+        let mut port = Loopback {
+            rot: ac_rot::AcRot::new(ac_rot::Options {
+                identity: &Identity,
+                hasher: &mut hasher,
+                ciphers: &ciphers,
+                csrng: &mut csrng,
+                trust_chain: &mut chain,
+                pmrs: &pmrs,
+                device_id: device_id::DeviceIdentifier {
+                    vendor_id: 1,
+                    device_id: 2,
+                    subsys_vendor_id: 3,
+                    subsys_id: 4,
+                },
+                networking: capabilities::Networking {
+                    max_message_size: 1024,
+                    max_packet_size: 256,
+                    mode: capabilities::RotMode::Active,
+                    roles: capabilities::BusRole::Target.into(),
+                },
+                timeouts: TIMEOUTS,
+            }),
+            sent: Vec::new(),
+            header: None,
+            resp: Vec::new(),
+            cursor: 0,
+        };
+        f(&mut port, root, &pmr0);
+    }
+
+    const TIMEOUTS: Timeouts = Timeouts {
+        regular: Duration::from_millis(30),
+        crypto: Duration::from_millis(200),
+    };
+
+    fn device<'a>(roots: &'a [Digest], pmr0: &'a [&'a [u8]]) -> Device<'a> {
+        Device {
+            addr: 0x42,
+            slot: 0,
+            cert_format: CertFormat::RiotX509,
+            trusted_roots: roots,
+            expected_pmr0: pmr0,
+            status: Status::Unattested,
+        }
+    }
+
+    #[test]
+    fn attest() {
+        with_device(|port, root, pmr0| {
+            let mut hasher = ring::hash::Engine::new();
+            let mut ciphers = ring::sig::Ciphers::new();
+            let mut csrng = ring::csrng::Csrng::new();
+            let mut storage = [0; 4096];
+            let mut cache = SimpleCertCache::<4>::new(&mut storage);
+            let mut attestor = Attestor::new(Options {
+                port,
+                hasher: &mut hasher,
+                ciphers: &mut ciphers,
+                csrng: &mut csrng,
+                cache: &mut cache,
+                timeouts: TIMEOUTS,
+            });
+
+            let roots = [root];
+            let pmr0s = [pmr0];
+            let mut devices = [device(&roots, &pmr0s)];
+            let mut arena = BumpArena::new([0; 16384]);
+            attestor.attest_all(&mut devices, &mut arena);
+            assert!(matches!(devices[0].status, Status::Passed));
+            assert_eq!(attestor.failures(), 0);
+
+            // The second time around, every certificate comes from the cache.
+            port.sent.clear();
+            let mut attestor = Attestor::new(Options {
+                port,
+                hasher: &mut hasher,
+                ciphers: &mut ciphers,
+                csrng: &mut csrng,
+                cache: &mut cache,
+                timeouts: TIMEOUTS,
+            });
+            arena.reset();
+            assert!(attestor.attest(&mut devices[0], &arena));
+            assert_eq!(
+                port.sent,
+                [CommandType::GetDigests, CommandType::Challenge]
+            );
+        })
+    }
+
+    #[test]
+    fn attestation_failures() {
+        with_device(|port, root, pmr0| {
+            let mut hasher = ring::hash::Engine::new();
+            let mut ciphers = ring::sig::Ciphers::new();
+            let mut csrng = ring::csrng::Csrng::new();
+            let mut storage = [0; 4096];
+            let mut cache = SimpleCertCache::<4>::new(&mut storage);
+            let mut attestor = Attestor::new(Options {
+                port,
+                hasher: &mut hasher,
+                ciphers: &mut ciphers,
+                csrng: &mut csrng,
+                cache: &mut cache,
+                timeouts: TIMEOUTS,
+            });
+            let mut arena = BumpArena::new([0; 16384]);
+
+            let untrusted = [[0xaa; 32]];
+            let pmr0s = [pmr0];
+            let mut dev = device(&untrusted, &pmr0s);
+            assert!(!attestor.attest(&mut dev, &arena));
+            assert!(matches!(dev.status, Status::Failed(Error::UntrustedRoot)));
+
+            arena.reset();
+            let roots = [root];
+            let wrong_pmr0: [&[u8]; 1] = [&[0x77; 32]];
+            let mut dev = device(&roots, &wrong_pmr0);
+            assert!(!attestor.attest(&mut dev, &arena));
+            assert!(matches!(dev.status, Status::Failed(Error::Pmr0Mismatch)));
+
+            assert_eq!(attestor.failures(), 2);
+        })
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Authorization tokens.
+//!
+//! Some operations, such as [`protocol::ResetConfig`], are too destructive to
+//! perform on the say-so of whoever is on the other end of the bus. Instead,
+//! the device issues a single-use token, which must be signed by an authority
+//! it trusts before the operation is performed.
+//!
+//! A token has the following layout, expressed as a pseudo-Rust struct:
+//! ```ignore
+//! struct Token {
+//!     // An operation-specific value, such as the `ResetConfig` reset type.
+//!     op: u8,
+//!     // A fresh random nonce.
+//!     nonce: [u8; 32],
+//! }
+//! ```
+//!
+//! A signed token is the token followed by the authority's signature over it.
+//! Each token may only be redeemed once, and issuing a new token invalidates
+//! the previous one.
+
+use crate::crypto::csrng;
+use crate::crypto::sig;
+
+#[cfg(doc)]
+use crate::protocol;
+
+/// The length of an unsigned authorization token.
+pub const TOKEN_LEN: usize = 33;
+
+/// An unsigned authorization token.
+pub type Token = [u8; TOKEN_LEN];
+
+/// An error returned by an authorization operation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// Indicates that there was no outstanding token to redeem.
+    NoToken,
+    /// Indicates that a signed token did not match the outstanding token.
+    BadToken,
+    /// Indicates that the signature on a token did not check out.
+    Signature(sig::Error),
+    /// Indicates that a nonce could not be generated.
+    Csrng(csrng::Error),
+}
+
+impl From<sig::Error> for Error {
+    fn from(e: sig::Error) -> Self {
+        Self::Signature(e)
+    }
+}
+
+impl From<csrng::Error> for Error {
+    fn from(e: csrng::Error) -> Self {
+        Self::Csrng(e)
+    }
+}
+
+/// Issues and redeems authorization tokens.
+///
+/// See the [module documentation][self] for more information.
+#[derive(Default)]
+pub struct Authorizer {
+    token: Option<Token>,
+}
+
+impl Authorizer {
+    /// Creates a new `Authorizer` with no outstanding token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issues a new token for `op`, invalidating any outstanding token.
+    pub fn issue(
+        &mut self,
+        op: u8,
+        csrng: &mut dyn csrng::Csrng,
+    ) -> Result<&Token, Error> {
+        self.token = None;
+
+        let mut token = [0; TOKEN_LEN];
+        token[0] = op;
+        csrng.fill(&mut token[1..])?;
+        Ok(self.token.get_or_insert(token))
+    }
+
+    /// Redeems `signed`, a signed token for `op`.
+    ///
+    /// `authority` checks the signature. The outstanding token is consumed
+    /// regardless of whether it is redeemed successfully.
+    pub fn redeem(
+        &mut self,
+        op: u8,
+        signed: &[u8],
+        authority: &mut dyn sig::Verify,
+    ) -> Result<(), Error> {
+        let expected = self.token.take().ok_or(Error::NoToken)?;
+        if signed.len() < TOKEN_LEN || expected[0] != op {
+            return Err(Error::BadToken);
+        }
+
+        let (token, signature) = signed.split_at(TOKEN_LEN);
+        if token != expected {
+            return Err(Error::BadToken);
+        }
+        authority.verify(&[token], signature)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use testutil::data::keys;
+
+    use crate::crypto::ring;
+    use crate::crypto::sig::Sign as _;
+
+    fn sign(token: &[u8], signer: &mut ring::rsa::Sign256) -> Vec<u8> {
+        let mut sig = vec![0; signer.sig_bytes()];
+        let len = signer.sign(&[token], &mut sig).unwrap();
+        [token, &sig[..len]].concat()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn issue_and_redeem() {
+        let mut csrng = ring::csrng::Csrng::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut auth = Authorizer::new();
+
+        let token = *auth.issue(3, &mut csrng).unwrap();
+        assert_eq!(token[0], 3);
+        let signed = sign(&token, &mut signer);
+        auth.redeem(3, &signed, &mut rsa).unwrap();
+
+        // Tokens are single-use.
+        assert_eq!(auth.redeem(3, &signed, &mut rsa), Err(Error::NoToken));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn bad_tokens() {
+        let mut csrng = ring::csrng::Csrng::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let (_, mut other_signer) =
+            ring::rsa::from_keypair(keys::KEY2_RSA_KEYPAIR);
+        let mut auth = Authorizer::new();
+
+        // A token for the wrong operation.
+        let token = *auth.issue(1, &mut csrng).unwrap();
+        let signed = sign(&token, &mut signer);
+        assert_eq!(auth.redeem(2, &signed, &mut rsa), Err(Error::BadToken));
+
+        // A stale token.
+        let stale = *auth.issue(1, &mut csrng).unwrap();
+        auth.issue(1, &mut csrng).unwrap();
+        let signed = sign(&stale, &mut signer);
+        assert_eq!(auth.redeem(1, &signed, &mut rsa), Err(Error::BadToken));
+
+        // A token signed by someone else.
+        let token = *auth.issue(1, &mut csrng).unwrap();
+        let signed = sign(&token, &mut other_signer);
+        assert!(matches!(
+            auth.redeem(1, &signed, &mut rsa),
+            Err(Error::Signature(_))
+        ));
+
+        // A truncated token.
+        let token = *auth.issue(1, &mut csrng).unwrap();
+        assert_eq!(auth.redeem(1, &token[..8], &mut rsa), Err(Error::BadToken));
+    }
+}
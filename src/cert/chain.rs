@@ -5,14 +5,20 @@
 //! Cert chains.
 
 use core::fmt;
+use core::marker::PhantomData;
 use core::num::NonZeroUsize;
+use core::ops::Range;
+use core::slice;
 
 use arrayvec::ArrayVec;
 
+use crate::cert::x509;
+use crate::cert::x509::csr;
 use crate::cert::Cert;
 use crate::cert::CertFormat;
 use crate::cert::Error;
 use crate::crypto::sig;
+use crate::io;
 
 /// A trust chain collection.
 ///
@@ -42,9 +48,121 @@ pub trait TrustChain {
     ///
     /// Returns `None` if no such chain is present.
     fn signer(&mut self, slot: u8) -> Option<&mut dyn sig::Sign>;
+
+    /// Returns a handle for provisioning this chain at runtime, if it
+    /// supports it.
+    ///
+    /// By default, chains cannot be provisioned.
+    fn provisioner(&mut self) -> Option<&mut dyn Provision> {
+        None
+    }
 }
 impl dyn TrustChain {} // Ensure object-safe.
 
+/// The role of a certificate in a trust chain.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CertRole {
+    /// The self-signed root certificate.
+    Root,
+    /// An intermediate certificate, between the root and the leaf.
+    Intermediate,
+    /// The leaf certificate for the device's DeviceID key.
+    DeviceId,
+}
+
+/// The provisioning state of a trust chain.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ProvisionState {
+    /// A complete, valid chain has been provisioned.
+    Provisioned,
+    /// A valid chain has not been provisioned yet.
+    NotProvisioned,
+}
+
+/// A trust chain that can be provisioned at runtime, such as during
+/// manufacturing.
+///
+/// Provisioning happens in two steps: first, a certificate signing request
+/// for the device's DeviceID key is exported, and then, once it has been
+/// signed, the resulting certificate is imported, along with the rest of the
+/// chain it belongs to.
+pub trait Provision {
+    /// Returns an upper bound on the length of the CSR for the `index`th key.
+    ///
+    /// Returns `None` if there is no such key.
+    fn csr_len(&self, index: u8) -> Option<usize>;
+
+    /// Writes a DER-encoded PKCS#10 certificate signing request for the
+    /// `index`th key to `out`, returning the number of bytes written.
+    ///
+    /// Index `0` refers to the DeviceID key.
+    fn export_csr(&mut self, index: u8, out: &mut [u8])
+        -> Result<usize, Error>;
+
+    /// Imports `cert` into the chain, in the given `role`.
+    ///
+    /// Once the chain is complete, it is validated using `ciphers`; the
+    /// result of that validation is reported by [`Provision::state()`].
+    ///
+    /// If the validated chain's DeviceID certificate is not for the device's
+    /// own DeviceID key, or that key is not suitable for the DeviceID key's
+    /// signature algorithm, that certificate is discarded and
+    /// [`Error::WrongKey`] is returned.
+    fn import(
+        &mut self,
+        role: CertRole,
+        cert: &[u8],
+        ciphers: &mut dyn sig::Ciphers,
+    ) -> Result<(), Error>;
+
+    /// Returns the current provisioning state.
+    fn state(&self) -> ProvisionState;
+}
+impl dyn Provision {} // Ensure object-safe.
+
+/// Parses and verifies `raw_chain`, which starts at the root certificate.
+fn parse_chain<'cert, const LEN: usize>(
+    raw_chain: &[&'cert [u8]],
+    format: CertFormat,
+    ciphers: &mut (impl sig::Ciphers + ?Sized),
+) -> Result<ArrayVec<Cert<'cert>, LEN>, Error> {
+    if raw_chain.len() > LEN {
+        return Err(Error::ChainTooLong);
+    }
+
+    let mut chain = ArrayVec::new();
+    for (i, &raw_cert) in raw_chain.iter().enumerate() {
+        let prev = chain.last();
+        let key = prev.map(|cert: &Cert| cert.subject_key());
+        let cert = Cert::parse(raw_cert, format, key, ciphers)?;
+
+        let prev = prev.unwrap_or(&cert);
+        if prev.subject() != cert.issuer() {
+            return Err(Error::BadChainLink);
+        }
+        if !prev.supports_cert_signing() {
+            return Err(Error::BadChainLink);
+        }
+
+        // None is also ok; it means the format (e.g. CWT) does not support
+        // a CA bit.
+        if prev.is_ca_cert() == Some(false) {
+            return Err(Error::BadChainLink);
+        }
+
+        // raw_chain.len() - i is the number of certificates that follow
+        // `cert`; the path length constraint for `prev` is the number of
+        // certs that follow it, except the leaf; these numbers are the
+        // same.
+        if !prev.is_within_path_len_constraint(raw_chain.len() - i) {
+            return Err(Error::BadChainLink);
+        }
+
+        chain.push(cert);
+    }
+    Ok(chain)
+}
+
 /// A simple trust chain with only one slot.
 pub struct SimpleChain<'cert, const LEN: usize> {
     chain: ArrayVec<Cert<'cert>, LEN>,
@@ -63,49 +181,168 @@ impl<'cert, const LEN: usize> SimpleChain<'cert, LEN> {
     pub fn parse(
         raw_chain: &[&'cert [u8]],
         format: CertFormat,
-        ciphers: &mut impl sig::Ciphers,
+        ciphers: &mut (impl sig::Ciphers + ?Sized),
         signer: Option<&'cert mut dyn sig::Sign>,
     ) -> Result<Self, Error> {
-        if raw_chain.len() > LEN {
-            return Err(Error::ChainTooLong);
+        let chain = parse_chain(raw_chain, format, ciphers)?;
+        Ok(Self { chain, signer })
+    }
+}
+
+impl<const LEN: usize> TrustChain for SimpleChain<'_, LEN> {
+    fn cert(&self, slot: u8, index: usize) -> Option<&Cert> {
+        if slot != 0 {
+            return None;
+        }
+        self.chain.get(index)
+    }
+
+    fn chain_len(&self, slot: u8) -> Option<NonZeroUsize> {
+        if slot != 0 {
+            return None;
         }
+        NonZeroUsize::new(self.chain.len())
+    }
 
-        let mut chain = ArrayVec::new();
-        for (i, &raw_cert) in raw_chain.iter().enumerate() {
-            let prev = chain.last();
-            let key = prev.map(|cert: &Cert| cert.subject_key());
-            let cert = Cert::parse(raw_cert, format, key, ciphers)?;
+    fn signer(&mut self, slot: u8) -> Option<&mut dyn sig::Sign> {
+        match (&mut self.signer, slot) {
+            (Some(signer), 0) => Some(*signer),
+            _ => None,
+        }
+    }
+}
 
-            let prev = prev.unwrap_or(&cert);
-            if prev.subject() != cert.issuer() {
-                return Err(Error::BadChainLink);
-            }
-            if !prev.supports_cert_signing() {
-                return Err(Error::BadChainLink);
-            }
+/// A single-slot trust chain that is provisioned at runtime.
+///
+/// A `WritableChain` starts out empty. Certificates imported through
+/// [`Provision::import()`] are copied into a caller-provided storage buffer;
+/// once both a root and a DeviceID certificate are present, the whole chain
+/// is re-parsed and validated with [`Cert::parse()`]. Until that succeeds,
+/// the chain reports itself as having no slots.
+///
+/// Replacing a certificate, such as by importing a new root or DeviceID
+/// certificate, reclaims the storage used by the certificates it replaces,
+/// so a chain may be re-provisioned any number of times.
+pub struct WritableChain<'cert, const LEN: usize> {
+    format: CertFormat,
+    subject: &'cert [u8],
+    spki: &'cert [u8],
+    algo: sig::Algo,
+    signer: &'cert mut dyn sig::Sign,
+
+    root: Option<Range<usize>>,
+    intermediates: ArrayVec<Range<usize>, LEN>,
+    device_id: Option<Range<usize>>,
+    chain: ArrayVec<Cert<'cert>, LEN>,
 
-            // None is also ok; it means the format (e.g. CWT) does not support
-            // a CA bit.
-            if prev.is_ca_cert() == Some(false) {
-                return Err(Error::BadChainLink);
-            }
+    // `chain` borrows from `storage`, which `import()` rearranges, so it is
+    // kept as a raw pointer; see `stored()` and `storage_mut()`.
+    storage: *mut u8,
+    storage_len: usize,
+    _ph: PhantomData<&'cert mut [u8]>,
+}
 
-            // raw_chain.len() - i is the number of certificates that follow
-            // `cert`; the path length constraint for `prev` is the number of
-            // certs that follow it, except the leaf; these numbers are the
-            // same.
-            if !prev.is_within_path_len_constraint(raw_chain.len() - i) {
-                return Err(Error::BadChainLink);
-            }
+impl<const LEN: usize> fmt::Debug for WritableChain<'_, LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.chain.fmt(f)
+    }
+}
 
-            chain.push(cert);
+impl<'cert, const LEN: usize> WritableChain<'cert, LEN> {
+    /// Creates a new, empty `WritableChain`.
+    ///
+    /// `subject` and `spki` are the DER-encoded X.509 `Name` and
+    /// `SubjectPublicKeyInfo` to request in the DeviceID CSR, and `signer` is
+    /// the DeviceID key, whose signatures are described by `algo`.
+    /// Imported certificates are stored in `storage`.
+    pub fn new(
+        format: CertFormat,
+        subject: &'cert [u8],
+        spki: &'cert [u8],
+        algo: sig::Algo,
+        signer: &'cert mut dyn sig::Sign,
+        storage: &'cert mut [u8],
+    ) -> Self {
+        Self {
+            format,
+            subject,
+            spki,
+            algo,
+            signer,
+            root: None,
+            intermediates: ArrayVec::new(),
+            device_id: None,
+            chain: ArrayVec::new(),
+            storage: storage.as_mut_ptr(),
+            storage_len: storage.len(),
+            _ph: PhantomData,
         }
+    }
 
-        Ok(Self { chain, signer })
+    /// Returns the certificate stored at `range`.
+    fn stored(&self, range: Range<usize>) -> &'cert [u8] {
+        assert!(range.start <= range.end && range.end <= self.storage_len);
+        // SAFETY: `storage` came from a `&'cert mut [u8]` and `range` is in
+        // bounds. Slices returned by this function are only kept in `chain`,
+        // whose certificates are only ever handed out for the duration of a
+        // borrow of `self`; `storage_mut()` requires `chain` to be empty, so
+        // no such slice is live while `storage` is written to.
+        #[allow(unsafe_code)]
+        unsafe {
+            slice::from_raw_parts(self.storage.add(range.start), range.len())
+        }
+    }
+
+    /// Returns the storage buffer for writing.
+    ///
+    /// Panics if `chain`, which may borrow from it, is not empty.
+    fn storage_mut(&mut self) -> &mut [u8] {
+        assert!(self.chain.is_empty());
+        // SAFETY: See `stored()`.
+        #[allow(unsafe_code)]
+        unsafe {
+            slice::from_raw_parts_mut(self.storage, self.storage_len)
+        }
+    }
+
+    /// Moves all imported certificates to the start of storage, returning the
+    /// number of bytes they take up.
+    fn compact(&mut self) -> usize {
+        let mut used = 0;
+        loop {
+            // Certificates are moved in order of their position in storage;
+            // ones that have already been moved start before `used`.
+            let next = self
+                .root
+                .iter()
+                .chain(&self.intermediates)
+                .chain(&self.device_id)
+                .filter(|r| r.start >= used)
+                .min_by_key(|r| r.start)
+                .cloned();
+            let range = match next {
+                Some(range) => range,
+                None => return used,
+            };
+
+            let len = range.len();
+            self.storage_mut().copy_within(range.clone(), used);
+            for r in self
+                .root
+                .iter_mut()
+                .chain(&mut self.intermediates)
+                .chain(&mut self.device_id)
+            {
+                if *r == range {
+                    *r = used..used + len;
+                }
+            }
+            used += len;
+        }
     }
 }
 
-impl<const LEN: usize> TrustChain for SimpleChain<'_, LEN> {
+impl<const LEN: usize> TrustChain for WritableChain<'_, LEN> {
     fn cert(&self, slot: u8, index: usize) -> Option<&Cert> {
         if slot != 0 {
             return None;
@@ -121,9 +358,125 @@ impl<const LEN: usize> TrustChain for SimpleChain<'_, LEN> {
     }
 
     fn signer(&mut self, slot: u8) -> Option<&mut dyn sig::Sign> {
-        match (&mut self.signer, slot) {
-            (Some(signer), 0) => Some(*signer),
-            _ => None,
+        if slot != 0 || self.chain.is_empty() {
+            return None;
+        }
+        Some(self.signer)
+    }
+
+    fn provisioner(&mut self) -> Option<&mut dyn Provision> {
+        Some(self)
+    }
+}
+
+impl<const LEN: usize> Provision for WritableChain<'_, LEN> {
+    fn csr_len(&self, index: u8) -> Option<usize> {
+        if index != 0 {
+            return None;
+        }
+        Some(csr::max_len(self.subject, self.spki, self.signer))
+    }
+
+    fn export_csr(
+        &mut self,
+        index: u8,
+        out: &mut [u8],
+    ) -> Result<usize, Error> {
+        if index != 0 {
+            return Err(Error::Unsupported);
+        }
+        csr::encode(self.subject, self.spki, self.algo, self.signer, out)
+    }
+
+    fn import(
+        &mut self,
+        role: CertRole,
+        cert: &[u8],
+        ciphers: &mut dyn sig::Ciphers,
+    ) -> Result<(), Error> {
+        if cert.is_empty() {
+            return Err(Error::BadEncoding);
+        }
+        // The root and the DeviceID cert always take up two slots.
+        if role == CertRole::Intermediate && self.intermediates.len() + 2 >= LEN
+        {
+            return Err(Error::ChainTooLong);
+        }
+
+        // Make sure that `cert` fits once the certificates it replaces are
+        // gone, before touching anything.
+        let root_len = self.root.as_ref().map_or(0, Range::len);
+        let device_id_len = self.device_id.as_ref().map_or(0, Range::len);
+        let intermediates_len =
+            self.intermediates.iter().map(Range::len).sum::<usize>();
+        let kept_len = match role {
+            // A new root starts a new chain.
+            CertRole::Root => device_id_len,
+            CertRole::Intermediate => {
+                root_len + intermediates_len + device_id_len
+            }
+            CertRole::DeviceId => root_len + intermediates_len,
+        };
+        if kept_len + cert.len() > self.storage_len {
+            return Err(Error::Io(io::Error::BufferExhausted));
+        }
+
+        self.chain.clear();
+        match role {
+            CertRole::Root => {
+                self.root = None;
+                self.intermediates.clear();
+            }
+            CertRole::Intermediate => {}
+            CertRole::DeviceId => self.device_id = None,
+        }
+        let start = self.compact();
+        let range = start..start + cert.len();
+        self.storage_mut()[range.clone()].copy_from_slice(cert);
+        match role {
+            CertRole::Root => self.root = Some(range),
+            CertRole::Intermediate => self.intermediates.push(range),
+            CertRole::DeviceId => self.device_id = Some(range),
+        }
+
+        let (root, device_id) = match (&self.root, &self.device_id) {
+            (Some(root), Some(device_id)) => (root.clone(), device_id.clone()),
+            _ => return Ok(()),
+        };
+
+        let mut raw_chain = ArrayVec::<&[u8], LEN>::new();
+        raw_chain.push(self.stored(root));
+        for i in 0..self.intermediates.len() {
+            raw_chain.push(self.stored(self.intermediates[i].clone()));
+        }
+        raw_chain.push(self.stored(device_id));
+
+        // A chain that fails to validate is not an error for this
+        // particular import, since the missing piece may simply not have
+        // been imported yet.
+        let chain = match parse_chain(&raw_chain, self.format, ciphers) {
+            Ok(chain) => chain,
+            Err(_) => return Ok(()),
+        };
+
+        // Never serve a certificate for someone else's key, or for a key
+        // that doesn't match the DeviceID key's algorithm.
+        let key = x509::parse_public_key(self.spki)?;
+        if !key.is_params_for(self.algo)
+            || chain.last().map(Cert::subject_key) != Some(&key)
+        {
+            self.device_id = None;
+            return Err(Error::WrongKey);
+        }
+        self.chain = chain;
+        Ok(())
+    }
+
+    fn state(&self) -> ProvisionState {
+        if self.chain.is_empty() {
+            ProvisionState::NotProvisioned
+        } else {
+            ProvisionState::Provisioned
         }
     }
 }
@@ -261,4 +614,225 @@ mod test {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn x509_provision() {
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY3_RSA_KEYPAIR);
+        let mut storage = [0; 4096];
+        let mut chain = WritableChain::<3>::new(
+            CertFormat::RiotX509,
+            &[0x30, 0x00],
+            keys::KEY3_RSA_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut storage,
+        );
+        let mut ciphers = ring::sig::Ciphers::new();
+
+        assert!(chain.chain_len(0).is_none());
+        assert!(chain.signer(0).is_none());
+
+        let p = chain.provisioner().unwrap();
+        assert_eq!(p.state(), ProvisionState::NotProvisioned);
+
+        let mut csr = vec![0; p.csr_len(0).unwrap()];
+        assert!(p.export_csr(0, &mut csr).unwrap() > 0);
+        assert!(p.csr_len(1).is_none());
+        assert!(matches!(p.export_csr(1, &mut csr), Err(Error::Unsupported)));
+
+        p.import(CertRole::Root, x509::CHAIN1, &mut ciphers)
+            .unwrap();
+        p.import(CertRole::DeviceId, x509::CHAIN3, &mut ciphers)
+            .unwrap();
+        // Missing the intermediate, so this chain doesn't validate yet.
+        assert_eq!(p.state(), ProvisionState::NotProvisioned);
+
+        p.import(CertRole::Intermediate, x509::CHAIN2, &mut ciphers)
+            .unwrap();
+        assert_eq!(p.state(), ProvisionState::Provisioned);
+        assert!(matches!(
+            p.import(CertRole::Intermediate, x509::CHAIN2, &mut ciphers),
+            Err(Error::ChainTooLong)
+        ));
+
+        assert_eq!(chain.chain_len(0), NonZeroUsize::new(3));
+        assert_eq!(chain.cert(0, 2).unwrap().raw(), x509::CHAIN3);
+        assert!(chain.signer(0).is_some());
+    }
+
+    #[test]
+    fn x509_provision_bad_chain() {
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY3_RSA_KEYPAIR);
+        let mut storage = [0; 4096];
+        let mut chain = WritableChain::<3>::new(
+            CertFormat::RiotX509,
+            &[0x30, 0x00],
+            keys::KEY3_RSA_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut storage,
+        );
+        let mut ciphers = ring::sig::Ciphers::new();
+
+        let p = chain.provisioner().unwrap();
+        p.import(CertRole::Root, x509::CHAIN2, &mut ciphers)
+            .unwrap();
+        p.import(CertRole::Intermediate, x509::CHAIN1, &mut ciphers)
+            .unwrap();
+        p.import(CertRole::DeviceId, x509::CHAIN3, &mut ciphers)
+            .unwrap();
+        assert_eq!(p.state(), ProvisionState::NotProvisioned);
+        assert!(chain.chain_len(0).is_none());
+    }
+
+    #[test]
+    fn x509_provision_wrong_key() {
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut storage = [0; 4096];
+        let mut chain = WritableChain::<3>::new(
+            CertFormat::RiotX509,
+            &[0x30, 0x00],
+            keys::KEY1_RSA_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut storage,
+        );
+        let mut ciphers = ring::sig::Ciphers::new();
+
+        // CHAIN3 certifies KEY3, not this device's KEY1.
+        let p = chain.provisioner().unwrap();
+        p.import(CertRole::Root, x509::CHAIN1, &mut ciphers)
+            .unwrap();
+        p.import(CertRole::Intermediate, x509::CHAIN2, &mut ciphers)
+            .unwrap();
+        assert!(matches!(
+            p.import(CertRole::DeviceId, x509::CHAIN3, &mut ciphers),
+            Err(Error::WrongKey)
+        ));
+        assert_eq!(p.state(), ProvisionState::NotProvisioned);
+        assert!(chain.chain_len(0).is_none());
+        assert!(chain.signer(0).is_none());
+    }
+
+    #[test]
+    fn provision_out_of_storage() {
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY3_RSA_KEYPAIR);
+        let mut storage = [0; 16];
+        let mut chain = WritableChain::<3>::new(
+            CertFormat::RiotX509,
+            &[0x30, 0x00],
+            keys::KEY3_RSA_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut storage,
+        );
+
+        let result = chain.provisioner().unwrap().import(
+            CertRole::Root,
+            x509::CHAIN1,
+            &mut ring::sig::Ciphers::new(),
+        );
+        assert!(matches!(result, Err(Error::Io(io::Error::BufferExhausted))));
+    }
+
+    #[test]
+    fn x509_provision_ecdsa() {
+        let mut signer = ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(
+            keys::KEY1_ECDSA_P256_KEYPAIR,
+        )
+        .unwrap();
+        let mut storage = [0; 4096];
+        let mut chain = WritableChain::<3>::new(
+            CertFormat::RiotX509,
+            &[0x30, 0x00],
+            keys::KEY1_ECDSA_P256_PUBLIC,
+            sig::Algo::EcdsaDerP256,
+            &mut signer,
+            &mut storage,
+        );
+        let mut ciphers = ring::sig::Ciphers::new();
+
+        let p = chain.provisioner().unwrap();
+        let mut csr = vec![0; p.csr_len(0).unwrap()];
+        assert!(p.export_csr(0, &mut csr).unwrap() > 0);
+
+        p.import(CertRole::Root, x509::CHAIN1, &mut ciphers)
+            .unwrap();
+        p.import(CertRole::Intermediate, x509::CHAIN2, &mut ciphers)
+            .unwrap();
+        p.import(CertRole::DeviceId, x509::CHAIN3_ECDSA_P256, &mut ciphers)
+            .unwrap();
+        assert_eq!(p.state(), ProvisionState::Provisioned);
+        assert_eq!(chain.cert(0, 2).unwrap().raw(), x509::CHAIN3_ECDSA_P256);
+        assert!(chain.signer(0).is_some());
+    }
+
+    #[test]
+    fn x509_provision_wrong_algo() {
+        let mut signer = ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(
+            keys::KEY1_ECDSA_P256_KEYPAIR,
+        )
+        .unwrap();
+        let mut storage = [0; 4096];
+        let mut chain = WritableChain::<3>::new(
+            CertFormat::RiotX509,
+            &[0x30, 0x00],
+            keys::KEY1_ECDSA_P256_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut storage,
+        );
+        let mut ciphers = ring::sig::Ciphers::new();
+
+        let p = chain.provisioner().unwrap();
+        p.import(CertRole::Root, x509::CHAIN1, &mut ciphers)
+            .unwrap();
+        p.import(CertRole::Intermediate, x509::CHAIN2, &mut ciphers)
+            .unwrap();
+        assert!(matches!(
+            p.import(CertRole::DeviceId, x509::CHAIN3_ECDSA_P256, &mut ciphers),
+            Err(Error::WrongKey)
+        ));
+        assert_eq!(p.state(), ProvisionState::NotProvisioned);
+    }
+
+    #[test]
+    fn x509_reprovision() {
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY3_RSA_KEYPAIR);
+        // Only enough room for one copy of the chain.
+        let mut storage =
+            vec![
+                0;
+                x509::CHAIN1.len() + x509::CHAIN2.len() + x509::CHAIN3.len()
+            ];
+        let mut chain = WritableChain::<3>::new(
+            CertFormat::RiotX509,
+            &[0x30, 0x00],
+            keys::KEY3_RSA_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut storage,
+        );
+        let mut ciphers = ring::sig::Ciphers::new();
+
+        let p = chain.provisioner().unwrap();
+        for _ in 0..3 {
+            p.import(CertRole::DeviceId, x509::CHAIN3, &mut ciphers)
+                .unwrap();
+            p.import(CertRole::Root, x509::CHAIN1, &mut ciphers)
+                .unwrap();
+            p.import(CertRole::Intermediate, x509::CHAIN2, &mut ciphers)
+                .unwrap();
+            assert_eq!(p.state(), ProvisionState::Provisioned);
+
+            // Replacing the DeviceID cert keeps the rest of the chain.
+            p.import(CertRole::DeviceId, x509::CHAIN3, &mut ciphers)
+                .unwrap();
+            assert_eq!(p.state(), ProvisionState::Provisioned);
+        }
+
+        assert_eq!(chain.cert(0, 0).unwrap().raw(), x509::CHAIN1);
+        assert_eq!(chain.cert(0, 1).unwrap().raw(), x509::CHAIN2);
+        assert_eq!(chain.cert(0, 2).unwrap().raw(), x509::CHAIN3);
+    }
 }
@@ -78,7 +78,7 @@ mod label {
 pub fn parse<'cert>(
     cert: &'cert [u8],
     key: Option<&sig::PublicKeyParams<'_>>,
-    ciphers: &mut impl sig::Ciphers,
+    ciphers: &mut (impl sig::Ciphers + ?Sized),
 ) -> Result<Cert<'cert>, Error> {
     let buf = untrusted::Input::from(cert);
     let cose = buf.read_all(Error::BadEncoding, Cose::parse)?;
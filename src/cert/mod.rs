@@ -90,6 +90,13 @@ pub enum Error {
     BadChainLink,
     /// A certificate chain was longer than it was expected to be.
     ChainTooLong,
+    /// Signing a certificate signing request failed.
+    SigningFailed,
+    /// The requested operation is not supported by this chain.
+    Unsupported,
+    /// A certificate was issued for a key other than the one it was expected
+    /// to certify.
+    WrongKey,
 }
 
 impl From<io::Error> for Error {
@@ -117,7 +124,7 @@ impl<'cert> Cert<'cert> {
         cert: &'cert [u8],
         format: CertFormat,
         key: Option<&sig::PublicKeyParams<'_>>,
-        ciphers: &mut impl sig::Ciphers,
+        ciphers: &mut (impl sig::Ciphers + ?Sized),
     ) -> Result<Self, Error> {
         match format {
             CertFormat::RiotX509 => x509::parse(cert, format, key, ciphers),
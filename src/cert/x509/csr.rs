@@ -0,0 +1,261 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! PKCS#10 certificate signing request encoding.
+//!
+//! See RFC 2986. We only ever emit the simplest possible request:
+//! ```text
+//! CertificationRequest ::= SEQUENCE {
+//!   certificationRequestInfo SEQUENCE {
+//!     version       INTEGER { v1(0) },
+//!     subject       Name,
+//!     subjectPKInfo SubjectPublicKeyInfo,
+//!     attributes    [0] IMPLICIT SET OF Attribute,  -- Always empty.
+//!   },
+//!   signatureAlgorithm AlgorithmIdentifier,
+//!   signature          BIT STRING,
+//! }
+//! ```
+
+use crate::cert::Error;
+use crate::crypto::sig;
+use crate::io;
+
+/// DER encoding of `INTEGER 0`, the CSR version.
+const VERSION: &[u8] = &[0x02, 0x01, 0x00];
+
+/// DER encoding of an empty `[0] IMPLICIT SET OF Attribute`.
+const NO_ATTRIBUTES: &[u8] = &[0xa0, 0x00];
+
+/// DER encoding of `sha256WithRSAEncryption`, with NULL parameters.
+const RSA_PKCS1_SHA256: &[u8] = &[
+    0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01,
+    0x0b, 0x05, 0x00,
+];
+
+/// DER encoding of `ecdsa-with-SHA256`, with absent parameters.
+const ECDSA_SHA256: &[u8] = &[
+    0x30, 0x0a, 0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02,
+];
+
+/// DER encoding of `ecdsa-with-SHA384`, with absent parameters.
+const ECDSA_SHA384: &[u8] = &[
+    0x30, 0x0a, 0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03,
+];
+
+/// The largest header `header_len()` will ever return.
+const MAX_HEADER_LEN: usize = 4;
+
+const SEQUENCE: u8 = 0x30;
+const BIT_STRING: u8 = 0x03;
+
+/// Returns the length of a DER header for contents of length `len`.
+fn header_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 2,
+        0x80..=0xff => 3,
+        _ => 4,
+    }
+}
+
+/// Writes a DER header with the given tag and content length to the start of
+/// `out`, returning the number of bytes written.
+fn write_header(tag: u8, len: usize, out: &mut [u8]) -> Result<usize, Error> {
+    let header_len = header_len(len);
+    if len > 0xffff {
+        return Err(Error::BadEncoding);
+    }
+    if out.len() < header_len {
+        return Err(Error::Io(io::Error::BufferExhausted));
+    }
+
+    out[0] = tag;
+    match header_len {
+        2 => out[1] = len as u8,
+        3 => out[1..3].copy_from_slice(&[0x81, len as u8]),
+        _ => out[1..4].copy_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+    }
+    Ok(header_len)
+}
+
+/// Writes `bytes` to `out` at `*pos`, advancing it.
+fn put(out: &mut [u8], pos: &mut usize, bytes: &[u8]) -> Result<(), Error> {
+    let end = *pos + bytes.len();
+    out.get_mut(*pos..end)
+        .ok_or(Error::Io(io::Error::BufferExhausted))?
+        .copy_from_slice(bytes);
+    *pos = end;
+    Ok(())
+}
+
+/// Returns the size of the buffer `encode()` needs to encode a CSR with the
+/// given parameters.
+pub fn max_len(subject: &[u8], spki: &[u8], signer: &dyn sig::Sign) -> usize {
+    let info_body_len =
+        VERSION.len() + subject.len() + spki.len() + NO_ATTRIBUTES.len();
+    let algo_len = RSA_PKCS1_SHA256.len().max(ECDSA_SHA256.len());
+    MAX_HEADER_LEN * 3 + 1 + info_body_len + algo_len + signer.sig_bytes()
+}
+
+/// Encodes a CSR for the key described by `spki`, signing it with `signer`.
+///
+/// `subject` must be a DER-encoded `Name` and `spki` must be a DER-encoded
+/// `SubjectPublicKeyInfo`; neither is validated. `algo` describes the
+/// signatures produced by `signer`.
+///
+/// Returns the number of bytes written to `out`.
+pub fn encode(
+    subject: &[u8],
+    spki: &[u8],
+    algo: sig::Algo,
+    signer: &mut dyn sig::Sign,
+    out: &mut [u8],
+) -> Result<usize, Error> {
+    let algo_der = match algo {
+        sig::Algo::RsaPkcs1Sha256 => RSA_PKCS1_SHA256,
+        sig::Algo::EcdsaDerP256 => ECDSA_SHA256,
+        sig::Algo::EcdsaDerP384 => ECDSA_SHA384,
+        // X.509 requires DER-encoded ECDSA signatures.
+        sig::Algo::EcdsaPkcs11P256 | sig::Algo::EcdsaPkcs11P384 => {
+            return Err(Error::UnsupportedSig)
+        }
+    };
+
+    // We don't know how long the signature will be ahead of time, so we
+    // write the to-be-signed portion leaving enough space for the largest
+    // possible outer header, and then shift everything into place once the
+    // signature is known.
+    let info_body_len =
+        VERSION.len() + subject.len() + spki.len() + NO_ATTRIBUTES.len();
+    let info_len = header_len(info_body_len) + info_body_len;
+
+    let info_start = MAX_HEADER_LEN;
+    let mut pos = info_start;
+    pos += write_header(
+        SEQUENCE,
+        info_body_len,
+        out.get_mut(pos..)
+            .ok_or(Error::Io(io::Error::BufferExhausted))?,
+    )?;
+    put(out, &mut pos, VERSION)?;
+    put(out, &mut pos, subject)?;
+    put(out, &mut pos, spki)?;
+    put(out, &mut pos, NO_ATTRIBUTES)?;
+    let info_end = pos;
+
+    // Leave room for the algorithm, the BIT STRING header, and its
+    // unused-bits byte.
+    let sig_start = info_end + algo_der.len() + MAX_HEADER_LEN + 1;
+    let sig_end = sig_start + signer.sig_bytes();
+    if out.len() < sig_end {
+        return Err(Error::Io(io::Error::BufferExhausted));
+    }
+    let (tbs, sig) = out[..sig_end].split_at_mut(sig_start);
+    let sig_len = signer
+        .sign(&[&tbs[info_start..info_end]], sig)
+        .map_err(|_| Error::SigningFailed)?;
+
+    let bits_len = 1 + sig_len;
+    let body_len = info_len + algo_der.len() + header_len(bits_len) + bits_len;
+    let outer_len = header_len(body_len);
+
+    // Every move below is leftwards, and no write clobbers the signature
+    // before it has been moved.
+    out.copy_within(info_start..info_end, outer_len);
+    let mut pos = outer_len + info_len;
+    put(out, &mut pos, algo_der)?;
+    pos += write_header(BIT_STRING, bits_len, &mut out[pos..])?;
+    put(out, &mut pos, &[0])?;
+    out.copy_within(sig_start..sig_start + sig_len, pos);
+    write_header(SEQUENCE, body_len, out)?;
+
+    Ok(outer_len + body_len)
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    use super::*;
+    use testutil::data::keys;
+
+    use crate::cert::x509::der;
+    use crate::cert::x509::der::Tag;
+    use crate::crypto::ring;
+    use crate::crypto::sig::Verify as _;
+
+    /// `CN=manticore`.
+    const SUBJECT: &[u8] = &[
+        0x30, 0x14, 0x31, 0x12, 0x30, 0x10, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0c,
+        0x09, b'm', b'a', b'n', b't', b'i', b'c', b'o', b'r', b'e',
+    ];
+
+    #[test]
+    fn rsa_csr() {
+        let (mut verifier, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let mut out = [0; 1024];
+        let len = encode(
+            SUBJECT,
+            keys::KEY1_RSA_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut out,
+        )
+        .unwrap();
+
+        let csr = untrusted::Input::from(&out[..len]);
+        let (info, sig) = csr
+            .read_all(Error::BadEncoding, |buf| {
+                der::tagged(Tag::SEQUENCE, buf, |buf| {
+                    let mark = buf.mark();
+                    let (tag, _) = der::any(buf)?;
+                    assert_eq!(tag, Tag::SEQUENCE);
+                    let tbs = buf.get_input_between_marks(mark, buf.mark())?;
+
+                    let algo = der::parse(Tag::SEQUENCE, buf)?;
+                    assert_eq!(
+                        &RSA_PKCS1_SHA256[2..],
+                        algo.as_slice_less_safe()
+                    );
+                    let sig = der::bits_total(buf)?;
+                    Ok((tbs, sig))
+                })
+            })
+            .unwrap();
+
+        verifier
+            .verify(&[info.as_slice_less_safe()], sig.as_slice_less_safe())
+            .unwrap();
+        info.read_all(Error::BadEncoding, |buf| {
+            der::tagged(Tag::SEQUENCE, buf, |buf| {
+                assert_eq!(der::u32(buf)?, 0);
+                let (_, subject) = der::any(buf)?;
+                assert_eq!(subject.as_slice_less_safe(), &SUBJECT[2..]);
+                let (_, spki) = der::any(buf)?;
+                assert_eq!(
+                    spki.as_slice_less_safe(),
+                    &keys::KEY1_RSA_PUBLIC[4..]
+                );
+                let attrs = der::parse(Tag::context_specific(0), buf)?;
+                assert!(attrs.is_empty());
+                Ok(())
+            })
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn too_small() {
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut out = [0; 128];
+        let result = encode(
+            SUBJECT,
+            keys::KEY1_RSA_PUBLIC,
+            sig::Algo::RsaPkcs1Sha256,
+            &mut signer,
+            &mut out,
+        );
+        assert!(matches!(result, Err(Error::Io(io::Error::BufferExhausted))));
+    }
+}
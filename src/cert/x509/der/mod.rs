@@ -326,8 +326,9 @@ macro_rules! oid {
                 // little-endian order because that's easier.
                 // We then reverse it after the fact below.
                 let mark = len;
+                // Note that a zero component is still encoded as one byte.
                 let mut first = true;
-                while c != 0 {
+                loop {
                     buf[len] = (c & 0x7f) as u8;
                     if !first {
                         buf[len] |= 0x80;
@@ -336,6 +337,9 @@ macro_rules! oid {
                     c >>= 7;
                     first = false;
                     len += 1;
+                    if c == 0 {
+                        break;
+                    }
                 }
 
                 // No `const` reverse() yet, so we have to do it by hand.
@@ -13,6 +13,7 @@ use testutil::data;
 
 use crate::cert::x509::der;
 use crate::cert::x509::der::Tag;
+use crate::cert::x509::oid;
 use crate::cert::Error::BadEncoding;
 
 type Result = core::result::Result<(), crate::cert::Error>;
@@ -28,6 +29,19 @@ fn context_tag() {
     assert_eq!(Tag::context_specific(3), Tag(0b10_1_00011));
 }
 
+#[test]
+fn oid_encoding() {
+    assert_eq!(
+        oid::RSA_PKCS1_SHA256,
+        der::Oid::new(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b])
+    );
+    // Zero components must not be dropped.
+    assert_eq!(
+        oid::NIST_P384,
+        der::Oid::new(&[0x2b, 0x81, 0x04, 0x00, 0x22])
+    );
+}
+
 // NOTE: We don't test der::any very much, since it's tested indirectly by all
 // of the helpers.
 #[test]
@@ -14,6 +14,8 @@ use crate::crypto::sig;
 #[macro_use]
 mod der;
 
+pub mod csr;
+
 #[cfg(test)]
 mod test;
 
@@ -25,6 +27,10 @@ mod oid {
     pub const RSA_ENCRYPTION: Oid = oid!(1, 2, 840, 113549, 1, 1, 1);
     pub const RSA_PKCS1_SHA256: Oid = oid!(1, 2, 840, 113549, 1, 1, 11);
 
+    pub const EC_PUBLIC_KEY: Oid = oid!(1, 2, 840, 10045, 2, 1);
+    pub const NIST_P256: Oid = oid!(1, 2, 840, 10045, 3, 1, 7);
+    pub const NIST_P384: Oid = oid!(1, 3, 132, 0, 34);
+
     pub const KEY_USAGE: Oid = oid!(2, 5, 29, 15);
     pub const BASIC_CONSTRAINTS: Oid = oid!(2, 5, 29, 19);
     pub const TCG_DICE_FWID: Oid = oid!(2, 23, 133, 5, 4, 1);
@@ -56,7 +62,7 @@ pub fn parse<'cert>(
     raw_cert: &'cert [u8],
     format: cert::CertFormat,
     key: Option<&sig::PublicKeyParams<'_>>,
-    ciphers: &mut impl sig::Ciphers,
+    ciphers: &mut (impl sig::Ciphers + ?Sized),
 ) -> Result<Cert<'cert>, Error> {
     let buf = untrusted::Input::from(raw_cert);
     let (mut cert, tbs, sig_algo, sig) =
@@ -146,33 +152,7 @@ fn parse_tbs<'cert>(
     // The subject is also opaque
     let subject = Name(der::parse(Tag::SEQUENCE, buf)?.as_slice_less_safe());
 
-    let subject_key = der::tagged(Tag::SEQUENCE, buf, |buf| {
-        let (algo, aparams) = der::tagged(Tag::SEQUENCE, buf, |buf| {
-            let algo = der::oid(buf)?;
-            let aparams = buf.read_bytes_to_end();
-            Ok((algo, aparams))
-        })?;
-
-        der::bits_total(buf)?.read_all(Error::BadEncoding, |buf| match algo {
-            oid::RSA_ENCRYPTION => {
-                aparams.read_all(Error::BadEncoding, der::null)?;
-                der::tagged(Tag::SEQUENCE, buf, |buf| {
-                    let mut modulus = der::uint(buf)?.as_slice_less_safe();
-                    // DER inserts a leading zero sometimes (to disambiguate
-                    // negative integers) so we need to remove it.
-                    if modulus[0] == 0 {
-                        modulus = &modulus[1..];
-                    }
-                    let mut exponent = der::uint(buf)?.as_slice_less_safe();
-                    if exponent[0] == 0 {
-                        exponent = &exponent[1..];
-                    }
-                    Ok(sig::PublicKeyParams::Rsa { modulus, exponent })
-                })
-            }
-            _ => Err(Error::UnknownAlgorithm),
-        })
-    })?;
+    let subject_key = parse_spki(buf)?;
 
     // We don't care about the UIDs at all.
     let _issuer_uid = der::opt(Tag::context_specific(1), buf)?;
@@ -216,6 +196,61 @@ fn parse_tbs<'cert>(
     })
 }
 
+/// Parses a DER-encoded `SubjectPublicKeyInfo` into raw key parameters.
+pub(crate) fn parse_public_key(
+    spki: &[u8],
+) -> Result<sig::PublicKeyParams<'_>, Error> {
+    untrusted::Input::from(spki).read_all(Error::BadEncoding, parse_spki)
+}
+
+fn parse_spki<'cert>(
+    buf: &mut untrusted::Reader<'cert>,
+) -> Result<sig::PublicKeyParams<'cert>, Error> {
+    der::tagged(Tag::SEQUENCE, buf, |buf| {
+        let (algo, aparams) = der::tagged(Tag::SEQUENCE, buf, |buf| {
+            let algo = der::oid(buf)?;
+            let aparams = buf.read_bytes_to_end();
+            Ok((algo, aparams))
+        })?;
+
+        der::bits_total(buf)?.read_all(Error::BadEncoding, |buf| match algo {
+            oid::RSA_ENCRYPTION => {
+                aparams.read_all(Error::BadEncoding, der::null)?;
+                der::tagged(Tag::SEQUENCE, buf, |buf| {
+                    let mut modulus = der::uint(buf)?.as_slice_less_safe();
+                    // DER inserts a leading zero sometimes (to disambiguate
+                    // negative integers) so we need to remove it.
+                    if modulus[0] == 0 {
+                        modulus = &modulus[1..];
+                    }
+                    let mut exponent = der::uint(buf)?.as_slice_less_safe();
+                    if exponent[0] == 0 {
+                        exponent = &exponent[1..];
+                    }
+                    Ok(sig::PublicKeyParams::Rsa { modulus, exponent })
+                })
+            }
+            oid::EC_PUBLIC_KEY => {
+                let (curve, coord_len) =
+                    match aparams.read_all(Error::BadEncoding, der::oid)? {
+                        oid::NIST_P256 => (sig::Curve::NistP256, 32),
+                        oid::NIST_P384 => (sig::Curve::NistP384, 48),
+                        _ => return Err(Error::UnknownAlgorithm),
+                    };
+
+                // Only uncompressed points are supported.
+                if buf.read_byte()? != 0x04 {
+                    return Err(Error::BadEncoding);
+                }
+                let x = buf.read_bytes(coord_len)?.as_slice_less_safe();
+                let y = buf.read_bytes(coord_len)?.as_slice_less_safe();
+                Ok(sig::PublicKeyParams::Ecc { curve, x, y })
+            }
+            _ => Err(Error::UnknownAlgorithm),
+        })
+    })
+}
+
 /// An X.509 `KeyUsage` value, representing the valid usages of a subject
 /// public key.
 ///
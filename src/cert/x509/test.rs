@@ -12,6 +12,7 @@ use testutil::data::keys;
 use crate::cert::Cert;
 use crate::cert::CertFormat;
 use crate::crypto::ring;
+use crate::crypto::sig::Curve;
 use crate::crypto::sig::NoVerify;
 use crate::crypto::sig::PublicKeyParams;
 
@@ -52,6 +53,31 @@ fn explicit_key() {
     assert!(!cert.is_within_path_len_constraint(2));
 }
 
+#[test]
+#[cfg_attr(miri, ignore)]
+fn ecdsa_subject_key() {
+    let cert = Cert::parse(
+        data::x509::CHAIN3_ECDSA_P256,
+        CertFormat::RiotX509,
+        Some(&PublicKeyParams::Rsa {
+            modulus: keys::KEY2_RSA_MOD,
+            exponent: keys::KEY2_RSA_EXP,
+        }),
+        &mut ring::sig::Ciphers::new(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        cert.subject_key(),
+        &PublicKeyParams::Ecc {
+            curve: Curve::NistP256,
+            x: keys::KEY1_ECDSA_P256_X,
+            y: keys::KEY1_ECDSA_P256_Y,
+        }
+    );
+    assert!(!cert.supports_cert_signing());
+}
+
 macro_rules! table_test {
     ([$pred:ident] $($test:ident:$data:ident,)*) => {$(
         #[test]
@@ -0,0 +1,69 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Authenticated encryption, as used by encrypted Cerberus sessions.
+//!
+//! Cerberus protects session traffic with AES-GCM, using a 96-bit IV and a
+//! 128-bit authentication tag. No additional authenticated data is used.
+
+/// The length of an AES-GCM IV, in bytes.
+pub const IV_LEN: usize = 12;
+
+/// The length of an AES-GCM authentication tag, in bytes.
+pub const TAG_LEN: usize = 16;
+
+/// An AES-GCM initialization vector.
+pub type Iv = [u8; IV_LEN];
+
+/// An AES-GCM authentication tag.
+pub type Tag = [u8; TAG_LEN];
+
+/// An error returned by an AEAD operation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// Indicates that the key was not of a supported length.
+    BadKey,
+
+    /// Indicates that a ciphertext failed authentication.
+    BadTag,
+
+    /// Indicates an unspecified, internal error.
+    Unspecified,
+}
+
+/// An AES-GCM engine.
+///
+/// Keys are provided on each call, since Cerberus sessions are short-lived
+/// and typically re-keyed much more frequently than hardware key slots are
+/// provisioned. Implementations should support 256-bit keys, which is what
+/// [`session::Session`] produces; they may additionally support 128-bit keys.
+///
+/// [`session::Session`]: crate::session::Session
+pub trait Aead {
+    /// Encrypts `buf` in place using `key` and `iv`, writing the resulting
+    /// authentication tag to `tag`.
+    ///
+    /// Callers must never reuse an `iv` with the same `key`.
+    fn encrypt(
+        &mut self,
+        key: &[u8],
+        iv: &Iv,
+        buf: &mut [u8],
+        tag: &mut Tag,
+    ) -> Result<(), Error>;
+
+    /// Decrypts `buf` in place using `key` and `iv`, after checking that
+    /// `tag` authenticates it.
+    ///
+    /// If authentication fails, returns [`Error::BadTag`]; the contents of
+    /// `buf` are unspecified in that case.
+    fn decrypt(
+        &mut self,
+        key: &[u8],
+        iv: &Iv,
+        buf: &mut [u8],
+        tag: &Tag,
+    ) -> Result<(), Error>;
+}
+impl dyn Aead {} // Ensure object-safe.
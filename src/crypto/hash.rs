@@ -8,6 +8,10 @@
 //! adds functions to [`Engine`] for more ergonomic usage, but which would
 //! otherwise make it object-unsafe.
 
+use crate::hardware::flash;
+use crate::hardware::flash::Flash;
+use crate::hardware::flash::FlashExt as _;
+use crate::hardware::flash::Region;
 use crate::mem::Arena;
 use crate::mem::ArenaExt as _;
 use crate::mem::OutOfMemory;
@@ -68,6 +72,34 @@ pub enum Error {
     Unspecified,
 }
 
+/// An error returned while hashing the contents of a [`Flash`].
+#[derive(Copy, Clone, Debug)]
+pub enum FlashError {
+    /// Indicates that reading from the [`Flash`] failed.
+    Flash(flash::Error),
+
+    /// Indicates that the hashing operation itself failed.
+    Hash(Error),
+}
+
+impl From<flash::Error> for FlashError {
+    fn from(e: flash::Error) -> Self {
+        Self::Flash(e)
+    }
+}
+
+impl From<OutOfMemory> for FlashError {
+    fn from(e: OutOfMemory) -> Self {
+        Self::Flash(e.into())
+    }
+}
+
+impl From<Error> for FlashError {
+    fn from(e: Error) -> Self {
+        Self::Hash(e)
+    }
+}
+
 /// A hashing engine, which maintains the state for one digest.
 ///
 /// Callers should not use the `raw` API directly; [`Hasher`] is a type-safe
@@ -160,6 +192,25 @@ pub impl<E: Engine + ?Sized> E {
         h.write(buf)?;
         h.finish(out)
     }
+
+    /// Convenience helper for hashing regions of a [`Flash`].
+    ///
+    /// See [`Hasher::write_flash()`].
+    ///
+    /// Implementers do not need to implement this function themselves.
+    #[inline]
+    fn flash_hash(
+        &mut self,
+        algo: Algo,
+        flash: &dyn Flash,
+        regions: impl IntoIterator<Item = Region>,
+        buf: &mut [u8],
+        out: &mut [u8],
+    ) -> Result<(), FlashError> {
+        let mut h = self.new_hash(algo)?;
+        h.write_flash(flash, regions, buf)?;
+        Ok(h.finish(out)?)
+    }
 }
 
 // Ensure Engine is object-safe.
@@ -179,6 +230,47 @@ impl<E: Engine + ?Sized> Hasher<&mut E> {
         self.engine.write_raw(data)
     }
 
+    /// Adds the contents of `regions` of `flash`, in order, to the hashing
+    /// state.
+    ///
+    /// Data is read out of `flash` through `buf`, one chunk at a time; a
+    /// larger buffer means fewer calls to [`Flash::read()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is empty.
+    pub fn write_flash(
+        &mut self,
+        flash: &dyn Flash,
+        regions: impl IntoIterator<Item = Region>,
+        buf: &mut [u8],
+    ) -> Result<(), FlashError> {
+        assert!(!buf.is_empty(), "cannot hash through an empty buffer");
+        for region in regions {
+            flash.read_chunks(region, buf, |_, chunk| {
+                self.write(chunk).map(|_| true).map_err(FlashError::from)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Adds the contents of `regions` of `flash`, in order, to the hashing
+    /// state, reading each region in one piece.
+    ///
+    /// Regions are read with [`Flash::read_direct()`], which does not copy for
+    /// memory-backed devices; otherwise, each region is allocated on `arena`.
+    pub fn write_flash_direct(
+        &mut self,
+        flash: &dyn Flash,
+        regions: impl IntoIterator<Item = Region>,
+        arena: &dyn Arena,
+    ) -> Result<(), FlashError> {
+        for region in regions {
+            self.write(flash.read_direct(region, arena, 1)?)?;
+        }
+        Ok(())
+    }
+
     /// Completes the hashing/HMAC operation, writing the result to `out`.
     pub fn finish(self, out: &mut [u8]) -> Result<(), Error> {
         self.engine.finish_raw(out)
@@ -23,6 +23,7 @@
 //!
 //! [`ring` module]: ring/index.html
 
+pub mod aead;
 pub mod csrng;
 pub mod hash;
 pub mod sig;
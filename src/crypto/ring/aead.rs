@@ -0,0 +1,123 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implementations of [`crypto::aead`] based on `ring`.
+//!
+//! Requires the `std` feature flag to be enabled.
+
+use ring::aead as ring_aead;
+
+use crate::crypto::aead;
+
+#[cfg(doc)]
+use crate::crypto;
+
+/// A `ring`-based [`aead::Aead`] supporting AES-128-GCM and
+/// AES-256-GCM.
+pub struct AesGcm;
+
+impl AesGcm {
+    /// Creates a new `AesGcm`.
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn key(key: &[u8]) -> Result<ring_aead::LessSafeKey, aead::Error> {
+        let algo = match key.len() {
+            16 => &ring_aead::AES_128_GCM,
+            32 => &ring_aead::AES_256_GCM,
+            _ => return Err(aead::Error::BadKey),
+        };
+        let key = ring_aead::UnboundKey::new(algo, key)
+            .map_err(|_| aead::Error::BadKey)?;
+        Ok(ring_aead::LessSafeKey::new(key))
+    }
+}
+
+impl Default for AesGcm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl aead::Aead for AesGcm {
+    fn encrypt(
+        &mut self,
+        key: &[u8],
+        iv: &aead::Iv,
+        buf: &mut [u8],
+        tag: &mut aead::Tag,
+    ) -> Result<(), aead::Error> {
+        let key = Self::key(key)?;
+        let nonce = ring_aead::Nonce::assume_unique_for_key(*iv);
+        let out = key
+            .seal_in_place_separate_tag(nonce, ring_aead::Aad::empty(), buf)
+            .map_err(|_| aead::Error::Unspecified)?;
+        tag.copy_from_slice(out.as_ref());
+        Ok(())
+    }
+
+    fn decrypt(
+        &mut self,
+        key: &[u8],
+        iv: &aead::Iv,
+        buf: &mut [u8],
+        tag: &aead::Tag,
+    ) -> Result<(), aead::Error> {
+        let key = Self::key(key)?;
+        let nonce = ring_aead::Nonce::assume_unique_for_key(*iv);
+
+        // `ring` can only decrypt a ciphertext that is immediately followed
+        // by its tag, so we need to glue them together first.
+        let mut in_out = Vec::with_capacity(buf.len() + tag.len());
+        in_out.extend_from_slice(buf);
+        in_out.extend_from_slice(tag);
+        let plaintext = key
+            .open_in_place(nonce, ring_aead::Aad::empty(), &mut in_out)
+            .map_err(|_| aead::Error::BadTag)?;
+        buf.copy_from_slice(plaintext);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::aead::Aead as _;
+    use testutil::data::misc_crypto;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn round_trip() {
+        let mut aes = AesGcm::new();
+        let key = [0x42; 32];
+        let iv = [0x17; aead::IV_LEN];
+
+        let mut buf = misc_crypto::PLAIN_TEXT.to_vec();
+        let mut tag = [0; aead::TAG_LEN];
+        aes.encrypt(&key, &iv, &mut buf, &mut tag).unwrap();
+        assert_ne!(buf, misc_crypto::PLAIN_TEXT);
+
+        aes.decrypt(&key, &iv, &mut buf, &tag).unwrap();
+        assert_eq!(buf, misc_crypto::PLAIN_TEXT);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn bad_tag() {
+        let mut aes = AesGcm::new();
+        let key = [0x42; 16];
+        let iv = [0x17; aead::IV_LEN];
+
+        let mut buf = misc_crypto::PLAIN_TEXT.to_vec();
+        let mut tag = [0; aead::TAG_LEN];
+        aes.encrypt(&key, &iv, &mut buf, &mut tag).unwrap();
+
+        tag[0] ^= 1;
+        assert_eq!(
+            aes.decrypt(&key, &iv, &mut buf, &tag),
+            Err(aead::Error::BadTag)
+        );
+    }
+}
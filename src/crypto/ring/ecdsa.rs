@@ -6,12 +6,14 @@
 //!
 //! Requires the `std` feature flag to be enabled.
 
+use ring::signature::EcdsaSigningAlgorithm as EcdsaSignAlgo;
 use ring::signature::EcdsaVerificationAlgorithm as EcdsaAlgo;
+use ring::signature::KeyPair as _;
 use ring::signature::VerificationAlgorithm as _;
 
 use crate::crypto::sig;
 
-/// A `ring`-based [`sig::Verify`] for DER-encoded ECDSA using the P-256 curve.
+/// A `ring`-based [`sig::Verify`] for ECDSA using the P-256 curve.
 pub struct VerifyP256 {
     key: [u8; 65],
     algo: &'static EcdsaAlgo,
@@ -51,55 +53,119 @@ impl sig::Verify for VerifyP256 {
         message_vec: &[&[u8]],
         signature: &[u8],
     ) -> Result<(), sig::Error> {
-        let mut message = Vec::new();
-        for bytes in message_vec {
-            message.extend_from_slice(bytes);
+        verify(self.algo, &self.key, message_vec, signature)
+    }
+}
+
+/// A `ring`-based [`sig::Verify`] for ECDSA using the P-384 curve.
+pub struct VerifyP384 {
+    key: [u8; 97],
+    algo: &'static EcdsaAlgo,
+}
+
+impl VerifyP384 {
+    /// Creates a new `VerifyP384` using the DER encoding and the given
+    /// public-key coordinates.
+    pub fn with_der_encoding(x: [u8; 48], y: [u8; 48]) -> Self {
+        let mut key = [4u8; 97];
+        key[1..49].copy_from_slice(&x);
+        key[49..97].copy_from_slice(&y);
+
+        Self {
+            key,
+            algo: &ring::signature::ECDSA_P384_SHA384_ASN1,
+        }
+    }
+
+    /// Creates a new `VerifyP384` using the PKCS#11 encoding and the given
+    /// public-key coordinates.
+    pub fn with_pkcs11_encoding(x: [u8; 48], y: [u8; 48]) -> Self {
+        let mut key = [4u8; 97];
+        key[1..49].copy_from_slice(&x);
+        key[49..97].copy_from_slice(&y);
+
+        Self {
+            key,
+            algo: &ring::signature::ECDSA_P384_SHA384_FIXED,
         }
+    }
+}
+
+impl sig::Verify for VerifyP384 {
+    fn verify(
+        &mut self,
+        message_vec: &[&[u8]],
+        signature: &[u8],
+    ) -> Result<(), sig::Error> {
+        verify(self.algo, &self.key, message_vec, signature)
+    }
+}
 
-        self.algo
-            .verify(
-                (&self.key[..]).into(),
-                message.as_slice().into(),
-                signature.into(),
-            )
-            .map_err(|_| sig::Error::Unspecified)
+fn verify(
+    algo: &EcdsaAlgo,
+    key: &[u8],
+    message_vec: &[&[u8]],
+    signature: &[u8],
+) -> Result<(), sig::Error> {
+    let mut message = Vec::new();
+    for bytes in message_vec {
+        message.extend_from_slice(bytes);
     }
+
+    algo.verify(key.into(), message.as_slice().into(), signature.into())
+        .map_err(|_| sig::Error::Unspecified)
 }
 
-/// A `ring`-based [`sig::Sign`] for PKCS#1.5 RSA using SHA-256.
+/// A `ring`-based [`sig::Sign`] for ECDSA using the P-256 curve.
 pub struct SignP256 {
     keypair: ring::signature::EcdsaKeyPair,
+    der: bool,
 }
 
 impl SignP256 {
     /// Creates a new `SignP256` from the given PKCS#8-encoded private key,
     /// using the DER encoding for signatures.
     ///
-    /// Returns `None` if the key fails to parse.
+    /// Returns an error if the key fails to parse.
     pub fn with_der_encoding_from_pkcs8(
         pkcs8: &[u8],
     ) -> Result<Self, sig::Error> {
-        let keypair = ring::signature::EcdsaKeyPair::from_pkcs8(
+        let keypair = keypair_from_pkcs8(
             &ring::signature::ECDSA_P256_SHA256_ASN1_SIGNING,
             pkcs8,
-        )
-        .map_err(|_| sig::Error::Unspecified)?;
-        Ok(Self { keypair })
+        )?;
+        Ok(Self { keypair, der: true })
     }
 
     /// Creates a new `SignP256` from the given PKCS#8-encoded private key,
     /// using the PKCS#11-style encoding for signatures.
     ///
-    /// Returns `None` if the key fails to parse.
+    /// Returns an error if the key fails to parse.
     pub fn with_pkcs11_encoding_from_pkcs8(
         pkcs8: &[u8],
     ) -> Result<Self, sig::Error> {
-        let keypair = ring::signature::EcdsaKeyPair::from_pkcs8(
+        let keypair = keypair_from_pkcs8(
             &ring::signature::ECDSA_P256_SHA256_FIXED_SIGNING,
             pkcs8,
-        )
-        .map_err(|_| sig::Error::Unspecified)?;
-        Ok(Self { keypair })
+        )?;
+        Ok(Self {
+            keypair,
+            der: false,
+        })
+    }
+
+    /// Creates a `VerifyP256` for verifying signatures generated by this
+    /// signer.
+    pub fn verifier(&self) -> VerifyP256 {
+        let point = self.keypair.public_key().as_ref();
+        let mut x = [0; 32];
+        let mut y = [0; 32];
+        x.copy_from_slice(&point[1..33]);
+        y.copy_from_slice(&point[33..65]);
+        match self.der {
+            true => VerifyP256::with_der_encoding(x, y),
+            false => VerifyP256::with_pkcs11_encoding(x, y),
+        }
     }
 }
 
@@ -124,22 +190,105 @@ impl sig::Sign for SignP256 {
         message_vec: &[&[u8]],
         signature: &mut [u8],
     ) -> Result<usize, sig::Error> {
-        let mut message = Vec::new();
-        for bytes in message_vec {
-            message.extend_from_slice(bytes);
+        sign(&self.keypair, message_vec, signature)
+    }
+}
+
+/// A `ring`-based [`sig::Sign`] for ECDSA using the P-384 curve.
+pub struct SignP384 {
+    keypair: ring::signature::EcdsaKeyPair,
+    der: bool,
+}
+
+impl SignP384 {
+    /// Creates a new `SignP384` from the given PKCS#8-encoded private key,
+    /// using the DER encoding for signatures.
+    ///
+    /// Returns an error if the key fails to parse.
+    pub fn with_der_encoding_from_pkcs8(
+        pkcs8: &[u8],
+    ) -> Result<Self, sig::Error> {
+        let keypair = keypair_from_pkcs8(
+            &ring::signature::ECDSA_P384_SHA384_ASN1_SIGNING,
+            pkcs8,
+        )?;
+        Ok(Self { keypair, der: true })
+    }
+
+    /// Creates a new `SignP384` from the given PKCS#8-encoded private key,
+    /// using the PKCS#11-style encoding for signatures.
+    ///
+    /// Returns an error if the key fails to parse.
+    pub fn with_pkcs11_encoding_from_pkcs8(
+        pkcs8: &[u8],
+    ) -> Result<Self, sig::Error> {
+        let keypair = keypair_from_pkcs8(
+            &ring::signature::ECDSA_P384_SHA384_FIXED_SIGNING,
+            pkcs8,
+        )?;
+        Ok(Self {
+            keypair,
+            der: false,
+        })
+    }
+
+    /// Creates a `VerifyP384` for verifying signatures generated by this
+    /// signer.
+    pub fn verifier(&self) -> VerifyP384 {
+        let point = self.keypair.public_key().as_ref();
+        let mut x = [0; 48];
+        let mut y = [0; 48];
+        x.copy_from_slice(&point[1..49]);
+        y.copy_from_slice(&point[49..97]);
+        match self.der {
+            true => VerifyP384::with_der_encoding(x, y),
+            false => VerifyP384::with_pkcs11_encoding(x, y),
         }
+    }
+}
+
+impl sig::Sign for SignP384 {
+    fn sig_bytes(&self) -> usize {
+        // See `SignP256::sig_bytes()`; the coordinates are now 48 bytes each.
+        96 + 8
+    }
+
+    fn sign(
+        &mut self,
+        message_vec: &[&[u8]],
+        signature: &mut [u8],
+    ) -> Result<usize, sig::Error> {
+        sign(&self.keypair, message_vec, signature)
+    }
+}
+
+fn keypair_from_pkcs8(
+    algo: &'static EcdsaSignAlgo,
+    pkcs8: &[u8],
+) -> Result<ring::signature::EcdsaKeyPair, sig::Error> {
+    ring::signature::EcdsaKeyPair::from_pkcs8(algo, pkcs8)
+        .map_err(|_| sig::Error::Unspecified)
+}
 
-        let rng = ring::rand::SystemRandom::new();
-        let sig = self
-            .keypair
-            .sign(&rng, &message)
-            .map_err(|_| sig::Error::Unspecified)?;
-        let signature = signature
-            .get_mut(..sig.as_ref().len())
-            .ok_or(sig::Error::Unspecified)?;
-        signature.copy_from_slice(sig.as_ref());
-        Ok(signature.len())
+fn sign(
+    keypair: &ring::signature::EcdsaKeyPair,
+    message_vec: &[&[u8]],
+    signature: &mut [u8],
+) -> Result<usize, sig::Error> {
+    let mut message = Vec::new();
+    for bytes in message_vec {
+        message.extend_from_slice(bytes);
     }
+
+    let rng = ring::rand::SystemRandom::new();
+    let sig = keypair
+        .sign(&rng, &message)
+        .map_err(|_| sig::Error::Unspecified)?;
+    let signature = signature
+        .get_mut(..sig.as_ref().len())
+        .ok_or(sig::Error::Unspecified)?;
+    signature.copy_from_slice(sig.as_ref());
+    Ok(signature.len())
 }
 
 #[cfg(test)]
@@ -193,4 +342,57 @@ mod tests {
             .verify(&[misc_crypto::PLAIN_TEXT], &generated_sig[..sig_len])
             .unwrap();
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn p384_der() {
+        let mut signer = SignP384::with_der_encoding_from_pkcs8(
+            keys::KEY1_ECDSA_P384_KEYPAIR,
+        )
+        .unwrap();
+        let mut verifier = VerifyP384::with_der_encoding(
+            *keys::KEY1_ECDSA_P384_X,
+            *keys::KEY1_ECDSA_P384_Y,
+        );
+
+        let mut generated_sig = vec![0; signer.sig_bytes()];
+        let sig_len = signer
+            .sign(&[misc_crypto::PLAIN_TEXT], &mut generated_sig)
+            .unwrap();
+
+        verifier
+            .verify(&[misc_crypto::PLAIN_TEXT], &generated_sig[..sig_len])
+            .unwrap();
+        signer
+            .verifier()
+            .verify(&[misc_crypto::PLAIN_TEXT], &generated_sig[..sig_len])
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn p384_pkcs11() {
+        let mut signer = SignP384::with_pkcs11_encoding_from_pkcs8(
+            keys::KEY1_ECDSA_P384_KEYPAIR,
+        )
+        .unwrap();
+        let mut verifier = VerifyP384::with_pkcs11_encoding(
+            *keys::KEY1_ECDSA_P384_X,
+            *keys::KEY1_ECDSA_P384_Y,
+        );
+
+        let mut generated_sig = vec![0; signer.sig_bytes()];
+        let sig_len = signer
+            .sign(&[misc_crypto::PLAIN_TEXT], &mut generated_sig)
+            .unwrap();
+        assert_eq!(sig_len, 96);
+
+        verifier
+            .verify(&[misc_crypto::PLAIN_TEXT], &generated_sig[..sig_len])
+            .unwrap();
+        signer
+            .verifier()
+            .verify(&[misc_crypto::PLAIN_TEXT], &generated_sig[..sig_len])
+            .unwrap();
+    }
 }
@@ -170,4 +170,55 @@ mod tests {
         ctx.write(&misc_crypto::PLAIN_TEXT[16..]).unwrap();
         ctx.expect(misc_crypto::PLAIN_HMAC256).unwrap();
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn hash_flash() {
+        use crate::crypto::hash::FlashError;
+        use crate::hardware::flash;
+        use crate::hardware::flash::Ram;
+        use crate::hardware::flash::Region;
+        use crate::mem::OutOfMemory;
+
+        let mut e = Engine::new();
+        let text = misc_crypto::PLAIN_TEXT;
+        let flash = Ram(text);
+        let regions =
+            [Region::new(16, 8), Region::new(0, 16), Region::new(24, 9)];
+
+        let mut expected = [0; Sha256.bytes()];
+        let mut ctx = e.new_hash(Sha256).unwrap();
+        ctx.write(&text[16..24]).unwrap();
+        ctx.write(&text[0..16]).unwrap();
+        ctx.write(&text[24..33]).unwrap();
+        ctx.finish(&mut expected).unwrap();
+
+        let mut digest = [0; Sha256.bytes()];
+        e.flash_hash(
+            Sha256,
+            &flash,
+            regions.iter().copied(),
+            &mut [0; 7],
+            &mut digest,
+        )
+        .unwrap();
+        assert_eq!(digest, expected);
+
+        let mut ctx = e.new_hash(Sha256).unwrap();
+        ctx.write_flash_direct(&flash, regions.iter().copied(), &OutOfMemory)
+            .unwrap();
+        ctx.expect(&expected).unwrap();
+
+        let too_far = Region::new(text.len() as u32 - 4, 8);
+        assert!(matches!(
+            e.flash_hash(
+                Sha256,
+                &flash,
+                core::iter::once(too_far),
+                &mut [0; 64],
+                &mut digest
+            ),
+            Err(FlashError::Flash(flash::Error::OutOfRange))
+        ));
+    }
 }
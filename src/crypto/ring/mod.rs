@@ -17,6 +17,8 @@
 //!
 //! [`ring` warranty disclaimer]: https://github.com/briansmith/ring/blob/main/README.md
 
+#[cfg(feature = "std")]
+pub mod aead;
 #[cfg(feature = "std")]
 pub mod csrng;
 #[cfg(feature = "std")]
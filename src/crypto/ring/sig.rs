@@ -92,6 +92,34 @@ impl sig::Ciphers for Ciphers {
                     ecdsa::VerifyP256::with_pkcs11_encoding(*x, *y),
                 ));
             }
+            (
+                Algo::EcdsaDerP384,
+                PublicKeyParams::Ecc {
+                    curve: Curve::NistP384,
+                    x,
+                    y,
+                },
+            ) => {
+                let x: &[u8; 48] = (*x).try_into().ok()?;
+                let y: &[u8; 48] = (*y).try_into().ok()?;
+                self.verifier = Some(Box::new(
+                    ecdsa::VerifyP384::with_der_encoding(*x, *y),
+                ));
+            }
+            (
+                Algo::EcdsaPkcs11P384,
+                PublicKeyParams::Ecc {
+                    curve: Curve::NistP384,
+                    x,
+                    y,
+                },
+            ) => {
+                let x: &[u8; 48] = (*x).try_into().ok()?;
+                let y: &[u8; 48] = (*y).try_into().ok()?;
+                self.verifier = Some(Box::new(
+                    ecdsa::VerifyP384::with_pkcs11_encoding(*x, *y),
+                ));
+            }
             _ => {}
         }
 
@@ -62,7 +62,7 @@ impl dyn Sign {} // Ensure object-safe.
 ///
 /// This must be paired with a compatible [`Algo`] (which specifies *algorithm*
 /// parameters) to be usable for signature verification.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum PublicKeyParams<'cert> {
     /// Raw RSA parameters.
     Rsa {
@@ -93,7 +93,9 @@ impl PublicKeyParams<'_> {
             (self, algo),
             (Self::Rsa { .. }, Algo::RsaPkcs1Sha256) |
             (Self::Ecc { curve: Curve::NistP256, .. }, Algo::EcdsaDerP256) |
-            (Self::Ecc { curve: Curve::NistP256, .. }, Algo::EcdsaPkcs11P256)
+            (Self::Ecc { curve: Curve::NistP256, .. }, Algo::EcdsaPkcs11P256) |
+            (Self::Ecc { curve: Curve::NistP384, .. }, Algo::EcdsaDerP384) |
+            (Self::Ecc { curve: Curve::NistP384, .. }, Algo::EcdsaPkcs11P384)
         );
         ok
     }
@@ -106,6 +108,7 @@ impl PublicKeyParams<'_> {
 #[allow(missing_docs)]
 pub enum Curve {
     NistP256,
+    NistP384,
 }
 
 /// A signature algorithm for a certificate subject key.
@@ -121,6 +124,12 @@ pub enum Algo {
     /// Fixed-width (PKCS#11-style) ECDSA signatures using the NIST
     /// P-256 curve and SHA-256 for hashing.
     EcdsaPkcs11P256,
+    /// DER-encoded ECDSA signatures using the NIST P-384 curve and
+    /// SHA-384 for hashing.
+    EcdsaDerP384,
+    /// Fixed-width (PKCS#11-style) ECDSA signatures using the NIST
+    /// P-384 curve and SHA-384 for hashing.
+    EcdsaPkcs11P384,
 }
 
 /// A collection of ciphers that are provided to certificate machinery.
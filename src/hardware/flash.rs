@@ -16,6 +16,21 @@ use core::alloc::Layout;
 use core::convert::TryInto;
 use core::mem;
 
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::fs::OpenOptions;
+#[cfg(feature = "std")]
+use std::io::Read as _;
+#[cfg(feature = "std")]
+use std::io::Seek as _;
+#[cfg(feature = "std")]
+use std::io::SeekFrom;
+#[cfg(feature = "std")]
+use std::io::Write as _;
+#[cfg(feature = "std")]
+use std::path::Path;
+
 use zerocopy::AsBytes;
 use zerocopy::FromBytes;
 use zerocopy::LayoutVerified;
@@ -28,6 +43,10 @@ use crate::io::Read as _;
 use crate::mem::Arena;
 use crate::mem::OutOfMemory;
 
+#[cfg(doc)]
+use crate::manifest::pfm::FlashDeviceInfo;
+#[cfg(doc)]
+use crate::manifest::pfm::ParsedPfm;
 #[cfg(doc)]
 use crate::mem::ArenaExt;
 
@@ -49,6 +68,14 @@ pub enum Error {
     /// of memory.
     Internal,
 
+    /// Indicates that an erase operation was not aligned to the device's
+    /// [erase size][Flash::erase_size].
+    Unaligned,
+
+    /// Indicates that a program operation would have needed to set a bit that
+    /// was not erased first.
+    NotErased,
+
     /// Indicates that an unspecified error occured.
     Unspecified,
 }
@@ -134,12 +161,40 @@ pub unsafe trait Flash {
     /// Implementations are, as an optimization, permitted to assume that
     /// writes will be serial and localized, so as to minimize clearing
     /// operations on flash hardware.
+    ///
+    /// Devices with an [erase size][Flash::erase_size] greater than one, such
+    /// as SPI NOR, can usually only clear bits when programming; the region
+    /// being programmed must be erased with [`Flash::erase()`] first.
     fn program(&mut self, offset: u32, buf: &[u8]) -> Result<(), Error>;
 
     /// Flushes any pending `program()` operations.
     fn flush(&mut self) -> Result<(), Error> {
         Ok(())
     }
+
+    /// Returns the size, in bytes, of the smallest region this device can
+    /// erase at once, such as a NOR sector.
+    ///
+    /// This must be a power of two; helpers such as
+    /// [`FlashExt::erase_covering()`] fail with [`Error::OutOfRange`]
+    /// otherwise. By default, this is one, indicating that any byte can be
+    /// reprogrammed without an erase.
+    fn erase_size(&self) -> Result<u32, Error> {
+        Ok(1)
+    }
+
+    /// Attempts to erase `region`, which must be aligned to
+    /// [`Flash::erase_size()`].
+    ///
+    /// What value erased bytes read as is device-specific; a PFM records it
+    /// as [`FlashDeviceInfo::blank_byte()`].
+    ///
+    /// By default, this function does nothing, which is suitable for devices
+    /// that do not need to be erased before being programmed.
+    fn erase(&mut self, region: Region) -> Result<(), Error> {
+        let _ = region;
+        Ok(())
+    }
 }
 impl dyn Flash {} // Ensure object-safety.
 
@@ -173,6 +228,16 @@ unsafe impl<F: Flash + ?Sized> Flash for &F {
     fn flush(&mut self) -> Result<(), Error> {
         Err(Error::Locked)
     }
+
+    #[inline]
+    fn erase_size(&self) -> Result<u32, Error> {
+        F::erase_size(self)
+    }
+
+    #[inline]
+    fn erase(&mut self, _: Region) -> Result<(), Error> {
+        Err(Error::Locked)
+    }
 }
 
 unsafe impl<F: Flash + ?Sized> Flash for &mut F {
@@ -205,8 +270,46 @@ unsafe impl<F: Flash + ?Sized> Flash for &mut F {
     fn flush(&mut self) -> Result<(), Error> {
         F::flush(self)
     }
+
+    #[inline]
+    fn erase_size(&self) -> Result<u32, Error> {
+        F::erase_size(self)
+    }
+
+    #[inline]
+    fn erase(&mut self, region: Region) -> Result<(), Error> {
+        F::erase(self, region)
+    }
 }
 
+/// Write protection for a [`Flash`] device.
+///
+/// This may be implemented by a flash device's own block-protection
+/// registers, or by an RoT interposed between a host and its flash that
+/// filters the host's writes. Locked regions may not be modified by whatever
+/// the protection guards against; a PFM can be used to compute which regions
+/// those should be, with [`ParsedPfm::protect()`].
+pub trait Protection {
+    /// Returns the size, in bytes, of the smallest region that can be locked
+    /// or unlocked at once.
+    ///
+    /// This is always a power of two. By default, this is one.
+    fn granularity(&self) -> Result<u32, Error> {
+        Ok(1)
+    }
+
+    /// Locks `region`, which must be aligned to [`Protection::granularity()`].
+    fn lock(&mut self, region: Region) -> Result<(), Error>;
+
+    /// Unlocks `region`, which must be aligned to
+    /// [`Protection::granularity()`].
+    fn unlock(&mut self, region: Region) -> Result<(), Error>;
+
+    /// Returns whether every byte of `region` is locked.
+    fn is_locked(&self, region: Region) -> Result<bool, Error>;
+}
+impl dyn Protection {} // Ensure object-safety.
+
 /// Convenience functions for direct flash reads, exposed as a trait.
 ///
 /// Note that this trait is implemened for `&impl Flash`, which is the reason
@@ -281,6 +384,80 @@ pub impl<F: Flash + ?Sized> F {
             .expect("read_direct() implemented incorrectly");
         Ok(lv.into_slice())
     }
+
+    /// Reads `region` one chunk at a time through `buf`, without loading all
+    /// of it into memory at once.
+    ///
+    /// `f` is called with the offset of each chunk within `region` and its
+    /// contents, and returns whether to keep going. Returns `false` if `f`
+    /// stopped early.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is empty.
+    fn read_chunks<E: From<Error>>(
+        &self,
+        region: Region,
+        buf: &mut [u8],
+        f: impl FnMut(u32, &[u8]) -> Result<bool, E>,
+    ) -> Result<bool, E> {
+        let mut f = f;
+        assert!(!buf.is_empty(), "cannot read through an empty buffer");
+        let mut offset = 0;
+        while offset < region.len {
+            let len = (region.len - offset).min(buf.len() as u32);
+            let chunk = &mut buf[..len as usize];
+            self.read(region.offset + offset, chunk)?;
+            if !f(offset, chunk)? {
+                return Ok(false);
+            }
+            offset += len;
+        }
+        Ok(true)
+    }
+
+    /// Checks whether every byte in `region` is equal to `blank_byte`, i.e.,
+    /// whether `region` is erased.
+    ///
+    /// `blank_byte` is usually [`FlashDeviceInfo::blank_byte()`].
+    fn is_blank(&self, region: Region, blank_byte: u8) -> Result<bool, Error> {
+        self.read_chunks(region, &mut [0; 32], |_, chunk| {
+            Ok(chunk.iter().all(|&b| b == blank_byte))
+        })
+    }
+
+    /// Copies `region` of `src` into the same region of `self`, a chunk at a
+    /// time.
+    ///
+    /// As with [`Flash::program()`], `region` may need to be erased first.
+    fn copy_from(
+        &mut self,
+        src: &dyn Flash,
+        region: Region,
+    ) -> Result<(), Error> {
+        src.read_chunks(region, &mut [0; 64], |offset, chunk| {
+            self.program(region.offset + offset, chunk).map(|_| true)
+        })?;
+        Ok(())
+    }
+
+    /// Erases the smallest region aligned to [`Flash::erase_size()`] that
+    /// contains `region`, returning the region that was erased.
+    ///
+    /// Note that this may erase bytes outside of `region`, if it does not
+    /// start and end on erase boundaries.
+    fn erase_covering(&mut self, region: Region) -> Result<Region, Error> {
+        let erase = region
+            .align_to(self.erase_size()?)
+            .ok_or(Error::OutOfRange)?;
+        if erase.end() > self.size()? {
+            return Err(Error::OutOfRange);
+        }
+        if erase.len != 0 {
+            self.erase(erase)?;
+        }
+        Ok(erase)
+    }
 }
 
 /// Adapter for converting RAM-backed storage into a [`Flash`].
@@ -337,6 +514,10 @@ unsafe impl<Bytes: AsRef<[u8]>> Flash for Ram<Bytes> {
     fn program(&mut self, _: u32, _: &[u8]) -> Result<(), Error> {
         Err(Error::Locked)
     }
+
+    fn erase(&mut self, _: Region) -> Result<(), Error> {
+        Err(Error::Locked)
+    }
 }
 
 /// Adapter for converting mutable, RAM-backed storage into a [`Flash`].
@@ -400,6 +581,327 @@ unsafe impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> Flash for RamMut<Bytes> {
         self.0.as_mut()[start..end].copy_from_slice(buf);
         Ok(())
     }
+
+    /// Fills `region` with `0xff`, matching the erased state of NOR flash.
+    fn erase(&mut self, region: Region) -> Result<(), Error> {
+        let start = region.offset as usize;
+        let end = start
+            .checked_add(region.len as usize)
+            .ok_or(Error::OutOfRange)?;
+        if end > self.0.as_ref().len() {
+            return Err(Error::OutOfRange);
+        }
+
+        for b in &mut self.0.as_mut()[start..end] {
+            *b = 0xff;
+        }
+        Ok(())
+    }
+}
+
+/// A RAM-backed [`Flash`] that simulates the behavior of SPI NOR flash.
+///
+/// Unlike [`RamMut`], a `Nor` can only be erased a whole sector at a time, and
+/// programming can only clear bits; erased bytes read as `0xff`. Programming
+/// a byte that would need a bit set fails with [`Error::NotErased`], so that
+/// a missing erase is caught in tests, rather than silently corrupting data as
+/// real hardware would.
+pub struct Nor<Bytes> {
+    ram: RamMut<Bytes>,
+    sector_size: u32,
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> Nor<Bytes> {
+    /// Creates a new `Nor` with the given sector size, which must be a power
+    /// of two.
+    ///
+    /// The initial contents of `bytes` are left as-is.
+    pub fn new(bytes: Bytes, sector_size: u32) -> Self {
+        assert!(sector_size.is_power_of_two());
+        Self {
+            ram: RamMut(bytes),
+            sector_size,
+        }
+    }
+
+    /// Returns the underlying storage.
+    pub fn into_inner(self) -> Bytes {
+        self.ram.0
+    }
+}
+
+unsafe impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> Flash for Nor<Bytes> {
+    #[inline]
+    fn size(&self) -> Result<u32, Error> {
+        self.ram.size()
+    }
+
+    #[inline]
+    fn read(&self, offset: u32, out: &mut [u8]) -> Result<(), Error> {
+        self.ram.read(offset, out)
+    }
+
+    #[inline]
+    fn read_direct<'a: 'c, 'b: 'c, 'c>(
+        &'a self,
+        region: Region,
+        arena: &'b dyn Arena,
+        align: usize,
+    ) -> Result<&'c [u8], Error> {
+        self.ram.read_direct(region, arena, align)
+    }
+
+    fn program(&mut self, offset: u32, buf: &[u8]) -> Result<(), Error> {
+        let region = Region::new(offset, buf.len() as u32);
+        let old = self.ram.read_direct(region, &OutOfMemory, 1)?;
+        if old.iter().zip(buf).any(|(&old, &new)| new & !old != 0) {
+            return Err(Error::NotErased);
+        }
+
+        self.ram.program(offset, buf)
+    }
+
+    fn erase_size(&self) -> Result<u32, Error> {
+        Ok(self.sector_size)
+    }
+
+    fn erase(&mut self, region: Region) -> Result<(), Error> {
+        if region.offset % self.sector_size != 0
+            || region.len % self.sector_size != 0
+        {
+            return Err(Error::Unaligned);
+        }
+
+        self.ram.erase(region)
+    }
+}
+
+/// A [`Flash`] that exposes only a [`Region`] of another `Flash`.
+///
+/// Offset zero of a `SubFlash` is the start of its region; accesses that fall
+/// outside of the region fail with [`Error::OutOfRange`].
+pub struct SubFlash<F> {
+    flash: F,
+    region: Region,
+}
+
+impl<F: Flash> SubFlash<F> {
+    /// Creates a new `SubFlash` over `region` of `flash`.
+    pub fn new(flash: F, region: Region) -> Self {
+        Self { flash, region }
+    }
+
+    /// Translates `sub` into a region of the underlying flash.
+    fn translate(&self, sub: Region) -> Result<Region, Error> {
+        self.region.subregion(sub).ok_or(Error::OutOfRange)
+    }
+}
+
+unsafe impl<F: Flash> Flash for SubFlash<F> {
+    #[inline]
+    fn size(&self) -> Result<u32, Error> {
+        Ok(self.region.len)
+    }
+
+    fn read(&self, offset: u32, out: &mut [u8]) -> Result<(), Error> {
+        let region = self.translate(Region::new(offset, out.len() as u32))?;
+        self.flash.read(region.offset, out)
+    }
+
+    fn read_direct<'a: 'c, 'b: 'c, 'c>(
+        &'a self,
+        region: Region,
+        arena: &'b dyn Arena,
+        align: usize,
+    ) -> Result<&'c [u8], Error> {
+        self.flash
+            .read_direct(self.translate(region)?, arena, align)
+    }
+
+    fn program(&mut self, offset: u32, buf: &[u8]) -> Result<(), Error> {
+        let region = self.translate(Region::new(offset, buf.len() as u32))?;
+        self.flash.program(region.offset, buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), Error> {
+        self.flash.flush()
+    }
+
+    #[inline]
+    fn erase_size(&self) -> Result<u32, Error> {
+        self.flash.erase_size()
+    }
+
+    fn erase(&mut self, region: Region) -> Result<(), Error> {
+        let region = self.translate(region)?;
+        self.flash.erase(region)
+    }
+}
+
+/// A [`Flash`] backed by a file, such as a dump of a SPI flash chip.
+///
+/// Reads and writes go straight to the file, so the image never needs to be
+/// held in memory in its entirety. A `FileFlash` may be made
+/// [read-only][FileFlash::read_only], and may [emulate][FileFlash::emulate_nor]
+/// NOR flash in the same way as [`Nor`].
+#[cfg(feature = "std")]
+pub struct FileFlash {
+    file: File,
+    size: u32,
+    read_only: bool,
+    sector_size: Option<u32>,
+}
+
+#[cfg(feature = "std")]
+impl FileFlash {
+    /// Creates a new `FileFlash` over `file`.
+    ///
+    /// `file` must be open for reading and, unless this `FileFlash` is
+    /// read-only, for writing. The size of the device is fixed to the size of
+    /// the file at the time of this call.
+    pub fn new(file: File) -> std::io::Result<Self> {
+        let size = file.metadata()?.len().try_into().map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "file too large for a flash device",
+            )
+        })?;
+        Ok(Self {
+            file,
+            size,
+            read_only: false,
+            sector_size: None,
+        })
+    }
+
+    /// Opens the file at `path` for reading and writing, and creates a new
+    /// `FileFlash` over it.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::new(OpenOptions::new().read(true).write(true).open(path)?)
+    }
+
+    /// Opens the file at `path` for reading only, and creates a new
+    /// [read-only][FileFlash::read_only] `FileFlash` over it.
+    pub fn open_read_only(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::new(File::open(path)?)?.read_only())
+    }
+
+    /// Makes this `FileFlash` read-only: any operation that would modify the
+    /// file fails with [`Error::Locked`].
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Makes this `FileFlash` behave like NOR flash with the given sector
+    /// size, which must be a power of two.
+    ///
+    /// See [`Nor`].
+    pub fn emulate_nor(mut self, sector_size: u32) -> Self {
+        assert!(sector_size.is_power_of_two());
+        self.sector_size = Some(sector_size);
+        self
+    }
+
+    /// Returns the underlying file.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+
+    /// Checks that `len` bytes at `offset` are in-bounds.
+    fn check_range(&self, offset: u32, len: usize) -> Result<(), Error> {
+        let len: u32 = len.try_into().map_err(|_| Error::OutOfRange)?;
+        let end = offset.checked_add(len).ok_or(Error::OutOfRange)?;
+        if end > self.size {
+            return Err(Error::OutOfRange);
+        }
+        Ok(())
+    }
+
+    /// Writes `buf` at `offset`, which must already be in-bounds.
+    fn write_at(&mut self, offset: u32, buf: &[u8]) -> Result<(), Error> {
+        if self.read_only {
+            return Err(Error::Locked);
+        }
+        self.file
+            .seek(SeekFrom::Start(offset.into()))
+            .and_then(|_| self.file.write_all(buf))
+            .map_err(|_| Error::Unspecified)
+    }
+}
+
+#[cfg(feature = "std")]
+unsafe impl Flash for FileFlash {
+    fn size(&self) -> Result<u32, Error> {
+        Ok(self.size)
+    }
+
+    fn read(&self, offset: u32, out: &mut [u8]) -> Result<(), Error> {
+        self.check_range(offset, out.len())?;
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset.into()))
+            .and_then(|_| file.read_exact(out))
+            .map_err(|_| Error::Unspecified)
+    }
+
+    fn read_direct<'a: 'c, 'b: 'c, 'c>(
+        &'a self,
+        region: Region,
+        arena: &'b dyn Arena,
+        align: usize,
+    ) -> Result<&'c [u8], Error> {
+        let layout = Layout::from_size_align(region.len as usize, align)
+            .map_err(|_| Error::Internal)?;
+        let buf = arena.alloc_raw(layout)?;
+        self.read(region.offset, buf)?;
+        Ok(buf)
+    }
+
+    fn program(&mut self, offset: u32, buf: &[u8]) -> Result<(), Error> {
+        self.check_range(offset, buf.len())?;
+        if self.sector_size.is_some() {
+            let mut old = vec![0; buf.len()];
+            self.read(offset, &mut old)?;
+            if old.iter().zip(buf).any(|(&old, &new)| new & !old != 0) {
+                return Err(Error::NotErased);
+            }
+        }
+
+        self.write_at(offset, buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.read_only {
+            return Ok(());
+        }
+        self.file.flush().map_err(|_| Error::Unspecified)
+    }
+
+    fn erase_size(&self) -> Result<u32, Error> {
+        Ok(self.sector_size.unwrap_or(1))
+    }
+
+    /// Fills `region` with `0xff`, matching the erased state of NOR flash.
+    fn erase(&mut self, region: Region) -> Result<(), Error> {
+        self.check_range(region.offset, region.len as usize)?;
+        let sector_size = self.erase_size()?;
+        if region.offset % sector_size != 0 || region.len % sector_size != 0 {
+            return Err(Error::Unaligned);
+        }
+
+        if self.read_only {
+            return Err(Error::Locked);
+        }
+        // Stream the fill through a bounded buffer, rather than allocating all
+        // of `region` at once.
+        let mut fill = std::io::repeat(0xff).take(region.len.into());
+        self.file
+            .seek(SeekFrom::Start(region.offset.into()))
+            .and_then(|_| std::io::copy(&mut fill, &mut self.file))
+            .map(|_| ())
+            .map_err(|_| Error::Unspecified)
+    }
 }
 
 #[cfg(doc)]
@@ -604,4 +1106,135 @@ impl Region {
     pub fn take(self, n: u32) -> Option<Self> {
         Some(Region::new(self.offset, self.len.checked_sub(n)?))
     }
+
+    /// Expands `self` to the smallest region that contains it and whose start
+    /// and end are both multiples of `align`.
+    ///
+    /// Returns `None` if `align` is not a power of two, or if any overflow
+    /// occurs.
+    pub fn align_to(self, align: u32) -> Option<Self> {
+        if !align.is_power_of_two() {
+            return None;
+        }
+        let mask = align - 1;
+        let start = self.offset & !mask;
+        if self.len == 0 {
+            return Some(Region::new(start, 0));
+        }
+        let end = self.offset.checked_add(self.len)?.checked_add(mask)? & !mask;
+        Some(Region::new(start, end - start))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn align_to() {
+        assert_eq!(
+            Region::new(0x100, 0x100).align_to(0x100),
+            Some(Region::new(0x100, 0x100))
+        );
+        assert_eq!(
+            Region::new(0x1ff, 2).align_to(0x100),
+            Some(Region::new(0x100, 0x200))
+        );
+        assert_eq!(Region::new(5, 0).align_to(4), Some(Region::new(4, 0)));
+        assert_eq!(Region::new(0xffff_fff0, 8).align_to(0x100), None);
+        assert_eq!(Region::new(0, 8).align_to(0), None);
+        assert_eq!(Region::new(0, 8).align_to(24), None);
+    }
+
+    #[test]
+    fn nor() {
+        let mut nor = Nor::new(vec![0xff; 64], 16);
+        assert_eq!(nor.erase_size().unwrap(), 16);
+        assert!(nor.is_blank(Region::new(0, 64), 0xff).unwrap());
+
+        // Programming may only clear bits.
+        nor.program(4, &[0xf0, 0x0f]).unwrap();
+        nor.program(4, &[0xa0]).unwrap();
+        assert!(matches!(nor.program(4, &[0xf0]), Err(Error::NotErased)));
+        assert!(!nor.is_blank(Region::new(0, 16), 0xff).unwrap());
+        assert!(nor.is_blank(Region::new(6, 58), 0xff).unwrap());
+
+        assert!(matches!(
+            nor.erase(Region::new(4, 16)),
+            Err(Error::Unaligned)
+        ));
+        assert_eq!(
+            nor.erase_covering(Region::new(4, 2)).unwrap(),
+            Region::new(0, 16)
+        );
+        assert!(nor.is_blank(Region::new(0, 64), 0xff).unwrap());
+        nor.program(4, &[0xf0]).unwrap();
+
+        assert!(matches!(
+            nor.erase_covering(Region::new(60, 8)),
+            Err(Error::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn sub_flash() {
+        let mut bytes = *b"0123456789abcdef";
+        let mut sub = SubFlash::new(RamMut(&mut bytes[..]), Region::new(4, 8));
+        assert_eq!(sub.size().unwrap(), 8);
+
+        let mut buf = [0; 3];
+        sub.read(2, &mut buf).unwrap();
+        assert_eq!(&buf, b"678");
+        assert!(matches!(sub.read(6, &mut buf), Err(Error::OutOfRange)));
+
+        sub.program(0, b"xy").unwrap();
+        assert!(matches!(sub.program(7, b"xy"), Err(Error::OutOfRange)));
+        assert_eq!(&bytes, b"0123xy6789abcdef");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore)]
+    fn file() {
+        use crate::mem::BumpArena;
+
+        let path = std::env::temp_dir()
+            .join(format!("manticore-file-flash-{}", std::process::id()));
+        std::fs::write(&path, [0xff; 64]).unwrap();
+
+        let mut flash = FileFlash::open(&path).unwrap().emulate_nor(16);
+        assert_eq!(flash.size().unwrap(), 64);
+        assert_eq!(flash.erase_size().unwrap(), 16);
+
+        flash.program(8, b"manticore").unwrap();
+        flash.program(8, b"m").unwrap();
+        assert!(matches!(flash.program(8, &[0xff]), Err(Error::NotErased)));
+        assert!(matches!(
+            flash.program(60, b"manticore"),
+            Err(Error::OutOfRange)
+        ));
+        flash.flush().unwrap();
+
+        let mut arena = [0; 64];
+        let arena = BumpArena::new(&mut arena);
+        let bytes = flash.read_direct(Region::new(8, 9), &arena, 1).unwrap();
+        assert_eq!(bytes, b"manticore");
+
+        flash.erase_covering(Region::new(8, 9)).unwrap();
+        assert!(flash.is_blank(Region::new(0, 64), 0xff).unwrap());
+        flash.program(0, b"manticore").unwrap();
+        drop(flash);
+
+        let mut flash = FileFlash::open_read_only(&path).unwrap();
+        let mut buf = [0; 9];
+        flash.read(0, &mut buf).unwrap();
+        assert_eq!(&buf, b"manticore");
+        assert!(matches!(flash.program(0, b"m"), Err(Error::Locked)));
+        assert!(matches!(
+            flash.erase(Region::new(0, 16)),
+            Err(Error::Locked)
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -11,6 +11,9 @@
 
 use core::time::Duration;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 pub mod flash;
 
 /// Provides access to "chip identity" information of various types.
@@ -29,6 +32,24 @@ pub trait Identity {
     /// Returns the "unique device identity" for the device. This is a binary
     /// value of unspecified format.
     fn unique_device_identity(&self) -> &[u8];
+
+    /// Returns the number of certificate chains presented to this device that
+    /// failed to validate, if the device keeps track of them.
+    fn certificate_failures(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns the number of failed attestations of devices this device is
+    /// responsible for, if the device keeps track of them.
+    fn attestation_failures(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns a vendor-defined chip identifier, such as a silicon serial
+    /// number, if the device has one.
+    fn vendor_chip_identifier(&self) -> Option<&[u8]> {
+        None
+    }
 }
 impl dyn Identity {} // Ensure object-safe.
 
@@ -45,5 +66,101 @@ pub trait Reset {
     /// The resolution and accuracy of this value are expected to be
     /// best-effort.
     fn uptime(&self) -> Duration;
+
+    /// Returns the number of resets of kind `ty` that the device on `port`
+    /// has undergone since it was powered on.
+    ///
+    /// Port 0 is this device; its local resets are reported by
+    /// [`Reset::resets_since_power_on()`] instead. Returns `None` if `port`
+    /// does not exist or does not track resets of kind `ty`.
+    fn port_resets_since_power_on(
+        &self,
+        port: u8,
+        ty: ResetKind,
+    ) -> Option<u32> {
+        let _ = (port, ty);
+        None
+    }
+
+    /// Returns the uptime of the device on `port`.
+    ///
+    /// Port 0 is this device; its uptime is reported by [`Reset::uptime()`]
+    /// instead. Returns `None` if `port` does not exist.
+    fn port_uptime(&self, port: u8) -> Option<Duration> {
+        let _ = port;
+        None
+    }
+
+    /// Returns the reset state of the host processor on `port`.
+    ///
+    /// Returns `None` if there is no host on `port`.
+    fn host_state(&self, port: u8) -> Option<HostState> {
+        let _ = port;
+        None
+    }
 }
 impl dyn Reset {} // Ensure object-safe.
+
+/// A kind of reset counted by [`Reset::port_resets_since_power_on()`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ResetKind {
+    /// A reset of the device on the port itself.
+    Local,
+    /// A reset of an external device, such as a flash chip, connected to the
+    /// device on the port.
+    External,
+}
+
+/// The reset state of a host processor, as reported by
+/// [`Reset::host_state()`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HostState {
+    /// The host is running.
+    Running,
+    /// The host is being held in reset.
+    InReset,
+    /// The host is not being held in reset, but is not running.
+    NotRunning,
+}
+
+/// An error returned by a [`ConfigReset`] action.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConfigResetError {
+    /// Indicates that the requested kind of reset is not supported by this
+    /// device.
+    Unsupported,
+    /// Indicates that the reset was attempted, but failed.
+    Failed,
+}
+
+/// Provides hooks for wiping device configuration, in response to an
+/// authorized [`protocol::ResetConfig`] request.
+///
+/// [`protocol::ResetConfig`]: crate::protocol::ResetConfig
+pub trait ConfigReset {
+    /// Returns whether this device supports the reset described by `ty`.
+    ///
+    /// Hosts are not issued authorization tokens for unsupported resets.
+    fn supports(&self, ty: ConfigResetKind) -> bool;
+
+    /// Performs the reset described by `ty`.
+    ///
+    /// This function is only called once the reset has been authorized.
+    fn reset(&mut self, ty: ConfigResetKind) -> Result<(), ConfigResetError>;
+}
+impl dyn ConfigReset {} // Ensure object-safe.
+
+/// A kind of configuration reset performed by a [`ConfigReset`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ConfigResetKind {
+    /// Erases all PFMs, returning the device to bypass mode, in which the
+    /// host's firmware is not protected.
+    RestoreBypass,
+    /// Restores the device to its factory-default configuration.
+    RestoreDefaults,
+    /// Erases platform-specific configuration, such as the PCD.
+    ClearPlatformConfig,
+    /// Clears the device's chassis-intrusion state.
+    ResetIntrusion,
+}
@@ -52,6 +52,8 @@ pub mod protocol;
 #[cfg(feature = "serde")]
 mod serde;
 
+pub mod attestation;
+pub mod auth;
 pub mod cert;
 pub mod crypto;
 pub mod hardware;
@@ -59,5 +61,8 @@ pub mod io;
 pub mod manifest;
 pub mod mem;
 pub mod net;
+pub mod pmr;
+pub mod recovery;
+pub mod seal;
 pub mod server;
 pub mod session;
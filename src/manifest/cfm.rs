@@ -0,0 +1,920 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The Component Firmware Manifest (CFM)
+//!
+//! A CFM is a computer-readable policy document describing the measurements
+//! that components attested by a PA-RoT are expected to report. It consists
+//! of a number of `ComponentDevice` elements, each of which has children
+//! describing allowable PMR digests, measurement digests, and raw measurement
+//! data for that component.
+//!
+//! # Element Formats
+//!
+//! CFM-specific elements have the following layout, in the same notation as
+//! the [module-level documentation](super):
+//! ```ignore
+//! struct ComponentDevice {
+//!     cert_slot: u8,
+//!     attestation_protocol: u8, // See `AttestationProtocol`.
+//!     _: [u8; 2],
+//!     component_id: u32,
+//! }
+//!
+//! // Child of a `ComponentDevice`.
+//! struct PmrDigest {
+//!     pmr_id: u8,
+//!     digest_count: u8,
+//!     hash_type: u8,
+//!     _: u8,
+//!     digests: [Hash<hash_type>; self.digest_count],
+//! }
+//!
+//! // Child of a `ComponentDevice`.
+//! struct Measurement {
+//!     pmr_id: u8,
+//!     measurement_id: u8,
+//!     digest_count: u8,
+//!     hash_type: u8,
+//!     digests: [Hash<hash_type>; self.digest_count],
+//! }
+//!
+//! // Child of a `ComponentDevice`.
+//! struct AllowableData {
+//!     pmr_id: u8,
+//!     measurement_id: u8,
+//!     check: u8, // See `DataCheck`.
+//!     data_count: u8,
+//!     bitmask_len: u16,
+//!     _: u16,
+//!     bitmask: [u8; self.bitmask_len],
+//!     _: [u8; misalign_of(self.bitmask_len, 4)],
+//!     data: [Data; self.data_count],
+//! }
+//!
+//! struct Data {
+//!     len: u16,
+//!     _: u16,
+//!     data: [u8; self.len],
+//!     _: [u8; misalign_of(self.len, 4)],
+//! }
+//! ```
+//!
+//! The [`ParsedCfm`] type is the entry-point for this module.
+
+use core::cmp::Ordering;
+
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+
+use crate::crypto::hash;
+use crate::manifest::provenance;
+use crate::manifest::provenance::Provenance;
+use crate::manifest::Container;
+use crate::manifest::Error;
+use crate::manifest::Manifest;
+use crate::manifest::ManifestType;
+use crate::manifest::Parse;
+use crate::manifest::ParsedManifest;
+use crate::manifest::TocEntry;
+use crate::manifest::ValidationTime;
+use crate::mem::misalign_of;
+use crate::mem::Arena;
+
+use crate::protocol::wire::WireEnum as _;
+
+wire_enum! {
+    /// A CFM element type.
+    pub enum ElementType: u8 {
+      /// A `ComponentDevice` describes a single kind of component that is
+      /// attested against a particular CFM.
+      ComponentDevice = 0x70,
+
+      /// A list of allowable digests for one of a component's PMRs, as a
+      /// subelement of a `ComponentDevice`.
+      PmrDigest = 0x72,
+
+      /// A list of allowable digests for a single measurement, as a
+      /// subelement of a `ComponentDevice`.
+      Measurement = 0x73,
+
+      /// A list of allowable raw values for a single measurement, as a
+      /// subelement of a `ComponentDevice`.
+      AllowableData = 0x75,
+    }
+}
+
+wire_enum! {
+    /// A protocol used to attest a component.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum AttestationProtocol: u8 {
+        /// The Cerberus challenge protocol.
+        Cerberus = 0x00,
+        /// The DMTF SPDM protocol.
+        Spdm = 0x01,
+    }
+}
+
+wire_enum! {
+    /// A comparison to perform between a reported measurement and the values
+    /// in an [`AllowableData`].
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum DataCheck: u8 {
+        /// The measurement must equal one of the values.
+        Equal = 0x00,
+        /// The measurement must equal none of the values.
+        NotEqual = 0x01,
+        /// The measurement must be less than one of the values.
+        LessThan = 0x02,
+        /// The measurement must be less than or equal to one of the values.
+        LessOrEqual = 0x03,
+        /// The measurement must be greater than one of the values.
+        GreaterThan = 0x04,
+        /// The measurement must be greater than or equal to one of the
+        /// values.
+        GreaterOrEqual = 0x05,
+    }
+}
+
+/// A Component Firmware Manifest.
+///
+/// This type provides functions for parsing a CFM's table of contents and
+/// using it to extract other portions of the CFM.
+///
+/// This type only maintains the TOC in memory for book-keeping.
+pub struct ParsedCfm<'cfm, Provenance = provenance::Signed> {
+    container: Container<'cfm, Cfm, Provenance>,
+}
+
+/// A [`Manifest`] implementation mapping onto [`ParsedCfm`], for use in generic
+/// contexts.
+///
+/// See [`Manifest`] and [`Parse`].
+pub enum Cfm {}
+
+impl Manifest for Cfm {
+    type ElementType = ElementType;
+    const TYPE: ManifestType = ManifestType::Cfm;
+
+    fn min_version(_: ElementType) -> u8 {
+        // Every revision of these elements so far begins with the fields
+        // Manticore reads, so any of them can be parsed.
+        0
+    }
+}
+
+impl<'f, P> Parse<'f, P> for Cfm {
+    type Parsed = ParsedCfm<'f, P>;
+
+    fn parse(container: Container<'f, Self, P>) -> Result<Self::Parsed, Error> {
+        Ok(ParsedCfm::new(container))
+    }
+
+    fn container(manifest: &Self::Parsed) -> &Container<'f, Self, P> {
+        &manifest.container
+    }
+
+    type Guarded = ();
+    fn validate(
+        _manifest: &Self::Parsed,
+        _when: ValidationTime,
+        _args: &Self::Guarded,
+        _hasher: &mut dyn hash::Engine,
+        _arena: &'f dyn Arena,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<P> ParsedManifest for ParsedCfm<'_, P> {
+    type Manifest = Cfm;
+}
+
+impl<'cfm, P> ParsedCfm<'cfm, P> {
+    /// Creates a new CFM handle using the given `Container`.
+    pub fn new(container: Container<'cfm, Cfm, P>) -> Self {
+        ParsedCfm { container }
+    }
+}
+
+impl<'cfm, P> ParsedCfm<'cfm, P>
+where
+    P: Provenance,
+{
+    /// Returns an iterator over the `ComponentDevice` elements of this CFM.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn component_devices(
+        &self,
+    ) -> impl Iterator<Item = ComponentDeviceEntry<'_, 'cfm, P>> + '_ {
+        self.container
+            .toc()
+            .entries_of(ElementType::ComponentDevice.into())
+            .map(move |entry| ComponentDeviceEntry { cfm: self, entry })
+    }
+}
+
+/// Decodes the hash type encoding shared by CFM elements.
+fn decode_hash_type(raw: u8) -> Result<hash::Algo, Error> {
+    match raw {
+        0b00 => Ok(hash::Algo::Sha256),
+        0b01 => Ok(hash::Algo::Sha384),
+        0b10 => Ok(hash::Algo::Sha512),
+        _ => Err(Error::OutOfRange),
+    }
+}
+
+/// A "component device" element entry in a CFM's `Toc`.
+///
+/// This type allows for lazily reading the [`ComponentDevice`] described by
+/// this entry, as obtained from [`ParsedCfm::component_devices()`].
+pub struct ComponentDeviceEntry<'a, 'cfm, Provenance = provenance::Signed> {
+    cfm: &'a ParsedCfm<'cfm, Provenance>,
+    entry: TocEntry<'a, 'cfm, Cfm>,
+}
+
+impl<'a, 'cfm, P> ComponentDeviceEntry<'a, 'cfm, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'cfm dyn Arena,
+    ) -> Result<ComponentDevice<'a, 'cfm, P>, Error> {
+        #[derive(Copy, Clone, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            cert_slot: u8,
+            attestation_protocol: u8,
+            _unused: [u8; 2],
+            component_id: u32,
+        }
+        let (header, _) = self.entry.read_with_header::<Header, P>(
+            self.cfm.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let protocol =
+            AttestationProtocol::from_wire_value(header.attestation_protocol)
+                .ok_or(Error::OutOfRange)?;
+
+        Ok(ComponentDevice {
+            entry: self,
+            cert_slot: header.cert_slot,
+            protocol,
+            component_id: header.component_id,
+        })
+    }
+}
+
+/// A "component device" element from a CFM, describing a component to be
+/// attested.
+///
+/// To obtain a value of this type, see [`ParsedCfm::component_devices()`] and
+/// [`ComponentDeviceEntry::read()`].
+pub struct ComponentDevice<'a, 'cfm, Provenance = provenance::Signed> {
+    entry: ComponentDeviceEntry<'a, 'cfm, Provenance>,
+    cert_slot: u8,
+    protocol: AttestationProtocol,
+    component_id: u32,
+}
+
+impl<'a, 'cfm, P> ComponentDevice<'a, 'cfm, P> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry.entry
+    }
+
+    /// Returns the certificate chain slot to challenge this component on.
+    pub fn cert_slot(&self) -> u8 {
+        self.cert_slot
+    }
+
+    /// Returns the protocol this component is attested with.
+    pub fn attestation_protocol(&self) -> AttestationProtocol {
+        self.protocol
+    }
+
+    /// Returns an identifier for this kind of component.
+    pub fn component_id(&self) -> u32 {
+        self.component_id
+    }
+
+    /// Returns an iterator over the `PmrDigest` subelements of this
+    /// `ComponentDevice`.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn pmr_digests(
+        &self,
+    ) -> impl Iterator<Item = PmrDigestEntry<'_, 'cfm, P>> + '_ {
+        self.entry().children_of(ElementType::PmrDigest.into()).map(
+            move |entry| PmrDigestEntry {
+                device: self,
+                entry,
+            },
+        )
+    }
+
+    /// Returns an iterator over the `Measurement` subelements of this
+    /// `ComponentDevice`.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn measurements(
+        &self,
+    ) -> impl Iterator<Item = MeasurementEntry<'_, 'cfm, P>> + '_ {
+        self.entry()
+            .children_of(ElementType::Measurement.into())
+            .map(move |entry| MeasurementEntry {
+                device: self,
+                entry,
+            })
+    }
+
+    /// Returns an iterator over the `AllowableData` subelements of this
+    /// `ComponentDevice`.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn allowable_data(
+        &self,
+    ) -> impl Iterator<Item = AllowableDataEntry<'_, 'cfm, P>> + '_ {
+        self.entry()
+            .children_of(ElementType::AllowableData.into())
+            .map(move |entry| AllowableDataEntry {
+                device: self,
+                entry,
+            })
+    }
+}
+
+/// A list of digests of a single type, as found in several CFM elements.
+struct Digests<'cfm> {
+    hash_type: hash::Algo,
+    digests: &'cfm [u8],
+}
+
+impl<'cfm> Digests<'cfm> {
+    /// Splits `count` digests of type `hash_type` off of the front of `buf`.
+    fn parse(
+        hash_type: u8,
+        count: u8,
+        buf: &'cfm [u8],
+        toc_index: usize,
+    ) -> Result<Self, Error> {
+        let hash_type = decode_hash_type(hash_type)?;
+        let digests = buf
+            .get(..hash_type.bytes() * count as usize)
+            .ok_or(Error::TooShort { toc_index })?;
+        Ok(Self { hash_type, digests })
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &'cfm [u8]> {
+        self.digests.chunks_exact(self.hash_type.bytes())
+    }
+
+    fn contains(&self, digest: &[u8]) -> bool {
+        self.iter().any(|d| d == digest)
+    }
+}
+
+/// A "PMR digest" element entry in a CFM's `Toc`.
+///
+/// This type allows for lazily reading the [`PmrDigest`] described by this
+/// entry, as obtained from [`ComponentDevice::pmr_digests()`].
+pub struct PmrDigestEntry<'a, 'cfm, Provenance = provenance::Signed> {
+    device: &'a ComponentDevice<'a, 'cfm, Provenance>,
+    entry: TocEntry<'a, 'cfm, Cfm>,
+}
+
+impl<'a, 'cfm, P> PmrDigestEntry<'a, 'cfm, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'cfm dyn Arena,
+    ) -> Result<PmrDigest<'a, 'cfm, P>, Error> {
+        #[derive(Copy, Clone, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            pmr_id: u8,
+            digest_count: u8,
+            hash_type: u8,
+            _unused: u8,
+        }
+        let (header, rest) = self.entry.read_with_header::<Header, P>(
+            self.device.entry.cfm.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let digests = Digests::parse(
+            header.hash_type,
+            header.digest_count,
+            rest,
+            self.entry.index(),
+        )?;
+
+        Ok(PmrDigest {
+            entry: self,
+            pmr_id: header.pmr_id,
+            digests,
+        })
+    }
+}
+
+/// A "PMR digest" element from a CFM, listing the values a component's PMR
+/// may take.
+///
+/// To obtain a value of this type, see [`ComponentDevice::pmr_digests()`] and
+/// [`PmrDigestEntry::read()`].
+pub struct PmrDigest<'a, 'cfm, Provenance = provenance::Signed> {
+    entry: PmrDigestEntry<'a, 'cfm, Provenance>,
+    pmr_id: u8,
+    digests: Digests<'cfm>,
+}
+
+impl<'a, 'cfm, P> PmrDigest<'a, 'cfm, P> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry.entry
+    }
+
+    /// Returns the PMR this element applies to.
+    pub fn pmr_id(&self) -> u8 {
+        self.pmr_id
+    }
+
+    /// Returns the hash type of this element's digests.
+    pub fn hash_type(&self) -> hash::Algo {
+        self.digests.hash_type
+    }
+
+    /// Returns an iterator over the allowable digests for this PMR.
+    pub fn digests(&self) -> impl Iterator<Item = &'cfm [u8]> {
+        self.digests.iter()
+    }
+
+    /// Returns whether `digest` is one of the allowable digests for this PMR.
+    pub fn contains(&self, digest: &[u8]) -> bool {
+        self.digests.contains(digest)
+    }
+}
+
+/// A "measurement" element entry in a CFM's `Toc`.
+///
+/// This type allows for lazily reading the [`Measurement`] described by this
+/// entry, as obtained from [`ComponentDevice::measurements()`].
+pub struct MeasurementEntry<'a, 'cfm, Provenance = provenance::Signed> {
+    device: &'a ComponentDevice<'a, 'cfm, Provenance>,
+    entry: TocEntry<'a, 'cfm, Cfm>,
+}
+
+impl<'a, 'cfm, P> MeasurementEntry<'a, 'cfm, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'cfm dyn Arena,
+    ) -> Result<Measurement<'a, 'cfm, P>, Error> {
+        #[derive(Copy, Clone, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            pmr_id: u8,
+            measurement_id: u8,
+            digest_count: u8,
+            hash_type: u8,
+        }
+        let (header, rest) = self.entry.read_with_header::<Header, P>(
+            self.device.entry.cfm.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let digests = Digests::parse(
+            header.hash_type,
+            header.digest_count,
+            rest,
+            self.entry.index(),
+        )?;
+
+        Ok(Measurement {
+            entry: self,
+            pmr_id: header.pmr_id,
+            measurement_id: header.measurement_id,
+            digests,
+        })
+    }
+}
+
+/// A "measurement" element from a CFM, listing the digests a single
+/// measurement reported by a component may take.
+///
+/// To obtain a value of this type, see [`ComponentDevice::measurements()`] and
+/// [`MeasurementEntry::read()`].
+pub struct Measurement<'a, 'cfm, Provenance = provenance::Signed> {
+    entry: MeasurementEntry<'a, 'cfm, Provenance>,
+    pmr_id: u8,
+    measurement_id: u8,
+    digests: Digests<'cfm>,
+}
+
+impl<'a, 'cfm, P> Measurement<'a, 'cfm, P> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry.entry
+    }
+
+    /// Returns the PMR the measurement is extended into.
+    pub fn pmr_id(&self) -> u8 {
+        self.pmr_id
+    }
+
+    /// Returns the index of the measurement within its PMR.
+    pub fn measurement_id(&self) -> u8 {
+        self.measurement_id
+    }
+
+    /// Returns the hash type of this element's digests.
+    pub fn hash_type(&self) -> hash::Algo {
+        self.digests.hash_type
+    }
+
+    /// Returns an iterator over the allowable digests for this measurement.
+    pub fn digests(&self) -> impl Iterator<Item = &'cfm [u8]> {
+        self.digests.iter()
+    }
+
+    /// Returns whether `digest` is one of the allowable digests for this
+    /// measurement.
+    pub fn contains(&self, digest: &[u8]) -> bool {
+        self.digests.contains(digest)
+    }
+}
+
+/// An "allowable data" element entry in a CFM's `Toc`.
+///
+/// This type allows for lazily reading the [`AllowableData`] described by
+/// this entry, as obtained from [`ComponentDevice::allowable_data()`].
+pub struct AllowableDataEntry<'a, 'cfm, Provenance = provenance::Signed> {
+    device: &'a ComponentDevice<'a, 'cfm, Provenance>,
+    entry: TocEntry<'a, 'cfm, Cfm>,
+}
+
+impl<'a, 'cfm, P> AllowableDataEntry<'a, 'cfm, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'cfm dyn Arena,
+    ) -> Result<AllowableData<'a, 'cfm, P>, Error> {
+        #[derive(Copy, Clone, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            pmr_id: u8,
+            measurement_id: u8,
+            check: u8,
+            data_count: u8,
+            bitmask_len: u16,
+            _unused: u16,
+        }
+        let (header, rest) = self.entry.read_with_header::<Header, P>(
+            self.device.entry.cfm.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let too_short = Error::TooShort {
+            toc_index: self.entry.index(),
+        };
+
+        let check = DataCheck::from_wire_value(header.check)
+            .ok_or(Error::OutOfRange)?;
+        let bitmask_len = header.bitmask_len as usize;
+        let bitmask = rest.get(..bitmask_len).ok_or(too_short)?;
+        let data = rest
+            .get(bitmask_len + misalign_of(bitmask_len, 4)..)
+            .ok_or(too_short)?;
+
+        // Check that every data entry is in-bounds, so that `data()` can
+        // walk them without further checks.
+        let mut buf = data;
+        for _ in 0..header.data_count {
+            let (value, rest) = split_data(buf).ok_or(too_short)?;
+            if !bitmask.is_empty() && value.len() != bitmask.len() {
+                return Err(Error::OutOfRange);
+            }
+            buf = rest;
+        }
+
+        Ok(AllowableData {
+            entry: self,
+            pmr_id: header.pmr_id,
+            measurement_id: header.measurement_id,
+            check,
+            data_count: header.data_count,
+            bitmask,
+            data,
+        })
+    }
+}
+
+/// Splits a single length-prefixed `Data` off of the front of `buf`.
+fn split_data(buf: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len = u16::from_le_bytes([*buf.first()?, *buf.get(1)?]) as usize;
+    let end = 4 + len;
+    let value = buf.get(4..end)?;
+    let rest = buf.get(end + misalign_of(end, 4)..)?;
+    Some((value, rest))
+}
+
+/// An "allowable data" element from a CFM, listing the raw values a single
+/// measurement reported by a component may take.
+///
+/// To obtain a value of this type, see [`ComponentDevice::allowable_data()`]
+/// and [`AllowableDataEntry::read()`].
+pub struct AllowableData<'a, 'cfm, Provenance = provenance::Signed> {
+    entry: AllowableDataEntry<'a, 'cfm, Provenance>,
+    pmr_id: u8,
+    measurement_id: u8,
+    check: DataCheck,
+    data_count: u8,
+    bitmask: &'cfm [u8],
+    data: &'cfm [u8],
+}
+
+impl<'a, 'cfm, P> AllowableData<'a, 'cfm, P> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'cfm, Cfm> {
+        self.entry.entry
+    }
+
+    /// Returns the PMR the measurement is extended into.
+    pub fn pmr_id(&self) -> u8 {
+        self.pmr_id
+    }
+
+    /// Returns the index of the measurement within its PMR.
+    pub fn measurement_id(&self) -> u8 {
+        self.measurement_id
+    }
+
+    /// Returns the comparison to perform against this element's values.
+    pub fn check(&self) -> DataCheck {
+        self.check
+    }
+
+    /// Returns the bitmask to apply before comparing against this element's
+    /// values.
+    ///
+    /// If empty, no mask is applied.
+    pub fn bitmask(&self) -> &'cfm [u8] {
+        self.bitmask
+    }
+
+    /// Returns an iterator over this element's values.
+    pub fn data(&self) -> impl Iterator<Item = &'cfm [u8]> {
+        let mut buf = self.data;
+        (0..self.data_count).map(move |_| {
+            let (value, rest) =
+                split_data(buf).expect("already checked in `read()`");
+            buf = rest;
+            value
+        })
+    }
+
+    /// Returns whether `value`, a measurement reported by a component, is
+    /// acceptable according to this element.
+    ///
+    /// `value` and each of this element's values are masked with the
+    /// bitmask and then compared as big-endian integers; values of a
+    /// different length than `value` are never equal to it and are otherwise
+    /// ignored. For [`DataCheck::NotEqual`], `value` must differ from every
+    /// value; for all other checks, it need only satisfy one.
+    pub fn accepts(&self, value: &[u8]) -> bool {
+        let compare = |data: &[u8]| {
+            if data.len() != value.len() {
+                return None;
+            }
+            let ord = value
+                .iter()
+                .zip(data)
+                .enumerate()
+                .map(|(i, (a, b))| {
+                    let mask = self.bitmask.get(i).copied().unwrap_or(0xff);
+                    (a & mask).cmp(&(b & mask))
+                })
+                .find(|&o| o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal);
+            Some(ord)
+        };
+
+        if self.check == DataCheck::NotEqual {
+            return self.data().all(|d| compare(d) != Some(Ordering::Equal));
+        }
+        self.data().filter_map(compare).any(|ord| match self.check {
+            DataCheck::Equal => ord == Ordering::Equal,
+            DataCheck::NotEqual => unreachable!(),
+            DataCheck::LessThan => ord == Ordering::Less,
+            DataCheck::LessOrEqual => ord != Ordering::Greater,
+            DataCheck::GreaterThan => ord == Ordering::Greater,
+            DataCheck::GreaterOrEqual => ord != Ordering::Less,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::from_str;
+    use testutil::data::keys;
+
+    use crate::crypto::ring;
+    use crate::hardware::flash::Ram;
+    use crate::manifest::owned;
+    use crate::manifest::testdata;
+    use crate::manifest::ManifestExt as _;
+    use crate::mem::OutOfMemory;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn empty() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let cfm: owned::Cfm = from_str(r#"{
+            "version_id": 42,
+            "elements": []
+        }"#).unwrap();
+        let bytes = Ram(cfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let cfm = ParsedCfm::new(container);
+
+        assert!(cfm
+            .platform_id(&mut hasher, &OutOfMemory)
+            .unwrap()
+            .is_none());
+        assert_eq!(cfm.component_devices().count(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn component_devices() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let cfm: owned::Cfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [
+                { "platform_id": "my cfm" },
+                {
+                    "cert_slot": 1,
+                    "attestation_protocol": "Cerberus",
+                    "component_id": "0xc0ffee",
+                    "children": [
+                        {
+                            "pmr_id": 0,
+                            "hash_type": "Sha256",
+                            "digests": [
+                                "5555555555555555555555555555555555555555555555555555555555555555",
+                                "7777777777777777777777777777777777777777777777777777777777777777"
+                            ]
+                        },
+                        {
+                            "pmr_id": 0,
+                            "measurement_id": 2,
+                            "hash_type": "Sha256",
+                            "digests": [
+                                "2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a"
+                            ]
+                        },
+                        {
+                            "pmr_id": 0,
+                            "measurement_id": 3,
+                            "check": "GreaterOrEqual",
+                            "bitmask": "ff00",
+                            "data": ["0500", "0700"]
+                        }
+                    ]
+                }
+            ]
+        }"#).unwrap();
+        let bytes = Ram(cfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let cfm = ParsedCfm::new(container);
+
+        let id = cfm.platform_id(&mut hasher, &OutOfMemory).unwrap().unwrap();
+        assert_eq!(id.id_string(), b"my cfm");
+
+        let mut devices = cfm.component_devices().map(Some).collect::<Vec<_>>();
+        assert_eq!(devices.len(), 1);
+        let device = devices[0]
+            .take()
+            .unwrap()
+            .read(&mut hasher, &OutOfMemory)
+            .unwrap();
+        assert_eq!(device.cert_slot(), 1);
+        assert_eq!(
+            device.attestation_protocol(),
+            AttestationProtocol::Cerberus
+        );
+        assert_eq!(device.component_id(), 0xc0ffee);
+
+        let pmrs = device
+            .pmr_digests()
+            .map(|e| e.read(&mut hasher, &OutOfMemory).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(pmrs.len(), 1);
+        assert_eq!(pmrs[0].pmr_id(), 0);
+        assert_eq!(pmrs[0].hash_type(), hash::Algo::Sha256);
+        assert_eq!(pmrs[0].digests().count(), 2);
+        assert!(pmrs[0].contains(&[0x77; 32]));
+        assert!(!pmrs[0].contains(&[0x66; 32]));
+
+        let measurements = device
+            .measurements()
+            .map(|e| e.read(&mut hasher, &OutOfMemory).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(measurements.len(), 1);
+        assert_eq!(measurements[0].measurement_id(), 2);
+        assert!(measurements[0].contains(&[42; 32]));
+
+        let data = device
+            .allowable_data()
+            .map(|e| e.read(&mut hasher, &OutOfMemory).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].measurement_id(), 3);
+        assert_eq!(data[0].check(), DataCheck::GreaterOrEqual);
+        assert_eq!(data[0].bitmask(), &[0xff, 0x00]);
+        assert_eq!(
+            data[0].data().collect::<Vec<_>>(),
+            [&[0x05, 0x00], &[0x07, 0x00]]
+        );
+
+        assert!(data[0].accepts(&[0x05, 0x00]));
+        assert!(data[0].accepts(&[0x06, 0xff]));
+        assert!(!data[0].accepts(&[0x04, 0xff]));
+        assert!(!data[0].accepts(&[0x06]));
+    }
+}
@@ -6,6 +6,7 @@
 //!
 //! See the `manticore::manifest` documentation for more information.
 
+use core::iter;
 use core::marker::PhantomData;
 use core::mem;
 
@@ -17,10 +18,9 @@ use crate::crypto::hash::EngineExt as _;
 use crate::crypto::sig;
 use crate::hardware::flash::Flash;
 use crate::hardware::flash::FlashExt as _;
-use crate::hardware::flash::FlashIo;
 use crate::hardware::flash::Region;
-use crate::io::Read as _;
 use crate::manifest::provenance;
+use crate::manifest::ElementType;
 use crate::manifest::ElementsOf;
 use crate::manifest::Error;
 use crate::manifest::Manifest;
@@ -47,6 +47,111 @@ pub struct Metadata {
     pub version_id: u32,
 }
 
+impl Metadata {
+    /// Checks whether a manifest with this `Metadata` can replace one with
+    /// `other`, i.e., whether its version ID is greater than or equal to
+    /// `other`'s.
+    ///
+    /// See also [`Container::can_replace()`].
+    pub fn can_replace(&self, other: &Self) -> bool {
+        self.version_id >= other.version_id
+    }
+}
+
+wire_enum! {
+    /// The kind of key a manifest is signed with.
+    ///
+    /// This is encoded in the upper five bits of a manifest's signature type;
+    /// see [`SigType`].
+    pub enum KeyType: u8 {
+        /// An RSA key with a 2048-bit modulus.
+        Rsa2048 = 0x00,
+        /// An RSA key with a 3072-bit modulus.
+        Rsa3072 = 0x08,
+        /// An RSA key with a 4096-bit modulus.
+        Rsa4096 = 0x10,
+        /// An ECDSA key over the NIST P-256 curve.
+        EcdsaP256 = 0x40,
+        /// An ECDSA key over the NIST P-384 curve.
+        EcdsaP384 = 0x48,
+        /// An ECDSA key over the NIST P-521 curve.
+        EcdsaP521 = 0x50,
+    }
+}
+
+impl KeyType {
+    /// Returns whether this is an ECDSA key type.
+    pub fn is_ecdsa(self) -> bool {
+        matches!(self, Self::EcdsaP256 | Self::EcdsaP384 | Self::EcdsaP521)
+    }
+}
+
+/// The signature type of a [`Container`].
+///
+/// On the wire, this is a single byte: the low three bits encode the hash
+/// algorithm used to digest the manifest for signing, and the remaining bits
+/// encode the [`KeyType`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SigType {
+    /// The kind of key the manifest is signed with.
+    pub key_type: KeyType,
+    /// The hash algorithm the signature is computed with.
+    pub hash_type: hash::Algo,
+}
+
+impl SigType {
+    /// Decodes a `SigType` from its wire encoding, returning `None` if it is
+    /// not a valid encoding.
+    pub fn from_wire_value(byte: u8) -> Option<Self> {
+        let hash_type = match byte & 0b111 {
+            0b00 => hash::Algo::Sha256,
+            0b01 => hash::Algo::Sha384,
+            0b10 => hash::Algo::Sha512,
+            _ => return None,
+        };
+        let key_type = KeyType::from_wire_value(byte & !0b111)?;
+        Some(Self {
+            key_type,
+            hash_type,
+        })
+    }
+
+    /// Encodes this `SigType` into its wire encoding.
+    pub fn to_wire_value(self) -> u8 {
+        let hash_type = match self.hash_type {
+            hash::Algo::Sha256 => 0b00,
+            hash::Algo::Sha384 => 0b01,
+            hash::Algo::Sha512 => 0b10,
+        };
+        self.key_type.to_wire_value() | hash_type
+    }
+
+    /// Returns the [`sig::Algo`] used to verify signatures of this type, if
+    /// Manticore supports one.
+    ///
+    /// Manifest signatures are computed over a digest of the signed region of
+    /// a manifest, using [`SigType::hash_type`]; a verifier for this
+    /// algorithm, which will be handed that digest, is what should be passed
+    /// to [`Container::parse_and_verify()`]. ECDSA signatures are DER-encoded,
+    /// and zero-padded out to the manifest's signature length.
+    pub fn sig_algo(self) -> Option<sig::Algo> {
+        match (self.key_type, self.hash_type) {
+            (KeyType::Rsa2048, hash::Algo::Sha256)
+            | (KeyType::Rsa3072, hash::Algo::Sha256)
+            | (KeyType::Rsa4096, hash::Algo::Sha256) => {
+                Some(sig::Algo::RsaPkcs1Sha256)
+            }
+            (KeyType::EcdsaP256, hash::Algo::Sha256) => {
+                Some(sig::Algo::EcdsaDerP256)
+            }
+            (KeyType::EcdsaP384, hash::Algo::Sha384) => {
+                Some(sig::Algo::EcdsaDerP384)
+            }
+            _ => None,
+        }
+    }
+}
+
 /// A TOC entry's raw bits.
 #[derive(Copy, Clone, PartialEq, Eq, Default, Debug, AsBytes, FromBytes)]
 #[repr(C)]
@@ -92,9 +197,10 @@ impl<'entry, 'toc, M: Manifest> TocEntry<'entry, 'toc, M> {
 
     /// Returns the format version of this TOC entry.
     ///
-    /// Note that this is always a version Manticore supports;
-    /// Manticore will refuse to parse TOCs with versions it does not
-    /// support.
+    /// Note that this is never below [`Manifest::min_version()`] for
+    /// manifest-specific elements; Manticore will refuse to parse TOCs with
+    /// versions older than it supports. Newer versions are parsed by
+    /// ignoring any trailing fields.
     pub fn format_version(self) -> u8 {
         self.raw().format_version
     }
@@ -265,6 +371,17 @@ impl<'toc, M: Manifest> Toc<'toc, M> {
                 return Err(Error::BadHashIndex { toc_index: i });
             }
 
+            // Elements newer than the version Manticore understands are
+            // fine, since trailing fields are ignored, but older ones are
+            // missing fields that Manticore expects.
+            if let Some(ElementType::Specific(ty)) =
+                ElementsOf::<M>::from_wire_value(entry.element_type)
+            {
+                if entry.format_version < M::min_version(ty) {
+                    return Err(Error::UnsupportedVersion { toc_index: i });
+                }
+            }
+
             // NOTE: Unfortunately, this check is necessarilly quadratic
             // in the TOC size.
             if entry.parent_type != 0xff {
@@ -392,6 +509,11 @@ impl<'f, M: Manifest> Container<'f, M, provenance::Signed> {
     /// This is the only function capable of prodiucing a container with the
     /// `Signed` provenance.
     ///
+    /// `sig_verify` must implement the algorithm given by the manifest's
+    /// [`SigType::sig_algo()`]. The signed portion of the manifest is hashed
+    /// a chunk at a time, so `verify_arena` only needs to hold the signature
+    /// and TOC hash.
+    ///
     /// `buf` must be aligned to a four-byte boundary.
     pub fn parse_and_verify(
         flash: &'f dyn Flash,
@@ -467,35 +589,42 @@ impl<'f, M: Manifest, Provenance> Container<'f, M, Provenance> {
     }
 
     /// Verifies the signature for this `Container`.
+    ///
+    /// The signature is computed over a digest of the entire signed region, as
+    /// described by the manifest's [`SigType`].
     pub(crate) fn verify_signature(
         &self,
         hasher: &mut dyn hash::Engine,
         sig_verify: &mut dyn sig::Verify,
         verify_arena: &dyn Arena,
     ) -> Result<(), Error> {
-        let mut bytes = [0u8; 16];
-        let signed_region = self.signed_region();
-        let mut r = FlashIo::new(&self.flash)?;
-        r.reslice(signed_region);
-
-        // Currently hard-coded to SHA-256, but this is not the correct way
-        // to verify the signature; the signature is not a hash of hashes as
-        // specified, but we do not have a way to do this with our current
-        // APIs.
-        let mut hasher = hasher.new_hash(hash::Algo::Sha256)?;
-        while r.remaining_data() > 0 {
-            let to_read = r.remaining_data().min(16);
-            r.read_bytes(&mut bytes[..to_read])?;
-            hasher.write(&bytes[..to_read])?;
+        let sig_type = self.sig_type();
+        if sig_type.sig_algo().is_none() {
+            return Err(Error::UnsupportedSigType(sig_type));
         }
 
-        let mut digest = [0; 32];
-        hasher.finish(&mut digest)?;
-
-        let sig =
+        let mut sig =
             self.flash
                 .read_direct(self.signature_region(), verify_arena, 1)?;
-        sig_verify.verify(&[&digest], sig)?;
+        if sig_type.key_type.is_ecdsa() {
+            sig = strip_der_padding(sig);
+        }
+
+        let hash_type = sig_type.hash_type;
+        if !hasher.supports(hash_type) {
+            return Err(Error::UnsupportedSigType(sig_type));
+        }
+        let mut digest = [0; hash::Algo::Sha512.bytes()];
+        let digest = &mut digest[..hash_type.bytes()];
+        let mut hasher = hasher.new_hash(hash_type)?;
+        hasher.write_flash(
+            self.flash,
+            iter::once(self.signed_region()),
+            &mut [0; 64],
+        )?;
+        hasher.finish(digest)?;
+
+        sig_verify.verify(&[digest], sig)?;
         Ok(())
     }
 
@@ -506,7 +635,6 @@ impl<'f, M: Manifest, Provenance> Container<'f, M, Provenance> {
         flash: &'f dyn Flash,
         toc_arena: &'f dyn Arena,
     ) -> Result<Self, Error> {
-        // TODO(#58): Manticore currently ignores header.sig_type.
         let header = flash.read_object::<RawHeader>(0, toc_arena)?;
 
         if ManifestType::from_wire_value(header.manifest_type) != Some(M::TYPE)
@@ -518,6 +646,10 @@ impl<'f, M: Manifest, Provenance> Container<'f, M, Provenance> {
             return Err(Error::OutOfRange);
         }
 
+        if SigType::from_wire_value(header.sig_ty).is_none() {
+            return Err(Error::OutOfRange);
+        }
+
         let hash_type = match header.hash_type {
             0b00 => hash::Algo::Sha256,
             0b01 => hash::Algo::Sha384,
@@ -566,6 +698,12 @@ impl<'f, M: Manifest, Provenance> Container<'f, M, Provenance> {
             .expect("verified in parse_inner()")
     }
 
+    /// Returns the [`SigType`] for this `Container`.
+    pub fn sig_type(&self) -> SigType {
+        SigType::from_wire_value(self.header.sig_ty)
+            .expect("verified in parse_inner()")
+    }
+
     /// Checks whether this `Container` can replace `other`.
     ///
     /// In other words, `self` must:
@@ -573,7 +711,7 @@ impl<'f, M: Manifest, Provenance> Container<'f, M, Provenance> {
     /// - Have a greater or equal `id` number than `other`.
     pub fn can_replace(&self, other: &Self) -> bool {
         self.header.manifest_type == other.header.manifest_type
-            && self.header.version_id >= other.header.version_id
+            && self.metadata().can_replace(&other.metadata())
     }
 
     /// Returns this `Container`'s [`Metadata`] value.
@@ -606,6 +744,20 @@ impl<'f, M: Manifest, Provenance> Container<'f, M, Provenance> {
     }
 }
 
+/// Strips the zero-padding that follows a DER-encoded ECDSA signature in a
+/// manifest or recovery image.
+///
+/// If `sig` does not look like a DER `SEQUENCE`, it is returned unchanged,
+/// and left for the verifier to reject.
+pub(crate) fn strip_der_padding(sig: &[u8]) -> &[u8] {
+    let len = match *sig {
+        [0x30, len, ..] if len < 0x80 => len as usize + 2,
+        [0x30, 0x81, len, ..] => len as usize + 3,
+        _ => return sig,
+    };
+    sig.get(..len).unwrap_or(sig)
+}
+
 #[cfg(test)]
 pub(crate) mod test {
     use super::*;
@@ -614,11 +766,13 @@ pub(crate) mod test {
     use testutil::data::keys;
 
     use crate::crypto::ring;
+    use crate::crypto::sig::Verify as _;
     use crate::hardware::flash::Ram;
     use crate::manifest;
     use crate::manifest::owned;
     use crate::manifest::pfm;
     use crate::manifest::pfm::Pfm;
+    use crate::manifest::testdata;
     use crate::mem::OutOfMemory;
 
     // NOTE: To effectively run these tests, we use PFM-from-JSON to generate
@@ -638,7 +792,7 @@ pub(crate) mod test {
             "elements": []
         }"#).unwrap();
         let bytes = Ram(pfm
-            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
             .unwrap());
 
         let container: Container<Pfm> = Container::parse_and_verify(
@@ -658,6 +812,247 @@ pub(crate) mod test {
         assert_eq!(toc.entries().count(), 0);
     }
 
+    #[test]
+    fn sig_type_encoding() {
+        let sig_type = SigType::from_wire_value(0x49).unwrap();
+        assert_eq!(sig_type.key_type, KeyType::EcdsaP384);
+        assert_eq!(sig_type.hash_type, hash::Algo::Sha384);
+        assert_eq!(sig_type.to_wire_value(), 0x49);
+
+        assert_eq!(testdata::RSA_SHA256.to_wire_value(), 0x00);
+        assert!(SigType::from_wire_value(0x03).is_none());
+        assert!(SigType::from_wire_value(0x20).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn signature() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [{ "platform_id": "blah" }]
+        }"#).unwrap();
+        let bytes = pfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap();
+
+        let flash = Ram(&bytes);
+        let container: Container<Pfm> = Container::parse_and_verify(
+            &flash,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        assert_eq!(container.sig_type(), testdata::RSA_SHA256);
+
+        // The signature covers a digest of the signed bytes, rather than the
+        // bytes themselves.
+        let signed = container.signed_region();
+        let (data, sig) = bytes.split_at(signed.len as usize);
+        let mut digest = [0; 32];
+        hasher
+            .contiguous_hash(hash::Algo::Sha256, data, &mut digest)
+            .unwrap();
+        assert!(rsa.verify(&[&digest], sig).is_ok());
+        assert!(rsa.verify(&[data], sig).is_err());
+
+        // RSA-2048 with SHA-384.
+        let mut unsupported = bytes.clone();
+        unsupported[10] = 0x01;
+        let flash = Ram(&unsupported);
+        let result = Container::<Pfm>::parse_and_verify(
+            &flash,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        );
+        assert!(matches!(
+            result,
+            Err(manifest::Error::UnsupportedSigType(SigType {
+                key_type: KeyType::Rsa2048,
+                hash_type: hash::Algo::Sha384,
+            }))
+        ));
+
+        let mut invalid = bytes.clone();
+        invalid[10] = 0x07;
+        let flash = Ram(&invalid);
+        let result = Container::<Pfm>::parse_and_verify(
+            &flash,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        );
+        assert!(matches!(result, Err(manifest::Error::OutOfRange)));
+    }
+
+    /// A verifier that checks that it is handed exactly `digest`.
+    struct Digest {
+        digest: Vec<u8>,
+        signature: Vec<u8>,
+    }
+
+    impl sig::Verify for Digest {
+        fn verify(
+            &mut self,
+            message_vec: &[&[u8]],
+            signature: &[u8],
+        ) -> Result<(), sig::Error> {
+            if message_vec != [&self.digest[..]] || signature != self.signature
+            {
+                return Err(sig::Error::Unspecified);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn streamed_signature() {
+        let mut hasher = ring::hash::Engine::new();
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [{ "platform_id": "blah" }]
+        }"#).unwrap();
+        let bytes = pfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap();
+
+        let flash = Ram(&bytes);
+        let container =
+            Container::<Pfm, _>::parse(&flash, &OutOfMemory).unwrap();
+        let signed = container.signed_region().len as usize;
+        let mut digest = [0; 32];
+        hasher
+            .contiguous_hash(hash::Algo::Sha256, &bytes[..signed], &mut digest)
+            .unwrap();
+
+        // The signed region is streamed through the hasher, and only its
+        // digest is handed to the verifier.
+        let mut verify = Digest {
+            digest: digest.to_vec(),
+            signature: bytes[signed..].to_vec(),
+        };
+        let container = Container::<Pfm>::parse_and_verify(
+            &flash,
+            &mut hasher,
+            &mut verify,
+            &OutOfMemory,
+            &OutOfMemory,
+        );
+        assert!(container.is_ok());
+
+        let mut tampered = bytes.clone();
+        tampered[signed - 1] ^= 0xff;
+        let flash = Ram(&tampered);
+        let result = Container::<Pfm>::parse_and_verify(
+            &flash,
+            &mut hasher,
+            &mut verify,
+            &OutOfMemory,
+            &OutOfMemory,
+        );
+        assert!(matches!(
+            result,
+            Err(manifest::Error::SigError(sig::Error::Unspecified))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn ecdsa_signature() {
+        let mut hasher = ring::hash::Engine::new();
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [{ "platform_id": "blah" }]
+        }"#).unwrap();
+
+        let mut p256 = ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(
+            keys::KEY1_ECDSA_P256_KEYPAIR,
+        )
+        .unwrap();
+        let sig_type = SigType {
+            key_type: KeyType::EcdsaP256,
+            hash_type: hash::Algo::Sha256,
+        };
+        let bytes =
+            Ram(pfm.sign(0x0, sig_type, &mut hasher, &mut p256).unwrap());
+        let container: Container<Pfm> = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut p256.verifier(),
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        assert_eq!(container.sig_type(), sig_type);
+
+        let mut p384 = ring::ecdsa::SignP384::with_der_encoding_from_pkcs8(
+            keys::KEY1_ECDSA_P384_KEYPAIR,
+        )
+        .unwrap();
+        let sig_type = SigType {
+            key_type: KeyType::EcdsaP384,
+            hash_type: hash::Algo::Sha384,
+        };
+        let bytes =
+            Ram(pfm.sign(0x0, sig_type, &mut hasher, &mut p384).unwrap());
+        let container: Container<Pfm> = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut p384.verifier(),
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        assert_eq!(container.sig_type(), sig_type);
+        assert_eq!(container.toc().hash_type(), hash::Algo::Sha384);
+
+        // A verifier for the wrong key should still fail.
+        let result = Container::<Pfm>::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut p256.verifier(),
+            &OutOfMemory,
+            &OutOfMemory,
+        );
+        assert!(matches!(result, Err(manifest::Error::SigError(_))));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn sign_unsupported() {
+        let mut hasher = ring::hash::Engine::new();
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 42,
+            "elements": []
+        }"#).unwrap();
+        let sig_type = SigType {
+            key_type: KeyType::Rsa2048,
+            hash_type: hash::Algo::Sha512,
+        };
+        assert!(matches!(
+            pfm.sign(0x0, sig_type, &mut hasher, &mut signer),
+            Err(owned::EncodingError::UnsupportedSigType(_))
+        ));
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn one_element() {
@@ -671,7 +1066,7 @@ pub(crate) mod test {
             "elements": [{ "platform_id": "blah" }]
         }"#).unwrap();
         let bytes = Ram(pfm
-            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
             .unwrap());
 
         let container: Container<Pfm> = Container::parse_and_verify(
@@ -720,7 +1115,7 @@ pub(crate) mod test {
             }]
         }"#).unwrap();
         let bytes = Ram(pfm
-            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
             .unwrap());
 
         let container: Container<Pfm> = Container::parse_and_verify(
@@ -759,4 +1154,139 @@ pub(crate) mod test {
         assert_eq!(second.parent().unwrap().index(), 0);
         assert!(second.hash().is_none());
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn format_version() {
+        /// A PFM whose parser only understands newer `FlashDevice`s.
+        enum NewerPfm {}
+        impl Manifest for NewerPfm {
+            type ElementType = pfm::ElementType;
+            const TYPE: ManifestType = ManifestType::Pfm;
+
+            fn min_version(ty: pfm::ElementType) -> u8 {
+                match ty {
+                    pfm::ElementType::FlashDevice => 1,
+                    _ => 0,
+                }
+            }
+        }
+
+        let mut hasher = ring::hash::Engine::new();
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [
+                {
+                    "platform_id": "blah",
+                    "format_version": 3,
+                    "trailing": "c0ffee00"
+                },
+                { "blank_byte": "0x55" }
+            ]
+        }"#).unwrap();
+        let bytes = Ram(pfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+
+        let container =
+            Container::<Pfm, _>::parse(&bytes, &OutOfMemory).unwrap();
+        let toc = container.toc();
+        assert_eq!(toc.entry(0).unwrap().format_version(), 3);
+        assert_eq!(toc.entry(1).unwrap().format_version(), 0);
+
+        // The trailing bytes of the newer platform ID survive a round trip.
+        let parse = owned::Pfm::parse(&bytes.0, &mut hasher, None).unwrap();
+        assert_eq!(parse.container, pfm);
+
+        let result = Container::<NewerPfm, _>::parse(&bytes, &OutOfMemory);
+        assert!(matches!(
+            result,
+            Err(manifest::Error::UnsupportedVersion { toc_index: 1 })
+        ));
+
+        let mut pfm = pfm;
+        pfm.elements[1].format_version = 1;
+        pfm.elements[1].trailing = vec![0; 4];
+        let bytes = Ram(pfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+        let container =
+            Container::<NewerPfm, _>::parse(&bytes, &OutOfMemory).unwrap();
+        assert_eq!(container.toc().entry(1).unwrap().format_version(), 1);
+    }
+
+    /// Checks that `M` rejects `element` exactly when it is encoded with a
+    /// format version below `M::min_version()`.
+    fn check_min_version<M, E>(element: E)
+    where
+        M: Manifest<ElementType = E::ElementType>,
+        E: owned::Element + Clone,
+    {
+        let mut hasher = ring::hash::Engine::new();
+        let (_, mut signer) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let ty = match element.element_type() {
+            manifest::ElementType::Specific(ty) => ty,
+            _ => panic!("expected a manifest-specific element"),
+        };
+        let min = M::min_version(ty);
+        for version in min.saturating_sub(1)..=min.saturating_add(1) {
+            let trailing = if version > element.format_version() {
+                vec![0; 4]
+            } else {
+                vec![]
+            };
+            let manifest = owned::Container {
+                metadata: Metadata { version_id: 42 },
+                elements: vec![owned::Node {
+                    hashed: true,
+                    format_version: version,
+                    trailing,
+                    element: element.clone(),
+                    children: vec![],
+                }],
+            };
+            let bytes = Ram(manifest
+                .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+                .unwrap());
+
+            let result = Container::<M, _>::parse(&bytes, &OutOfMemory);
+            if version < min {
+                assert!(matches!(
+                    result,
+                    Err(manifest::Error::UnsupportedVersion { toc_index: 0 })
+                ));
+            } else {
+                let container = result.unwrap();
+                let entry = container.toc().entry(0).unwrap();
+                assert_eq!(entry.format_version(), version);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn min_version() {
+        check_min_version::<Pfm, _>(owned::pfm::Element::FlashDevice {
+            blank_byte: 0xff,
+        });
+        check_min_version::<manifest::cfm::Cfm, _>(
+            owned::cfm::Element::ComponentDevice {
+                cert_slot: 0,
+                attestation_protocol:
+                    manifest::cfm::AttestationProtocol::Cerberus,
+                component_id: 0x1234,
+            },
+        );
+        check_min_version::<manifest::pcd::Pcd, _>(owned::pcd::Element::Port {
+            port_id: 0,
+            flags: 0,
+            policy: manifest::pcd::Policy::Passive,
+            pulse_interval: 0,
+            spi_frequency_hz: 0,
+        });
+    }
 }
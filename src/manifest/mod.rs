@@ -21,7 +21,7 @@
 //!     manifest_type: u16, // See `ManifestType`.
 //!     version_id: u32,
 //!     signature_len: u16,
-//!     signature_type: u8,
+//!     signature_type: u8, // See `SigType`.
 //!     _: u8,
 //!
 //!     // Table-of-contents.
@@ -61,6 +61,12 @@
 //! For more information on how to interact with the `TOC`, see
 //! the [`Toc`](struct.Toc.html) type.
 //!
+//! The signature is computed over a digest of every byte of the manifest that
+//! precedes it, using the key and hash algorithm described by
+//! `signature_type`: the digest is computed with that hash algorithm, and is
+//! then signed as a message in its own right. This allows the manifest to be
+//! streamed through a hasher, rather than held in memory in its entirety.
+//!
 //! # Parsing and Encoding APIs
 //!
 //! The `manticore::manifest` module provides an API for reading a manifest
@@ -77,24 +83,33 @@ use crate::crypto::hash;
 use crate::crypto::sig;
 use crate::hardware::flash;
 use crate::io;
+use crate::mem::Arena;
 use crate::mem::OutOfMemory;
 use crate::protocol::wire::WireEnum;
 
+pub mod cfm;
+
 mod container;
 pub use container::Container;
+pub use container::KeyType;
 pub use container::Metadata;
+pub use container::SigType;
 pub use container::Toc;
 pub use container::TocEntry;
 
+pub(crate) use container::strip_der_padding;
+
 mod generic;
 pub use generic::*;
 
 #[cfg(feature = "std")]
 pub mod owned;
+pub mod pcd;
 pub mod pfm;
+pub mod store;
 
 #[cfg(test)]
-mod testdata;
+pub(crate) mod testdata;
 
 #[cfg(doc)]
 use crate::hardware::flash::Flash;
@@ -109,6 +124,18 @@ wire_enum! {
         ///
         /// ["Platform Firmware Manifest"]: pfm/index.html
         Pfm = 0x706d,
+
+        /// A ["Component Firmware Manifest"], a manifest which describes
+        /// measurements that attested components are expected to report.
+        ///
+        /// ["Component Firmware Manifest"]: cfm/index.html
+        Cfm = 0xa592,
+
+        /// A ["Platform Configuration Data"] manifest, which describes the
+        /// configuration of the RoT itself.
+        ///
+        /// ["Platform Configuration Data"]: pcd/index.html
+        Pcd = 0x1029,
     }
 }
 
@@ -201,6 +228,13 @@ pub enum Error {
         toc_index: usize,
     },
 
+    /// Indicates that a TOC entry had a format version older than the one
+    /// Manticore understands; see [`Manifest::min_version()`].
+    UnsupportedVersion {
+        /// The index of the bad entry.
+        toc_index: usize,
+    },
+
     /// Indicates that a TOC entry's hash did not match the actual value.
     BadElementHash {
         /// The reason for the failure.
@@ -233,6 +267,10 @@ pub enum Error {
     /// the hash engine being used.
     UnsupportedHashType(hash::Algo),
 
+    /// Indicates that a manifest was signed with a [`SigType`] that Manticore
+    /// cannot verify.
+    UnsupportedSigType(SigType),
+
     /// Indicates that the signature length is incompatible with either the
     /// given manifest length or the signature algorithm.
     BadSignatureLen,
@@ -242,6 +280,29 @@ pub enum Error {
 
     /// Indicates that a signature operation failed for some reason.
     SigError(sig::Error),
+
+    /// Indicates that none of the versions listed in a manifest element
+    /// matched the contents of the guarded device.
+    UnknownVersion {
+        /// The index of the element whose versions did not match.
+        toc_index: usize,
+    },
+
+    /// Indicates that a read-write region needed to be restored from a
+    /// known-good copy of a device, but none was available.
+    NoRestoreSource {
+        /// The index of the element describing the region.
+        toc_index: usize,
+    },
+
+    /// Indicates that an image in the guarded device did not match the hash
+    /// recorded for it in a manifest.
+    BadImageHash {
+        /// The reason for the failure.
+        error: hash::Error,
+        /// The index of the element describing the image.
+        toc_index: usize,
+    },
 }
 
 impl From<io::Error> for Error {
@@ -274,6 +335,15 @@ impl From<hash::Error> for Error {
     }
 }
 
+impl From<hash::FlashError> for Error {
+    fn from(e: hash::FlashError) -> Self {
+        match e {
+            hash::FlashError::Flash(e) => Self::Flash(e),
+            hash::FlashError::Hash(e) => Self::HashError(e),
+        }
+    }
+}
+
 /// A manifest type.
 ///
 /// A type that implements this trait is not itself a "parsed" instance of the
@@ -296,6 +366,9 @@ pub trait Manifest: Sized {
     /// The minimum version of a particular `ElementType` understood by
     /// Manticore. All manifest elements must be future-compatible, so knowing
     /// a "maximum version" is not necessary.
+    ///
+    /// TOC entries with an older `format_version` are rejected with
+    /// [`Error::UnsupportedVersion`].
     fn min_version(ty: Self::ElementType) -> u8;
 }
 
@@ -337,11 +410,14 @@ pub trait Parse<'f, Provenance>: Manifest {
     fn container(manifest: &Self::Parsed) -> &Container<'f, Self, Provenance>;
 
     /// The type of data this manifest guards.
-    type Guarded;
+    type Guarded: ?Sized;
     /// Validates that `manifest` is "valid"; that is, whatever state of
     /// the system this manifest protects is consistent with the manifest's
     /// expectation.
     ///
+    /// `hasher` and `arena` are used for reading elements out of `manifest`,
+    /// and for whatever hashing the check itself requires.
+    ///
     /// Some manifests may not have anything interesting to do here; in that
     /// case `Self::Guarded` should be `()` and this function should do
     /// nothing.
@@ -349,6 +425,8 @@ pub trait Parse<'f, Provenance>: Manifest {
         manifest: &Self::Parsed,
         when: ValidationTime,
         args: &Self::Guarded,
+        hasher: &mut dyn hash::Engine,
+        arena: &'f dyn Arena,
     ) -> Result<(), Error>;
 }
 
@@ -0,0 +1,497 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! CFM element structures.
+//!
+//! See [`owned::Cfm`](../type.Cfm.html).
+
+use core::convert::TryInto;
+
+use crate::crypto::hash;
+use crate::crypto::ring;
+use crate::manifest;
+use crate::manifest::cfm;
+use crate::manifest::cfm::AttestationProtocol;
+use crate::manifest::cfm::DataCheck;
+use crate::manifest::owned;
+use crate::manifest::owned::EncodingError;
+use crate::manifest::provenance;
+use crate::manifest::Error;
+use crate::manifest::ManifestExt as _;
+use crate::manifest::ManifestType;
+use crate::mem::misalign_of;
+use crate::mem::Arena as _;
+use crate::mem::BumpArena;
+use crate::protocol::wire::WireEnum as _;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An owned CFM element.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[allow(missing_docs)]
+pub enum Element {
+    // NOTE: Because this enum is untagged, variants whose fields are a
+    // superset of another variant's must come first.
+    AllowableData {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        pmr_id: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        measurement_id: u8,
+        check: DataCheck,
+        #[cfg_attr(
+            feature = "serde",
+            serde(
+                with = "crate::serde::hexstring",
+                default,
+                skip_serializing_if = "Vec::is_empty"
+            )
+        )]
+        bitmask: Vec<u8>,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::hexstrings")
+        )]
+        data: Vec<Vec<u8>>,
+    },
+    Measurement {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        pmr_id: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        measurement_id: u8,
+        hash_type: hash::Algo,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::hexstrings")
+        )]
+        digests: Vec<Vec<u8>>,
+    },
+    PmrDigest {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        pmr_id: u8,
+        hash_type: hash::Algo,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::hexstrings")
+        )]
+        digests: Vec<Vec<u8>>,
+    },
+    ComponentDevice {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        cert_slot: u8,
+        attestation_protocol: AttestationProtocol,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        component_id: u32,
+    },
+    PlatformId {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::bytestring")
+        )]
+        platform_id: Vec<u8>,
+    },
+}
+
+/// Encodes a hash type and a list of digests of that type.
+fn encode_digests(
+    hash_type: hash::Algo,
+    digests: &[Vec<u8>],
+) -> Result<(u8, u8, Vec<u8>), EncodingError> {
+    let count: u8 = digests
+        .len()
+        .try_into()
+        .map_err(|_| EncodingError::TooManyElements)?;
+    let mut bytes = Vec::new();
+    for digest in digests {
+        if digest.len() != hash_type.bytes() {
+            return Err(EncodingError::BadHashLen(digest.clone()));
+        }
+        bytes.extend_from_slice(digest);
+    }
+
+    let hash_type = match hash_type {
+        hash::Algo::Sha256 => 0b00,
+        hash::Algo::Sha384 => 0b01,
+        hash::Algo::Sha512 => 0b10,
+    };
+    Ok((hash_type, count, bytes))
+}
+
+impl owned::Element for Element {
+    type ElementType = cfm::ElementType;
+    const TYPE: ManifestType = ManifestType::Cfm;
+
+    fn element_type(&self) -> manifest::ElementType<cfm::ElementType> {
+        match self {
+            Self::PlatformId { .. } => manifest::ElementType::PlatformId,
+
+            Self::ComponentDevice { .. } => {
+                cfm::ElementType::ComponentDevice.into()
+            }
+            Self::PmrDigest { .. } => cfm::ElementType::PmrDigest.into(),
+            Self::Measurement { .. } => cfm::ElementType::Measurement.into(),
+            Self::AllowableData { .. } => {
+                cfm::ElementType::AllowableData.into()
+            }
+        }
+    }
+
+    fn to_bytes(&self, padding_byte: u8) -> Result<Vec<u8>, EncodingError> {
+        match self {
+            Self::ComponentDevice {
+                cert_slot,
+                attestation_protocol,
+                component_id,
+            } => {
+                let mut bytes = vec![
+                    *cert_slot,
+                    attestation_protocol.to_wire_value(),
+                    padding_byte,
+                    padding_byte,
+                ];
+                bytes.extend_from_slice(&component_id.to_le_bytes());
+                Ok(bytes)
+            }
+            Self::PmrDigest {
+                pmr_id,
+                hash_type,
+                digests,
+            } => {
+                let (hash_type, count, digests) =
+                    encode_digests(*hash_type, digests)?;
+                let mut bytes = vec![*pmr_id, count, hash_type, padding_byte];
+                bytes.extend_from_slice(&digests);
+                Ok(bytes)
+            }
+            Self::Measurement {
+                pmr_id,
+                measurement_id,
+                hash_type,
+                digests,
+            } => {
+                let (hash_type, count, digests) =
+                    encode_digests(*hash_type, digests)?;
+                let mut bytes =
+                    vec![*pmr_id, *measurement_id, count, hash_type];
+                bytes.extend_from_slice(&digests);
+                Ok(bytes)
+            }
+            Self::AllowableData {
+                pmr_id,
+                measurement_id,
+                check,
+                bitmask,
+                data,
+            } => {
+                let data_count: u8 = data
+                    .len()
+                    .try_into()
+                    .map_err(|_| EncodingError::TooManyElements)?;
+                let bitmask_len: u16 = bitmask
+                    .len()
+                    .try_into()
+                    .map_err(|_| EncodingError::OutOfSpace)?;
+                let mut bytes = vec![
+                    *pmr_id,
+                    *measurement_id,
+                    check.to_wire_value(),
+                    data_count,
+                ];
+                bytes.extend_from_slice(&bitmask_len.to_le_bytes());
+                bytes.extend_from_slice(&[padding_byte; 2]);
+
+                bytes.extend_from_slice(bitmask);
+                for _ in 0..misalign_of(bytes.len(), 4) {
+                    bytes.push(padding_byte);
+                }
+
+                for value in data {
+                    let len: u16 = value
+                        .len()
+                        .try_into()
+                        .map_err(|_| EncodingError::OutOfSpace)?;
+                    bytes.extend_from_slice(&len.to_le_bytes());
+                    bytes.extend_from_slice(&[padding_byte; 2]);
+
+                    bytes.extend_from_slice(value);
+                    for _ in 0..misalign_of(bytes.len(), 4) {
+                        bytes.push(padding_byte);
+                    }
+                }
+
+                Ok(bytes)
+            }
+            Self::PlatformId { platform_id: id } => {
+                let id_len: u8 = id
+                    .len()
+                    .try_into()
+                    .map_err(|_| EncodingError::StringTooLong(id.clone()))?;
+                let mut bytes = vec![padding_byte; 4];
+                bytes[0] = id_len;
+
+                bytes.extend_from_slice(id);
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+impl owned::FromUnowned for Element {
+    type Manifest = manifest::cfm::Cfm;
+
+    fn from_container(
+        container: manifest::Container<Self::Manifest, provenance::Adhoc>,
+    ) -> Result<Vec<owned::Node<Self>>, Error> {
+        let mut arena = BumpArena::new(vec![0; 2048]);
+        let flash = container.flash();
+        let cfm = manifest::cfm::ParsedCfm::new(container);
+        let mut h = ring::hash::Engine::new();
+        let mut nodes = Vec::new();
+
+        if let Some(id) = cfm.platform_id(&mut h, &arena)? {
+            nodes.push(owned::Node::from_entry(
+                Element::PlatformId {
+                    platform_id: id.id_string().to_vec(),
+                },
+                id.entry(),
+                flash,
+            )?)
+        }
+        arena.reset();
+
+        for device in cfm.component_devices() {
+            let device = device.read(&mut h, &arena)?;
+
+            let mut node = owned::Node::from_entry(
+                Element::ComponentDevice {
+                    cert_slot: device.cert_slot(),
+                    attestation_protocol: device.attestation_protocol(),
+                    component_id: device.component_id(),
+                },
+                device.entry(),
+                flash,
+            )?;
+
+            for pmr in device.pmr_digests() {
+                let pmr = pmr.read(&mut h, &arena)?;
+                node.children.push(owned::Node::from_entry(
+                    Element::PmrDigest {
+                        pmr_id: pmr.pmr_id(),
+                        hash_type: pmr.hash_type(),
+                        digests: pmr.digests().map(<[u8]>::to_vec).collect(),
+                    },
+                    pmr.entry(),
+                    flash,
+                )?);
+            }
+
+            for measurement in device.measurements() {
+                let measurement = measurement.read(&mut h, &arena)?;
+                node.children.push(owned::Node::from_entry(
+                    Element::Measurement {
+                        pmr_id: measurement.pmr_id(),
+                        measurement_id: measurement.measurement_id(),
+                        hash_type: measurement.hash_type(),
+                        digests: measurement
+                            .digests()
+                            .map(<[u8]>::to_vec)
+                            .collect(),
+                    },
+                    measurement.entry(),
+                    flash,
+                )?);
+            }
+
+            for data in device.allowable_data() {
+                let data = data.read(&mut h, &arena)?;
+                node.children.push(owned::Node::from_entry(
+                    Element::AllowableData {
+                        pmr_id: data.pmr_id(),
+                        measurement_id: data.measurement_id(),
+                        check: data.check(),
+                        bitmask: data.bitmask().to_vec(),
+                        data: data.data().map(<[u8]>::to_vec).collect(),
+                    },
+                    data.entry(),
+                    flash,
+                )?);
+            }
+
+            nodes.push(node);
+            arena.reset();
+        }
+
+        Ok(nodes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::from_str;
+    use testutil::data::keys;
+
+    use crate::crypto::ring::rsa;
+    use crate::manifest::owned::Cfm;
+    use crate::manifest::testdata;
+    use crate::manifest::Metadata;
+
+    fn device() -> owned::Node<Element> {
+        owned::Node {
+            element: Element::ComponentDevice {
+                cert_slot: 1,
+                attestation_protocol: AttestationProtocol::Cerberus,
+                component_id: 0xc0ffee,
+            },
+            children: vec![
+                owned::Node {
+                    element: Element::PmrDigest {
+                        pmr_id: 0,
+                        hash_type: hash::Algo::Sha256,
+                        digests: vec![vec![0x55; 32], vec![0x77; 32]],
+                    },
+                    children: vec![],
+                    hashed: true,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+                owned::Node {
+                    element: Element::Measurement {
+                        pmr_id: 0,
+                        measurement_id: 2,
+                        hash_type: hash::Algo::Sha256,
+                        digests: vec![vec![42; 32]],
+                    },
+                    children: vec![],
+                    hashed: false,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+                owned::Node {
+                    element: Element::AllowableData {
+                        pmr_id: 0,
+                        measurement_id: 3,
+                        check: DataCheck::Equal,
+                        bitmask: vec![],
+                        data: vec![b"abc".to_vec(), b"defgh".to_vec()],
+                    },
+                    children: vec![],
+                    hashed: true,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+            ],
+            hashed: true,
+            format_version: 0,
+            trailing: vec![],
+        }
+    }
+
+    #[test]
+    fn parse_component_device() {
+        #[rustfmt::skip]
+        let cfm: Cfm = from_str(r#"{
+            "version_id": 42,
+            "elements": [{
+                "cert_slot": 1,
+                "attestation_protocol": "Cerberus",
+                "component_id": "0xc0ffee",
+                "children": [
+                    {
+                        "pmr_id": 0,
+                        "hash_type": "Sha256",
+                        "digests": [
+                            "5555555555555555555555555555555555555555555555555555555555555555",
+                            "7777777777777777777777777777777777777777777777777777777777777777"
+                        ]
+                    },
+                    {
+                        "pmr_id": 0,
+                        "measurement_id": 2,
+                        "hash_type": "Sha256",
+                        "hashed": false,
+                        "digests": [
+                            "2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a"
+                        ]
+                    },
+                    {
+                        "pmr_id": 0,
+                        "measurement_id": 3,
+                        "check": "Equal",
+                        "data": ["616263", "6465666768"]
+                    }
+                ]
+            }]
+        }"#).unwrap();
+
+        assert_eq!(
+            cfm,
+            owned::Container {
+                metadata: Metadata { version_id: 42 },
+                elements: vec![device()],
+            }
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn round_trip() {
+        let cfm = owned::Container {
+            metadata: Metadata { version_id: 42 },
+            elements: vec![
+                owned::Node {
+                    element: Element::PlatformId {
+                        platform_id: b"abcdfg".to_vec(),
+                    },
+                    children: vec![],
+                    hashed: false,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+                device(),
+            ],
+        };
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) = rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let bytes = cfm
+            .sign(0x00, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap();
+        let cfm2 = owned::Container::parse(&bytes, &mut hasher, Some(&mut rsa))
+            .unwrap();
+        assert!(!cfm2.bad_signature);
+        assert!(!cfm2.bad_toc_hash);
+        assert!(cfm2.bad_hashes.is_empty());
+        assert_eq!(cfm, cfm2.container);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn bad_digest_len() {
+        let cfm: Cfm = owned::Container {
+            metadata: Metadata { version_id: 42 },
+            elements: vec![owned::Node {
+                element: Element::PmrDigest {
+                    pmr_id: 0,
+                    hash_type: hash::Algo::Sha256,
+                    digests: vec![vec![0x55; 20]],
+                },
+                children: vec![],
+                hashed: true,
+                format_version: 0,
+                trailing: vec![],
+            }],
+        };
+        let mut hasher = ring::hash::Engine::new();
+        let (_, mut signer) = rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        assert!(matches!(
+            cfm.sign(0x00, testdata::RSA_SHA256, &mut hasher, &mut signer),
+            Err(EncodingError::BadHashLen(_))
+        ));
+    }
+}
@@ -11,6 +11,7 @@
 //! When the `serde` feature is enabled, owned manifests can be de/serialized.
 
 use std::convert::TryInto;
+use std::iter;
 use std::mem;
 
 use zerocopy::AsBytes;
@@ -18,22 +19,28 @@ use zerocopy::AsBytes;
 use crate::crypto::hash;
 use crate::crypto::hash::EngineExt as _;
 use crate::crypto::sig;
+use crate::hardware::flash::Flash;
 use crate::hardware::flash::Ram;
 use crate::io::write::StdWrite;
 use crate::io::Write as _;
 use crate::manifest;
 use crate::manifest::container::RawTocEntry;
+use crate::manifest::container::TocEntry;
 use crate::manifest::provenance;
 use crate::manifest::Error;
 use crate::manifest::Manifest;
 use crate::manifest::ManifestType;
 use crate::manifest::Metadata;
+use crate::mem::align_to;
 use crate::mem::BumpArena;
+use crate::mem::OutOfMemory;
 use crate::protocol::wire::WireEnum;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+pub mod cfm;
+pub mod pcd;
 pub mod pfm;
 
 /// An "owned" manifest element.
@@ -59,7 +66,19 @@ pub trait Element: Sized {
 
     /// Attempts to encode this `Element` into bytes, using the given
     /// padding byte as "filler".
+    ///
+    /// The encoding should not include padding at its end; the container
+    /// pads each element out to four-byte alignment when encoding.
     fn to_bytes(&self, padding_byte: u8) -> Result<Vec<u8>, EncodingError>;
+
+    /// Returns the format version of the encoding produced by
+    /// [`Element::to_bytes()`].
+    ///
+    /// Nodes may be encoded with a newer version only if they carry the
+    /// fields that version appends in [`Node::trailing`].
+    fn format_version(&self) -> u8 {
+        0
+    }
 }
 
 /// An "owned" manifest element that can be built from its unowned counterpart.
@@ -82,13 +101,23 @@ pub trait FromUnowned: Element {
 /// See [`manifest::pfm`] for lazy parsing out of flash.
 pub type Pfm = Container<self::pfm::Element>;
 
+/// A heap-allocated CFM.
+///
+/// See [`manifest::cfm`] for lazy parsing out of flash.
+pub type Cfm = Container<self::cfm::Element>;
+
+/// A heap-allocated PCD.
+///
+/// See [`manifest::pcd`] for lazy parsing out of flash.
+pub type Pcd = Container<self::pcd::Element>;
+
 /// A heap-allocated Cerberus manifest, represented as a tree structure.
 ///
 /// Prefer to access this type through one of the provided type aliases,
 /// instead:
 /// - Platform Firmware Manifest: [`Pfm`](type.Pfm.html)
-/// - Component Firmware Manifest: NYI
-/// - Platform Configuration Descriptor: NYI
+/// - Component Firmware Manifest: [`Cfm`](type.Cfm.html)
+/// - Platform Configuration Data: [`Pcd`](type.Pcd.html)
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Container<E> {
@@ -116,6 +145,32 @@ pub struct Node<E> {
     )]
     pub hashed: bool,
 
+    /// The format version to record for this node in the TOC.
+    ///
+    /// Parsing a manifest records each element's original version here, so
+    /// that it round-trips. Versions newer than [`Element::format_version()`]
+    /// require `trailing` to hold the fields they add.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::serde::is_default")
+    )]
+    pub format_version: u8,
+
+    /// Bytes encoded after the fields Manticore understands, such as fields
+    /// appended by a newer format version.
+    ///
+    /// These are kept verbatim when parsing a manifest and re-emitted when
+    /// encoding it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde::hexstring",
+            default,
+            skip_serializing_if = "Vec::is_empty"
+        )
+    )]
+    pub trailing: Vec<u8>,
+
     /// The element that this node contains.
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub element: E,
@@ -128,6 +183,42 @@ pub struct Node<E> {
     pub children: Vec<Node<E>>,
 }
 
+impl<E: Element> Node<E> {
+    /// Creates a childless `Node` for `element`, which was parsed out of
+    /// `entry`.
+    ///
+    /// Any bytes of `entry` past the end of `element`'s encoding are kept in
+    /// [`Node::trailing`], so that re-encoding the node is lossless.
+    fn from_entry<M: Manifest>(
+        element: E,
+        entry: TocEntry<'_, '_, M>,
+        flash: &dyn Flash,
+    ) -> Result<Self, Error> {
+        // An element that was just parsed always fits in its own encoding.
+        let mut known_len =
+            element.to_bytes(0).map_err(|_| Error::OutOfRange)?.len();
+        // A newer format version may place fields where older versions only
+        // had alignment padding, so the padding only belongs to versions we
+        // know the layout of.
+        if entry.format_version() <= element.format_version() {
+            known_len = align_to(known_len, 4);
+        }
+        let known_len = known_len as u32;
+        let region = entry.region();
+        let mut trailing =
+            vec![0; region.len.saturating_sub(known_len) as usize];
+        flash.read(region.offset + known_len, &mut trailing)?;
+
+        Ok(Self {
+            element,
+            hashed: entry.hash().is_some(),
+            format_version: entry.format_version(),
+            trailing,
+            children: Vec::new(),
+        })
+    }
+}
+
 /// A parsing result.
 ///
 /// See [`Container::parse()`].
@@ -167,6 +258,20 @@ pub enum EncodingError {
     /// Indicates a range was empty when it shouldn't have been.
     EmptyRegion,
 
+    /// Indicates that a digest's length did not match its hash type.
+    ///
+    /// The bad digest is included in the error.
+    BadHashLen(Vec<u8>),
+
+    /// Indicates that an element was to be encoded with a format version
+    /// newer than its encoding, without the fields that version adds.
+    ///
+    /// The requested version is included in the error.
+    UnsupportedVersion(u8),
+
+    /// Indicates that the requested signature type cannot be produced.
+    UnsupportedSigType(manifest::SigType),
+
     /// Indicates an error while computing a hash.
     HashError(hash::Error),
 
@@ -195,7 +300,7 @@ impl<E: Element> Container<E> {
     pub fn parse(
         bytes: &[u8],
         hasher: &mut impl hash::Engine,
-        sig_verify: Option<&mut impl sig::Verify>,
+        sig_verify: Option<&mut dyn sig::Verify>,
     ) -> Result<Parse<E>, Error>
     where
         E: FromUnowned,
@@ -235,15 +340,18 @@ impl<E: Element> Container<E> {
                 None => continue,
             };
 
-            let region = entry.region();
-            let start = region.offset as usize;
-            let end = region.end() as usize;
-            let bytes = bytes
-                .get(start..end)
-                .ok_or(Error::TooShort { toc_index: i })?;
-
             let mut hash = vec![0; expected.len()];
-            hasher.contiguous_hash(hash_algo, bytes, &mut hash)?;
+            let mut h = hasher.new_hash(hash_algo)?;
+            h.write_flash_direct(
+                &ram,
+                iter::once(entry.region()),
+                &OutOfMemory,
+            )
+            .map_err(|e| match e {
+                hash::FlashError::Flash(_) => Error::TooShort { toc_index: i },
+                hash::FlashError::Hash(e) => Error::HashError(e),
+            })?;
+            h.finish(&mut hash)?;
             if hash != expected {
                 parse.bad_hashes.push(i);
             }
@@ -257,13 +365,22 @@ impl<E: Element> Container<E> {
     ///
     /// `padding_byte` is the byte inserted to pad each element to a
     /// four-byte alignment; usually this will want to be `0x00` or `0xff.
+    ///
+    /// `sig_type` describes `signer`; its hash algorithm is also used for the
+    /// manifest's table of contents. ECDSA signers must produce DER-encoded
+    /// signatures.
     pub fn sign(
         &self,
         padding_byte: u8,
-        hash_type: hash::Algo,
+        sig_type: manifest::SigType,
         hasher: &mut impl hash::Engine,
-        signer: &mut impl sig::Sign,
+        signer: &mut dyn sig::Sign,
     ) -> Result<Vec<u8>, EncodingError> {
+        if sig_type.sig_algo().is_none() {
+            return Err(EncodingError::UnsupportedSigType(sig_type));
+        }
+        let hash_type = sig_type.hash_type;
+
         let mut bytes = Vec::new();
         let mut w = StdWrite(&mut bytes);
 
@@ -272,7 +389,7 @@ impl<E: Element> Container<E> {
         let _ = w.write_le(E::TYPE.to_wire_value());
         let _ = w.write_le(self.metadata.version_id);
         let _ = w.write_le(signer.sig_bytes() as u16);
-        let _ = w.write_le(0u8); // Should be sig_type.
+        let _ = w.write_le(sig_type.to_wire_value());
         let _ = w.write_le(padding_byte);
 
         let mut index = 0;
@@ -311,7 +428,17 @@ impl<E: Element> Container<E> {
                     return Err(EncodingError::TooManyElements);
                 }
 
-                let data = node.element.to_bytes(padding_byte)?;
+                if node.format_version > node.element.format_version()
+                    && node.trailing.is_empty()
+                {
+                    return Err(EncodingError::UnsupportedVersion(
+                        node.format_version,
+                    ));
+                }
+
+                let mut data = node.element.to_bytes(padding_byte)?;
+                data.extend_from_slice(&node.trailing);
+                data.resize(align_to(data.len(), 4), padding_byte);
                 let len = data
                     .len()
                     .try_into()
@@ -320,7 +447,7 @@ impl<E: Element> Container<E> {
                 let element_type = node.element.element_type().to_wire_value();
                 let entry = RawTocEntry {
                     element_type,
-                    format_version: 0, // TODO(#59)
+                    format_version: node.format_version,
                     offset: *offset,
                     len,
                     parent_type,
@@ -400,14 +527,15 @@ impl<E: Element> Container<E> {
             .map_err(|_| EncodingError::OutOfSpace)?;
         bytes[0..2].copy_from_slice(&total_len.to_le_bytes());
 
-        let mut signed = [0; 32];
+        let mut digest = vec![0; hash_type.bytes()];
+        hasher.contiguous_hash(hash_type, &bytes, &mut digest)?;
         let mut signature = vec![0; signer.sig_bytes()];
-        hasher.contiguous_hash(hash::Algo::Sha256, &bytes, &mut signed)?;
-        let sig_len = signer.sign(&[&signed], &mut signature)?;
+        let sig_len = signer.sign(&[&digest], &mut signature)?;
 
         // NOTE: Due to how manifests are constructed, we cannot use
-        // a variable-length signature scheme.
-        if sig_len != signature.len() {
+        // a variable-length signature scheme. DER-encoded ECDSA signatures
+        // are the exception: they are zero-padded to their maximum length.
+        if sig_len != signature.len() && !sig_type.key_type.is_ecdsa() {
             return Err(EncodingError::SigError(sig::Error::Unspecified));
         }
         bytes.extend_from_slice(&signature);
@@ -0,0 +1,582 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! PCD element structures.
+//!
+//! See [`owned::Pcd`](../type.Pcd.html).
+
+use core::convert::TryInto;
+
+use crate::crypto::ring;
+use crate::manifest;
+use crate::manifest::owned;
+use crate::manifest::owned::EncodingError;
+use crate::manifest::pcd;
+use crate::manifest::pcd::I2cMode;
+use crate::manifest::pcd::Policy;
+use crate::manifest::provenance;
+use crate::manifest::Error;
+use crate::manifest::ManifestExt as _;
+use crate::manifest::ManifestType;
+use crate::mem::Arena as _;
+use crate::mem::BumpArena;
+use crate::protocol::capabilities::RotMode;
+use crate::protocol::device_id::DeviceIdentifier;
+use crate::protocol::wire::WireEnum as _;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An owned PCD element.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[allow(missing_docs)]
+pub enum Element {
+    // NOTE: Because this enum is untagged, variants whose fields are a
+    // superset of another variant's must come first.
+    DirectComponent {
+        policy: Policy,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        power_ctrl_reg: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        power_ctrl_mask: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        component_id: u32,
+        i2c: I2c,
+    },
+    BridgeComponent {
+        policy: Policy,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        power_ctrl_reg: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        power_ctrl_mask: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        component_id: u32,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        component_count: u8,
+        device_id: DeviceIdentifier,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        eid: u8,
+    },
+    PowerController {
+        i2c: I2c,
+    },
+    Port {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        port_id: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
+        flags: u8,
+        policy: Policy,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        pulse_interval: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+        spi_frequency_hz: u32,
+    },
+    Rot {
+        mode: RotMode,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        address: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        eid: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        bridge_address: u8,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+        bridge_eid: u8,
+    },
+    PlatformId {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serde::bytestring")
+        )]
+        platform_id: Vec<u8>,
+    },
+}
+
+/// An I2C path to a device.
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct I2c {
+    pub mode: I2cMode,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+    pub bus: u8,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+    pub address: u8,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+    pub eid: u8,
+    pub muxes: Vec<Mux>,
+}
+
+/// An I2C mux.
+#[allow(missing_docs)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mux {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
+    pub address: u8,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::dec"))]
+    pub channel: u8,
+}
+
+impl I2c {
+    fn from_unowned(i2c: &pcd::I2c) -> Self {
+        Self {
+            mode: i2c.mode(),
+            bus: i2c.bus(),
+            address: i2c.address(),
+            eid: i2c.eid(),
+            muxes: i2c
+                .muxes()
+                .iter()
+                .map(|m| Mux {
+                    address: m.address(),
+                    channel: m.channel(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Appends the encoding of `self` to `bytes`.
+    fn encode(
+        &self,
+        padding_byte: u8,
+        bytes: &mut Vec<u8>,
+    ) -> Result<(), EncodingError> {
+        let mux_count: u8 = self
+            .muxes
+            .len()
+            .try_into()
+            .map_err(|_| EncodingError::TooManyElements)?;
+        bytes.extend_from_slice(&[
+            self.mode.to_wire_value(),
+            self.bus,
+            self.address,
+            self.eid,
+            mux_count,
+            padding_byte,
+            padding_byte,
+            padding_byte,
+        ]);
+        for mux in &self.muxes {
+            bytes.extend_from_slice(&[
+                mux.address,
+                mux.channel,
+                padding_byte,
+                padding_byte,
+            ]);
+        }
+        Ok(())
+    }
+}
+
+impl owned::Element for Element {
+    type ElementType = pcd::ElementType;
+    const TYPE: ManifestType = ManifestType::Pcd;
+
+    fn element_type(&self) -> manifest::ElementType<pcd::ElementType> {
+        match self {
+            Self::PlatformId { .. } => manifest::ElementType::PlatformId,
+
+            Self::Rot { .. } => pcd::ElementType::Rot.into(),
+            Self::Port { .. } => pcd::ElementType::Port.into(),
+            Self::PowerController { .. } => {
+                pcd::ElementType::PowerController.into()
+            }
+            Self::DirectComponent { .. } => {
+                pcd::ElementType::DirectComponent.into()
+            }
+            Self::BridgeComponent { .. } => {
+                pcd::ElementType::BridgeComponent.into()
+            }
+        }
+    }
+
+    fn to_bytes(&self, padding_byte: u8) -> Result<Vec<u8>, EncodingError> {
+        match self {
+            Self::Rot {
+                mode,
+                address,
+                eid,
+                bridge_address,
+                bridge_eid,
+            } => Ok(vec![
+                mode.to_wire_value(),
+                *address,
+                *eid,
+                *bridge_address,
+                *bridge_eid,
+                padding_byte,
+                padding_byte,
+                padding_byte,
+            ]),
+            Self::Port {
+                port_id,
+                flags,
+                policy,
+                pulse_interval,
+                spi_frequency_hz,
+            } => {
+                let mut bytes = vec![
+                    *port_id,
+                    *flags,
+                    policy.to_wire_value(),
+                    *pulse_interval,
+                ];
+                bytes.extend_from_slice(&spi_frequency_hz.to_le_bytes());
+                Ok(bytes)
+            }
+            Self::PowerController { i2c } => {
+                let mut bytes = Vec::new();
+                i2c.encode(padding_byte, &mut bytes)?;
+                Ok(bytes)
+            }
+            Self::DirectComponent {
+                policy,
+                power_ctrl_reg,
+                power_ctrl_mask,
+                component_id,
+                i2c,
+            } => {
+                let mut bytes = vec![
+                    policy.to_wire_value(),
+                    *power_ctrl_reg,
+                    *power_ctrl_mask,
+                    padding_byte,
+                ];
+                bytes.extend_from_slice(&component_id.to_le_bytes());
+                i2c.encode(padding_byte, &mut bytes)?;
+                Ok(bytes)
+            }
+            Self::BridgeComponent {
+                policy,
+                power_ctrl_reg,
+                power_ctrl_mask,
+                component_id,
+                component_count,
+                device_id,
+                eid,
+            } => {
+                let mut bytes = vec![
+                    policy.to_wire_value(),
+                    *power_ctrl_reg,
+                    *power_ctrl_mask,
+                    *component_count,
+                ];
+                bytes.extend_from_slice(&component_id.to_le_bytes());
+                bytes.extend_from_slice(&device_id.vendor_id.to_le_bytes());
+                bytes.extend_from_slice(&device_id.device_id.to_le_bytes());
+                bytes.extend_from_slice(
+                    &device_id.subsys_vendor_id.to_le_bytes(),
+                );
+                bytes.extend_from_slice(&device_id.subsys_id.to_le_bytes());
+                bytes.extend_from_slice(&[
+                    *eid,
+                    padding_byte,
+                    padding_byte,
+                    padding_byte,
+                ]);
+                Ok(bytes)
+            }
+            Self::PlatformId { platform_id: id } => {
+                let id_len: u8 = id
+                    .len()
+                    .try_into()
+                    .map_err(|_| EncodingError::StringTooLong(id.clone()))?;
+                let mut bytes = vec![padding_byte; 4];
+                bytes[0] = id_len;
+
+                bytes.extend_from_slice(id);
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+impl owned::FromUnowned for Element {
+    type Manifest = manifest::pcd::Pcd;
+
+    fn from_container(
+        container: manifest::Container<Self::Manifest, provenance::Adhoc>,
+    ) -> Result<Vec<owned::Node<Self>>, Error> {
+        let mut arena = BumpArena::new(vec![0; 2048]);
+        let flash = container.flash();
+        let pcd = manifest::pcd::ParsedPcd::new(container);
+        let mut h = ring::hash::Engine::new();
+        let mut nodes = Vec::new();
+
+        if let Some(id) = pcd.platform_id(&mut h, &arena)? {
+            nodes.push(owned::Node::from_entry(
+                Element::PlatformId {
+                    platform_id: id.id_string().to_vec(),
+                },
+                id.entry(),
+                flash,
+            )?)
+        }
+        arena.reset();
+
+        if let Some(rot) = pcd.rot(&mut h, &arena)? {
+            let mut node = owned::Node::from_entry(
+                Element::Rot {
+                    mode: rot.mode(),
+                    address: rot.address(),
+                    eid: rot.eid(),
+                    bridge_address: rot.bridge_address(),
+                    bridge_eid: rot.bridge_eid(),
+                },
+                rot.entry(),
+                flash,
+            )?;
+
+            for port in rot.ports() {
+                let port = port.read(&mut h, &arena)?;
+                node.children.push(owned::Node::from_entry(
+                    Element::Port {
+                        port_id: port.port_id(),
+                        flags: port.raw_flags(),
+                        policy: port.policy(),
+                        pulse_interval: (port.pulse_interval_ms() / 10) as u8,
+                        spi_frequency_hz: port.spi_frequency_hz(),
+                    },
+                    port.entry(),
+                    flash,
+                )?);
+            }
+
+            nodes.push(node);
+        }
+        arena.reset();
+
+        if let Some(power) = pcd.power_controller(&mut h, &arena)? {
+            nodes.push(owned::Node::from_entry(
+                Element::PowerController {
+                    i2c: I2c::from_unowned(power.i2c()),
+                },
+                power.entry(),
+                flash,
+            )?);
+        }
+        arena.reset();
+
+        for component in pcd.direct_components() {
+            let component = component.read(&mut h, &arena)?;
+            let power = component.power_control();
+            nodes.push(owned::Node::from_entry(
+                Element::DirectComponent {
+                    policy: component.policy(),
+                    power_ctrl_reg: power.register,
+                    power_ctrl_mask: power.mask,
+                    component_id: component.component_id(),
+                    i2c: I2c::from_unowned(component.i2c()),
+                },
+                component.entry(),
+                flash,
+            )?);
+            arena.reset();
+        }
+
+        for component in pcd.bridge_components() {
+            let component = component.read(&mut h, &arena)?;
+            let power = component.power_control();
+            nodes.push(owned::Node::from_entry(
+                Element::BridgeComponent {
+                    policy: component.policy(),
+                    power_ctrl_reg: power.register,
+                    power_ctrl_mask: power.mask,
+                    component_id: component.component_id(),
+                    component_count: component.component_count() as u8,
+                    device_id: component.device_id(),
+                    eid: component.eid(),
+                },
+                component.entry(),
+                flash,
+            )?);
+            arena.reset();
+        }
+
+        Ok(nodes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::from_str;
+    use testutil::data::keys;
+
+    use crate::crypto::ring::rsa;
+    use crate::manifest::owned::Pcd;
+    use crate::manifest::testdata;
+    use crate::manifest::Metadata;
+
+    #[test]
+    fn parse_rot() {
+        #[rustfmt::skip]
+        let pcd: Pcd = from_str(r#"{
+            "version_id": 42,
+            "elements": [{
+                "mode": "Active",
+                "address": "0x41",
+                "eid": "0x0b",
+                "bridge_address": "0x10",
+                "bridge_eid": "0x08",
+                "children": [{
+                    "port_id": 1,
+                    "flags": "0b1001",
+                    "policy": "Passive",
+                    "pulse_interval": 0,
+                    "spi_frequency_hz": 1000000
+                }]
+            }]
+        }"#).unwrap();
+
+        assert_eq!(
+            pcd,
+            owned::Container {
+                metadata: Metadata { version_id: 42 },
+                elements: vec![owned::Node {
+                    element: Element::Rot {
+                        mode: RotMode::Active,
+                        address: 0x41,
+                        eid: 0x0b,
+                        bridge_address: 0x10,
+                        bridge_eid: 0x08,
+                    },
+                    children: vec![owned::Node {
+                        element: Element::Port {
+                            port_id: 1,
+                            flags: 0b1001,
+                            policy: Policy::Passive,
+                            pulse_interval: 0,
+                            spi_frequency_hz: 1_000_000,
+                        },
+                        children: vec![],
+                        hashed: true,
+                        format_version: 0,
+                        trailing: vec![],
+                    }],
+                    hashed: true,
+                    format_version: 0,
+                    trailing: vec![],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn round_trip() {
+        let pcd = owned::Container {
+            metadata: Metadata { version_id: 42 },
+            elements: vec![
+                owned::Node {
+                    element: Element::PlatformId {
+                        platform_id: b"abcdfg".to_vec(),
+                    },
+                    children: vec![],
+                    hashed: false,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+                owned::Node {
+                    element: Element::Rot {
+                        mode: RotMode::Platform,
+                        address: 0x41,
+                        eid: 0x0b,
+                        bridge_address: 0x10,
+                        bridge_eid: 0x08,
+                    },
+                    children: vec![owned::Node {
+                        element: Element::Port {
+                            port_id: 0,
+                            flags: 0b0101,
+                            policy: Policy::Active,
+                            pulse_interval: 5,
+                            spi_frequency_hz: 50_000_000,
+                        },
+                        children: vec![],
+                        hashed: true,
+                        format_version: 0,
+                        trailing: vec![],
+                    }],
+                    hashed: true,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+                owned::Node {
+                    element: Element::PowerController {
+                        i2c: I2c {
+                            mode: I2cMode::MasterSlave,
+                            bus: 2,
+                            address: 0x22,
+                            eid: 0,
+                            muxes: vec![],
+                        },
+                    },
+                    children: vec![],
+                    hashed: true,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+                owned::Node {
+                    element: Element::DirectComponent {
+                        policy: Policy::Passive,
+                        power_ctrl_reg: 3,
+                        power_ctrl_mask: 0b10,
+                        component_id: 0xc0ffee,
+                        i2c: I2c {
+                            mode: I2cMode::MultiMaster,
+                            bus: 1,
+                            address: 0x42,
+                            eid: 0x0c,
+                            muxes: vec![Mux {
+                                address: 0x70,
+                                channel: 3,
+                            }],
+                        },
+                    },
+                    children: vec![],
+                    hashed: false,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+                owned::Node {
+                    element: Element::BridgeComponent {
+                        policy: Policy::Active,
+                        power_ctrl_reg: 4,
+                        power_ctrl_mask: 1,
+                        component_id: 0xbeef,
+                        component_count: 2,
+                        device_id: DeviceIdentifier {
+                            vendor_id: 0x1414,
+                            device_id: 1,
+                            subsys_vendor_id: 0x1414,
+                            subsys_id: 2,
+                        },
+                        eid: 0x0d,
+                    },
+                    children: vec![],
+                    hashed: true,
+                    format_version: 0,
+                    trailing: vec![],
+                },
+            ],
+        };
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) = rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let bytes = pcd
+            .sign(0x00, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap();
+        let pcd2 = owned::Container::parse(&bytes, &mut hasher, Some(&mut rsa))
+            .unwrap();
+        assert!(!pcd2.bad_signature);
+        assert!(!pcd2.bad_toc_hash);
+        assert!(pcd2.bad_hashes.is_empty());
+        assert_eq!(pcd, pcd2.container);
+    }
+}
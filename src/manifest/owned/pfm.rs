@@ -6,8 +6,6 @@
 //!
 //! See [`owned::Pfm`](../type.Pfm.html).
 
-// TODO(#60): Provide a more ergonomic way of specifying flags bytes.
-
 use core::convert::TryInto;
 
 use crate::crypto::hash;
@@ -17,6 +15,7 @@ use crate::manifest;
 use crate::manifest::owned;
 use crate::manifest::owned::EncodingError;
 use crate::manifest::pfm;
+use crate::manifest::pfm::RwFailurePolicy;
 use crate::manifest::provenance;
 use crate::manifest::Error;
 use crate::manifest::ManifestExt as _;
@@ -24,6 +23,7 @@ use crate::manifest::ManifestType;
 use crate::mem::misalign_of;
 use crate::mem::Arena as _;
 use crate::mem::BumpArena;
+use crate::protocol::wire::WireEnum as _;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -46,8 +46,15 @@ pub enum Element {
             serde(with = "crate::serde::bytestring")
         )]
         firmware_id: Vec<u8>,
-        #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
-        flags: u8,
+        #[cfg_attr(
+            feature = "serde",
+            serde(
+                default,
+                deserialize_with = "crate::serde::de_flags",
+                skip_serializing_if = "crate::serde::is_default"
+            )
+        )]
+        flags: AllowableFwFlags,
     },
     FwVersion {
         #[cfg_attr(feature = "serde", serde(with = "crate::serde::hex"))]
@@ -74,8 +81,15 @@ pub enum Element {
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Rw {
-    #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
-    pub flags: u8,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            deserialize_with = "crate::serde::de_flags",
+            skip_serializing_if = "crate::serde::is_default"
+        )
+    )]
+    pub flags: RwFlags,
     pub region: Region,
 }
 
@@ -84,14 +98,148 @@ pub struct Rw {
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Image {
-    #[cfg_attr(feature = "serde", serde(with = "crate::serde::bin"))]
-    pub flags: u8,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            deserialize_with = "crate::serde::de_flags",
+            skip_serializing_if = "crate::serde::is_default"
+        )
+    )]
+    pub flags: ImageFlags,
     pub hash_type: hash::Algo,
     #[cfg_attr(feature = "serde", serde(with = "crate::serde::hexstring"))]
     pub hash: Vec<u8>,
     pub regions: Vec<Region>,
 }
 
+/// Flags for an [`Element::AllowableFw`].
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct AllowableFwFlags {
+    /// Whether updates to this firmware can be applied at runtime, without a
+    /// reboot.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serde::is_default")
+    )]
+    pub runtime_update: bool,
+    /// Any remaining bits, which have no defined meaning.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde::bin",
+            skip_serializing_if = "crate::serde::is_default"
+        )
+    )]
+    pub reserved: u8,
+}
+
+impl From<u8> for AllowableFwFlags {
+    fn from(bits: u8) -> Self {
+        Self {
+            runtime_update: bits & 1 == 1,
+            reserved: bits & !1,
+        }
+    }
+}
+
+impl From<AllowableFwFlags> for u8 {
+    fn from(flags: AllowableFwFlags) -> u8 {
+        flags.runtime_update as u8 | flags.reserved
+    }
+}
+
+/// Flags for an [`Rw`] region.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct RwFlags {
+    /// What to do with this region if it fails verification.
+    pub failure_policy: RwFailurePolicy,
+    /// Any remaining bits, which have no defined meaning.
+    ///
+    /// This includes the policy bits if they do not encode a known
+    /// [`RwFailurePolicy`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde::bin",
+            skip_serializing_if = "crate::serde::is_default"
+        )
+    )]
+    pub reserved: u8,
+}
+
+impl Default for RwFlags {
+    fn default() -> Self {
+        Self {
+            failure_policy: RwFailurePolicy::DoNothing,
+            reserved: 0,
+        }
+    }
+}
+
+impl From<u8> for RwFlags {
+    fn from(bits: u8) -> Self {
+        match RwFailurePolicy::from_wire_value(bits & 0b11) {
+            Some(failure_policy) => Self {
+                failure_policy,
+                reserved: bits & !0b11,
+            },
+            None => Self {
+                failure_policy: RwFailurePolicy::DoNothing,
+                reserved: bits,
+            },
+        }
+    }
+}
+
+impl From<RwFlags> for u8 {
+    fn from(flags: RwFlags) -> u8 {
+        flags.failure_policy.to_wire_value() | flags.reserved
+    }
+}
+
+/// Flags for an [`Image`] region.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct ImageFlags {
+    /// Whether this region must be validated on boot, rather than just when
+    /// loading a new firmware update.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "crate::serde::is_default")
+    )]
+    pub must_validate_on_boot: bool,
+    /// Any remaining bits, which have no defined meaning.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            with = "crate::serde::bin",
+            skip_serializing_if = "crate::serde::is_default"
+        )
+    )]
+    pub reserved: u8,
+}
+
+impl From<u8> for ImageFlags {
+    fn from(bits: u8) -> Self {
+        Self {
+            must_validate_on_boot: bits & 1 == 1,
+            reserved: bits & !1,
+        }
+    }
+}
+
+impl From<ImageFlags> for u8 {
+    fn from(flags: ImageFlags) -> u8 {
+        flags.must_validate_on_boot as u8 | flags.reserved
+    }
+}
+
 impl owned::Element for Element {
     type ElementType = pfm::ElementType;
     const TYPE: ManifestType = ManifestType::Pfm;
@@ -123,13 +271,9 @@ impl owned::Element for Element {
                         EncodingError::StringTooLong(firmware_id.clone())
                     })?;
                 let mut bytes =
-                    vec![*version_count, id_len, *flags, padding_byte];
+                    vec![*version_count, id_len, (*flags).into(), padding_byte];
 
                 bytes.extend_from_slice(&firmware_id);
-                for _ in 0..misalign_of(bytes.len(), 4) {
-                    bytes.push(padding_byte);
-                }
-
                 Ok(bytes)
             }
             Self::FwVersion {
@@ -161,7 +305,7 @@ impl owned::Element for Element {
 
                 for rw in rw_regions {
                     let mut header = [padding_byte; 4];
-                    header[0] = rw.flags;
+                    header[0] = rw.flags.into();
                     bytes.extend_from_slice(&header);
 
                     let (start, end) = rw
@@ -185,7 +329,7 @@ impl owned::Element for Element {
                             hash::Algo::Sha512 => 0b10,
                         },
                         reg_len,
-                        image.flags,
+                        image.flags.into(),
                         padding_byte,
                     ]);
                     bytes.extend_from_slice(&image.hash);
@@ -209,10 +353,6 @@ impl owned::Element for Element {
                 bytes[0] = id_len;
 
                 bytes.extend_from_slice(&id);
-                for _ in 0..misalign_of(bytes.len(), 4) {
-                    bytes.push(padding_byte);
-                }
-
                 Ok(bytes)
             }
         }
@@ -226,44 +366,45 @@ impl owned::FromUnowned for Element {
         container: manifest::Container<Self::Manifest, provenance::Adhoc>,
     ) -> Result<Vec<owned::Node<Self>>, Error> {
         let mut arena = BumpArena::new(vec![0; 2048]);
+        let flash = container.flash();
         let pfm = manifest::pfm::ParsedPfm::new(container);
         let mut h = ring::hash::Engine::new();
         let mut nodes = Vec::new();
 
         if let Some(id) = pfm.platform_id(&mut h, &arena)? {
-            nodes.push(owned::Node {
-                element: Element::PlatformId {
+            nodes.push(owned::Node::from_entry(
+                Element::PlatformId {
                     platform_id: id.id_string().to_vec(),
                 },
-                hashed: id.entry().hash().is_some(),
-                children: Vec::new(),
-            })
+                id.entry(),
+                flash,
+            )?)
         }
         arena.reset();
 
         if let Some(info) = pfm.flash_device_info(&mut h, &arena)? {
-            nodes.push(owned::Node {
-                element: Element::FlashDevice {
+            nodes.push(owned::Node::from_entry(
+                Element::FlashDevice {
                     blank_byte: info.blank_byte(),
                 },
-                hashed: info.entry().hash().is_some(),
-                children: Vec::new(),
-            })
+                info.entry(),
+                flash,
+            )?)
         }
         arena.reset();
 
         for allowable_fw in pfm.allowable_fws() {
             let allowable_fw = allowable_fw.read(&mut h, &arena)?;
 
-            let mut node = owned::Node {
-                element: Element::AllowableFw {
+            let mut node = owned::Node::from_entry(
+                Element::AllowableFw {
                     version_count: allowable_fw.firmware_count() as u8,
                     firmware_id: allowable_fw.firmware_id().to_vec(),
-                    flags: allowable_fw.raw_flags(),
+                    flags: allowable_fw.raw_flags().into(),
                 },
-                hashed: allowable_fw.entry().hash().is_some(),
-                children: Vec::new(),
-            };
+                allowable_fw.entry(),
+                flash,
+            )?;
 
             for fw in allowable_fw.firmware_versions() {
                 let fw = fw.read(&mut h, &arena)?;
@@ -271,7 +412,7 @@ impl owned::FromUnowned for Element {
                 let mut rw_regions = Vec::new();
                 for rw in fw.rw_regions() {
                     rw_regions.push(Rw {
-                        flags: rw.raw_flags(),
+                        flags: rw.raw_flags().into(),
                         region: rw.region(),
                     });
                 }
@@ -280,7 +421,7 @@ impl owned::FromUnowned for Element {
                 for image in fw.image_regions() {
                     let (hash_type, hash) = image.image_hash();
                     image_regions.push(Image {
-                        flags: image.raw_flags(),
+                        flags: image.raw_flags().into(),
                         hash_type,
                         hash: hash.to_vec(),
                         regions: image.regions().collect(),
@@ -288,16 +429,16 @@ impl owned::FromUnowned for Element {
                 }
 
                 let (version_region, version_str) = fw.version();
-                node.children.push(owned::Node {
-                    element: Element::FwVersion {
+                node.children.push(owned::Node::from_entry(
+                    Element::FwVersion {
                         version_addr: version_region.offset,
                         version_str: version_str.to_vec(),
                         rw_regions,
                         image_regions,
                     },
-                    hashed: fw.entry().hash().is_some(),
-                    children: Vec::new(),
-                });
+                    fw.entry(),
+                    flash,
+                )?);
             }
 
             nodes.push(node);
@@ -320,6 +461,7 @@ mod test {
     use crate::crypto::ring::rsa;
     use crate::manifest::owned;
     use crate::manifest::owned::Pfm;
+    use crate::manifest::testdata;
     use crate::manifest::Metadata;
 
     #[test]
@@ -359,6 +501,8 @@ mod test {
                     },
                     children: vec![],
                     hashed: true,
+                    format_version: 0,
+                    trailing: vec![],
                 }],
             }
         );
@@ -370,17 +514,17 @@ mod test {
         let pfm: Pfm = from_str(r#"{
             "version_id": 42,
             "elements": [
-                { "blank_byte": "0xff" },
+                { "blank_byte": "0xff", "format_version": 1 },
                 {
                     "version_count": 1,
                     "firmware_id": "my cool firmware",
-                    "flags": "0b10101010",
+                    "flags": { "runtime_update": true },
                     "hashed": false,
                     "children": [{
                         "version_addr": "0x12345678",
                         "version_str": "ver-1.2.2",
                         "rw_regions": [{
-                            "flags": "0b00110011",
+                            "flags": { "failure_policy": "Erase" },
                             "region": {
                                 "offset": "0x00008000",
                                 "len": "0x8000"
@@ -402,7 +546,6 @@ mod test {
                                 ]
                             },
                             {
-                                "flags": 0,
                                 "hash_type": "Sha256",
                                 "hash": [
                                     77, 77, 77, 77, 77, 77, 77, 77,
@@ -430,24 +573,35 @@ mod test {
                         element: Element::FlashDevice { blank_byte: 0xff },
                         children: vec![],
                         hashed: true,
+                        format_version: 1,
+                        trailing: vec![],
                     },
                     owned::Node {
                         element: Element::AllowableFw {
                             version_count: 1,
                             firmware_id: b"my cool firmware".to_vec(),
-                            flags: 0xaa,
+                            flags: AllowableFwFlags {
+                                runtime_update: true,
+                                reserved: 0,
+                            },
                         },
                         children: vec![owned::Node {
                             element: Element::FwVersion {
                                 version_addr: 0x12345678,
                                 version_str: b"ver-1.2.2".to_vec(),
                                 rw_regions: vec![Rw {
-                                    flags: 0b00110011,
+                                    flags: RwFlags {
+                                        failure_policy: RwFailurePolicy::Erase,
+                                        reserved: 0,
+                                    },
                                     region: Region::new(0x8000, 0x8000),
                                 }],
                                 image_regions: vec![
                                     Image {
-                                        flags: 0o7,
+                                        flags: ImageFlags {
+                                            must_validate_on_boot: true,
+                                            reserved: 0o6,
+                                        },
                                         hash_type: hash::Algo::Sha256,
                                         hash: vec![42; 32],
                                         regions: vec![
@@ -456,7 +610,7 @@ mod test {
                                         ],
                                     },
                                     Image {
-                                        flags: 0,
+                                        flags: ImageFlags::default(),
                                         hash_type: hash::Algo::Sha256,
                                         hash: vec![77; 32],
                                         regions: vec![
@@ -468,8 +622,12 @@ mod test {
                             },
                             children: vec![],
                             hashed: true,
+                            format_version: 0,
+                            trailing: vec![],
                         }],
                         hashed: false,
+                        format_version: 0,
+                        trailing: vec![],
                     },
                 ],
             }
@@ -488,29 +646,33 @@ mod test {
                     },
                     children: vec![],
                     hashed: false,
+                    format_version: 0,
+                    trailing: vec![],
                 },
                 owned::Node {
                     element: Element::FlashDevice { blank_byte: 0xff },
                     children: vec![],
                     hashed: true,
+                    format_version: 1,
+                    trailing: vec![0x01, 0x02, 0x03, 0x04],
                 },
                 owned::Node {
                     element: Element::AllowableFw {
                         version_count: 1,
-                        firmware_id: b"my cool firmware".to_vec(),
-                        flags: 0xaa,
+                        firmware_id: b"my cool firmware!".to_vec(),
+                        flags: AllowableFwFlags::from(0xaa),
                     },
                     children: vec![owned::Node {
                         element: Element::FwVersion {
                             version_addr: 0x12345678,
                             version_str: b"ver-1.2.2".to_vec(),
                             rw_regions: vec![Rw {
-                                flags: 0b00110011,
+                                flags: RwFlags::from(0b00110011),
                                 region: Region::new(0x8000, 0x8000),
                             }],
                             image_regions: vec![
                                 Image {
-                                    flags: 0o7,
+                                    flags: ImageFlags::from(0o7),
                                     hash_type: hash::Algo::Sha256,
                                     hash: vec![42; 32],
                                     regions: vec![
@@ -519,7 +681,7 @@ mod test {
                                     ],
                                 },
                                 Image {
-                                    flags: 0,
+                                    flags: ImageFlags::from(0),
                                     hash_type: hash::Algo::Sha256,
                                     hash: vec![77; 32],
                                     regions: vec![
@@ -531,8 +693,14 @@ mod test {
                         },
                         children: vec![],
                         hashed: true,
+                        format_version: 2,
+                        trailing: vec![0xaa; 8],
                     }],
                     hashed: false,
+                    // These trailing bytes start where an older encoding
+                    // would have had padding after the firmware ID.
+                    format_version: 1,
+                    trailing: vec![0xbb; 3],
                 },
             ],
         };
@@ -540,13 +708,48 @@ mod test {
         let (mut rsa, mut signer) = rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
 
         let bytes = pfm
-            .sign(0x00, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .sign(0x00, testdata::RSA_SHA256, &mut hasher, &mut signer)
             .unwrap();
         let pfm2 = owned::Container::parse(&bytes, &mut hasher, Some(&mut rsa))
             .unwrap();
         assert!(!pfm2.bad_signature);
         assert!(!pfm2.bad_toc_hash);
         assert!(pfm2.bad_hashes.is_empty());
+        // The newer elements are decoded by ignoring their trailing bytes,
+        // which are nonetheless preserved.
         assert_eq!(pfm, pfm2.container);
+
+        let mut pfm = pfm;
+        pfm.elements[2].children[0].trailing.clear();
+        let result =
+            pfm.sign(0x00, testdata::RSA_SHA256, &mut hasher, &mut signer);
+        assert!(matches!(result, Err(EncodingError::UnsupportedVersion(2))));
+    }
+
+    #[test]
+    fn flags() {
+        for bits in 0..=0xff {
+            assert_eq!(u8::from(AllowableFwFlags::from(bits)), bits);
+            assert_eq!(u8::from(RwFlags::from(bits)), bits);
+            assert_eq!(u8::from(ImageFlags::from(bits)), bits);
+        }
+
+        let rw = RwFlags::from(0b00110011);
+        assert_eq!(rw.failure_policy, RwFailurePolicy::DoNothing);
+        let json = serde_json::to_string(&rw).unwrap();
+        assert_eq!(
+            json,
+            r#"{"failure_policy":"DoNothing","reserved":"0b00110011"}"#
+        );
+        let rw2: RwFlags = serde_json::from_str(&json).unwrap();
+        assert_eq!(rw, rw2);
+
+        let image = ImageFlags::from(1);
+        let json = serde_json::to_string(&image).unwrap();
+        assert_eq!(json, r#"{"must_validate_on_boot":true}"#);
+
+        assert!(
+            serde_json::from_str::<ImageFlags>(r#"{"bogus":true}"#).is_err()
+        );
     }
 }
@@ -0,0 +1,1032 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The Platform Configuration Data (PCD)
+//!
+//! A PCD is a computer-readable document describing the configuration of the
+//! RoT itself: the SPI flash ports it protects, the I2C topology it uses to
+//! reach the power controller and the components it attests, and the policies
+//! it should apply to each of them.
+//!
+//! # Element Formats
+//!
+//! PCD-specific elements have the following layout, in the same notation as
+//! the [module-level documentation](super):
+//! ```ignore
+//! struct Rot {
+//!     mode: u8, // See `RotMode`.
+//!     address: u8,
+//!     eid: u8,
+//!     bridge_address: u8,
+//!     bridge_eid: u8,
+//!     _: [u8; 3],
+//! }
+//!
+//! // Child of a `Rot`.
+//! struct Port {
+//!     port_id: u8,
+//!     flags: u8,
+//!     policy: u8, // See `Policy`.
+//!     pulse_interval: u8,
+//!     spi_frequency_hz: u32,
+//! }
+//!
+//! struct PowerController {
+//!     i2c: I2c,
+//! }
+//!
+//! struct DirectComponent {
+//!     policy: u8, // See `Policy`.
+//!     power_ctrl_reg: u8,
+//!     power_ctrl_mask: u8,
+//!     _: u8,
+//!     component_id: u32,
+//!     i2c: I2c,
+//! }
+//!
+//! struct BridgeComponent {
+//!     policy: u8, // See `Policy`.
+//!     power_ctrl_reg: u8,
+//!     power_ctrl_mask: u8,
+//!     component_count: u8,
+//!     component_id: u32,
+//!     device_id: DeviceIdentifier,
+//!     eid: u8,
+//!     _: [u8; 3],
+//! }
+//!
+//! struct I2c {
+//!     mode: u8, // See `I2cMode`.
+//!     bus: u8,
+//!     address: u8,
+//!     eid: u8,
+//!     mux_count: u8,
+//!     _: [u8; 3],
+//!     muxes: [Mux; self.mux_count],
+//! }
+//!
+//! struct Mux {
+//!     address: u8,
+//!     channel: u8,
+//!     _: [u8; 2],
+//! }
+//! ```
+//!
+//! The [`ParsedPcd`] type is the entry-point for this module.
+
+use core::mem;
+
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+use zerocopy::LayoutVerified;
+
+use crate::crypto::hash;
+use crate::manifest::provenance;
+use crate::manifest::provenance::Provenance;
+use crate::manifest::Container;
+use crate::manifest::Error;
+use crate::manifest::Manifest;
+use crate::manifest::ManifestType;
+use crate::manifest::Parse;
+use crate::manifest::ParsedManifest;
+use crate::manifest::TocEntry;
+use crate::manifest::ValidationTime;
+use crate::mem::Arena;
+use crate::protocol::capabilities::RotMode;
+use crate::protocol::device_id::DeviceIdentifier;
+
+use crate::protocol::wire::WireEnum as _;
+
+wire_enum! {
+    /// A PCD element type.
+    pub enum ElementType: u8 {
+      /// A `Rot` describes the RoT the PCD configures.
+      Rot = 0x40,
+
+      /// A SPI flash port protected by the RoT, as a subelement of a `Rot`.
+      Port = 0x41,
+
+      /// The I2C power controller used to power components on and off.
+      PowerController = 0x42,
+
+      /// A component that the RoT reaches directly over I2C.
+      DirectComponent = 0x43,
+
+      /// A component that the RoT reaches through an MCTP bridge.
+      BridgeComponent = 0x44,
+    }
+}
+
+wire_enum! {
+    /// A policy for responding to a failure to verify a port or component.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum Policy: u8 {
+        /// Report the failure, but take no further action.
+        Passive = 0x00,
+        /// Hold the port or component in reset until it passes.
+        Active = 0x01,
+    }
+}
+
+wire_enum! {
+    /// The role an RoT plays on an I2C bus.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum I2cMode: u8 {
+        /// All devices on the bus may initiate transactions.
+        MultiMaster = 0x00,
+        /// Only the RoT initiates transactions.
+        MasterSlave = 0x01,
+    }
+}
+
+wire_enum! {
+    /// How a SPI flash port is wired up.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum FlashMode: u8 {
+        /// Two flash devices, one active and one being updated.
+        Dual = 0b00,
+        /// A single flash device.
+        Single = 0b01,
+        /// Two flash devices, with the RoT filtering in bypass mode.
+        DualFilteredBypass = 0b10,
+        /// A single flash device, with the RoT filtering in bypass mode.
+        SingleFilteredBypass = 0b11,
+    }
+}
+
+/// A Platform Configuration Data manifest.
+///
+/// This type provides functions for parsing a PCD's table of contents and
+/// using it to extract other portions of the PCD.
+///
+/// This type only maintains the TOC in memory for book-keeping.
+pub struct ParsedPcd<'pcd, Provenance = provenance::Signed> {
+    container: Container<'pcd, Pcd, Provenance>,
+}
+
+/// A [`Manifest`] implementation mapping onto [`ParsedPcd`], for use in generic
+/// contexts.
+///
+/// See [`Manifest`] and [`Parse`].
+pub enum Pcd {}
+
+impl Manifest for Pcd {
+    type ElementType = ElementType;
+    const TYPE: ManifestType = ManifestType::Pcd;
+
+    fn min_version(_: ElementType) -> u8 {
+        // Every revision of these elements so far begins with the fields
+        // Manticore reads, so any of them can be parsed.
+        0
+    }
+}
+
+impl<'f, P> Parse<'f, P> for Pcd {
+    type Parsed = ParsedPcd<'f, P>;
+
+    fn parse(container: Container<'f, Self, P>) -> Result<Self::Parsed, Error> {
+        Ok(ParsedPcd::new(container))
+    }
+
+    fn container(manifest: &Self::Parsed) -> &Container<'f, Self, P> {
+        &manifest.container
+    }
+
+    type Guarded = ();
+    fn validate(
+        _manifest: &Self::Parsed,
+        _when: ValidationTime,
+        _args: &Self::Guarded,
+        _hasher: &mut dyn hash::Engine,
+        _arena: &'f dyn Arena,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<P> ParsedManifest for ParsedPcd<'_, P> {
+    type Manifest = Pcd;
+}
+
+impl<'pcd, P> ParsedPcd<'pcd, P> {
+    /// Creates a new PCD handle using the given `Container`.
+    pub fn new(container: Container<'pcd, Pcd, P>) -> Self {
+        ParsedPcd { container }
+    }
+}
+
+impl<'pcd, P> ParsedPcd<'pcd, P>
+where
+    P: Provenance,
+{
+    /// Extracts the `Rot` element from this PCD.
+    ///
+    /// This function will also verify the hash of the `Rot` if one is
+    /// present.
+    pub fn rot(
+        &self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<Option<Rot<'_, 'pcd, P>>, Error> {
+        let entry =
+            match self.container.toc().singleton(ElementType::Rot.into()) {
+                Some(x) => x,
+                None => return Ok(None),
+            };
+
+        #[derive(Clone, Copy, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            mode: u8,
+            address: u8,
+            eid: u8,
+            bridge_address: u8,
+            bridge_eid: u8,
+            _unused: [u8; 3],
+        }
+        let (header, _) = entry.read_with_header::<Header, P>(
+            self.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let mode =
+            RotMode::from_wire_value(header.mode).ok_or(Error::OutOfRange)?;
+
+        Ok(Some(Rot {
+            pcd: self,
+            entry,
+            mode,
+            address: header.address,
+            eid: header.eid,
+            bridge_address: header.bridge_address,
+            bridge_eid: header.bridge_eid,
+        }))
+    }
+
+    /// Extracts the `PowerController` element from this PCD.
+    ///
+    /// This function will also verify the hash of the `PowerController` if
+    /// one is present.
+    pub fn power_controller(
+        &self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<Option<PowerController<'_, 'pcd>>, Error> {
+        let entry = match self
+            .container
+            .toc()
+            .singleton(ElementType::PowerController.into())
+        {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let (header, rest) = entry.read_with_header::<I2cHeader, P>(
+            self.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let i2c = I2c::parse(header, rest, entry.index())?;
+
+        Ok(Some(PowerController { entry, i2c }))
+    }
+
+    /// Returns an iterator over the `DirectComponent` elements of this PCD.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn direct_components(
+        &self,
+    ) -> impl Iterator<Item = DirectComponentEntry<'_, 'pcd, P>> + '_ {
+        self.container
+            .toc()
+            .entries_of(ElementType::DirectComponent.into())
+            .map(move |entry| DirectComponentEntry { pcd: self, entry })
+    }
+
+    /// Returns an iterator over the `BridgeComponent` elements of this PCD.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn bridge_components(
+        &self,
+    ) -> impl Iterator<Item = BridgeComponentEntry<'_, 'pcd, P>> + '_ {
+        self.container
+            .toc()
+            .entries_of(ElementType::BridgeComponent.into())
+            .map(move |entry| BridgeComponentEntry { pcd: self, entry })
+    }
+}
+
+/// A descriptor for the RoT a PCD configures.
+pub struct Rot<'a, 'pcd, Provenance = provenance::Signed> {
+    pcd: &'a ParsedPcd<'pcd, Provenance>,
+    entry: TocEntry<'a, 'pcd, Pcd>,
+    mode: RotMode,
+    address: u8,
+    eid: u8,
+    bridge_address: u8,
+    bridge_eid: u8,
+}
+
+impl<'a, 'pcd, P> Rot<'a, 'pcd, P> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Returns whether this RoT is an AC-RoT or a PA-RoT.
+    pub fn mode(&self) -> RotMode {
+        self.mode
+    }
+
+    /// Returns this RoT's I2C address.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns this RoT's MCTP endpoint ID.
+    pub fn eid(&self) -> u8 {
+        self.eid
+    }
+
+    /// Returns the I2C address of the MCTP bridge this RoT is behind.
+    pub fn bridge_address(&self) -> u8 {
+        self.bridge_address
+    }
+
+    /// Returns the MCTP endpoint ID of the MCTP bridge this RoT is behind.
+    pub fn bridge_eid(&self) -> u8 {
+        self.bridge_eid
+    }
+
+    /// Returns an iterator over the `Port` subelements of this `Rot`.
+    ///
+    /// The returned values only contain the `Toc` information for the entry,
+    /// allowing the user to lazily select which entries to read from flash.
+    pub fn ports(&self) -> impl Iterator<Item = PortEntry<'_, 'pcd, P>> + '_ {
+        self.entry
+            .children_of(ElementType::Port.into())
+            .map(move |entry| PortEntry { rot: self, entry })
+    }
+}
+
+/// A "port" element entry in a PCD's `Toc`.
+///
+/// This type allows for lazily reading the [`Port`] described by this entry,
+/// as obtained from [`Rot::ports()`].
+pub struct PortEntry<'a, 'pcd, Provenance = provenance::Signed> {
+    rot: &'a Rot<'a, 'pcd, Provenance>,
+    entry: TocEntry<'a, 'pcd, Pcd>,
+}
+
+impl<'a, 'pcd, P> PortEntry<'a, 'pcd, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<Port<'a, 'pcd, P>, Error> {
+        #[derive(Clone, Copy, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            port_id: u8,
+            flags: u8,
+            policy: u8,
+            pulse_interval: u8,
+            spi_frequency_hz: u32,
+        }
+        let (header, _) = self.entry.read_with_header::<Header, P>(
+            self.rot.pcd.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let policy =
+            Policy::from_wire_value(header.policy).ok_or(Error::OutOfRange)?;
+
+        Ok(Port {
+            entry: self,
+            port_id: header.port_id,
+            flags: header.flags,
+            policy,
+            pulse_interval: header.pulse_interval,
+            spi_frequency_hz: header.spi_frequency_hz,
+        })
+    }
+}
+
+/// A "port" element from a PCD, describing a SPI flash port protected by the
+/// RoT.
+///
+/// To obtain a value of this type, see [`Rot::ports()`] and
+/// [`PortEntry::read()`].
+pub struct Port<'a, 'pcd, Provenance = provenance::Signed> {
+    entry: PortEntry<'a, 'pcd, Provenance>,
+    port_id: u8,
+    flags: u8,
+    policy: Policy,
+    pulse_interval: u8,
+    spi_frequency_hz: u32,
+}
+
+impl<'a, 'pcd, P> Port<'a, 'pcd, P> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry.entry
+    }
+
+    /// Returns the port's index, as used by e.g. [`protocol::ResetCounter`].
+    ///
+    /// [`protocol::ResetCounter`]: crate::protocol::ResetCounter
+    pub fn port_id(&self) -> u8 {
+        self.port_id
+    }
+
+    /// Returns how the flash devices behind this port are wired up.
+    pub fn flash_mode(&self) -> FlashMode {
+        // NOTE: Every two-bit value is a valid `FlashMode`.
+        FlashMode::from_wire_value(self.flags & 0b11).unwrap()
+    }
+
+    /// Returns whether the port's flash should be verified at runtime, rather
+    /// than only at boot.
+    pub fn runtime_verification(&self) -> bool {
+        self.flags & 0b100 != 0
+    }
+
+    /// Returns whether the RoT should monitor the host's watchdog.
+    pub fn watchdog_monitoring(&self) -> bool {
+        self.flags & 0b1000 != 0
+    }
+
+    /// Returns the raw encoded flags for this element.
+    pub fn raw_flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Returns the policy to apply when this port fails verification.
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// Returns the interval between host reset pulses, in milliseconds.
+    ///
+    /// Zero indicates that reset is held rather than pulsed.
+    pub fn pulse_interval_ms(&self) -> u32 {
+        self.pulse_interval as u32 * 10
+    }
+
+    /// Returns the frequency of the SPI bus, in hertz.
+    pub fn spi_frequency_hz(&self) -> u32 {
+        self.spi_frequency_hz
+    }
+}
+
+#[derive(Clone, Copy, FromBytes, AsBytes)]
+#[repr(C)]
+struct I2cHeader {
+    mode: u8,
+    bus: u8,
+    address: u8,
+    eid: u8,
+    mux_count: u8,
+    _unused: [u8; 3],
+}
+
+/// An I2C mux on the path from the RoT to a device.
+#[derive(FromBytes, AsBytes)]
+#[repr(C)]
+pub struct Mux {
+    address: u8,
+    channel: u8,
+    _reserved: [u8; 2],
+}
+
+impl Mux {
+    /// Returns the mux's I2C address.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns the mux channel the device is on.
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+}
+
+/// The I2C path from the RoT to a device.
+pub struct I2c<'pcd> {
+    mode: I2cMode,
+    header: &'pcd I2cHeader,
+    muxes: &'pcd [Mux],
+}
+
+impl<'pcd> I2c<'pcd> {
+    fn parse(
+        header: &'pcd I2cHeader,
+        rest: &'pcd [u8],
+        toc_index: usize,
+    ) -> Result<Self, Error> {
+        let mode =
+            I2cMode::from_wire_value(header.mode).ok_or(Error::OutOfRange)?;
+        let muxes_len = header.mux_count as usize * mem::size_of::<Mux>();
+        let muxes = rest
+            .get(..muxes_len)
+            .and_then(LayoutVerified::<_, [Mux]>::new_slice)
+            .ok_or(Error::TooShort { toc_index })?
+            .into_slice();
+        Ok(Self {
+            mode,
+            header,
+            muxes,
+        })
+    }
+
+    /// Returns the role the RoT plays on the bus.
+    pub fn mode(&self) -> I2cMode {
+        self.mode
+    }
+
+    /// Returns the RoT's I2C bus the device is on.
+    pub fn bus(&self) -> u8 {
+        self.header.bus
+    }
+
+    /// Returns the device's I2C address.
+    pub fn address(&self) -> u8 {
+        self.header.address
+    }
+
+    /// Returns the device's MCTP endpoint ID.
+    pub fn eid(&self) -> u8 {
+        self.header.eid
+    }
+
+    /// Returns the muxes between the RoT and the device, nearest first.
+    pub fn muxes(&self) -> &'pcd [Mux] {
+        self.muxes
+    }
+}
+
+/// A descriptor for the power controller a PCD describes.
+pub struct PowerController<'a, 'pcd> {
+    entry: TocEntry<'a, 'pcd, Pcd>,
+    i2c: I2c<'pcd>,
+}
+
+impl<'a, 'pcd> PowerController<'a, 'pcd> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Returns the I2C path to the power controller.
+    pub fn i2c(&self) -> &I2c<'pcd> {
+        &self.i2c
+    }
+}
+
+/// The location of a component's power control bits on the
+/// [`PowerController`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PowerControl {
+    /// The power controller register holding the component's bits.
+    pub register: u8,
+    /// A mask of the component's bits within `register`.
+    pub mask: u8,
+}
+
+/// A "direct component" element entry in a PCD's `Toc`.
+///
+/// This type allows for lazily reading the [`DirectComponent`] described by
+/// this entry, as obtained from [`ParsedPcd::direct_components()`].
+pub struct DirectComponentEntry<'a, 'pcd, Provenance = provenance::Signed> {
+    pcd: &'a ParsedPcd<'pcd, Provenance>,
+    entry: TocEntry<'a, 'pcd, Pcd>,
+}
+
+impl<'a, 'pcd, P> DirectComponentEntry<'a, 'pcd, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<DirectComponent<'a, 'pcd>, Error> {
+        #[derive(Clone, Copy, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            policy: u8,
+            power_ctrl_reg: u8,
+            power_ctrl_mask: u8,
+            _unused: u8,
+            component_id: u32,
+            i2c: I2cHeader,
+        }
+        let (header, rest) = self.entry.read_with_header::<Header, P>(
+            self.pcd.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let policy =
+            Policy::from_wire_value(header.policy).ok_or(Error::OutOfRange)?;
+        let i2c = I2c::parse(&header.i2c, rest, self.entry.index())?;
+
+        Ok(DirectComponent {
+            entry: self.entry,
+            policy,
+            power_control: PowerControl {
+                register: header.power_ctrl_reg,
+                mask: header.power_ctrl_mask,
+            },
+            component_id: header.component_id,
+            i2c,
+        })
+    }
+}
+
+/// A "direct component" element from a PCD, describing a component the RoT
+/// reaches directly over I2C.
+///
+/// To obtain a value of this type, see [`ParsedPcd::direct_components()`] and
+/// [`DirectComponentEntry::read()`].
+pub struct DirectComponent<'a, 'pcd> {
+    entry: TocEntry<'a, 'pcd, Pcd>,
+    policy: Policy,
+    power_control: PowerControl,
+    component_id: u32,
+    i2c: I2c<'pcd>,
+}
+
+impl<'a, 'pcd> DirectComponent<'a, 'pcd> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Returns the policy to apply when this component fails attestation.
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// Returns the location of this component's power control bits.
+    pub fn power_control(&self) -> PowerControl {
+        self.power_control
+    }
+
+    /// Returns an identifier for this kind of component, matching a
+    /// [`cfm::ComponentDevice::component_id()`].
+    ///
+    /// [`cfm::ComponentDevice::component_id()`]:
+    ///     crate::manifest::cfm::ComponentDevice::component_id
+    pub fn component_id(&self) -> u32 {
+        self.component_id
+    }
+
+    /// Returns the I2C path to this component.
+    pub fn i2c(&self) -> &I2c<'pcd> {
+        &self.i2c
+    }
+}
+
+/// A "bridge component" element entry in a PCD's `Toc`.
+///
+/// This type allows for lazily reading the [`BridgeComponent`] described by
+/// this entry, as obtained from [`ParsedPcd::bridge_components()`].
+pub struct BridgeComponentEntry<'a, 'pcd, Provenance = provenance::Signed> {
+    pcd: &'a ParsedPcd<'pcd, Provenance>,
+    entry: TocEntry<'a, 'pcd, Pcd>,
+}
+
+impl<'a, 'pcd, P> BridgeComponentEntry<'a, 'pcd, P>
+where
+    P: Provenance,
+{
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Reads the contents of this element into memory, verifying its hash
+    /// and potentially allocating it on `arena`.
+    pub fn read(
+        self,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pcd dyn Arena,
+    ) -> Result<BridgeComponent<'a, 'pcd>, Error> {
+        #[derive(Clone, Copy, FromBytes, AsBytes)]
+        #[repr(C)]
+        struct Header {
+            policy: u8,
+            power_ctrl_reg: u8,
+            power_ctrl_mask: u8,
+            component_count: u8,
+            component_id: u32,
+            vendor_id: u16,
+            device_id: u16,
+            subsys_vendor_id: u16,
+            subsys_id: u16,
+            eid: u8,
+            _unused: [u8; 3],
+        }
+        let (header, _) = self.entry.read_with_header::<Header, P>(
+            self.pcd.container.flash(),
+            arena,
+            hasher,
+        )?;
+        let policy =
+            Policy::from_wire_value(header.policy).ok_or(Error::OutOfRange)?;
+
+        Ok(BridgeComponent {
+            entry: self.entry,
+            policy,
+            power_control: PowerControl {
+                register: header.power_ctrl_reg,
+                mask: header.power_ctrl_mask,
+            },
+            component_count: header.component_count,
+            component_id: header.component_id,
+            device_id: DeviceIdentifier {
+                vendor_id: header.vendor_id,
+                device_id: header.device_id,
+                subsys_vendor_id: header.subsys_vendor_id,
+                subsys_id: header.subsys_id,
+            },
+            eid: header.eid,
+        })
+    }
+}
+
+/// A "bridge component" element from a PCD, describing one or more
+/// identical components the RoT reaches through an MCTP bridge.
+///
+/// To obtain a value of this type, see [`ParsedPcd::bridge_components()`] and
+/// [`BridgeComponentEntry::read()`].
+pub struct BridgeComponent<'a, 'pcd> {
+    entry: TocEntry<'a, 'pcd, Pcd>,
+    policy: Policy,
+    power_control: PowerControl,
+    component_count: u8,
+    component_id: u32,
+    device_id: DeviceIdentifier,
+    eid: u8,
+}
+
+impl<'a, 'pcd> BridgeComponent<'a, 'pcd> {
+    /// Returns the `Toc` entry defining this element.
+    pub fn entry(&self) -> TocEntry<'a, 'pcd, Pcd> {
+        self.entry
+    }
+
+    /// Returns the policy to apply when this component fails attestation.
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// Returns the location of this component's power control bits.
+    pub fn power_control(&self) -> PowerControl {
+        self.power_control
+    }
+
+    /// Returns the number of identical components this element describes.
+    pub fn component_count(&self) -> usize {
+        self.component_count as usize
+    }
+
+    /// Returns an identifier for this kind of component, matching a
+    /// [`cfm::ComponentDevice::component_id()`].
+    ///
+    /// [`cfm::ComponentDevice::component_id()`]:
+    ///     crate::manifest::cfm::ComponentDevice::component_id
+    pub fn component_id(&self) -> u32 {
+        self.component_id
+    }
+
+    /// Returns the identifier the component reports for itself, which the
+    /// bridge uses to discover it.
+    pub fn device_id(&self) -> DeviceIdentifier {
+        self.device_id
+    }
+
+    /// Returns the MCTP endpoint ID of the component.
+    pub fn eid(&self) -> u8 {
+        self.eid
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::from_str;
+    use testutil::data::keys;
+
+    use crate::crypto::ring;
+    use crate::hardware::flash::Ram;
+    use crate::manifest::owned;
+    use crate::manifest::testdata;
+    use crate::manifest::ManifestExt as _;
+    use crate::mem::OutOfMemory;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn empty() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pcd: owned::Pcd = from_str(r#"{
+            "version_id": 42,
+            "elements": []
+        }"#).unwrap();
+        let bytes = Ram(pcd
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let pcd = ParsedPcd::new(container);
+
+        assert!(pcd
+            .platform_id(&mut hasher, &OutOfMemory)
+            .unwrap()
+            .is_none());
+        assert!(pcd.rot(&mut hasher, &OutOfMemory).unwrap().is_none());
+        assert!(pcd
+            .power_controller(&mut hasher, &OutOfMemory)
+            .unwrap()
+            .is_none());
+        assert_eq!(pcd.direct_components().count(), 0);
+        assert_eq!(pcd.bridge_components().count(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn topology() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pcd: owned::Pcd = from_str(r#"{
+            "version_id": 42,
+            "elements": [
+                {
+                    "mode": "Platform",
+                    "address": "0x41",
+                    "eid": "0x0b",
+                    "bridge_address": "0x10",
+                    "bridge_eid": "0x08",
+                    "children": [{
+                        "port_id": 0,
+                        "flags": "0b0101",
+                        "policy": "Active",
+                        "pulse_interval": 5,
+                        "spi_frequency_hz": 50000000
+                    }]
+                },
+                {
+                    "i2c": {
+                        "mode": "MasterSlave",
+                        "bus": 2,
+                        "address": "0x22",
+                        "eid": "0x00",
+                        "muxes": []
+                    }
+                },
+                {
+                    "policy": "Passive",
+                    "power_ctrl_reg": "0x03",
+                    "power_ctrl_mask": "0b10",
+                    "component_id": "0xc0ffee",
+                    "i2c": {
+                        "mode": "MultiMaster",
+                        "bus": 1,
+                        "address": "0x42",
+                        "eid": "0x0c",
+                        "muxes": [
+                            { "address": "0x70", "channel": 3 },
+                            { "address": "0x71", "channel": 0 }
+                        ]
+                    }
+                },
+                {
+                    "policy": "Active",
+                    "power_ctrl_reg": "0x04",
+                    "power_ctrl_mask": "0b1",
+                    "component_id": "0xbeef",
+                    "component_count": 2,
+                    "device_id": {
+                        "vendor_id": "0x1414",
+                        "device_id": "0x0001",
+                        "subsys_vendor_id": "0x1414",
+                        "subsys_id": "0x0002"
+                    },
+                    "eid": "0x0d"
+                }
+            ]
+        }"#).unwrap();
+        let bytes = Ram(pcd
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let pcd = ParsedPcd::new(container);
+
+        let rot = pcd.rot(&mut hasher, &OutOfMemory).unwrap().unwrap();
+        assert_eq!(rot.mode(), RotMode::Platform);
+        assert_eq!(rot.address(), 0x41);
+        assert_eq!(rot.eid(), 0x0b);
+        assert_eq!(rot.bridge_address(), 0x10);
+        assert_eq!(rot.bridge_eid(), 0x08);
+
+        let ports = rot
+            .ports()
+            .map(|e| e.read(&mut hasher, &OutOfMemory).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ports.len(), 1);
+        assert_eq!(ports[0].port_id(), 0);
+        assert_eq!(ports[0].flash_mode(), FlashMode::Single);
+        assert!(ports[0].runtime_verification());
+        assert!(!ports[0].watchdog_monitoring());
+        assert_eq!(ports[0].policy(), Policy::Active);
+        assert_eq!(ports[0].pulse_interval_ms(), 50);
+        assert_eq!(ports[0].spi_frequency_hz(), 50_000_000);
+
+        let power = pcd
+            .power_controller(&mut hasher, &OutOfMemory)
+            .unwrap()
+            .unwrap();
+        assert_eq!(power.i2c().mode(), I2cMode::MasterSlave);
+        assert_eq!(power.i2c().bus(), 2);
+        assert_eq!(power.i2c().address(), 0x22);
+        assert!(power.i2c().muxes().is_empty());
+
+        let direct = pcd
+            .direct_components()
+            .map(|e| e.read(&mut hasher, &OutOfMemory).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(direct.len(), 1);
+        assert_eq!(direct[0].policy(), Policy::Passive);
+        assert_eq!(
+            direct[0].power_control(),
+            PowerControl {
+                register: 3,
+                mask: 0b10
+            }
+        );
+        assert_eq!(direct[0].component_id(), 0xc0ffee);
+        let i2c = direct[0].i2c();
+        assert_eq!(i2c.mode(), I2cMode::MultiMaster);
+        assert_eq!((i2c.bus(), i2c.address(), i2c.eid()), (1, 0x42, 0x0c));
+        let muxes = i2c
+            .muxes()
+            .iter()
+            .map(|m| (m.address(), m.channel()))
+            .collect::<Vec<_>>();
+        assert_eq!(muxes, [(0x70, 3), (0x71, 0)]);
+
+        let bridged = pcd
+            .bridge_components()
+            .map(|e| e.read(&mut hasher, &OutOfMemory).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(bridged.len(), 1);
+        assert_eq!(bridged[0].policy(), Policy::Active);
+        assert_eq!(bridged[0].component_count(), 2);
+        assert_eq!(bridged[0].component_id(), 0xbeef);
+        assert_eq!(bridged[0].device_id().subsys_id, 2);
+        assert_eq!(bridged[0].eid(), 0x0d);
+    }
+}
@@ -26,6 +26,11 @@ use zerocopy::FromBytes;
 use zerocopy::LayoutVerified;
 
 use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::hardware::flash;
+use crate::hardware::flash::Flash;
+use crate::hardware::flash::FlashExt as _;
+use crate::hardware::flash::Protection;
 use crate::hardware::flash::Region;
 use crate::manifest::provenance;
 use crate::manifest::provenance::Provenance;
@@ -43,6 +48,9 @@ use crate::mem::Arena;
 use crate::mem::ArenaExt as _;
 use crate::protocol::wire::WireEnum as _;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 wire_enum! {
     /// A PFM element type.
     pub enum ElementType: u8 {
@@ -80,11 +88,16 @@ impl Manifest for Pfm {
     const TYPE: ManifestType = ManifestType::Pfm;
 
     fn min_version(_: ElementType) -> u8 {
+        // Every revision of these elements so far begins with the fields
+        // Manticore reads, so any of them can be parsed.
         0
     }
 }
 
-impl<'f, P> Parse<'f, P> for Pfm {
+impl<'f, P> Parse<'f, P> for Pfm
+where
+    P: Provenance,
+{
     type Parsed = ParsedPfm<'f, P>;
 
     fn parse(container: Container<'f, Self, P>) -> Result<Self::Parsed, Error> {
@@ -95,12 +108,34 @@ impl<'f, P> Parse<'f, P> for Pfm {
         &manifest.container
     }
 
-    type Guarded = ();
+    /// The host flash device described by the PFM.
+    type Guarded = dyn Flash + 'f;
+
+    /// Validates the contents of the host flash device against this PFM.
+    ///
+    /// For each `AllowableFw`, this function looks for the `FwVersion` whose
+    /// version string is present in `flash` at its version address, and then
+    /// checks the hash of each of that version's image regions. At
+    /// [`ValidationTime::Startup`], only images marked as
+    /// [`FwRegion::must_validate_on_boot()`] are checked.
     fn validate(
-        _manifest: &Self::Parsed,
-        _when: ValidationTime,
-        _args: &Self::Guarded,
+        manifest: &Self::Parsed,
+        when: ValidationTime,
+        flash: &Self::Guarded,
+        hasher: &mut dyn hash::Engine,
+        arena: &'f dyn Arena,
     ) -> Result<(), Error> {
+        for fw in manifest.allowable_fws() {
+            let version = fw.present_version(flash, hasher, arena)?;
+            for image in version.image_regions() {
+                if let ValidationTime::Startup = when {
+                    if !image.must_validate_on_boot() {
+                        continue;
+                    }
+                }
+                image.check_hash(flash, hasher, version.entry().index())?;
+            }
+        }
         Ok(())
     }
 }
@@ -168,6 +203,127 @@ where
             .entries_of(ElementType::AllowableFw.into())
             .map(move |entry| AllowableFwEntry { pfm: self, entry })
     }
+
+    /// Finds the `AllowableFw` element with the firmware ID `fw_id`.
+    ///
+    /// Unlike reading every element yielded by [`ParsedPfm::allowable_fws()`],
+    /// this function only allocates the matching element on `arena`; other
+    /// elements have just their IDs inspected, and their hashes are not
+    /// verified.
+    pub fn find_fw(
+        &self,
+        fw_id: &[u8],
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<Option<AllowableFw<'_, 'pfm, P>>, Error> {
+        let mut buf = [0; 255];
+        for fw in self.allowable_fws() {
+            let (_, id) = peek_string(
+                fw.entry,
+                self.container.flash(),
+                |h: &AllowableFwHeader| h.id_len,
+                &mut buf,
+            )?;
+            if id != fw_id {
+                continue;
+            }
+
+            let fw = fw.read(hasher, arena)?;
+            if fw.firmware_id() == fw_id {
+                return Ok(Some(fw));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Configures `protection` so that only the read-write regions of the
+    /// firmware present in `flash` remain writable.
+    ///
+    /// All of `flash` is locked first, and then the [`RwRegion`]s of each
+    /// `AllowableFw`'s [present version][AllowableFw::present_version] are
+    /// unlocked. If this fails for any firmware, such as because its version
+    /// cannot be determined, all of `flash` is left locked, including the
+    /// regions of any firmware that was already unlocked.
+    ///
+    /// Read-write regions must be aligned to [`Protection::granularity()`].
+    pub fn protect(
+        &self,
+        flash: &dyn Flash,
+        protection: &mut dyn Protection,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<(), Error> {
+        let whole = Region::new(0, flash.size()?);
+        protection.lock(whole)?;
+        let result = self.unlock_rw_regions(flash, protection, hasher, arena);
+        if result.is_err() {
+            protection.lock(whole)?;
+        }
+        result
+    }
+
+    /// Unlocks the read-write regions of each firmware present in `flash`.
+    ///
+    /// See [`ParsedPfm::protect()`].
+    fn unlock_rw_regions(
+        &self,
+        flash: &dyn Flash,
+        protection: &mut dyn Protection,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<(), Error> {
+        let granularity = protection.granularity()?;
+        for fw in self.allowable_fws() {
+            let version = fw.present_version(flash, hasher, arena)?;
+            for rw in version.rw_regions() {
+                let region = rw.region();
+                if region.align_to(granularity) != Some(region) {
+                    return Err(flash::Error::Unaligned.into());
+                }
+                protection.unlock(region)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Enacts the [`RwFailurePolicy`] of each read-write region of the
+    /// firmware present in `flash`.
+    ///
+    /// This is intended to be called when `flash` fails validation, such as
+    /// with [`Pfm::validate()`]. Regions with the
+    /// [`RestoreFromRo`][RwFailurePolicy::RestoreFromRo] policy are copied out
+    /// of `ro`, a known-good copy of `flash` with the same layout; if it is not
+    /// provided, [`Error::NoRestoreSource`] is returned.
+    ///
+    /// Regions that are erased or restored must be aligned to
+    /// [`Flash::erase_size()`].
+    pub fn apply_rw_failure_policy(
+        &self,
+        flash: &mut dyn Flash,
+        ro: Option<&dyn Flash>,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<(), Error> {
+        for fw in self.allowable_fws() {
+            let version = fw.present_version(&*flash, hasher, arena)?;
+            for rw in version.rw_regions() {
+                let region = rw.region();
+                match rw.failure_policy() {
+                    Some(RwFailurePolicy::Erase) => flash.erase(region)?,
+                    Some(RwFailurePolicy::RestoreFromRo) => {
+                        let ro = ro.ok_or(Error::NoRestoreSource {
+                            toc_index: version.entry().index(),
+                        })?;
+                        flash.erase(region)?;
+                        flash.copy_from(ro, region)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        flash.flush()?;
+        Ok(())
+    }
 }
 
 /// A descriptor for a flash device protected by a PFM.
@@ -195,6 +351,61 @@ impl<'a, 'pfm> FlashDeviceInfo<'a, 'pfm> {
     }
 }
 
+/// The fixed-size header of an `AllowableFw` element.
+#[derive(Copy, Clone, FromBytes, AsBytes)]
+#[repr(C)]
+struct AllowableFwHeader {
+    fw_count: u8,
+    id_len: u8,
+    flags: u8,
+    _unused: u8,
+}
+
+/// The fixed-size header of a `FwVersion` element.
+#[derive(Copy, Clone, FromBytes, AsBytes)]
+#[repr(C)]
+struct FwVersionHeader {
+    image_count: u8,
+    rw_count: u8,
+    version_len: u8,
+    _unused: u8,
+    version_addr: u32,
+}
+
+/// Reads the header of `entry`, and the string of length `str_len(header)`
+/// that immediately follows it, into `buf`.
+///
+/// This does not verify `entry`'s hash, and is only intended for cheaply
+/// deciding whether an element is worth reading in full; callers must check
+/// the string again after doing so.
+fn peek_string<'b, H>(
+    entry: TocEntry<'_, '_, Pfm>,
+    flash: &dyn Flash,
+    str_len: impl FnOnce(&H) -> u8,
+    buf: &'b mut [u8; 255],
+) -> Result<(H, &'b [u8]), Error>
+where
+    H: Copy + FromBytes + AsBytes,
+{
+    let region = entry.region();
+    let header_len = mem::size_of::<H>() as u32;
+    let mut header = H::new_zeroed();
+    let len = region.len.checked_sub(header_len).ok_or(Error::TooShort {
+        toc_index: entry.index(),
+    })?;
+    flash.read(region.offset, header.as_bytes_mut())?;
+
+    let str_len = str_len(&header);
+    if len < str_len as u32 {
+        return Err(Error::TooShort {
+            toc_index: entry.index(),
+        });
+    }
+    let string = &mut buf[..str_len as usize];
+    flash.read(region.offset + header_len, string)?;
+    Ok((header, string))
+}
+
 /// An "allowable firmware" element entry in a PFM's `Toc`.
 ///
 /// This type allows for lazily reading the [`AllowableFw`] described by this
@@ -220,19 +431,12 @@ where
         hasher: &mut dyn hash::Engine,
         arena: &'pfm dyn Arena,
     ) -> Result<AllowableFw<'a, 'pfm, P>, Error> {
-        #[derive(Copy, Clone, FromBytes, AsBytes)]
-        #[repr(C)]
-        struct Header {
-            fw_count: u8,
-            id_len: u8,
-            flags: u8,
-            _unused: u8,
-        }
-        let (header, rest) = self.entry.read_with_header::<Header, P>(
-            self.pfm.container.flash(),
-            arena,
-            hasher,
-        )?;
+        let (header, rest) =
+            self.entry.read_with_header::<AllowableFwHeader, P>(
+                self.pfm.container.flash(),
+                arena,
+                hasher,
+            )?;
         let fw_id =
             rest.get(..header.id_len as usize).ok_or(Error::TooShort {
                 toc_index: self.entry.index(),
@@ -245,6 +449,59 @@ where
             flags: header.flags,
         })
     }
+
+    /// Returns an iterator over the `FwVersion` subelements of this
+    /// `AllowableFw`, without reading the `AllowableFw` itself.
+    ///
+    /// See [`AllowableFw::firmware_versions()`].
+    pub fn firmware_versions(
+        &self,
+    ) -> impl Iterator<Item = FwVersionEntry<'a, 'pfm, P>> {
+        let pfm = self.pfm;
+        self.entry
+            .children_of(ElementType::FwVersion.into())
+            .map(move |entry| FwVersionEntry { pfm, entry })
+    }
+
+    /// Finds the `FwVersion` whose version string is present in `flash`, i.e.,
+    /// the version of this firmware that `flash` currently contains.
+    ///
+    /// Only the header of this element and the version strings of its
+    /// children are inspected; like [`AllowableFw::find_version()`], only the
+    /// matching `FwVersion` is allocated on `arena`.
+    ///
+    /// Returns [`Error::UnknownVersion`] if there is no such version.
+    pub fn present_version(
+        &self,
+        flash: &dyn Flash,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<FwVersion<'a, 'pfm, P>, Error> {
+        let mut buf = [0; 255];
+        let mut present = [0; 255];
+        for version in self.firmware_versions() {
+            let (header, expected) = peek_string(
+                version.entry,
+                self.pfm.container.flash(),
+                |h: &FwVersionHeader| h.version_len,
+                &mut buf,
+            )?;
+            let present = &mut present[..expected.len()];
+            flash.read(header.version_addr, present)?;
+            if present != expected {
+                continue;
+            }
+
+            let version = version.read(hasher, arena)?;
+            if version.version().1 == present {
+                return Ok(version);
+            }
+        }
+
+        Err(Error::UnknownVersion {
+            toc_index: self.entry.index(),
+        })
+    }
 }
 
 /// An "allowable firmware" element from a PFM, describing how platform
@@ -279,24 +536,75 @@ impl<'a, 'pfm, P> AllowableFw<'a, 'pfm, P> {
         self.fw_id
     }
 
+    /// Returns whether updates to this firmware can be applied at runtime,
+    /// without a reboot.
+    pub fn runtime_update(&self) -> bool {
+        (self.flags & 1) == 1
+    }
+
     /// Returns the raw encoded flags for this element.
     pub fn raw_flags(&self) -> u8 {
         self.flags
     }
+}
 
+impl<'a, 'pfm, P> AllowableFw<'a, 'pfm, P>
+where
+    P: Provenance,
+{
     /// Returns an iterator over the `FwVersion` subelements of this `AllowableFw`.
     ///
     /// The returned values only contain the `Toc` information for the entry,
     /// allowing the user to lazily select which entries to read from flash.
     pub fn firmware_versions(
         &self,
-    ) -> impl Iterator<Item = FwVersionEntry<'_, 'pfm, P>> + '_ {
-        self.entry().children_of(ElementType::FwVersion.into()).map(
-            move |entry| FwVersionEntry {
-                version: self,
-                entry,
-            },
-        )
+    ) -> impl Iterator<Item = FwVersionEntry<'a, 'pfm, P>> {
+        self.entry.firmware_versions()
+    }
+
+    /// Finds the `FwVersion` element with the version string `version_str`.
+    ///
+    /// Unlike reading every element yielded by
+    /// [`AllowableFw::firmware_versions()`], this function only allocates the
+    /// matching element on `arena`; other elements have just their version
+    /// strings inspected, and their hashes are not verified.
+    pub fn find_version(
+        &self,
+        version_str: &[u8],
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<Option<FwVersion<'_, 'pfm, P>>, Error> {
+        let mut buf = [0; 255];
+        for version in self.firmware_versions() {
+            let (_, found) = peek_string(
+                version.entry,
+                self.entry.pfm.container.flash(),
+                |h: &FwVersionHeader| h.version_len,
+                &mut buf,
+            )?;
+            if found != version_str {
+                continue;
+            }
+
+            let version = version.read(hasher, arena)?;
+            if version.version().1 == version_str {
+                return Ok(Some(version));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds the `FwVersion` whose version string is present in `flash`, i.e.,
+    /// the version of this firmware that `flash` currently contains.
+    ///
+    /// See [`AllowableFwEntry::present_version()`].
+    pub fn present_version(
+        &self,
+        flash: &dyn Flash,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<FwVersion<'a, 'pfm, P>, Error> {
+        self.entry.present_version(flash, hasher, arena)
     }
 }
 
@@ -305,7 +613,7 @@ impl<'a, 'pfm, P> AllowableFw<'a, 'pfm, P> {
 /// This type allows for lazily reading the [`FwVersion`] described by this
 /// entry, as obtained from [`AllowableFw::firmware_versions()`].
 pub struct FwVersionEntry<'a, 'pfm, Provenance = provenance::Signed> {
-    version: &'a AllowableFw<'a, 'pfm, Provenance>,
+    pfm: &'a ParsedPfm<'pfm, Provenance>,
     entry: TocEntry<'a, 'pfm, Pfm>,
 }
 
@@ -325,20 +633,12 @@ where
         hasher: &mut dyn hash::Engine,
         arena: &'pfm dyn Arena,
     ) -> Result<FwVersion<'a, 'pfm, P>, Error> {
-        #[derive(Clone, Copy, FromBytes, AsBytes)]
-        #[repr(C)]
-        struct Header {
-            image_count: u8,
-            rw_count: u8,
-            version_len: u8,
-            _unused: u8,
-            version_addr: u32,
-        }
-        let (header, rest) = self.entry.read_with_header::<Header, P>(
-            self.version.entry.pfm.container.flash(),
-            arena,
-            hasher,
-        )?;
+        let (header, rest) =
+            self.entry.read_with_header::<FwVersionHeader, P>(
+                self.pfm.container.flash(),
+                arena,
+                hasher,
+            )?;
 
         if rest.len() < header.version_len as usize {
             return Err(Error::TooShort {
@@ -378,6 +678,7 @@ where
 
         let image_region_offsets =
             arena.alloc_slice::<u32>(header.image_count as usize)?;
+        let mut image_regions_len = 0;
         if header.image_count > 0 {
             // FIXME: we don't need to actually track this "first" offset.
             image_region_offsets[0] = 0;
@@ -425,16 +726,21 @@ where
                         })?;
                 }
 
+                image_regions_len = image_region_offsets[i as usize]
+                    + ranges_len as u32
+                    + hash_type.bytes() as u32
+                    + mem::size_of::<FwRegionHeader>() as u32;
                 if i != header.image_count - 1 {
-                    image_region_offsets[(i + 1) as usize] =
-                        image_region_offsets[i as usize]
-                            + ranges_len as u32
-                            + hash_type.bytes() as u32
-                            + mem::size_of::<FwRegionHeader>() as u32;
+                    image_region_offsets[(i + 1) as usize] = image_regions_len;
                 }
             }
         }
 
+        // Anything past the last image region belongs to fields added by a
+        // newer format version, which are ignored.
+        let unparsed_image_regions =
+            &unparsed_image_regions[..image_regions_len as usize];
+
         Ok(FwVersion {
             entry: self,
             version_addr: header.version_addr,
@@ -547,6 +853,7 @@ wire_enum! {
     /// Cerberus currently does not fully specify what these policies mean
     /// precisely, nor what failure mode they should be enacted with respect
     /// to.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub enum RwFailurePolicy: u8 {
         /// Do nothing.
         DoNothing = 0b00,
@@ -657,6 +964,25 @@ impl FwRegion<'_> {
     pub fn regions(&self) -> impl Iterator<Item = Region> + '_ {
         (0..self.region_count()).map(move |n| self.region(n).unwrap())
     }
+
+    /// Hashes the contents of this region's flash regions, in order, and
+    /// compares the result against [`FwRegion::image_hash()`].
+    ///
+    /// A mismatch is reported as [`Error::BadImageHash`], blaming the element
+    /// at `toc_index`; any other failure is returned as-is.
+    fn check_hash(
+        &self,
+        flash: &dyn Flash,
+        hasher: &mut dyn hash::Engine,
+        toc_index: usize,
+    ) -> Result<(), Error> {
+        let (algo, expected) = self.image_hash();
+        let mut hasher = hasher.new_hash(algo)?;
+        hasher.write_flash(flash, self.regions(), &mut [0; 64])?;
+        hasher
+            .expect(expected)
+            .map_err(|error| Error::BadImageHash { error, toc_index })
+    }
 }
 
 #[cfg(test)]
@@ -668,6 +994,7 @@ mod test {
     use testutil::data::keys;
 
     use crate::crypto::ring;
+    use crate::hardware::flash::Nor;
     use crate::hardware::flash::Ram;
     use crate::io::Write as _;
     use crate::manifest::owned;
@@ -691,7 +1018,7 @@ mod test {
             "elements": []
         }"#).unwrap();
         let bytes = Ram(pfm
-            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
             .unwrap());
 
         let container = Container::parse_and_verify(
@@ -728,7 +1055,7 @@ mod test {
             "elements": [{ "platform_id": "my pfm" }]
         }"#).unwrap();
         let bytes = Ram(pfm
-            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
             .unwrap());
 
         let container = Container::parse_and_verify(
@@ -807,7 +1134,7 @@ mod test {
             ]
         }"#).unwrap();
         let bytes = Ram(pfm
-            .sign(0x0, hash::Algo::Sha256, &mut hasher, &mut signer)
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
             .unwrap());
 
         let container = Container::parse_and_verify(
@@ -942,4 +1269,503 @@ mod test {
         assert_eq!(imgs[0].region(0), Some(Region::new(0x0, 0x200_0000)));
         assert!(imgs[0].region(1).is_none());
     }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn validate() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        let mut host = vec![0xff; 0x400];
+        host[0x10..0x15].copy_from_slice(b"ver-2");
+        for (i, b) in host[0x100..].iter_mut().enumerate() {
+            *b = i as u8;
+        }
+
+        let mut boot_hash = vec![0; 32];
+        let mut h = hasher.new_hash(hash::Algo::Sha256).unwrap();
+        h.write(&host[0x100..0x180]).unwrap();
+        h.write(&host[0x200..0x240]).unwrap();
+        h.finish(&mut boot_hash).unwrap();
+        let mut runtime_hash = vec![0; 32];
+        hasher
+            .contiguous_hash(
+                hash::Algo::Sha256,
+                &host[0x300..0x380],
+                &mut runtime_hash,
+            )
+            .unwrap();
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(&format!(r#"{{
+            "version_id": 1,
+            "elements": [{{
+                "version_count": 2,
+                "firmware_id": "host",
+                "flags": 0,
+                "hashed": true,
+                "children": [
+                    {{
+                        "version_addr": "0x10",
+                        "version_str": "ver-1",
+                        "rw_regions": [],
+                        "image_regions": []
+                    }},
+                    {{
+                        "version_addr": "0x10",
+                        "version_str": "ver-2",
+                        "rw_regions": [],
+                        "image_regions": [
+                            {{
+                                "flags": 1,
+                                "hash_type": "Sha256",
+                                "hash": {:?},
+                                "regions": [
+                                    {{ "offset": "0x100", "len": "0x80" }},
+                                    {{ "offset": "0x200", "len": "0x40" }}
+                                ]
+                            }},
+                            {{
+                                "flags": 0,
+                                "hash_type": "Sha256",
+                                "hash": {:?},
+                                "regions": [
+                                    {{ "offset": "0x300", "len": "0x80" }}
+                                ]
+                            }}
+                        ]
+                    }}
+                ]
+            }}]
+        }}"#, boot_hash, runtime_hash)).unwrap();
+        let bytes = Ram(pfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+
+        let mut arena = vec![0; 4096];
+        let arena = BumpArena::new(&mut arena);
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &arena,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let pfm = ParsedPfm::new(container);
+
+        let mut check = |host: &[u8], when| {
+            Pfm::validate(&pfm, when, &Ram(host), &mut hasher, &arena)
+        };
+
+        assert!(check(&host, ValidationTime::Startup).is_ok());
+        assert!(check(&host, ValidationTime::Activation).is_ok());
+
+        // Corrupting an image that need not be validated on boot is only
+        // caught on activation.
+        let mut bad_runtime = host.clone();
+        bad_runtime[0x340] ^= 1;
+        assert!(check(&bad_runtime, ValidationTime::Startup).is_ok());
+        assert!(matches!(
+            check(&bad_runtime, ValidationTime::Activation),
+            Err(Error::BadImageHash { .. })
+        ));
+
+        let mut bad_boot = host.clone();
+        bad_boot[0x220] ^= 1;
+        assert!(matches!(
+            check(&bad_boot, ValidationTime::Startup),
+            Err(Error::BadImageHash { .. })
+        ));
+
+        let mut unknown = host.clone();
+        unknown[0x10..0x15].copy_from_slice(b"ver-3");
+        assert!(matches!(
+            check(&unknown, ValidationTime::Startup),
+            Err(Error::UnknownVersion { .. })
+        ));
+
+        // Failing to read an image is not a hash mismatch.
+        assert!(matches!(
+            check(&host[..0x280], ValidationTime::Activation),
+            Err(Error::Flash(_))
+        ));
+    }
+
+    /// Returns a signed PFM for firmware at version `ver-1` with read-write
+    /// regions at `0x100`, `0x200`, and `0x300`, which should be restored,
+    /// erased, and left alone on failure, respectively.
+    fn rw_pfm(
+        hasher: &mut ring::hash::Engine,
+        signer: &mut ring::rsa::Sign256,
+    ) -> Vec<u8> {
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 1,
+            "elements": [{
+                "version_count": 1,
+                "firmware_id": "host",
+                "flags": 0,
+                "hashed": true,
+                "children": [{
+                    "version_addr": "0x10",
+                    "version_str": "ver-1",
+                    "rw_regions": [
+                        {
+                            "flags": { "failure_policy": "RestoreFromRo" },
+                            "region": { "offset": "0x100", "len": "0x100" }
+                        },
+                        {
+                            "flags": { "failure_policy": "Erase" },
+                            "region": { "offset": "0x200", "len": "0x100" }
+                        },
+                        {
+                            "flags": { "failure_policy": "DoNothing" },
+                            "region": { "offset": "0x300", "len": "0x100" }
+                        }
+                    ],
+                    "image_regions": []
+                }]
+            }]
+        }"#).unwrap();
+        pfm.sign(0x0, testdata::RSA_SHA256, hasher, signer).unwrap()
+    }
+
+    /// A fake `Protection` that tracks locks with a fixed granularity.
+    struct FakeProtection {
+        granularity: u32,
+        locked: Vec<bool>,
+    }
+
+    impl FakeProtection {
+        fn set(
+            &mut self,
+            region: Region,
+            lock: bool,
+        ) -> Result<(), flash::Error> {
+            if region.align_to(self.granularity) != Some(region) {
+                return Err(flash::Error::Unaligned);
+            }
+            let start = (region.offset / self.granularity) as usize;
+            let end = (region.end() / self.granularity) as usize;
+            for l in self
+                .locked
+                .get_mut(start..end)
+                .ok_or(flash::Error::OutOfRange)?
+            {
+                *l = lock;
+            }
+            Ok(())
+        }
+    }
+
+    impl Protection for FakeProtection {
+        fn granularity(&self) -> Result<u32, flash::Error> {
+            Ok(self.granularity)
+        }
+
+        fn lock(&mut self, region: Region) -> Result<(), flash::Error> {
+            self.set(region, true)
+        }
+
+        fn unlock(&mut self, region: Region) -> Result<(), flash::Error> {
+            self.set(region, false)
+        }
+
+        fn is_locked(&self, region: Region) -> Result<bool, flash::Error> {
+            let start = (region.offset / self.granularity) as usize;
+            let end = ((region.end() + self.granularity - 1) / self.granularity)
+                as usize;
+            Ok(self.locked[start..end].iter().all(|&l| l))
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn protect() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let bytes = Ram(rw_pfm(&mut hasher, &mut signer));
+
+        let mut arena = vec![0; 4096];
+        let arena = BumpArena::new(&mut arena);
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &arena,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let pfm = ParsedPfm::new(container);
+
+        let mut host = vec![0xff; 0x400];
+        host[0x10..0x15].copy_from_slice(b"ver-1");
+        let mut protection = FakeProtection {
+            granularity: 0x100,
+            locked: vec![false; 4],
+        };
+        pfm.protect(&Ram(&host), &mut protection, &mut hasher, &arena)
+            .unwrap();
+        assert!(protection.is_locked(Region::new(0, 0x100)).unwrap());
+        assert_eq!(protection.locked, vec![true, false, false, false]);
+
+        // An unknown version leaves everything locked.
+        host[0x10..0x15].copy_from_slice(b"ver-2");
+        assert!(matches!(
+            pfm.protect(&Ram(&host), &mut protection, &mut hasher, &arena),
+            Err(Error::UnknownVersion { .. })
+        ));
+        assert!(protection.is_locked(Region::new(0, 0x400)).unwrap());
+
+        // The read-write regions are too small to be unlocked.
+        host[0x10..0x15].copy_from_slice(b"ver-1");
+        let mut protection = FakeProtection {
+            granularity: 0x200,
+            locked: vec![false; 2],
+        };
+        assert!(matches!(
+            pfm.protect(&Ram(&host), &mut protection, &mut hasher, &arena),
+            Err(Error::Flash(flash::Error::Unaligned))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn protect_two_firmwares() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 1,
+            "elements": [
+                {
+                    "version_count": 1,
+                    "firmware_id": "bios",
+                    "flags": 0,
+                    "children": [{
+                        "version_addr": "0x10",
+                        "version_str": "ver-1",
+                        "rw_regions": [{
+                            "flags": 0,
+                            "region": { "offset": "0x100", "len": "0x100" }
+                        }],
+                        "image_regions": []
+                    }]
+                },
+                {
+                    "version_count": 1,
+                    "firmware_id": "bmc",
+                    "flags": 0,
+                    "children": [{
+                        "version_addr": "0x20",
+                        "version_str": "ver-1",
+                        "rw_regions": [{
+                            "flags": 0,
+                            "region": { "offset": "0x280", "len": "0x80" }
+                        }],
+                        "image_regions": []
+                    }]
+                }
+            ]
+        }"#).unwrap();
+        let bytes = Ram(pfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+
+        let mut arena = vec![0; 4096];
+        let arena = BumpArena::new(&mut arena);
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &arena,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let pfm = ParsedPfm::new(container);
+
+        let mut host = vec![0xff; 0x400];
+        host[0x10..0x15].copy_from_slice(b"ver-1");
+        host[0x20..0x25].copy_from_slice(b"ver-1");
+        let mut protection = FakeProtection {
+            granularity: 0x80,
+            locked: vec![false; 8],
+        };
+        pfm.protect(&Ram(&host), &mut protection, &mut hasher, &arena)
+            .unwrap();
+        assert_eq!(
+            protection.locked,
+            vec![true, true, false, false, true, false, true, true]
+        );
+
+        // The second firmware's version is unknown, so the first firmware's
+        // regions must not stay unlocked either.
+        host[0x20..0x25].copy_from_slice(b"ver-2");
+        assert!(matches!(
+            pfm.protect(&Ram(&host), &mut protection, &mut hasher, &arena),
+            Err(Error::UnknownVersion { .. })
+        ));
+        assert!(protection.is_locked(Region::new(0, 0x400)).unwrap());
+
+        // Likewise if the second firmware's region is too small to unlock.
+        host[0x20..0x25].copy_from_slice(b"ver-1");
+        let mut protection = FakeProtection {
+            granularity: 0x100,
+            locked: vec![false; 4],
+        };
+        assert!(matches!(
+            pfm.protect(&Ram(&host), &mut protection, &mut hasher, &arena),
+            Err(Error::Flash(flash::Error::Unaligned))
+        ));
+        assert!(protection.is_locked(Region::new(0, 0x400)).unwrap());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn rw_failure_policy() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let bytes = Ram(rw_pfm(&mut hasher, &mut signer));
+
+        let mut arena = vec![0; 4096];
+        let arena = BumpArena::new(&mut arena);
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &arena,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let pfm = ParsedPfm::new(container);
+
+        let mut good = vec![0x55; 0x400];
+        good[0x10..0x15].copy_from_slice(b"ver-1");
+        let mut bad = vec![0x00; 0x400];
+        bad[0x10..0x15].copy_from_slice(b"ver-1");
+        let mut host = Nor::new(bad, 0x100);
+
+        assert!(matches!(
+            pfm.apply_rw_failure_policy(&mut host, None, &mut hasher, &arena),
+            Err(Error::NoRestoreSource { .. })
+        ));
+
+        pfm.apply_rw_failure_policy(
+            &mut host,
+            Some(&Ram(&good)),
+            &mut hasher,
+            &arena,
+        )
+        .unwrap();
+        let host = host.into_inner();
+        assert_eq!(&host[0x100..0x200], &good[0x100..0x200]);
+        assert!(host[0x200..0x300].iter().all(|&b| b == 0xff));
+        assert!(host[0x300..0x400].iter().all(|&b| b == 0x00));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn find() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+
+        // Every version has the same layout, except for its version string.
+        #[rustfmt::skip]
+        let version = r#"{
+            "version_addr": "0x10",
+            "version_str": "VERSION",
+            "rw_regions": [],
+            "image_regions": [{
+                "flags": 0,
+                "hash_type": "Sha256",
+                "hash": [
+                    0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 0, 0, 0, 0
+                ],
+                "regions": [{ "offset": "0x100", "len": "0x100" }]
+            }]
+        }"#;
+        let versions = |strs: &[&str]| {
+            strs.iter()
+                .map(|v| version.replace("VERSION", v))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(&r#"{
+            "version_id": 1,
+            "elements": [
+                {
+                    "version_count": 1,
+                    "firmware_id": "bios",
+                    "flags": 0,
+                    "hashed": true,
+                    "children": [BIOS]
+                },
+                {
+                    "version_count": 3,
+                    "firmware_id": "bmc",
+                    "flags": 0,
+                    "hashed": true,
+                    "children": [BMC]
+                }
+            ]
+        }"#
+        .replace("BIOS", &versions(&["ver-1"]))
+        .replace("BMC", &versions(&["ver-1", "ver-2", "ver-3"])))
+        .unwrap();
+        let bytes = Ram(pfm
+            .sign(0x0, testdata::RSA_SHA256, &mut hasher, &mut signer)
+            .unwrap());
+        let container = Container::parse_and_verify(
+            &bytes,
+            &mut hasher,
+            &mut rsa,
+            &OutOfMemory,
+            &OutOfMemory,
+        )
+        .unwrap();
+        let pfm = ParsedPfm::new(container);
+
+        assert!(pfm
+            .find_fw(b"nic", &mut hasher, &OutOfMemory)
+            .unwrap()
+            .is_none());
+        let fw = pfm
+            .find_fw(b"bmc", &mut hasher, &OutOfMemory)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fw.firmware_id(), b"bmc");
+
+        // Each `FwVersion` allocates one `u32` for its image region; there is
+        // only room for one of them.
+        let mut arena = [0; 7];
+        let mut arena = BumpArena::new(&mut arena);
+        assert!(fw
+            .find_version(b"ver-4", &mut hasher, &arena)
+            .unwrap()
+            .is_none());
+        let version = fw
+            .find_version(b"ver-2", &mut hasher, &arena)
+            .unwrap()
+            .unwrap();
+        assert_eq!(version.version().1, b"ver-2");
+        arena.reset();
+
+        let mut host = vec![0; 0x200];
+        host[0x10..0x15].copy_from_slice(b"ver-3");
+        let version =
+            fw.present_version(&Ram(host), &mut hasher, &arena).unwrap();
+        assert_eq!(version.version().1, b"ver-3");
+    }
 }
@@ -0,0 +1,661 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A/B manifest storage.
+//!
+//! A [`Store`] keeps two copies of a single type of manifest in flash: the
+//! *active* manifest, which the RoT enforces, and a *pending* manifest, which
+//! is being written as part of an update. Once a pending manifest has been
+//! written in full, activating it verifies its signature, checks that its
+//! version ID is no older than that of any manifest activated before it, and
+//! then swaps the roles of the two slots.
+//!
+//! # Boot Records
+//!
+//! Which slot is active is recorded in a separate flash region holding two
+//! *boot records*, each with the following layout, expressed as a
+//! pseudo-Rust struct. Integers are encoded in little-endian order, and `_`
+//! indicates reserved fields that should be set to 0.
+//! ```ignore
+//! struct BootRecord {
+//!     magic: u32, // See `MAGIC`.
+//!     seq: u32,
+//!     active_slot: u8,
+//!     _: [u8; 3],
+//!     version_id: u32,
+//!     check: u32,
+//! }
+//! ```
+//!
+//! `check` is the bitwise complement of
+//! `magic ^ seq ^ active_slot ^ version_id`. The valid record with the most
+//! recent `seq` determines the active slot; `seq` is compared using serial
+//! number arithmetic, so that it may wrap around. `version_id` is the highest
+//! version ID activated so far, which protects against rollback even if the
+//! active manifest is later corrupted.
+//!
+//! The two records are stored in separate erase sectors. Activation never
+//! touches the record currently in use: it erases the other one and writes a
+//! new record, with the next sequence number, in its place. If power is lost
+//! partway through, the half-written record fails its check and the previous
+//! one remains in effect, so activation is atomic.
+
+use core::marker::PhantomData;
+use core::mem;
+
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+
+use crate::crypto::hash;
+use crate::crypto::sig;
+use crate::hardware::flash;
+use crate::hardware::flash::Flash;
+use crate::hardware::flash::FlashExt as _;
+use crate::hardware::flash::Region;
+use crate::manifest;
+use crate::manifest::Container;
+use crate::manifest::Manifest;
+use crate::manifest::Metadata;
+use crate::mem::Arena;
+
+/// The magic number at the start of every boot record.
+pub const MAGIC: u32 = 0x5d2e_b007;
+
+/// An error returned by a manifest store operation.
+#[derive(Copy, Clone, Debug)]
+pub enum Error {
+    /// Indicates that an error occured in a [`flash`] type.
+    Flash(flash::Error),
+
+    /// Indicates that a manifest could not be parsed or verified.
+    Manifest(manifest::Error),
+
+    /// Indicates that a pending manifest's length did not match the number of
+    /// bytes written for it.
+    Malformed,
+
+    /// Indicates that a pending manifest was valid, but could not replace the
+    /// active one, e.g., because its version ID was older.
+    Rollback,
+
+    /// Indicates that an update operation was performed out of order, such as
+    /// writing a manifest before preparing for it or activating a manifest
+    /// that was only partially written.
+    NotPrepared,
+
+    /// Indicates that a manifest is too large for the flash it's stored in.
+    TooLarge,
+
+    /// Indicates that the flash for the boot records is too small to hold
+    /// two of them.
+    BootRecordsTooSmall,
+}
+
+impl From<flash::Error> for Error {
+    fn from(e: flash::Error) -> Self {
+        Self::Flash(e)
+    }
+}
+
+impl From<manifest::Error> for Error {
+    fn from(e: manifest::Error) -> Self {
+        Self::Manifest(e)
+    }
+}
+
+/// A boot record's raw bits.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, AsBytes, FromBytes)]
+#[repr(C)]
+struct RawBootRecord {
+    magic: u32,
+    seq: u32,
+    active_slot: u8,
+    reserved: [u8; 3],
+    version_id: u32,
+    check: u32,
+}
+
+impl RawBootRecord {
+    fn new(seq: u32, active_slot: u8, version_id: u32) -> Self {
+        Self {
+            magic: MAGIC,
+            seq,
+            active_slot,
+            reserved: [0; 3],
+            version_id,
+            check: !(MAGIC ^ seq ^ active_slot as u32 ^ version_id),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        *self == Self::new(self.seq, self.active_slot, self.version_id)
+            && self.active_slot < 2
+    }
+}
+
+/// Returns the region of `boot_records` holding the record at `index`.
+///
+/// Each record is given its own erase sector, so that erasing one never
+/// disturbs the other.
+fn record_region(
+    boot_records: &dyn Flash,
+    index: usize,
+) -> Result<Region, flash::Error> {
+    let len = mem::size_of::<RawBootRecord>() as u32;
+    let stride = Region::new(0, len)
+        .align_to(boot_records.erase_size()?)
+        .ok_or(flash::Error::OutOfRange)?
+        .len;
+    let offset = stride
+        .checked_mul(index as u32)
+        .ok_or(flash::Error::OutOfRange)?;
+    Ok(Region::new(offset, len))
+}
+
+/// The boot record currently in effect.
+#[derive(Copy, Clone)]
+struct Boot {
+    seq: u32,
+    active_slot: usize,
+    version_id: u32,
+    record_index: usize,
+}
+
+impl Boot {
+    /// Returns whether a record with sequence number `seq` was written after
+    /// this one.
+    ///
+    /// Only two records exist at a time, one sequence number apart, so
+    /// comparing them as serial numbers (RFC 1982) is unambiguous even once
+    /// `seq` wraps around.
+    fn is_superseded_by(&self, seq: u32) -> bool {
+        (seq.wrapping_sub(self.seq) as i32) > 0
+    }
+}
+
+/// A pair of boot records, which selects one of two slots as active.
+///
+/// This is shared with [`recovery::Store`], which uses the same A/B scheme
+/// for recovery images.
+///
+/// [`recovery::Store`]: crate::recovery::Store
+pub(crate) struct BootRecords<'a> {
+    flash: &'a mut dyn Flash,
+    boot: Option<Boot>,
+}
+
+impl<'a> BootRecords<'a> {
+    /// Reads the current boot record out of `flash`.
+    ///
+    /// Returns [`flash::Error::OutOfRange`] if `flash` is too small to hold
+    /// two boot records.
+    pub fn new(flash: &'a mut dyn Flash) -> Result<Self, flash::Error> {
+        if record_region(&*flash, 1)?.end() > flash.size()? {
+            return Err(flash::Error::OutOfRange);
+        }
+
+        let mut boot = None::<Boot>;
+        for record_index in 0..2 {
+            let mut record = RawBootRecord::new(0, 0, 0);
+            let region = record_region(&*flash, record_index)?;
+            flash.read(region.offset, record.as_bytes_mut())?;
+            if !record.is_valid() {
+                continue;
+            }
+            if boot.map(|b| b.is_superseded_by(record.seq)).unwrap_or(true) {
+                boot = Some(Boot {
+                    seq: record.seq,
+                    active_slot: record.active_slot as usize,
+                    version_id: record.version_id,
+                    record_index,
+                });
+            }
+        }
+
+        Ok(Self { flash, boot })
+    }
+
+    /// Returns the active slot, if there is one.
+    pub fn active_slot(&self) -> Option<usize> {
+        self.boot.map(|b| b.active_slot)
+    }
+
+    /// Returns the version ID recorded by the last activation, if there was
+    /// one.
+    pub fn version_id(&self) -> Option<u32> {
+        self.boot.map(|b| b.version_id)
+    }
+
+    /// Returns the slot that is not active.
+    pub fn pending_slot(&self) -> usize {
+        self.boot.map(|b| 1 - b.active_slot).unwrap_or(0)
+    }
+
+    /// Atomically makes `slot` the active slot, by writing a new boot record
+    /// in place of the one not currently in use.
+    ///
+    /// `version_id` is recorded alongside it, and reported by
+    /// [`BootRecords::version_id()`] from then on.
+    pub fn activate(
+        &mut self,
+        slot: usize,
+        version_id: u32,
+    ) -> Result<(), flash::Error> {
+        let (seq, record_index) = match self.boot {
+            Some(b) => (b.seq.wrapping_add(1), 1 - b.record_index),
+            None => (0, 0),
+        };
+        let record = RawBootRecord::new(seq, slot as u8, version_id);
+        let region = record_region(&*self.flash, record_index)?;
+        self.flash.erase_covering(region)?;
+        self.flash.program(region.offset, record.as_bytes())?;
+        self.flash.flush()?;
+
+        self.boot = Some(Boot {
+            seq,
+            active_slot: slot,
+            version_id,
+            record_index,
+        });
+        Ok(())
+    }
+}
+
+/// The state of an in-progress update.
+#[derive(Copy, Clone)]
+struct Update {
+    len: u32,
+    written: u32,
+}
+
+/// A/B storage for a single type of manifest.
+///
+/// See the [module documentation][self] for more information.
+pub struct Store<'a, M> {
+    slots: [&'a mut dyn Flash; 2],
+    boot: BootRecords<'a>,
+    update: Option<Update>,
+    _ph: PhantomData<fn() -> M>,
+}
+
+impl<'a, M: Manifest> Store<'a, M> {
+    /// Creates a new `Store`, reading the current boot record out of
+    /// `boot_records`.
+    ///
+    /// `slots` are the two regions manifests are written to; which of them is
+    /// active is tracked in `boot_records`, which must be large enough for
+    /// two boot records. If neither boot record is valid, there is no active
+    /// manifest.
+    pub fn new(
+        slots: [&'a mut dyn Flash; 2],
+        boot_records: &'a mut dyn Flash,
+    ) -> Result<Self, Error> {
+        let boot = BootRecords::new(boot_records).map_err(|e| match e {
+            flash::Error::OutOfRange => Error::BootRecordsTooSmall,
+            e => Error::Flash(e),
+        })?;
+        Ok(Self {
+            slots,
+            boot,
+            update: None,
+            _ph: PhantomData,
+        })
+    }
+
+    /// Returns the flash containing the active manifest, if there is one.
+    pub fn active_flash(&self) -> Option<&dyn Flash> {
+        self.boot.active_slot().map(|slot| &*self.slots[slot])
+    }
+
+    /// Returns the flash that the next pending manifest will be written to.
+    pub fn pending_flash(&self) -> &dyn Flash {
+        &*self.slots[self.boot.pending_slot()]
+    }
+
+    /// Parses and verifies the active manifest, if there is one.
+    ///
+    /// See [`Container::parse_and_verify()`].
+    pub fn active<'s>(
+        &'s self,
+        hasher: &mut dyn hash::Engine,
+        sig_verify: &mut dyn sig::Verify,
+        toc_arena: &'s dyn Arena,
+        verify_arena: &dyn Arena,
+    ) -> Result<Option<Container<'s, M>>, Error> {
+        let flash = match self.active_flash() {
+            Some(f) => f,
+            None => return Ok(None),
+        };
+        let container = Container::parse_and_verify(
+            flash,
+            hasher,
+            sig_verify,
+            toc_arena,
+            verify_arena,
+        )?;
+        Ok(Some(container))
+    }
+
+    /// Starts an update to a manifest of `len` bytes, discarding any update
+    /// already in progress.
+    pub fn prepare(&mut self, len: u32) -> Result<(), Error> {
+        let slot = &mut self.slots[self.boot.pending_slot()];
+        if len > slot.size()? {
+            return Err(Error::TooLarge);
+        }
+        slot.erase_covering(Region::new(0, len))?;
+
+        self.update = Some(Update { len, written: 0 });
+        Ok(())
+    }
+
+    /// Appends `data` to the pending manifest.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        let slot = self.boot.pending_slot();
+        let update = self.update.as_mut().ok_or(Error::NotPrepared)?;
+        let end = update
+            .written
+            .checked_add(data.len() as u32)
+            .filter(|&end| end <= update.len)
+            .ok_or(Error::TooLarge)?;
+
+        self.slots[slot].program(update.written, data)?;
+        update.written = end;
+        Ok(())
+    }
+
+    /// Validates the pending manifest and, if it is valid, makes it the
+    /// active manifest.
+    ///
+    /// The pending manifest must have a valid signature, and its version ID
+    /// must be no older than the highest one recorded in the boot records.
+    /// The active manifest itself is not consulted, so a corrupted active
+    /// slot can be replaced without weakening rollback protection.
+    ///
+    /// Whether or not validation succeeds, the update is over once this
+    /// function is called; a new one must be prepared to try again.
+    pub fn activate(
+        &mut self,
+        hasher: &mut dyn hash::Engine,
+        sig_verify: &mut dyn sig::Verify,
+        arena: &dyn Arena,
+    ) -> Result<(), Error> {
+        let len = match self.update.take() {
+            Some(u) if u.written == u.len => u.len,
+            _ => return Err(Error::NotPrepared),
+        };
+        let pending_slot = self.boot.pending_slot();
+        self.slots[pending_slot].flush()?;
+
+        let pending = Container::<M>::parse_and_verify(
+            &*self.slots[pending_slot],
+            hasher,
+            sig_verify,
+            arena,
+            arena,
+        )?;
+        if pending.signature_region().end() != len {
+            return Err(Error::Malformed);
+        }
+
+        // The highest version activated so far stands in for the active
+        // manifest, which may be missing or corrupted.
+        let metadata = pending.metadata();
+        if let Some(version_id) = self.boot.version_id() {
+            if !metadata.can_replace(&Metadata { version_id }) {
+                return Err(Error::Rollback);
+            }
+        }
+
+        self.boot.activate(pending_slot, metadata.version_id)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::from_str;
+    use testutil::data::keys;
+
+    use crate::crypto::ring;
+    use crate::hardware::flash::Nor;
+    use crate::hardware::flash::RamMut;
+    use crate::manifest::owned;
+    use crate::manifest::pfm::Pfm;
+    use crate::manifest::testdata;
+    use crate::mem::BumpArena;
+
+    /// Returns a signed PFM with the given version ID.
+    fn pfm(version_id: u32, signer: &mut ring::rsa::Sign256) -> Vec<u8> {
+        let pfm: owned::Pfm = from_str(&format!(
+            r#"{{
+                "version_id": {},
+                "elements": [{{ "platform_id": "my platform" }}]
+            }}"#,
+            version_id
+        ))
+        .unwrap();
+        pfm.sign(
+            0x0,
+            testdata::RSA_SHA256,
+            &mut ring::hash::Engine::new(),
+            signer,
+        )
+        .unwrap()
+    }
+
+    fn update(
+        store: &mut Store<Pfm>,
+        manifest: &[u8],
+        rsa: &mut ring::rsa::Verify256,
+        arena: &dyn Arena,
+    ) -> Result<(), Error> {
+        store.prepare(manifest.len() as u32)?;
+        for chunk in manifest.chunks(100) {
+            store.write(chunk)?;
+        }
+        store.activate(&mut ring::hash::Engine::new(), rsa, arena)
+    }
+
+    fn active_version(
+        store: &Store<Pfm>,
+        rsa: &mut ring::rsa::Verify256,
+    ) -> Option<u32> {
+        let mut arena = vec![0; 1024];
+        let arena = BumpArena::new(&mut arena);
+        store
+            .active(&mut ring::hash::Engine::new(), rsa, &arena, &arena)
+            .unwrap()
+            .map(|c| c.metadata().version_id)
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn update_and_activate() {
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut a = Nor::new(vec![0xff; 1024], 256);
+        let mut b = Nor::new(vec![0xff; 1024], 256);
+        let mut records = Nor::new(vec![0xff; 512], 256);
+        let mut arena = vec![0; 1024];
+        let mut arena = BumpArena::new(&mut arena);
+
+        {
+            let mut store =
+                Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+            assert!(store.active_flash().is_none());
+            assert_eq!(active_version(&store, &mut rsa), None);
+
+            update(&mut store, &pfm(1, &mut signer), &mut rsa, &arena).unwrap();
+            arena.reset();
+            assert_eq!(active_version(&store, &mut rsa), Some(1));
+
+            update(&mut store, &pfm(2, &mut signer), &mut rsa, &arena).unwrap();
+            arena.reset();
+            assert_eq!(active_version(&store, &mut rsa), Some(2));
+
+            // Equal versions may be reinstalled, but older ones may not.
+            update(&mut store, &pfm(2, &mut signer), &mut rsa, &arena).unwrap();
+            arena.reset();
+            assert!(matches!(
+                update(&mut store, &pfm(1, &mut signer), &mut rsa, &arena),
+                Err(Error::Rollback)
+            ));
+            arena.reset();
+            assert_eq!(active_version(&store, &mut rsa), Some(2));
+        }
+
+        // The active slot is remembered across reboots.
+        let store = Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+        assert_eq!(active_version(&store, &mut rsa), Some(2));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn torn_boot_record() {
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut a = Nor::new(vec![0xff; 1024], 256);
+        let mut b = Nor::new(vec![0xff; 1024], 256);
+        let mut records = Nor::new(vec![0xff; 512], 256);
+        let mut arena = vec![0; 1024];
+        let mut arena = BumpArena::new(&mut arena);
+
+        {
+            let mut store =
+                Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+            update(&mut store, &pfm(1, &mut signer), &mut rsa, &arena).unwrap();
+            arena.reset();
+            update(&mut store, &pfm(2, &mut signer), &mut rsa, &arena).unwrap();
+        }
+
+        // Simulate losing power halfway through writing the second record,
+        // which lives in the second sector.
+        let mut bytes = records.into_inner();
+        for b in &mut bytes[256 + 8..256 + 16] {
+            *b = 0xff;
+        }
+        let mut records = Nor::new(bytes, 256);
+        let store = Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+        assert_eq!(active_version(&store, &mut rsa), Some(1));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn corrupted_active_slot() {
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut a = Nor::new(vec![0xff; 1024], 256);
+        let mut b = Nor::new(vec![0xff; 1024], 256);
+        let mut records = Nor::new(vec![0xff; 512], 256);
+        let mut arena = vec![0; 1024];
+        let mut arena = BumpArena::new(&mut arena);
+
+        {
+            let mut store =
+                Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+            update(&mut store, &pfm(2, &mut signer), &mut rsa, &arena).unwrap();
+        }
+        arena.reset();
+
+        // Wipe the active manifest, so that its version can no longer be
+        // read out of it.
+        a.erase_covering(Region::new(0, 1024)).unwrap();
+        let mut store =
+            Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+        let mut arena2 = vec![0; 1024];
+        let arena2 = BumpArena::new(&mut arena2);
+        assert!(store
+            .active(&mut ring::hash::Engine::new(), &mut rsa, &arena2, &arena2)
+            .is_err());
+
+        // The boot record still remembers it.
+        assert!(matches!(
+            update(&mut store, &pfm(1, &mut signer), &mut rsa, &arena),
+            Err(Error::Rollback)
+        ));
+        arena.reset();
+        update(&mut store, &pfm(2, &mut signer), &mut rsa, &arena).unwrap();
+        assert_eq!(active_version(&store, &mut rsa), Some(2));
+    }
+
+    #[test]
+    fn seq_wraparound() {
+        let mut records = RamMut(vec![0xff; 40]);
+        let old = RawBootRecord::new(u32::MAX, 0, 1);
+        let new = RawBootRecord::new(0, 1, 2);
+        records.program(0, old.as_bytes()).unwrap();
+        let second = record_region(&records, 1).unwrap();
+        records.program(second.offset, new.as_bytes()).unwrap();
+
+        let boot = BootRecords::new(&mut records).unwrap();
+        assert_eq!(boot.active_slot(), Some(1));
+        assert_eq!(boot.version_id(), Some(2));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn bad_signature() {
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let (_, mut other_signer) =
+            ring::rsa::from_keypair(keys::KEY2_RSA_KEYPAIR);
+        let mut a = Nor::new(vec![0xff; 1024], 256);
+        let mut b = Nor::new(vec![0xff; 1024], 256);
+        let mut records = Nor::new(vec![0xff; 512], 256);
+        let mut arena = vec![0; 1024];
+        let mut arena = BumpArena::new(&mut arena);
+
+        let mut store =
+            Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+        update(&mut store, &pfm(1, &mut signer), &mut rsa, &arena).unwrap();
+        arena.reset();
+
+        assert!(matches!(
+            update(&mut store, &pfm(2, &mut other_signer), &mut rsa, &arena),
+            Err(Error::Manifest(manifest::Error::SigError(_)))
+        ));
+        arena.reset();
+        assert_eq!(active_version(&store, &mut rsa), Some(1));
+    }
+
+    #[test]
+    fn out_of_order() {
+        let (mut rsa, _) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let mut a = RamMut(vec![0; 16]);
+        let mut b = RamMut(vec![0; 16]);
+        let mut records = RamMut(vec![0; 40]);
+        let mut hasher = ring::hash::Engine::new();
+        let mut arena = vec![0; 64];
+        let arena = BumpArena::new(&mut arena);
+
+        let mut too_small = RamMut(vec![0; 16]);
+        assert!(matches!(
+            Store::<Pfm>::new([&mut a, &mut b], &mut too_small),
+            Err(Error::BootRecordsTooSmall)
+        ));
+
+        let mut store =
+            Store::<Pfm>::new([&mut a, &mut b], &mut records).unwrap();
+        assert!(matches!(store.write(&[0; 4]), Err(Error::NotPrepared)));
+        assert!(matches!(
+            store.activate(&mut hasher, &mut rsa, &arena),
+            Err(Error::NotPrepared)
+        ));
+        assert!(matches!(store.prepare(17), Err(Error::TooLarge)));
+
+        store.prepare(8).unwrap();
+        store.write(&[0; 4]).unwrap();
+        assert!(matches!(store.write(&[0; 5]), Err(Error::TooLarge)));
+        assert!(matches!(
+            store.activate(&mut hasher, &mut rsa, &arena),
+            Err(Error::NotPrepared)
+        ));
+
+        // A failed activation ends the update.
+        assert!(matches!(store.write(&[0; 4]), Err(Error::NotPrepared)));
+    }
+}
@@ -7,6 +7,10 @@
 //! Generated using
 //! https://github.com/Azure/Project-Cerberus/blob/master/tools/testing/generate_pfm.sh.
 
+use crate::crypto::hash;
+use crate::manifest::KeyType;
+use crate::manifest::SigType;
+
 /// PFM binary #1.
 ///
 /// Generated with
@@ -22,16 +26,14 @@
 ///     {
 ///       "version_count": 1,
 ///       "firmware_id": [70, 105, 114, 109, 119, 97, 114, 101 ],
-///       "flags": "0b0",
 ///       "children": [{
 ///         "version_addr": "0x12345",
 ///         "version_str": [84, 101, 115, 116, 105, 110, 103],
 ///         "rw_regions": [{
-///           "flags": "0b0",
 ///           "region": { "offset": "0x2000000", "len": "0x2000000" }
 ///         }],
 ///         "image_regions": [{
-///           "flags": "0b1",
+///           "flags": { "must_validate_on_boot": true },
 ///           "hash_type": "Sha256",
 ///           "hash": [
 ///             206, 158, 121, 205, 137, 192, 146,  23,
@@ -47,3 +49,9 @@
 /// }
 /// ```
 pub const PFM_RSA1: &[u8] = include_bytes!("pfm1.bin");
+
+/// The signature type for manifests signed with `testutil`'s RSA keys.
+pub const RSA_SHA256: SigType = SigType {
+    key_type: KeyType::Rsa2048,
+    hash_type: hash::Algo::Sha256,
+};
@@ -0,0 +1,653 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encrypted Cerberus messages.
+//!
+//! Once a session has been established with `KeyExchange`, Cerberus messages
+//! with the "crypt" bit set in their header carry an AES-GCM-encrypted
+//! payload, laid out as follows:
+//! ```text
+//! struct {
+//!   ciphertext: [u8; _],
+//!   tag: [u8; 16],
+//!   iv: [u8; 12],
+//! }
+//! ```
+//!
+//! This module provides adapters over [`HostPort`] and [`DevicePort`] that
+//! transparently decrypt incoming messages and encrypt outgoing ones, based on
+//! the [`CerberusHeader::crypt`] bit. Messages without the bit set are passed
+//! through unmodified.
+
+use core::mem;
+
+use crate::crypto::aead;
+use crate::crypto::csrng;
+use crate::io::Cursor;
+use crate::io::Read;
+use crate::io::ReadZero;
+use crate::io::Write;
+use crate::net;
+use crate::net::device::DevicePort;
+use crate::net::device::DeviceResponse;
+use crate::net::host::HostPort;
+use crate::net::host::HostRequest;
+use crate::net::host::HostResponse;
+use crate::net::CerberusHeader;
+use crate::session;
+
+/// The length of the trailer appended to an encrypted message.
+pub const TRAILER_LEN: usize = aead::TAG_LEN + aead::IV_LEN;
+
+/// Decrypts an encrypted message in `buf`, consisting of a ciphertext
+/// followed by a trailer, returning the plaintext portion.
+fn open<'a>(
+    aead: &mut dyn aead::Aead,
+    key: Option<&session::Key>,
+    buf: &'a mut [u8],
+) -> Result<&'a mut [u8], net::Error> {
+    let key = key.ok_or(net::Error::Crypto)?;
+    if buf.len() < TRAILER_LEN {
+        return Err(net::Error::Crypto);
+    }
+
+    let (data, trailer) = buf.split_at_mut(buf.len() - TRAILER_LEN);
+    let (tag, iv) = trailer.split_at(aead::TAG_LEN);
+    let mut tag_buf = [0; aead::TAG_LEN];
+    let mut iv_buf = [0; aead::IV_LEN];
+    tag_buf.copy_from_slice(tag);
+    iv_buf.copy_from_slice(iv);
+
+    aead.decrypt(key, &iv_buf, data, &tag_buf)
+        .map_err(|_| net::Error::Crypto)?;
+    Ok(data)
+}
+
+/// Encrypts `data` in place, returning the trailer that should follow it on
+/// the wire.
+///
+/// A fresh IV is generated for each message using `csrng`.
+fn seal(
+    aead: &mut dyn aead::Aead,
+    csrng: &mut dyn csrng::Csrng,
+    key: Option<&session::Key>,
+    data: &mut [u8],
+) -> Result<[u8; TRAILER_LEN], net::Error> {
+    let key = key.ok_or(net::Error::Crypto)?;
+
+    let mut iv = [0; aead::IV_LEN];
+    csrng.fill(&mut iv).map_err(|_| net::Error::Crypto)?;
+    let mut tag = [0; aead::TAG_LEN];
+    aead.encrypt(key, &iv, data, &mut tag)
+        .map_err(|_| net::Error::Crypto)?;
+
+    let mut trailer = [0; TRAILER_LEN];
+    trailer[..aead::TAG_LEN].copy_from_slice(&tag);
+    trailer[aead::TAG_LEN..].copy_from_slice(&iv);
+    Ok(trailer)
+}
+
+/// A [`HostPort`] adapter that decrypts encrypted requests and encrypts the
+/// corresponding replies.
+///
+/// An `EncryptedHost` wraps a single transaction: it should be created
+/// immediately before handing a port to a server, using the server's current
+/// session key, and discarded afterwards. Calling `receive()` more than once
+/// returns [`net::Error::OutOfOrder`].
+///
+/// Encrypted requests are buffered and decrypted into the provided scratch
+/// buffer, with the remainder of that buffer used to buffer the plaintext
+/// reply before it is encrypted. It must therefore be large enough to hold
+/// both the largest expected request and reply.
+///
+/// # Example
+/// ```
+/// # use manticore::crypto::ring;
+/// # use manticore::net;
+/// # use manticore::net::encrypted::*;
+/// # use manticore::net::host::*;
+/// # use manticore::protocol::CommandType;
+/// let mut out = [0; 64];
+/// let mut port = InMemHost::new(&mut out);
+/// port.request(
+///     net::CerberusHeader {
+///         command: CommandType::FirmwareVersion,
+///         crypt: false,
+///     },
+///     &[0],
+/// );
+///
+/// let mut aes = ring::aead::AesGcm::new();
+/// let mut rng = ring::csrng::Csrng::new();
+/// let mut scratch = [0; 128];
+/// let mut host = EncryptedHost::new(
+///     &mut port,
+///     &mut aes,
+///     &mut rng,
+///     None,
+///     &mut scratch,
+/// );
+///
+/// // Plaintext requests are passed through as-is.
+/// let req = host.receive()?;
+/// assert!(!req.header()?.crypt);
+/// # Ok::<(), net::Error>(())
+/// ```
+pub struct EncryptedHost<'p, 'req>(HostInner<'p, 'req>);
+
+/// The connection state of an [`EncryptedHost`].
+///
+/// See `net::host::InMemInner` for why this type is separate.
+struct HostInner<'p, 'req> {
+    port: Option<&'p mut dyn HostPort<'req, CerberusHeader>>,
+    req: Option<&'p mut dyn HostRequest<'req, CerberusHeader>>,
+    resp: Option<&'p mut dyn HostResponse<'req>>,
+
+    aead: &'p mut dyn aead::Aead,
+    csrng: &'p mut dyn csrng::Csrng,
+    key: Option<session::Key>,
+
+    header: Option<CerberusHeader>,
+    plaintext: &'req [u8],
+    scratch: &'req mut [u8],
+    reply: Option<Cursor<'req>>,
+    finished: bool,
+}
+
+impl<'p, 'req> EncryptedHost<'p, 'req> {
+    /// Creates a new `EncryptedHost` wrapping `port`.
+    ///
+    /// `key` should be the current session's AES key, if a session is active;
+    /// encrypted messages received while `key` is `None` are rejected with
+    /// [`net::Error::Crypto`].
+    pub fn new(
+        port: &'p mut dyn HostPort<'req, CerberusHeader>,
+        aead: &'p mut dyn aead::Aead,
+        csrng: &'p mut dyn csrng::Csrng,
+        key: Option<session::Key>,
+        scratch: &'req mut [u8],
+    ) -> Self {
+        Self(HostInner {
+            port: Some(port),
+            req: None,
+            resp: None,
+            aead,
+            csrng,
+            key,
+            header: None,
+            plaintext: &[],
+            scratch,
+            reply: None,
+            finished: false,
+        })
+    }
+}
+
+impl<'p, 'req> HostPort<'req, CerberusHeader> for EncryptedHost<'p, 'req> {
+    fn receive(
+        &mut self,
+    ) -> Result<&mut dyn HostRequest<'req, CerberusHeader>, net::Error> {
+        let inner = &mut self.0;
+        let port = inner.port.take().ok_or(net::Error::OutOfOrder)?;
+        let req = port.receive()?;
+        let header = req.header()?;
+
+        if header.crypt {
+            let payload = req.payload()?;
+            let len = payload.remaining_data();
+            if len > inner.scratch.len() {
+                return Err(net::Error::Io(crate::io::Error::BufferExhausted));
+            }
+
+            let (msg, rest) = mem::take(&mut inner.scratch).split_at_mut(len);
+            payload.read_bytes(msg)?;
+            inner.plaintext = open(inner.aead, inner.key.as_ref(), msg)?;
+            inner.scratch = rest;
+        }
+
+        inner.header = Some(header);
+        inner.req = Some(req);
+        Ok(inner)
+    }
+}
+
+impl<'p, 'req> HostRequest<'req, CerberusHeader> for HostInner<'p, 'req> {
+    fn header(&self) -> Result<CerberusHeader, net::Error> {
+        if self.req.is_none() {
+            return Err(net::Error::OutOfOrder);
+        }
+        self.header.ok_or(net::Error::OutOfOrder)
+    }
+
+    fn payload(&mut self) -> Result<&mut dyn ReadZero<'req>, net::Error> {
+        let header = self.header()?;
+        if header.crypt {
+            return Ok(&mut self.plaintext);
+        }
+        self.req.as_mut().ok_or(net::Error::OutOfOrder)?.payload()
+    }
+
+    fn reply(
+        &mut self,
+        header: CerberusHeader,
+    ) -> Result<&mut dyn HostResponse<'req>, net::Error> {
+        let req = self.req.take().ok_or(net::Error::OutOfOrder)?;
+        self.resp = Some(req.reply(header)?);
+        if header.crypt {
+            self.reply = Some(Cursor::new(mem::take(&mut self.scratch)));
+        }
+        Ok(self)
+    }
+}
+
+impl<'p, 'req> HostResponse<'req> for HostInner<'p, 'req> {
+    fn sink(&mut self) -> Result<&mut dyn Write, net::Error> {
+        if self.finished {
+            return Err(net::Error::OutOfOrder);
+        }
+        if let Some(reply) = &mut self.reply {
+            return Ok(reply);
+        }
+        self.resp.as_mut().ok_or(net::Error::OutOfOrder)?.sink()
+    }
+
+    fn finish(&mut self) -> Result<(), net::Error> {
+        if self.finished {
+            return Err(net::Error::OutOfOrder);
+        }
+        let resp = self.resp.as_mut().ok_or(net::Error::OutOfOrder)?;
+        if let Some(reply) = &mut self.reply {
+            let data = reply.take_consumed_bytes();
+            let trailer = seal(self.aead, self.csrng, self.key.as_ref(), data)?;
+
+            let sink = resp.sink()?;
+            sink.write_bytes(data)?;
+            sink.write_bytes(&trailer)?;
+        }
+
+        self.finished = true;
+        resp.finish()
+    }
+}
+
+/// A [`DevicePort`] adapter that encrypts outgoing requests and decrypts the
+/// corresponding responses.
+///
+/// Requests are encrypted if the header passed to `send()` has the crypt bit
+/// set; responses are decrypted if their header does. Encrypted requests and
+/// all responses are buffered in the provided scratch buffer, which must be
+/// large enough to hold the largest request or response, plus
+/// [`TRAILER_LEN`] bytes.
+pub struct EncryptedDevice<'p>(DeviceInner<'p>);
+
+/// The connection state of an [`EncryptedDevice`].
+struct DeviceInner<'p> {
+    port: &'p mut dyn DevicePort<CerberusHeader>,
+
+    aead: &'p mut dyn aead::Aead,
+    csrng: &'p mut dyn csrng::Csrng,
+    key: Option<session::Key>,
+
+    header: Option<CerberusHeader>,
+    scratch: &'p mut [u8],
+    plaintext: (usize, usize),
+}
+
+impl<'p> EncryptedDevice<'p> {
+    /// Creates a new `EncryptedDevice` wrapping `port`.
+    ///
+    /// `key` should be the AES key for the session established with the
+    /// device, if any; see [`EncryptedHost::new()`].
+    pub fn new(
+        port: &'p mut dyn DevicePort<CerberusHeader>,
+        aead: &'p mut dyn aead::Aead,
+        csrng: &'p mut dyn csrng::Csrng,
+        key: Option<session::Key>,
+        scratch: &'p mut [u8],
+    ) -> Self {
+        Self(DeviceInner {
+            port,
+            aead,
+            csrng,
+            key,
+            header: None,
+            scratch,
+            plaintext: (0, 0),
+        })
+    }
+
+    /// Replaces the session key used by this adapter, such as after a new
+    /// session has been established.
+    pub fn set_key(&mut self, key: Option<session::Key>) {
+        self.0.key = key;
+    }
+}
+
+impl<'p> DevicePort<CerberusHeader> for EncryptedDevice<'p> {
+    fn send(
+        &mut self,
+        dest: u8,
+        header: CerberusHeader,
+        msg: &[u8],
+    ) -> Result<(), net::Error> {
+        let inner = &mut self.0;
+        inner.header = None;
+        if !header.crypt {
+            return inner.port.send(dest, header, msg);
+        }
+
+        let len = msg.len() + TRAILER_LEN;
+        if len > inner.scratch.len() {
+            return Err(net::Error::Io(crate::io::Error::BufferExhausted));
+        }
+        let (data, trailer) = inner.scratch[..len].split_at_mut(msg.len());
+        data.copy_from_slice(msg);
+        trailer.copy_from_slice(&seal(
+            inner.aead,
+            inner.csrng,
+            inner.key.as_ref(),
+            data,
+        )?);
+        inner.port.send(dest, header, &inner.scratch[..len])
+    }
+
+    fn wait_for_response(&mut self, duration: usize) -> Result<(), net::Error> {
+        self.0.port.wait_for_response(duration)
+    }
+
+    fn receive_response(
+        &mut self,
+    ) -> Result<&mut dyn DeviceResponse<CerberusHeader>, net::Error> {
+        let inner = &mut self.0;
+        let resp = inner.port.receive_response()?;
+        let header = resp.header()?;
+
+        // Responses are buffered even if they are not encrypted, since we
+        // cannot return `resp` itself without holding onto `inner.port`.
+        let payload = resp.payload()?;
+        let len = payload.remaining_data();
+        if len > inner.scratch.len() {
+            return Err(net::Error::Io(crate::io::Error::BufferExhausted));
+        }
+        payload.read_bytes(&mut inner.scratch[..len])?;
+        let plaintext_len = if header.crypt {
+            open(inner.aead, inner.key.as_ref(), &mut inner.scratch[..len])?
+                .len()
+        } else {
+            len
+        };
+
+        inner.header = Some(header);
+        inner.plaintext = (0, plaintext_len);
+        Ok(inner)
+    }
+}
+
+impl DeviceResponse<CerberusHeader> for DeviceInner<'_> {
+    fn header(&self) -> Result<CerberusHeader, net::Error> {
+        self.header.ok_or(net::Error::OutOfOrder)
+    }
+
+    fn payload(&mut self) -> Result<&mut dyn Read, net::Error> {
+        if self.header.is_none() {
+            return Err(net::Error::OutOfOrder);
+        }
+        Ok(self)
+    }
+}
+
+impl Read for DeviceInner<'_> {
+    fn read_bytes(&mut self, out: &mut [u8]) -> Result<(), crate::io::Error> {
+        let (start, end) = self.plaintext;
+        let mut buf = &self.scratch[start..end];
+        buf.read_bytes(out)?;
+        self.plaintext.0 = end - buf.len();
+        Ok(())
+    }
+
+    fn remaining_data(&self) -> usize {
+        self.plaintext.1 - self.plaintext.0
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    use crate::crypto::ring;
+    use crate::mem::BumpArena;
+    use crate::net::host::InMemHost;
+    use crate::protocol::firmware_version::FirmwareVersionRequest;
+    use crate::protocol::firmware_version::FirmwareVersionResponse;
+    use crate::protocol::wire::FromWire as _;
+    use crate::protocol::wire::ToWire as _;
+    use crate::protocol::CommandType;
+
+    const KEY: session::Key = [0x42; 32];
+
+    fn header(crypt: bool) -> CerberusHeader {
+        CerberusHeader {
+            command: CommandType::FirmwareVersion,
+            crypt,
+        }
+    }
+
+    /// Encrypts `msg` the way a remote peer would.
+    fn encrypt(msg: &[u8]) -> Vec<u8> {
+        let mut out = msg.to_vec();
+        let trailer = seal(
+            &mut ring::aead::AesGcm::new(),
+            &mut ring::csrng::Csrng::new(),
+            Some(&KEY),
+            &mut out,
+        )
+        .unwrap();
+        out.extend_from_slice(&trailer);
+        out
+    }
+
+    /// Decrypts `msg` the way a remote peer would.
+    fn decrypt(msg: &[u8]) -> Vec<u8> {
+        let mut buf = msg.to_vec();
+        let len = open(&mut ring::aead::AesGcm::new(), Some(&KEY), &mut buf)
+            .unwrap()
+            .len();
+        buf.truncate(len);
+        buf
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn host_round_trip() {
+        let req = encrypt(&[0]);
+        let mut out = [0; 64];
+        let mut port = InMemHost::new(&mut out);
+        port.request(header(true), &req);
+
+        let mut aes = ring::aead::AesGcm::new();
+        let mut rng = ring::csrng::Csrng::new();
+        let mut scratch = [0; 128];
+        let mut host = EncryptedHost::new(
+            &mut port,
+            &mut aes,
+            &mut rng,
+            Some(KEY),
+            &mut scratch,
+        );
+
+        let mut arena = [0; 64];
+        let arena = BumpArena::new(&mut arena);
+
+        let host_req = host.receive().unwrap();
+        assert!(host_req.header().unwrap().crypt);
+        let req = FirmwareVersionRequest::from_wire(
+            host_req.payload().unwrap(),
+            &arena,
+        )
+        .unwrap();
+        assert_eq!(req.index, 0);
+
+        let host_resp = host_req.reply(header(true)).unwrap();
+        FirmwareVersionResponse {
+            version: &[0xba; 32],
+        }
+        .to_wire(host_resp.sink().unwrap())
+        .unwrap();
+        host_resp.finish().unwrap();
+        assert!(host.receive().is_err());
+
+        let (resp_header, resp) = port.response().unwrap();
+        assert!(resp_header.crypt);
+        assert_eq!(resp.len(), 32 + TRAILER_LEN);
+        assert_eq!(decrypt(resp), &[0xba; 32]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn host_passthrough() {
+        let mut out = [0; 64];
+        let mut port = InMemHost::new(&mut out);
+        port.request(header(false), &[0]);
+
+        let mut aes = ring::aead::AesGcm::new();
+        let mut rng = ring::csrng::Csrng::new();
+        let mut scratch = [0; 128];
+        let mut host = EncryptedHost::new(
+            &mut port,
+            &mut aes,
+            &mut rng,
+            None,
+            &mut scratch,
+        );
+
+        let host_req = host.receive().unwrap();
+        assert_eq!(host_req.payload().unwrap().remaining_data(), 1);
+        let host_resp = host_req.reply(header(false)).unwrap();
+        host_resp.sink().unwrap().write_bytes(&[1, 2, 3]).unwrap();
+        host_resp.finish().unwrap();
+
+        assert_eq!(port.response(), Some((header(false), &[1, 2, 3][..])));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn host_no_session() {
+        let req = encrypt(&[0]);
+        let mut out = [0; 64];
+        let mut port = InMemHost::new(&mut out);
+        port.request(header(true), &req);
+
+        let mut aes = ring::aead::AesGcm::new();
+        let mut rng = ring::csrng::Csrng::new();
+        let mut scratch = [0; 128];
+        let mut host = EncryptedHost::new(
+            &mut port,
+            &mut aes,
+            &mut rng,
+            None,
+            &mut scratch,
+        );
+        assert!(matches!(host.receive(), Err(net::Error::Crypto)));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn host_bad_tag() {
+        let mut req = encrypt(&[0]);
+        req[1] ^= 0xff;
+        let mut out = [0; 64];
+        let mut port = InMemHost::new(&mut out);
+        port.request(header(true), &req);
+
+        let mut aes = ring::aead::AesGcm::new();
+        let mut rng = ring::csrng::Csrng::new();
+        let mut scratch = [0; 128];
+        let mut host = EncryptedHost::new(
+            &mut port,
+            &mut aes,
+            &mut rng,
+            Some(KEY),
+            &mut scratch,
+        );
+        assert!(matches!(host.receive(), Err(net::Error::Crypto)));
+    }
+
+    /// A `DevicePort` that records requests and replies with a canned
+    /// response.
+    struct FakeDevice<'a> {
+        sent: Vec<u8>,
+        resp: &'a [u8],
+    }
+
+    impl DevicePort<CerberusHeader> for FakeDevice<'_> {
+        fn send(
+            &mut self,
+            _: u8,
+            _: CerberusHeader,
+            msg: &[u8],
+        ) -> Result<(), net::Error> {
+            self.sent = msg.to_vec();
+            Ok(())
+        }
+
+        fn wait_for_response(&mut self, _: usize) -> Result<(), net::Error> {
+            Ok(())
+        }
+
+        fn receive_response(
+            &mut self,
+        ) -> Result<&mut dyn DeviceResponse<CerberusHeader>, net::Error>
+        {
+            Ok(self)
+        }
+    }
+
+    impl DeviceResponse<CerberusHeader> for FakeDevice<'_> {
+        fn header(&self) -> Result<CerberusHeader, net::Error> {
+            Ok(header(true))
+        }
+
+        fn payload(&mut self) -> Result<&mut dyn Read, net::Error> {
+            Ok(&mut self.resp)
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn device_round_trip() {
+        let resp = encrypt(&[0xba; 32]);
+        let mut port = FakeDevice {
+            sent: Vec::new(),
+            resp: &resp,
+        };
+
+        let mut aes = ring::aead::AesGcm::new();
+        let mut rng = ring::csrng::Csrng::new();
+        let mut scratch = [0; 128];
+        let mut device = EncryptedDevice::new(
+            &mut port,
+            &mut aes,
+            &mut rng,
+            Some(KEY),
+            &mut scratch,
+        );
+
+        device.send(0, header(true), &[0]).unwrap();
+        device.wait_for_response(0).unwrap();
+        let resp = device.receive_response().unwrap();
+        assert!(resp.header().unwrap().crypt);
+
+        let mut arena = [0; 64];
+        let arena = BumpArena::new(&mut arena);
+        let payload = resp.payload().unwrap();
+        let mut bytes = vec![0; payload.remaining_data()];
+        payload.read_bytes(&mut bytes).unwrap();
+        let resp = FirmwareVersionResponse::from_wire(&mut &bytes[..], &arena)
+            .unwrap();
+        assert_eq!(resp.version, &[0xba; 32]);
+
+        assert_eq!(port.sent.len(), 1 + TRAILER_LEN);
+        assert_eq!(decrypt(&port.sent), &[0]);
+    }
+}
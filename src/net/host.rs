@@ -161,6 +161,7 @@ pub trait HostResponse<'req> {
 /// // Prepare a request to push into the host.
 /// let header = net::CerberusHeader {
 ///     command: CommandType::FirmwareVersion,
+///     crypt: false,
 /// };
 /// let req = [0];
 /// host.request(header, &req);
@@ -182,6 +183,7 @@ pub trait HostResponse<'req> {
 /// // Prepare to reply to the message.
 /// let mut host_resp = host_req.reply(net::CerberusHeader {
 ///     command: CommandType::FirmwareVersion,
+///     crypt: false,
 /// })?;
 ///
 /// // Build and write a reply.
@@ -20,6 +20,7 @@ use crate::protocol::cerberus;
 use crate::protocol::spdm;
 
 pub mod device;
+pub mod encrypted;
 pub mod host;
 
 #[cfg(doc)]
@@ -42,6 +43,10 @@ pub enum Error {
     OutOfOrder,
     /// The operation timed out.
     Timeout,
+    /// Indicates that an encrypted message could not be encrypted or
+    /// decrypted, such as because no session is active or because the
+    /// message failed authentication.
+    Crypto,
 }
 
 impl From<io::Error> for Error {
@@ -80,6 +85,12 @@ pub trait Header: Copy {
 #[allow(missing_docs)]
 pub struct CerberusHeader {
     pub command: cerberus::CommandType,
+    /// The Cerberus "crypt" bit, which indicates that the payload is
+    /// encrypted with the current session's key.
+    ///
+    /// See [`encrypted`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub crypt: bool,
 }
 
 impl Header for CerberusHeader {
@@ -89,7 +100,11 @@ impl Header for CerberusHeader {
         self.command
     }
     fn reply_with(&self, command: cerberus::CommandType) -> Self {
-        Self { command }
+        // Replies to encrypted requests must be encrypted, too.
+        Self {
+            command,
+            crypt: self.crypt,
+        }
     }
 
     fn reply_with_error(&self) -> Self {
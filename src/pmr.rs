@@ -0,0 +1,294 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Platform Measurement Registers.
+//!
+//! A PMR is a register that accumulates measurements (digests of firmware,
+//! configuration, and so on) in a way that cannot be undone short of
+//! resetting the register. A PMR can only be changed by "extending" it with a
+//! new measurement:
+//!
+//! ```text
+//! pmr := SHA-256(pmr || measurement)
+//! ```
+//!
+//! PMRs start out as all zeroes.
+//!
+//! Each PMR also has a measurement log, which records every measurement that
+//! went into it along with an event type describing what was measured. This
+//! log can be retrieved with [`protocol::GetAttestationData`], so that a
+//! verifier can replay it to check a PMR's value.
+//!
+//! PMR #0 is special: it records the measurements of the RoT itself, and its
+//! value is reported in the response to a [`protocol::Challenge`]. As such,
+//! Manticore will not allow remote hosts to extend or reset it.
+//!
+//! [`protocol::Challenge`]: crate::protocol::Challenge
+//! [`protocol::GetAttestationData`]: crate::protocol::GetAttestationData
+
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+
+#[cfg(doc)]
+use crate::protocol;
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+/// The hash algorithm used for computing PMR values.
+pub const ALGO: hash::Algo = hash::Algo::Sha256;
+
+/// A PMR value or measurement digest.
+pub type Digest = [u8; ALGO.bytes()];
+
+/// The event type recorded for measurements extended by a remote host with
+/// [`protocol::UpdatePmr`], which does not specify one.
+pub const HOST_EVENT_TYPE: u32 = 0;
+
+/// An entry in a PMR's measurement log.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, AsBytes, FromBytes)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(all(feature = "serde", feature = "std"), derive(serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+#[repr(C)]
+pub struct Measurement {
+    /// An integration-defined value describing what was measured.
+    ///
+    /// Manticore does not interpret this value, but it is intended to be
+    /// a TCG-style event type.
+    pub event_type: u32,
+    /// The digest that was extended into the PMR.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde::se_hexstring")
+    )]
+    #[cfg_attr(
+        all(feature = "serde", feature = "std"),
+        serde(deserialize_with = "crate::serde::de_hexstring")
+    )]
+    pub digest: Digest,
+}
+
+/// An error returned by a PMR operation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// Indicates that the requested PMR does not exist.
+    OutOfRange,
+    /// Indicates that a PMR has recorded as many measurements as it is able
+    /// to, and must be reset before it can be extended again.
+    Full,
+    /// Indicates an error computing the new PMR value.
+    Hash(hash::Error),
+}
+
+impl From<hash::Error> for Error {
+    fn from(e: hash::Error) -> Self {
+        Self::Hash(e)
+    }
+}
+
+/// A bank of Platform Measurement Registers.
+///
+/// Along with the current value of each PMR, implementations must keep a log
+/// of every [`Measurement`] that went into it since it was last reset, so
+/// that verifiers can recompute the value for themselves.
+///
+/// See the [module documentation][self] for more information.
+pub trait Pmrs {
+    /// Returns the number of PMRs in this bank.
+    fn count(&self) -> usize;
+
+    /// Returns the current value of the PMR at `index`, if it exists.
+    fn value(&self, index: usize) -> Option<&Digest>;
+
+    /// Returns every measurement that has been extended into the PMR at
+    /// `index` since it was last reset, in the order they were extended.
+    fn measurements(&self, index: usize) -> Option<&[Measurement]>;
+
+    /// Extends the PMR at `index` with `digest`, recording it in the log
+    /// along with `event_type`.
+    ///
+    /// `hasher` is used to compute the new value. On failure, the PMR must
+    /// be left unchanged.
+    fn extend(
+        &mut self,
+        index: usize,
+        event_type: u32,
+        digest: &Digest,
+        hasher: &mut dyn hash::Engine,
+    ) -> Result<(), Error>;
+
+    /// Resets the PMR at `index` to all zeroes, and clears its measurement
+    /// log.
+    fn reset(&mut self, index: usize) -> Result<(), Error>;
+}
+
+/// A simple [`Pmrs`] implementation backed by fixed-size arrays.
+///
+/// `COUNT` is the number of PMRs, and `LOG_LEN` is the maximum number of
+/// measurements each PMR can record.
+#[derive(Clone)]
+pub struct SimplePmrs<const COUNT: usize, const LOG_LEN: usize> {
+    pmrs: [Pmr<LOG_LEN>; COUNT],
+}
+
+#[derive(Copy, Clone)]
+struct Pmr<const LOG_LEN: usize> {
+    value: Digest,
+    log: [Measurement; LOG_LEN],
+    log_len: usize,
+}
+
+impl<const COUNT: usize, const LOG_LEN: usize> SimplePmrs<COUNT, LOG_LEN> {
+    /// Creates a new bank of PMRs, all of which start out zeroed.
+    pub fn new() -> Self {
+        Self {
+            pmrs: [Pmr {
+                value: [0; ALGO.bytes()],
+                log: [Measurement {
+                    event_type: 0,
+                    digest: [0; ALGO.bytes()],
+                }; LOG_LEN],
+                log_len: 0,
+            }; COUNT],
+        }
+    }
+}
+
+impl<const COUNT: usize, const LOG_LEN: usize> Default
+    for SimplePmrs<COUNT, LOG_LEN>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const COUNT: usize, const LOG_LEN: usize> Pmrs
+    for SimplePmrs<COUNT, LOG_LEN>
+{
+    fn count(&self) -> usize {
+        COUNT
+    }
+
+    fn value(&self, index: usize) -> Option<&Digest> {
+        self.pmrs.get(index).map(|pmr| &pmr.value)
+    }
+
+    fn measurements(&self, index: usize) -> Option<&[Measurement]> {
+        self.pmrs.get(index).map(|pmr| &pmr.log[..pmr.log_len])
+    }
+
+    fn extend(
+        &mut self,
+        index: usize,
+        event_type: u32,
+        digest: &Digest,
+        hasher: &mut dyn hash::Engine,
+    ) -> Result<(), Error> {
+        let pmr = self.pmrs.get_mut(index).ok_or(Error::OutOfRange)?;
+        if pmr.log_len == LOG_LEN {
+            return Err(Error::Full);
+        }
+
+        let mut value = [0; ALGO.bytes()];
+        let mut h = hasher.new_hash(ALGO)?;
+        h.write(&pmr.value)?;
+        h.write(digest)?;
+        h.finish(&mut value)?;
+
+        pmr.value = value;
+        pmr.log[pmr.log_len] = Measurement {
+            event_type,
+            digest: *digest,
+        };
+        pmr.log_len += 1;
+        Ok(())
+    }
+
+    fn reset(&mut self, index: usize) -> Result<(), Error> {
+        let pmr = self.pmrs.get_mut(index).ok_or(Error::OutOfRange)?;
+        pmr.value = [0; ALGO.bytes()];
+        pmr.log_len = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::crypto::ring;
+
+    #[test]
+    fn extend() {
+        let mut h = ring::hash::Engine::new();
+        let mut pmrs = SimplePmrs::<2, 4>::new();
+        assert_eq!(pmrs.count(), 2);
+        assert_eq!(pmrs.value(0), Some(&[0; 32]));
+        assert_eq!(pmrs.measurements(0), Some(&[][..]));
+
+        pmrs.extend(1, 5, &[0xaa; 32], &mut h).unwrap();
+        pmrs.extend(1, 6, &[0xbb; 32], &mut h).unwrap();
+
+        let mut expected = [0; 32];
+        for m in &[[0xaa; 32], [0xbb; 32]] {
+            let mut hasher = h.new_hash(ALGO).unwrap();
+            hasher.write(&expected).unwrap();
+            hasher.write(m).unwrap();
+            hasher.finish(&mut expected).unwrap();
+        }
+        assert_eq!(pmrs.value(1), Some(&expected));
+        assert_eq!(
+            pmrs.measurements(1),
+            Some(
+                &[
+                    Measurement {
+                        event_type: 5,
+                        digest: [0xaa; 32],
+                    },
+                    Measurement {
+                        event_type: 6,
+                        digest: [0xbb; 32],
+                    },
+                ][..]
+            )
+        );
+
+        // PMR #0 is unaffected.
+        assert_eq!(pmrs.value(0), Some(&[0; 32]));
+        assert_eq!(pmrs.measurements(0), Some(&[][..]));
+
+        pmrs.reset(1).unwrap();
+        assert_eq!(pmrs.value(1), Some(&[0; 32]));
+        assert_eq!(pmrs.measurements(1), Some(&[][..]));
+    }
+
+    #[test]
+    fn full() {
+        let mut h = ring::hash::Engine::new();
+        let mut pmrs = SimplePmrs::<1, 1>::new();
+        pmrs.extend(0, 0, &[0xaa; 32], &mut h).unwrap();
+        let value = *pmrs.value(0).unwrap();
+
+        assert_eq!(pmrs.extend(0, 0, &[0xbb; 32], &mut h), Err(Error::Full));
+        assert_eq!(pmrs.value(0), Some(&value));
+        assert_eq!(pmrs.measurements(0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut h = ring::hash::Engine::new();
+        let mut pmrs = SimplePmrs::<1, 1>::new();
+        assert!(pmrs.value(1).is_none());
+        assert!(pmrs.measurements(1).is_none());
+        assert_eq!(
+            pmrs.extend(1, 0, &[0xaa; 32], &mut h),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(pmrs.reset(1), Err(Error::OutOfRange));
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ActivateRecoveryImage` request.
+//!
+//! This module provides a Cerberus command for validating a new recovery
+//! image and making it the active one for a host port.
+
+use crate::io::ReadInt as _;
+use crate::protocol::error::Ack;
+use crate::protocol::error::RecoveryImageError;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for activating a new recovery image.
+    type ActivateRecoveryImage;
+    type Error = RecoveryImageError;
+    type Response = Ack;
+    const TYPE: CommandType = ActivateRecoveryImage;
+
+    struct Request {
+        /// The host port whose recovery image is being updated.
+        pub port_id: u8,
+    }
+
+    fn Request::from_wire(r, _) {
+        let port_id = r.read_le()?;
+        Ok(Self { port_id })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.port_id)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x02],
+            json: r#"{
+                "port_id": 2
+            }"#,
+            value: ActivateRecoveryImageRequest { port_id: 2 },
+        },
+    }
+}
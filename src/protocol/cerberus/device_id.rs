@@ -60,7 +60,7 @@ protocol_struct! {
 ///
 /// The meaning of the fields below is currently unspecified by Cerberus
 /// beyond their names.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 // TODO: Remove this once we have a better idea of what Cerberus expects of
@@ -38,9 +38,8 @@ protocol_struct! {
     struct Response<'wire> {
         /// The requested information, in some binary format.
         ///
-        /// The format of the response depends on which information index was sent.
-        /// Only `0x00` is specified by Cerberus, which is reqired to produce the
-        /// "Unique Chip Identifier".
+        /// The format of the response depends on which information index was
+        /// sent; see [`InfoIndex`].
         #[cfg_attr(feature = "serde", serde(
             serialize_with = "crate::serde::se_bytestring",
         ))]
@@ -69,7 +68,22 @@ wire_enum! {
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub enum InfoIndex: u8 {
         /// Represents getting the Unique Chip Identifier for the device.
+        ///
+        /// This is the only index required by Cerberus, and is returned as
+        /// an unstructured binary blob.
         UniqueChipIndex = 0x00,
+        /// Represents getting the number of certificate chains presented to
+        /// the device that failed to validate, as a little-endian `u32`.
+        CertificateFailures = 0x01,
+        /// Represents getting the number of failed attestations of devices
+        /// this device is responsible for, as a little-endian `u32`.
+        AttestationFailures = 0x02,
+        /// Represents getting a vendor-defined chip identifier, such as a
+        /// silicon serial number, as an unstructured binary blob.
+        ///
+        /// Unlike the Unique Chip Identifier, this value need not be unique
+        /// across devices.
+        VendorChipIdentifier = 0x03,
     }
 }
 
@@ -87,6 +101,15 @@ mod test {
                 index: InfoIndex::UniqueChipIndex,
             },
         },
+        attestation_failures_request_round_trip: {
+            bytes: &[0x2],
+            json: r#"{
+                "index": "AttestationFailures"
+            }"#,
+            value: DeviceInfoRequest {
+                index: InfoIndex::AttestationFailures,
+            },
+        },
         response_round_trip: {
             bytes: b"some unstructured data of no particular length",
             json: r#"{
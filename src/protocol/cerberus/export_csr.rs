@@ -0,0 +1,85 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ExportCsr` request and response.
+//!
+//! This module provides a Cerberus command for exporting a certificate
+//! signing request for a device's identity key, for use during
+//! manufacturing.
+
+use crate::io::ReadInt as _;
+use crate::mem::ArenaExt as _;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for requesting a certificate signing request.
+    type ExportCsr;
+    const TYPE: CommandType = ExportCsr;
+
+    struct Request {
+        /// The index of the CSR to export.
+        ///
+        /// Index `0` refers to the DeviceID key.
+        pub index: u8,
+    }
+
+    fn Request::from_wire(r, _) {
+        let index = r.read_le()?;
+        Ok(Self { index })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.index)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// A DER-encoded PKCS#10 certificate signing request.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub csr: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let len = r.remaining_data();
+        let csr = arena.alloc_slice::<u8>(len)?;
+        r.read_bytes(csr)?;
+        Ok(Self { csr })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_bytes(self.csr)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x00],
+            json: r#"{
+                "index": 0
+            }"#,
+            value: ExportCsrRequest {
+                index: 0,
+            },
+        },
+        response_round_trip: {
+            bytes: &[0x30, 0x03, 0x02, 0x01, 0x00],
+            json: r#"{
+                "csr": "3003020100"
+            }"#,
+            value: ExportCsrResponse {
+                csr: &[0x30, 0x03, 0x02, 0x01, 0x00],
+            },
+        },
+    }
+}
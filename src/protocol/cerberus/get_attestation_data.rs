@@ -0,0 +1,122 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `GetAttestationData` request and response.
+//!
+//! This module provides a Cerberus command for reading the measurement log
+//! of one of the device's Platform Measurement Registers.
+
+use core::convert::TryInto as _;
+
+use crate::io::ReadInt as _;
+use crate::mem::ArenaExt as _;
+use crate::pmr::Measurement;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for requesting the measurements that make up a PMR.
+    type GetAttestationData;
+    const TYPE: CommandType = GetAttestationData;
+
+    struct Request {
+        /// The index of the PMR to read the measurement log of.
+        pub index: u8,
+    }
+
+    fn Request::from_wire(r, _) {
+        let index = r.read_le()?;
+        Ok(Self { index })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.index)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// Every measurement extended into the PMR since it was last reset,
+        /// in order.
+        pub entries: &'wire [Measurement],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let len = r.read_le::<u8>()?;
+        let entries = arena.alloc_slice::<Measurement>(len as usize)?;
+        for entry in entries.iter_mut() {
+            entry.event_type = r.read_le()?;
+            r.read_bytes(&mut entry.digest)?;
+        }
+        Ok(Self { entries })
+    }
+
+    fn Response::to_wire(&self, w) {
+        let len: u8 = self
+            .entries
+            .len()
+            .try_into()
+            .map_err(|_| wire::Error::OutOfRange)?;
+        w.write_le(len)?;
+        for entry in self.entries {
+            w.write_le(entry.event_type)?;
+            w.write_bytes(&entry.digest)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01],
+            json: r#"{
+                "index": 1
+            }"#,
+            value: GetAttestationDataRequest { index: 1 },
+        },
+        response_round_trip: {
+            bytes: &[
+                0x02,  // Entry count.
+
+                0x07, 0x00, 0x00, 0x80,  // Event type.
+                0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+                0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+                0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+                0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+
+                0x01, 0x00, 0x00, 0x00,  // Event type.
+                0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb,
+                0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb,
+                0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb,
+                0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb, 0xbb,
+            ],
+            json: r#"{
+                "entries": [
+                    {
+                        "event_type": 2147483655,
+                        "digest": "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+                    },
+                    {
+                        "event_type": 1,
+                        "digest": "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"
+                    }
+                ]
+            }"#,
+            value: GetAttestationDataResponse {
+                entries: &[
+                    Measurement {
+                        event_type: 0x8000_0007,
+                        digest: [0xaa; 32],
+                    },
+                    Measurement {
+                        event_type: 1,
+                        digest: [0xbb; 32],
+                    },
+                ],
+            },
+        },
+    }
+}
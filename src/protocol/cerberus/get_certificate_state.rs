@@ -0,0 +1,88 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `GetCertificateState` request and response.
+//!
+//! This module provides a Cerberus command for querying whether a device's
+//! certificate chain has been successfully provisioned.
+
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for requesting the provisioning state of the device's
+    /// certificate chain.
+    type GetCertificateState;
+    const TYPE: CommandType = GetCertificateState;
+
+    struct Request {}
+
+    fn Request::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Request::to_wire(&self, _w) {
+        Ok(())
+    }
+
+    struct Response {
+        /// The state of the certificate chain.
+        pub state: CertificateState,
+        /// Implementation-defined details about a provisioning failure.
+        pub error_details: [u8; 3],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let state = CertificateState::from_wire(r, arena)?;
+        let mut error_details = [0; 3];
+        r.read_bytes(&mut error_details)?;
+        Ok(Self { state, error_details })
+    }
+
+    fn Response::to_wire(&self, w) {
+        self.state.to_wire(&mut w)?;
+        w.write_bytes(&self.error_details)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// The provisioning state of a certificate chain.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum CertificateState: u8 {
+        /// A valid chain has been provisioned.
+        Valid = 0x00,
+        /// A valid chain has not been provisioned.
+        NotProvisioned = 0x01,
+        /// The stored chain is being validated.
+        Validating = 0x02,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: GetCertificateStateRequest {},
+        },
+        response_round_trip: {
+            bytes: &[0x01, 0x00, 0x00, 0x00],
+            json: r#"{
+                "state": "NotProvisioned",
+                "error_details": [0, 0, 0]
+            }"#,
+            value: GetCertificateStateResponse {
+                state: CertificateState::NotProvisioned,
+                error_details: [0; 3],
+            },
+        },
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `GetPmr` request and response.
+//!
+//! This module provides a Cerberus command for reading a signed copy of one
+//! of the device's Platform Measurement Registers.
+
+use core::convert::TryInto as _;
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for requesting the value of a PMR.
+    type GetPmr;
+    const TYPE: CommandType = GetPmr;
+
+    struct Request<'wire> {
+        /// The index of the PMR to read.
+        pub index: u8,
+        /// A requester-chosen random nonce.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub nonce: &'wire [u8; 32],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let index = r.read_le()?;
+        let nonce = r.read_object::<[u8; 32]>(arena)?;
+        Ok(Self { index, nonce })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.index)?;
+        w.write_bytes(self.nonce)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// A responder-chosen random nonce.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub nonce: &'wire [u8; 32],
+        /// The value of the requested PMR.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub value: &'wire [u8],
+        /// A signature over the concatenation of the corresponding request
+        /// and the response up to the signature, made with the key for
+        /// certificate slot `0`.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub signature: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let nonce = r.read_object::<[u8; 32]>(arena)?;
+        let len = r.read_le::<u8>()?;
+        let value = r.read_slice::<u8>(len as usize, arena)?;
+        let signature = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { nonce, value, signature })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_bytes(self.nonce)?;
+        let len: u8 = self
+            .value
+            .len()
+            .try_into()
+            .map_err(|_| wire::Error::OutOfRange)?;
+        w.write_le(len)?;
+        w.write_bytes(self.value)?;
+        w.write_bytes(self.signature)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[
+                0x01,  // PMR #.
+
+                // Nonce.
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+                0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77, 0x77,
+            ],
+            json: r#"{
+                "index": 1,
+                "nonce": "7777777777777777777777777777777777777777777777777777777777777777"
+            }"#,
+            value: GetPmrRequest {
+                index: 1,
+                nonce: &[0x77; 32],
+            },
+        },
+        response_round_trip: {
+            bytes: &[
+                // Nonce.
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+                0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0xdd,
+
+                0x04,  // PMR len.
+                b'p', b'm', b'r', b'1',
+
+                // Signature.
+                b'e', b'c', b'd', b's', b'a',
+            ],
+            json: r#"{
+                "nonce": "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd",
+                "value": "706d7231",
+                "signature": "6563647361"
+            }"#,
+            value: GetPmrResponse {
+                nonce: &[0xdd; 32],
+                value: b"pmr1",
+                signature: b"ecdsa",
+            },
+        },
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `GetRecoveryImageVersion` request and response.
+//!
+//! This module provides a Cerberus command for querying the version of the
+//! recovery image held for a host port.
+
+use crate::io::ReadInt as _;
+use crate::mem::ArenaExt as _;
+use crate::protocol::error::RecoveryImageError;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for requesting a recovery image's version.
+    type GetRecoveryImageVersion;
+    type Error = RecoveryImageError;
+    const TYPE: CommandType = GetRecoveryImageVersion;
+
+    struct Request {
+        /// The host port whose recovery image should be queried.
+        pub port_id: u8,
+    }
+
+    fn Request::from_wire(r, _) {
+        let port_id = r.read_le()?;
+        Ok(Self { port_id })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.port_id)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// The recovery image's version. In practice, this is usually an ASCII
+        /// string.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_bytestring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_bytestring",
+        )))]
+        pub version: &'wire [u8; 32],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let version: &mut [u8; 32] = arena.alloc::<[u8; 32]>()?;
+        r.read_bytes(version)?;
+        Ok(Self { version })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_bytes(self.version)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const VERSION: &[u8; 32] = b"my cool recovery image version 1";
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01],
+            json: r#"{
+                "port_id": 1
+            }"#,
+            value: GetRecoveryImageVersionRequest { port_id: 1 },
+        },
+        response_round_trip: {
+            bytes: VERSION,
+            json: r#"{
+                "version": "my cool recovery image version 1"
+            }"#,
+            value: GetRecoveryImageVersionResponse { version: VERSION },
+        },
+    }
+}
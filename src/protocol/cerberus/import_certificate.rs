@@ -0,0 +1,93 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ImportCertificate` request.
+//!
+//! This module provides a Cerberus command for provisioning a device's
+//! certificate chain, for use during manufacturing.
+
+use core::convert::TryInto as _;
+
+use crate::io::ReadInt as _;
+use crate::mem::ArenaExt as _;
+use crate::protocol::error::Ack;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for importing a signed certificate into the device's
+    /// certificate chain.
+    type ImportCertificate;
+    type Response = Ack;
+    const TYPE: CommandType = ImportCertificate;
+
+    struct Request<'wire> {
+        /// The role of the certificate in the chain.
+        pub cert_type: CertificateType,
+        /// The DER-encoded certificate.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub cert: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let cert_type = CertificateType::from_wire(r, arena)?;
+        let len = r.read_le::<u16>()?;
+        let cert = arena.alloc_slice::<u8>(len as usize)?;
+        r.read_bytes(cert)?;
+        Ok(Self { cert_type, cert })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.cert_type.to_wire(&mut w)?;
+        let len: u16 = self
+            .cert
+            .len()
+            .try_into()
+            .map_err(|_| wire::Error::OutOfRange)?;
+        w.write_le(len)?;
+        w.write_bytes(self.cert)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// The role a certificate plays in a device's certificate chain.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum CertificateType: u8 {
+        /// The leaf certificate for the device's DeviceID key.
+        DeviceId = 0x00,
+        /// The self-signed root CA certificate.
+        RootCa = 0x01,
+        /// An intermediate CA certificate.
+        IntermediateCa = 0x02,
+    }
+}
+derive_borrowed!(CertificateType);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01, 0x03, 0x00, b'x', b'5', b'9'],
+            json: r#"{
+                "cert_type": "RootCa",
+                "cert": "783539"
+            }"#,
+            value: ImportCertificateRequest {
+                cert_type: CertificateType::RootCa,
+                cert: b"x59",
+            },
+        },
+    }
+}
@@ -40,6 +40,48 @@ pub use reset_counter::ResetCounter;
 pub mod request_counter;
 pub use request_counter::RequestCounter;
 
+pub mod export_csr;
+pub use export_csr::ExportCsr;
+
+pub mod import_certificate;
+pub use import_certificate::ImportCertificate;
+
+pub mod get_certificate_state;
+pub use get_certificate_state::GetCertificateState;
+
+pub mod get_pmr;
+pub use get_pmr::GetPmr;
+
+pub mod update_pmr;
+pub use update_pmr::UpdatePmr;
+
+pub mod reset_pmr;
+pub use reset_pmr::ResetPmr;
+
+pub mod get_attestation_data;
+pub use get_attestation_data::GetAttestationData;
+
+pub mod unseal;
+pub use unseal::Unseal;
+
+pub mod unseal_result;
+pub use unseal_result::UnsealResult;
+
+pub mod reset_config;
+pub use reset_config::ResetConfig;
+
+pub mod prepare_recovery_image;
+pub use prepare_recovery_image::PrepareRecoveryImage;
+
+pub mod update_recovery_image;
+pub use update_recovery_image::UpdateRecoveryImage;
+
+pub mod activate_recovery_image;
+pub use activate_recovery_image::ActivateRecoveryImage;
+
+pub mod get_recovery_image_version;
+pub use get_recovery_image_version::GetRecoveryImageVersion;
+
 #[cfg(doc)]
 use crate::protocol::error::{Ack, RawError};
 
@@ -70,6 +112,10 @@ wire_enum! {
         ///
         /// See [`DeviceInfo`].
         DeviceInfo = 0x04,
+        /// A request for the value of a Platform Measurement Register.
+        ///
+        /// See [`GetPmr`].
+        GetPmr = 0x80,
         /// A request for hashes of a certificate chain.
         ///
         /// See [`GetDigests`].
@@ -86,15 +132,64 @@ wire_enum! {
         ///
         /// See [`KeyExchange`].
         KeyExchange = 0x84,
+        /// A request to extend a Platform Measurement Register.
+        ///
+        /// See [`UpdatePmr`].
+        UpdatePmr = 0x86,
+        /// A request for a certificate signing request.
+        ///
+        /// See [`ExportCsr`].
+        ExportCsr = 0x20,
+        /// A request to provision a certificate.
+        ///
+        /// See [`ImportCertificate`].
+        ImportCertificate = 0x21,
+        /// A request for the provisioning state of the certificate chain.
+        ///
+        /// See [`GetCertificateState`].
+        GetCertificateState = 0x22,
+        /// A request for the measurement log of a Platform Measurement
+        /// Register.
+        ///
+        /// See [`GetAttestationData`].
+        GetAttestationData = 0x52,
         /// A request for the rest state of the host processor.
         ///
         /// See [`GetHostState`].
         GetHostState = 0x40,
+        /// A request to wipe some part of the device's configuration.
+        ///
+        /// See [`ResetConfig`].
+        ResetConfig = 0x6a,
+        /// A request to start updating a host recovery image.
+        ///
+        /// See [`PrepareRecoveryImage`].
+        PrepareRecoveryImage = 0x72,
+        /// A request to write part of a new host recovery image.
+        ///
+        /// See [`UpdateRecoveryImage`].
+        UpdateRecoveryImage = 0x73,
+        /// A request to activate a new host recovery image.
+        ///
+        /// See [`ActivateRecoveryImage`].
+        ActivateRecoveryImage = 0x74,
+        /// A request for the version of a host recovery image.
+        ///
+        /// See [`GetRecoveryImageVersion`].
+        GetRecoveryImageVersion = 0x75,
         /// A request for the number of times the device has been reset since
         /// POR.
         ///
         /// See [`ResetCounter`].
         ResetCounter = 0x87,
+        /// A request to unseal a secret bound to PMR values.
+        ///
+        /// See [`Unseal`].
+        Unseal = 0x89,
+        /// A request for the result of an [`Unseal`] request.
+        ///
+        /// See [`UnsealResult`].
+        UnsealResult = 0x8a,
         /// A request for the uptime of the device since last reset.
         ///
         /// Note that this command is a Manticore extension.
@@ -108,6 +203,12 @@ wire_enum! {
         ///
         /// See [`RequestCounter`].
         RequestCounter = 0xa1,
+        /// A request to reset a Platform Measurement Register.
+        ///
+        /// Note that this command is a Manticore extension.
+        ///
+        /// See [`ResetPmr`].
+        ResetPmr = 0xa2,
     }
 }
 
@@ -115,7 +216,7 @@ impl CommandType {
     /// Returns `true` when `self` represents a `manticore` extension to the
     /// protocol.
     pub fn is_manticore_extension(self) -> bool {
-        matches!(self, Self::DeviceUptime)
+        matches!(self, Self::DeviceUptime | Self::ResetPmr)
     }
 }
 
@@ -126,13 +227,27 @@ impl From<u8> for CommandType {
             0x02 => CommandType::DeviceCapabilities,
             0x03 => CommandType::DeviceId,
             0x04 => CommandType::DeviceInfo,
+            0x80 => CommandType::GetPmr,
             0x81 => CommandType::GetDigests,
             0x82 => CommandType::GetCert,
             0x83 => CommandType::Challenge,
+            0x86 => CommandType::UpdatePmr,
+            0x20 => CommandType::ExportCsr,
+            0x21 => CommandType::ImportCertificate,
+            0x22 => CommandType::GetCertificateState,
             0x40 => CommandType::GetHostState,
+            0x52 => CommandType::GetAttestationData,
+            0x6a => CommandType::ResetConfig,
+            0x72 => CommandType::PrepareRecoveryImage,
+            0x73 => CommandType::UpdateRecoveryImage,
+            0x74 => CommandType::ActivateRecoveryImage,
+            0x75 => CommandType::GetRecoveryImageVersion,
             0x87 => CommandType::ResetCounter,
+            0x89 => CommandType::Unseal,
+            0x8a => CommandType::UnsealResult,
             0xa0 => CommandType::DeviceUptime,
             0xa1 => CommandType::RequestCounter,
+            0xa2 => CommandType::ResetPmr,
             _ => CommandType::Error,
         }
     }
@@ -0,0 +1,56 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `PrepareRecoveryImage` request.
+//!
+//! This module provides a Cerberus command for starting an update to the
+//! recovery image held for a host port.
+
+use crate::io::ReadInt as _;
+use crate::protocol::error::Ack;
+use crate::protocol::error::RecoveryImageError;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for preparing to receive a new recovery image.
+    type PrepareRecoveryImage;
+    type Error = RecoveryImageError;
+    type Response = Ack;
+    const TYPE: CommandType = PrepareRecoveryImage;
+
+    struct Request {
+        /// The host port whose recovery image should be updated.
+        pub port_id: u8,
+        /// The total size of the new image, in bytes.
+        pub size: u32,
+    }
+
+    fn Request::from_wire(r, _) {
+        let port_id = r.read_le()?;
+        let size = r.read_le()?;
+        Ok(Self { port_id, size })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.port_id)?;
+        w.write_le(self.size)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x00, 0x00, 0x10, 0x00, 0x00],
+            json: r#"{
+                "port_id": 0,
+                "size": 4096
+            }"#,
+            value: PrepareRecoveryImageRequest { port_id: 0, size: 4096 },
+        },
+    }
+}
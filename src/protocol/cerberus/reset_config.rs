@@ -0,0 +1,136 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ResetConfig` request and response.
+//!
+//! This module provides a Cerberus command for wiping some part of the
+//! device's configuration, such as its manifests.
+//!
+//! Resetting configuration is a two-step process:
+//! 1. The host sends a request with empty `authorization`, and receives an
+//!    authorization token in response.
+//! 2. The host has the token signed by an authority the device trusts, and
+//!    sends it back in a second request. If the signature checks out, the
+//!    device performs the reset and responds with an empty `authorization`.
+//!
+//! See [`auth`] for the token format.
+//!
+//! [`auth`]: crate::auth
+
+use crate::io::read::ReadZeroExt as _;
+use crate::protocol::error::AuthorizationError;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for resetting device configuration.
+    type ResetConfig;
+    type Error = AuthorizationError;
+    const TYPE: CommandType = ResetConfig;
+
+    struct Request<'wire> {
+        /// The kind of reset to perform.
+        pub reset_type: ResetType,
+        /// A signed authorization token, or empty to request a new token.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub authorization: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let reset_type = ResetType::from_wire(r, arena)?;
+        let authorization = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { reset_type, authorization })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.reset_type.to_wire(&mut w)?;
+        w.write_bytes(self.authorization)?;
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// A new token to be signed, or empty if the reset was performed.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub authorization: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let authorization = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { authorization })
+    }
+
+    fn Response::to_wire(&self, w) {
+        w.write_bytes(self.authorization)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// A kind of configuration reset.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum ResetType: u8 {
+        /// Erases all PFMs, returning the device to bypass mode, in which the
+        /// host's firmware is not protected.
+        RestoreBypass = 0x00,
+        /// Restores the device to its factory-default configuration.
+        RestoreDefaults = 0x01,
+        /// Erases platform-specific configuration, such as the PCD.
+        ClearPlatformConfig = 0x02,
+        /// Clears the device's chassis-intrusion state.
+        ResetIntrusion = 0x03,
+    }
+}
+derive_borrowed!(ResetType);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01],
+            json: r#"{
+                "reset_type": "RestoreDefaults",
+                "authorization": ""
+            }"#,
+            value: ResetConfigRequest {
+                reset_type: ResetType::RestoreDefaults,
+                authorization: &[],
+            },
+        },
+        request_round_trip2: {
+            bytes: &[0x03, 0xaa, 0xbb],
+            json: r#"{
+                "reset_type": "ResetIntrusion",
+                "authorization": "aabb"
+            }"#,
+            value: ResetConfigRequest {
+                reset_type: ResetType::ResetIntrusion,
+                authorization: &[0xaa, 0xbb],
+            },
+        },
+        response_round_trip: {
+            bytes: &[0x01, 0x02, 0x03],
+            json: r#"{
+                "authorization": "010203"
+            }"#,
+            value: ResetConfigResponse {
+                authorization: &[0x01, 0x02, 0x03],
+            },
+        },
+    }
+}
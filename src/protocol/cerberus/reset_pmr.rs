@@ -0,0 +1,49 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `ResetPmr` request.
+//!
+//! This module provides a Manticore-specific command for clearing one of the
+//! device's Platform Measurement Registers.
+
+use crate::io::ReadInt as _;
+use crate::protocol::error::Ack;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for resetting a PMR back to all zeroes.
+    type ResetPmr;
+    type Response = Ack;
+    const TYPE: CommandType = ResetPmr;
+
+    struct Request {
+        /// The index of the PMR to reset.
+        pub index: u8,
+    }
+
+    fn Request::from_wire(r, _) {
+        let index = r.read_le()?;
+        Ok(Self { index })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.index)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x03],
+            json: r#"{
+                "index": 3
+            }"#,
+            value: ResetPmrRequest { index: 3 },
+        },
+    }
+}
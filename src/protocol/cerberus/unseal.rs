@@ -0,0 +1,212 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `Unseal` request.
+//!
+//! This module provides a Cerberus command for requesting that the device
+//! unseal a secret bound to its PMR values. The result is retrieved with
+//! [`UnsealResult`].
+//!
+//! See [`seal`] for the sealing scheme.
+//!
+//! [`UnsealResult`]: super::UnsealResult
+//! [`seal`]: crate::seal
+
+use core::convert::TryInto as _;
+
+use zerocopy::AsBytes as _;
+
+use crate::io::ReadInt as _;
+use crate::mem::ArenaExt as _;
+use crate::protocol::error::Ack;
+use crate::protocol::CommandType;
+use crate::seal;
+
+protocol_struct! {
+    /// A command for unsealing a secret.
+    type Unseal;
+    type Response = Ack;
+    const TYPE: CommandType = Unseal;
+
+    // NOTE: Because `[u8; 64]` does not implement Arbitrary, we're forced to
+    // skip using the derives, which is what this attribute achieves.
+    #![fuzz_derives_if = any()]
+    struct Request<'wire> {
+        /// The HMAC algorithm used to authenticate the sealed secret.
+        pub hmac_type: HmacType,
+        /// The kind of seed used to derive the sealing keys.
+        pub seed_type: SeedType,
+        /// Parameters for interpreting `seed`.
+        ///
+        /// For ECDH seeds, `0` indicates that the raw shared secret is the
+        /// seed, while `1` indicates it should be hashed with SHA-256 first.
+        pub seed_params: u8,
+        /// The seed; for ECDH seeds, this is the host's public key.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub seed: &'wire [u8],
+        /// The encrypted secret.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub ciphertext: &'wire [u8],
+        /// An HMAC over `ciphertext` and `policy`.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub hmac: &'wire [u8],
+        /// The sealing policy: the expected value of each of the first
+        /// [`seal::POLICY_PMRS`] PMRs.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstrings",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstrings",
+        )))]
+        pub policy: &'wire [seal::PolicyValue],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let hmac_type = HmacType::from_wire(r, arena)?;
+        let seed_type = SeedType::from_wire(r, arena)?;
+        let seed_params = r.read_le()?;
+
+        let mut read_field = || -> Result<&[u8], wire::Error> {
+            let len = r.read_le::<u16>()?;
+            let buf = arena.alloc_slice::<u8>(len as usize)?;
+            r.read_bytes(buf)?;
+            Ok(buf)
+        };
+        let seed = read_field()?;
+        let ciphertext = read_field()?;
+        let hmac = read_field()?;
+
+        let policy =
+            arena.alloc_slice::<seal::PolicyValue>(seal::POLICY_PMRS)?;
+        r.read_bytes(policy.as_bytes_mut())?;
+
+        Ok(Self {
+            hmac_type,
+            seed_type,
+            seed_params,
+            seed,
+            ciphertext,
+            hmac,
+            policy,
+        })
+    }
+
+    fn Request::to_wire(&self, w) {
+        self.hmac_type.to_wire(&mut w)?;
+        self.seed_type.to_wire(&mut w)?;
+        w.write_le(self.seed_params)?;
+        for &field in &[self.seed, self.ciphertext, self.hmac] {
+            let len: u16 = field
+                .len()
+                .try_into()
+                .map_err(|_| wire::Error::OutOfRange)?;
+            w.write_le(len)?;
+            w.write_bytes(field)?;
+        }
+
+        if self.policy.len() != seal::POLICY_PMRS {
+            return Err(wire::Error::OutOfRange);
+        }
+        w.write_bytes(self.policy.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// An HMAC algorithm for authenticating a sealed secret.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum HmacType: u8 {
+        /// HMAC-SHA-256.
+        Sha256 = 0x00,
+        /// HMAC-SHA-384.
+        Sha384 = 0x01,
+        /// HMAC-SHA-512.
+        Sha512 = 0x02,
+    }
+}
+derive_borrowed!(HmacType);
+
+wire_enum! {
+    /// A kind of seed for deriving sealing keys.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum SeedType: u8 {
+        /// The seed is encrypted with the device's RSA key.
+        ///
+        /// Manticore does not support this seed type.
+        Rsa = 0x00,
+        /// The seed is derived by ECDH with the device's sealing key.
+        Ecdh = 0x01,
+    }
+}
+derive_borrowed!(SeedType);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const POLICY: &[seal::PolicyValue] =
+        &[[0x00; 64], [0x11; 64], [0x00; 64], [0x00; 64], [0x44; 64]];
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[
+                &[
+                    0x00, 0x01, 0x01,  // HMAC type, seed type, seed params.
+                    0x02, 0x00, 0x04, 0x20,  // Seed.
+                    0x03, 0x00, b'k', b'e', b'y',  // Ciphertext.
+                    0x04, 0x00, b'h', b'm', b'a', b'c',  // HMAC.
+                ][..],
+                &[0x00; 64],
+                &[0x11; 64],
+                &[0x00; 64],
+                &[0x00; 64],
+                &[0x44; 64],
+            ].concat(),
+            json: &format!(r#"{{
+                "hmac_type": "Sha256",
+                "seed_type": "Ecdh",
+                "seed_params": 1,
+                "seed": "0420",
+                "ciphertext": "6b6579",
+                "hmac": "686d6163",
+                "policy": [
+                    "{0}",
+                    "{1}",
+                    "{0}",
+                    "{0}",
+                    "{2}"
+                ]
+            }}"#, "00".repeat(64), "11".repeat(64), "44".repeat(64)),
+            value: UnsealRequest {
+                hmac_type: HmacType::Sha256,
+                seed_type: SeedType::Ecdh,
+                seed_params: 1,
+                seed: &[0x04, 0x20],
+                ciphertext: b"key",
+                hmac: b"hmac",
+                policy: POLICY,
+            },
+        },
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `UnsealResult` request and response.
+//!
+//! This module provides a Cerberus command for retrieving the result of the
+//! most recent [`Unseal`] request.
+//!
+//! [`Unseal`]: super::Unseal
+
+use core::convert::TryInto as _;
+
+use crate::io::ReadInt as _;
+use crate::mem::ArenaExt as _;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for retrieving an unsealed secret.
+    type UnsealResult;
+    const TYPE: CommandType = UnsealResult;
+
+    struct Request {}
+
+    fn Request::from_wire(_, _) {
+        Ok(Self {})
+    }
+
+    fn Request::to_wire(&self, _w) {
+        Ok(())
+    }
+
+    struct Response<'wire> {
+        /// The status of the unsealing operation.
+        pub status: UnsealStatus,
+        /// The unsealed secret; empty unless `status` is
+        /// [`UnsealStatus::Success`].
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub key: &'wire [u8],
+    }
+
+    fn Response::from_wire(r, arena) {
+        let status = UnsealStatus::from_wire(r, arena)?;
+        let len = r.read_le::<u16>()?;
+        let key = arena.alloc_slice::<u8>(len as usize)?;
+        r.read_bytes(key)?;
+        Ok(Self { status, key })
+    }
+
+    fn Response::to_wire(&self, w) {
+        self.status.to_wire(&mut w)?;
+        let len: u16 = self
+            .key
+            .len()
+            .try_into()
+            .map_err(|_| wire::Error::OutOfRange)?;
+        w.write_le(len)?;
+        w.write_bytes(self.key)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "arbitrary-derive")]
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+wire_enum! {
+    /// The status of an unsealing operation.
+    #[cfg_attr(feature = "arbitrary-derive", derive(Arbitrary))]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum UnsealStatus: u32 {
+        /// The secret was successfully unsealed.
+        Success = 0x00,
+        /// No unsealing operation has been requested.
+        NotStarted = 0x01,
+        /// The sealed secret failed authentication.
+        BadHmac = 0x02,
+        /// The device's PMRs did not match the sealing policy.
+        PolicyMismatch = 0x03,
+        /// Unsealing failed for some other reason.
+        Failed = 0x04,
+    }
+}
+derive_borrowed!(UnsealStatus);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[],
+            json: "{}",
+            value: UnsealResultRequest {},
+        },
+        response_round_trip: {
+            bytes: &[
+                0x00, 0x00, 0x00, 0x00,  // Status.
+                0x03, 0x00, b'k', b'e', b'y',
+            ],
+            json: r#"{
+                "status": "Success",
+                "key": "6b6579"
+            }"#,
+            value: UnsealResultResponse {
+                status: UnsealStatus::Success,
+                key: b"key",
+            },
+        },
+        failure_round_trip: {
+            bytes: &[
+                0x03, 0x00, 0x00, 0x00,  // Status.
+                0x00, 0x00,
+            ],
+            json: r#"{
+                "status": "PolicyMismatch",
+                "key": ""
+            }"#,
+            value: UnsealResultResponse {
+                status: UnsealStatus::PolicyMismatch,
+                key: b"",
+            },
+        },
+    }
+}
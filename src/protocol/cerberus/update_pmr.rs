@@ -0,0 +1,64 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `UpdatePmr` request.
+//!
+//! This module provides a Cerberus command for extending one of the device's
+//! Platform Measurement Registers with a new measurement.
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::protocol::error::Ack;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for extending a PMR.
+    type UpdatePmr;
+    type Response = Ack;
+    const TYPE: CommandType = UpdatePmr;
+
+    struct Request<'wire> {
+        /// The index of the PMR to extend.
+        pub index: u8,
+        /// The measurement to extend the PMR with.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub measurement: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let index = r.read_le()?;
+        let measurement = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { index, measurement })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.index)?;
+        w.write_bytes(self.measurement)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x02, b'h', b'a', b's', b'h'],
+            json: r#"{
+                "index": 2,
+                "measurement": "68617368"
+            }"#,
+            value: UpdatePmrRequest {
+                index: 2,
+                measurement: b"hash",
+            },
+        },
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! `UpdateRecoveryImage` request.
+//!
+//! This module provides a Cerberus command for sending a chunk of a new
+//! recovery image, following a [`PrepareRecoveryImage`] request.
+//!
+//! [`PrepareRecoveryImage`]: super::PrepareRecoveryImage
+
+use crate::io::read::ReadZeroExt as _;
+use crate::io::ReadInt as _;
+use crate::protocol::error::Ack;
+use crate::protocol::error::RecoveryImageError;
+use crate::protocol::CommandType;
+
+protocol_struct! {
+    /// A command for sending part of a new recovery image.
+    type UpdateRecoveryImage;
+    type Error = RecoveryImageError;
+    type Response = Ack;
+    const TYPE: CommandType = UpdateRecoveryImage;
+
+    struct Request<'wire> {
+        /// The host port whose recovery image is being updated.
+        pub port_id: u8,
+        /// The next chunk of the image.
+        #[cfg_attr(feature = "serde", serde(
+            serialize_with = "crate::serde::se_hexstring",
+        ))]
+        #[@static(cfg_attr(feature = "serde", serde(
+            deserialize_with = "crate::serde::de_hexstring",
+        )))]
+        pub data: &'wire [u8],
+    }
+
+    fn Request::from_wire(r, arena) {
+        let port_id = r.read_le()?;
+        let data = r.read_slice::<u8>(r.remaining_data(), arena)?;
+        Ok(Self { port_id, data })
+    }
+
+    fn Request::to_wire(&self, w) {
+        w.write_le(self.port_id)?;
+        w.write_bytes(self.data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    round_trip_test! {
+        request_round_trip: {
+            bytes: &[0x01, b'i', b'm', b'g'],
+            json: r#"{
+                "port_id": 1,
+                "data": "696d67"
+            }"#,
+            value: UpdateRecoveryImageRequest {
+                port_id: 1,
+                data: b"img",
+            },
+        },
+    }
+}
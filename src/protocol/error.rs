@@ -7,17 +7,22 @@
 use core::convert::TryFrom;
 use core::convert::TryInto;
 
+use crate::auth;
+use crate::cert;
 use crate::crypto;
+use crate::hardware;
 use crate::io::ReadInt as _;
 use crate::io::ReadZero;
 use crate::io::Write;
 use crate::mem::Arena;
 use crate::mem::OutOfMemory;
+use crate::pmr;
 use crate::protocol::wire;
 use crate::protocol::wire::FromWire;
 use crate::protocol::wire::ToWire;
 use crate::protocol::CommandType;
 use crate::protocol::Message;
+use crate::recovery;
 use crate::session;
 
 #[cfg(doc)]
@@ -179,6 +184,10 @@ impl<'wire, E: SpecificError> FromWire<'wire> for Error<E> {
                 code: 3,
                 data: [0, 0, 0, 0],
             } => Ok(Self::Busy),
+            RawError {
+                code: 4,
+                data: [0xff, code, 0, 0],
+            } => Ok(Self::Specific(E::from_raw(code)?)),
             RawError {
                 code: 4,
                 data: [b, 0, 0, 0],
@@ -189,10 +198,6 @@ impl<'wire, E: SpecificError> FromWire<'wire> for Error<E> {
                 4 => Ok(Self::Internal),
                 _ => Err(wire::Error::OutOfRange),
             },
-            RawError {
-                code: 4,
-                data: [0xff, code, 0, 0],
-            } => Ok(Self::Specific(E::from_raw(code)?)),
             RawError { code: 4, data } => Ok(Self::Unspecified(data)),
             _ => Ok(Self::Unknown(error)),
         }
@@ -261,6 +266,72 @@ impl<E> From<crypto::sig::Error> for Error<E> {
     }
 }
 
+impl<E> From<cert::Error> for Error<E> {
+    fn from(e: cert::Error) -> Self {
+        use crate::io;
+        match e {
+            cert::Error::Unsupported => Self::OutOfRange,
+            cert::Error::WrongKey => Self::Malformed,
+            cert::Error::ChainTooLong
+            | cert::Error::Io(io::Error::BufferExhausted) => {
+                Self::ResourceLimit
+            }
+            _ => Self::Internal,
+        }
+    }
+}
+
+impl<E> From<pmr::Error> for Error<E> {
+    fn from(e: pmr::Error) -> Self {
+        match e {
+            pmr::Error::OutOfRange => Self::OutOfRange,
+            pmr::Error::Full => Self::ResourceLimit,
+            pmr::Error::Hash(_) => Self::Internal,
+        }
+    }
+}
+
+impl From<auth::Error> for Error<AuthorizationError> {
+    fn from(e: auth::Error) -> Self {
+        match e {
+            auth::Error::Csrng(_) => Self::Internal,
+            _ => Self::Specific(AuthorizationError::Unauthorized),
+        }
+    }
+}
+
+impl<E> From<hardware::ConfigResetError> for Error<E> {
+    fn from(e: hardware::ConfigResetError) -> Self {
+        match e {
+            hardware::ConfigResetError::Unsupported => Self::OutOfRange,
+            hardware::ConfigResetError::Failed => Self::Internal,
+        }
+    }
+}
+
+impl From<recovery::Error> for Error<RecoveryImageError> {
+    fn from(e: recovery::Error) -> Self {
+        match e {
+            recovery::Error::Malformed
+            | recovery::Error::Signature(_)
+            | recovery::Error::UnsupportedSigType(_) => {
+                Self::Specific(RecoveryImageError::BadImage)
+            }
+            recovery::Error::Incompatible => {
+                Self::Specific(RecoveryImageError::Incompatible)
+            }
+            recovery::Error::NotPrepared => {
+                Self::Specific(RecoveryImageError::NotPrepared)
+            }
+            recovery::Error::NoImage => {
+                Self::Specific(RecoveryImageError::NoImage)
+            }
+            recovery::Error::TooLarge => Self::ResourceLimit,
+            _ => Self::Internal,
+        }
+    }
+}
+
 impl<E> From<session::Error> for Error<E> {
     fn from(_: session::Error) -> Self {
         Self::Internal
@@ -340,3 +411,28 @@ specific_error! {
         UnknownChain = 0x00,
     }
 }
+
+specific_error! {
+    /// Errors specific to the [`protocol::PrepareRecoveryImage`] and related
+    /// messages.
+    pub enum RecoveryImageError {
+        /// The update was not prepared, or was only partially written.
+        NotPrepared = 0x00,
+        /// The image was malformed or its signature did not match.
+        BadImage = 0x01,
+        /// The image is not compatible with the active PFM.
+        Incompatible = 0x02,
+        /// There is no active image.
+        NoImage = 0x03,
+    }
+}
+
+specific_error! {
+    /// Errors specific to messages that require an authorization token, such
+    /// as [`protocol::ResetConfig`].
+    pub enum AuthorizationError {
+        /// The request's authorization token was missing, stale, or not
+        /// signed by a trusted authority.
+        Unauthorized = 0x00,
+    }
+}
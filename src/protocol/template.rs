@@ -10,6 +10,7 @@ macro_rules! protocol_struct {
         $(#[$cmd_meta:meta])*
         type $Command:ident;
         $(type Error = $Error:ty;)?
+        $(type Response = $RespAlias:ty;)?
 
         const TYPE: $CommandType:ty = $TYPE:ident;
 
@@ -68,6 +69,8 @@ macro_rules! protocol_struct {
                 });
             }
 
+            $(type Resp<'wire> = $RespAlias;)?
+
             protocol_struct!(@internal if_nonempty ($($req_lt)?) {
                 derive_borrowed! {
                     #[doc = "The [`" $Command "`] request."]
@@ -0,0 +1,837 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Host recovery images.
+//!
+//! A recovery image is a signed, known-good copy of the host's firmware,
+//! which a RoT restores to the host's flash when the active firmware fails
+//! validation against the [PFM][crate::manifest::pfm].
+//!
+//! # Wire Format
+//!
+//! In flash, a recovery image has the following layout, expressed as a
+//! pseudo-Rust struct. Integers are encoded in little-endian order, and `_`
+//! indicates reserved fields that should be set to 0.
+//! ```ignore
+//! struct RecoveryImage {
+//!     magic: u32, // See `MAGIC`.
+//!     total_len: u32,
+//!     sig_len: u16,
+//!     platform_id_len: u8,
+//!     sig_type: u8,
+//!     version: [u8; 32],
+//!     platform_id: [u8; self.platform_id_len],
+//!
+//!     image: [u8],
+//!
+//!     signature: [u8; self.sig_len],
+//! }
+//! ```
+//!
+//! `image` is a copy of the host's flash, starting at address zero. `version`
+//! is an opaque, usually ASCII, string that is reported to hosts but is not
+//! otherwise interpreted.
+//!
+//! `sig_type` is encoded in the same way as a manifest's
+//! [signature type][manifest::SigType], and the signature is computed in the
+//! same way: over the digest of every byte that precedes it, using the key and
+//! hash algorithm given by `sig_type`. DER-encoded ECDSA signatures are
+//! zero-padded out to `sig_len`. The signature is checked with the same key
+//! as the PFM.
+//!
+//! # Updates
+//!
+//! A [`Store`] manages the recovery image for a single host port, and
+//! implements the update flow driven by [`protocol::PrepareRecoveryImage`],
+//! [`protocol::UpdateRecoveryImage`], and [`protocol::ActivateRecoveryImage`].
+//!
+//! Like a [manifest store][crate::manifest::store], a `Store` keeps two
+//! slots. A new image is written to the slot not currently in use, and is
+//! only made active, by writing a new boot record, once its signature has
+//! been verified and it has been found to be compatible with the active PFM.
+//! The active image is never modified in place, so losing power at any point
+//! during an update leaves it intact.
+
+use core::iter;
+use core::mem;
+
+use zerocopy::AsBytes;
+use zerocopy::FromBytes;
+
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::crypto::sig;
+use crate::hardware::flash;
+use crate::hardware::flash::Flash;
+use crate::hardware::flash::FlashExt as _;
+use crate::hardware::flash::Region;
+use crate::manifest;
+use crate::manifest::pfm::ParsedPfm;
+use crate::manifest::pfm::Pfm;
+use crate::manifest::provenance::Provenance;
+use crate::manifest::store::BootRecords;
+use crate::manifest::Container;
+use crate::manifest::ManifestExt as _;
+use crate::mem::Arena;
+
+#[cfg(doc)]
+use crate::protocol;
+
+/// The magic number at the start of every recovery image.
+pub const MAGIC: u32 = 0x8a14_7c29;
+
+/// An error returned by a recovery image operation.
+#[derive(Copy, Clone, Debug)]
+pub enum Error {
+    /// Indicates that an error occured in a [`flash`] type.
+    Flash(flash::Error),
+
+    /// Indicates that the active PFM could not be parsed or verified.
+    Manifest(manifest::Error),
+
+    /// Indicates that an image was malformed, such as by having the wrong
+    /// magic number or inconsistent lengths.
+    Malformed,
+
+    /// Indicates that a signature operation failed, including a signature
+    /// not matching an image.
+    Signature(sig::Error),
+
+    /// Indicates that an image's signature type is not supported.
+    UnsupportedSigType(manifest::SigType),
+
+    /// Indicates that an image was well-formed, but not compatible with the
+    /// active PFM.
+    Incompatible,
+
+    /// Indicates that an update operation was performed out of order, such as
+    /// writing to an image before preparing for it or activating an image
+    /// that was only partially written.
+    NotPrepared,
+
+    /// Indicates that an image is too large for the flash it's stored in.
+    TooLarge,
+
+    /// Indicates that the flash for the boot records is too small to hold
+    /// two of them.
+    BootRecordsTooSmall,
+
+    /// Indicates that there is no active image.
+    NoImage,
+
+    /// Indicates an error while hashing.
+    Hash(hash::Error),
+}
+
+impl From<flash::Error> for Error {
+    fn from(e: flash::Error) -> Self {
+        Self::Flash(e)
+    }
+}
+
+impl From<manifest::Error> for Error {
+    fn from(e: manifest::Error) -> Self {
+        Self::Manifest(e)
+    }
+}
+
+impl From<sig::Error> for Error {
+    fn from(e: sig::Error) -> Self {
+        Self::Signature(e)
+    }
+}
+
+impl From<hash::Error> for Error {
+    fn from(e: hash::Error) -> Self {
+        Self::Hash(e)
+    }
+}
+
+impl From<hash::FlashError> for Error {
+    fn from(e: hash::FlashError) -> Self {
+        match e {
+            hash::FlashError::Flash(e) => Self::Flash(e),
+            hash::FlashError::Hash(e) => Self::Hash(e),
+        }
+    }
+}
+
+/// A recovery image's raw header bits.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, AsBytes, FromBytes)]
+#[repr(C)]
+struct RawHeader {
+    magic: u32,
+    total_len: u32,
+    sig_len: u16,
+    platform_id_len: u8,
+    sig_type: u8,
+    version: [u8; 32],
+}
+
+/// A parsed recovery image.
+///
+/// Parsing only checks that the image is well-formed; the signature must be
+/// checked separately, with [`RecoveryImage::verify_signature()`].
+///
+/// See the [module documentation][self] for more information.
+pub struct RecoveryImage<'f> {
+    header: &'f RawHeader,
+    flash: &'f dyn Flash,
+}
+
+impl<'f> RecoveryImage<'f> {
+    /// Parses a `RecoveryImage` out of `flash`, which must start with the
+    /// image.
+    pub fn parse(
+        flash: &'f dyn Flash,
+        arena: &'f dyn Arena,
+    ) -> Result<Self, Error> {
+        let header = flash.read_object::<RawHeader>(0, arena)?;
+        if header.magic != MAGIC
+            || manifest::SigType::from_wire_value(header.sig_type).is_none()
+        {
+            return Err(Error::Malformed);
+        }
+
+        let min_len = mem::size_of::<RawHeader>()
+            + header.platform_id_len as usize
+            + header.sig_len as usize;
+        if (header.total_len as usize) < min_len {
+            return Err(Error::Malformed);
+        }
+        if header.total_len > flash.size()? {
+            return Err(Error::TooLarge);
+        }
+
+        Ok(Self { header, flash })
+    }
+
+    /// Returns the total length of this image, including its signature.
+    pub fn total_len(&self) -> u32 {
+        self.header.total_len
+    }
+
+    /// Returns the [`manifest::SigType`] this image is signed with.
+    pub fn sig_type(&self) -> manifest::SigType {
+        manifest::SigType::from_wire_value(self.header.sig_type)
+            .expect("verified in parse()")
+    }
+
+    /// Returns the version string for this image.
+    pub fn version(&self) -> &'f [u8; 32] {
+        &self.header.version
+    }
+
+    /// Reads the platform ID for this image, potentially allocating it on
+    /// `arena`.
+    pub fn platform_id<'a: 'c, 'b: 'c, 'c>(
+        &'a self,
+        arena: &'b dyn Arena,
+    ) -> Result<&'c [u8], Error> {
+        let region = Region::new(
+            mem::size_of::<RawHeader>() as u32,
+            self.header.platform_id_len as u32,
+        );
+        Ok(self.flash.read_direct(region, arena, 1)?)
+    }
+
+    /// Returns the region of flash containing the host firmware carried by
+    /// this image.
+    pub fn image_region(&self) -> Region {
+        let start = mem::size_of::<RawHeader>() as u32
+            + self.header.platform_id_len as u32;
+        Region::new(start, self.signed_region().len - start)
+    }
+
+    /// Returns the region of flash containing the signed bytes of this image.
+    pub fn signed_region(&self) -> Region {
+        Region::new(0, self.header.total_len - self.header.sig_len as u32)
+    }
+
+    /// Returns the region of flash containing this image's signature.
+    pub fn signature_region(&self) -> Region {
+        let signed = self.signed_region();
+        Region::new(signed.len, self.header.sig_len as u32)
+    }
+
+    /// Verifies this image's signature using `sig_verify`, which must
+    /// implement the algorithm given by [`manifest::SigType::sig_algo()`].
+    pub fn verify_signature(
+        &self,
+        hasher: &mut dyn hash::Engine,
+        sig_verify: &mut dyn sig::Verify,
+        arena: &dyn Arena,
+    ) -> Result<(), Error> {
+        let sig_type = self.sig_type();
+        let hash_type = sig_type.hash_type;
+        if sig_type.sig_algo().is_none() || !hasher.supports(hash_type) {
+            return Err(Error::UnsupportedSigType(sig_type));
+        }
+
+        let mut digest = [0; hash::Algo::Sha512.bytes()];
+        let digest = &mut digest[..hash_type.bytes()];
+        hasher.flash_hash(
+            hash_type,
+            self.flash,
+            iter::once(self.signed_region()),
+            &mut [0; 64],
+            digest,
+        )?;
+
+        let mut sig =
+            self.flash.read_direct(self.signature_region(), arena, 1)?;
+        if sig_type.key_type.is_ecdsa() {
+            sig = manifest::strip_der_padding(sig);
+        }
+        sig_verify.verify(&[digest], sig)?;
+        Ok(())
+    }
+
+    /// Checks that this image is compatible with `pfm`.
+    ///
+    /// An image is compatible if:
+    /// - `pfm` has no platform ID, or its platform ID matches this image's.
+    /// - The image contains the version string of one of `pfm`'s firmware
+    ///   versions at that version's address.
+    pub fn check_pfm<'pfm, P: Provenance>(
+        &self,
+        pfm: &ParsedPfm<'pfm, P>,
+        hasher: &mut dyn hash::Engine,
+        arena: &'pfm dyn Arena,
+    ) -> Result<(), Error> {
+        if let Some(id) = pfm.platform_id(hasher, arena)? {
+            if id.id_string() != self.platform_id(arena)? {
+                return Err(Error::Incompatible);
+            }
+        }
+
+        let image = flash::SubFlash::new(self.flash, self.image_region());
+        for fw in pfm.allowable_fws() {
+            match fw.present_version(&image, hasher, arena) {
+                Ok(_) => return Ok(()),
+                // A version located past the end of the image can't be
+                // present in it.
+                Err(manifest::Error::UnknownVersion { .. })
+                | Err(manifest::Error::Flash(flash::Error::OutOfRange)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(Error::Incompatible)
+    }
+}
+
+/// Encodes and signs a recovery image.
+///
+/// `image` is the host firmware to carry in the recovery image, and `signer`
+/// must implement the algorithm given by [`manifest::SigType::sig_algo()`].
+/// See the [module documentation][self] for the encoding.
+#[cfg(feature = "std")]
+pub fn sign(
+    version: &[u8; 32],
+    platform_id: &[u8],
+    image: &[u8],
+    sig_type: manifest::SigType,
+    hasher: &mut dyn hash::Engine,
+    signer: &mut dyn sig::Sign,
+) -> Result<Vec<u8>, Error> {
+    use core::convert::TryInto as _;
+
+    if sig_type.sig_algo().is_none() {
+        return Err(Error::UnsupportedSigType(sig_type));
+    }
+
+    let sig_len = signer.sig_bytes();
+    let total_len =
+        mem::size_of::<RawHeader>() + platform_id.len() + image.len() + sig_len;
+    let header = RawHeader {
+        magic: MAGIC,
+        total_len: total_len.try_into().map_err(|_| Error::TooLarge)?,
+        sig_len: sig_len.try_into().map_err(|_| Error::Malformed)?,
+        platform_id_len: platform_id
+            .len()
+            .try_into()
+            .map_err(|_| Error::Malformed)?,
+        sig_type: sig_type.to_wire_value(),
+        version: *version,
+    };
+
+    let mut bytes = Vec::with_capacity(total_len);
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(platform_id);
+    bytes.extend_from_slice(image);
+
+    let mut digest = vec![0; sig_type.hash_type.bytes()];
+    hasher.contiguous_hash(sig_type.hash_type, &bytes, &mut digest)?;
+
+    // As with manifests, the signature length is fixed before signing, so
+    // DER-encoded ECDSA signatures are zero-padded to their maximum length.
+    let mut sig = vec![0; sig_len];
+    let len = signer.sign(&[&digest], &mut sig)?;
+    if len != sig_len && !sig_type.key_type.is_ecdsa() {
+        return Err(Error::Signature(sig::Error::Unspecified));
+    }
+    bytes.extend_from_slice(&sig);
+    Ok(bytes)
+}
+
+/// Storage for the recovery image of a single host port.
+///
+/// See the [module documentation][self] for more information.
+pub struct Store<'a> {
+    pfm: &'a dyn Flash,
+    verifier: &'a mut dyn sig::Verify,
+    slots: [&'a mut dyn Flash; 2],
+    boot: BootRecords<'a>,
+    update: Option<Update>,
+}
+
+/// The state of an in-progress update.
+#[derive(Copy, Clone)]
+struct Update {
+    len: u32,
+    written: u32,
+}
+
+impl<'a> Store<'a> {
+    /// Creates a new `Store`, reading the current boot record out of
+    /// `boot_records`.
+    ///
+    /// `pfm` is the host's active PFM, which new images are checked against,
+    /// and `verifier` checks the signatures of both the PFM and new images.
+    ///
+    /// `slots` are the two regions images are written to; which of them is
+    /// active is tracked in `boot_records`, which must be large enough for
+    /// two boot records. See [`manifest::store`] for their format.
+    pub fn new(
+        pfm: &'a dyn Flash,
+        verifier: &'a mut dyn sig::Verify,
+        slots: [&'a mut dyn Flash; 2],
+        boot_records: &'a mut dyn Flash,
+    ) -> Result<Self, Error> {
+        let boot = BootRecords::new(boot_records).map_err(|e| match e {
+            flash::Error::OutOfRange => Error::BootRecordsTooSmall,
+            e => Error::Flash(e),
+        })?;
+        Ok(Self {
+            pfm,
+            verifier,
+            slots,
+            boot,
+            update: None,
+        })
+    }
+
+    /// Parses the active recovery image, if there is one.
+    ///
+    /// The image's signature is not checked again, since it was checked when
+    /// the image was activated.
+    pub fn active_image<'s>(
+        &'s self,
+        arena: &'s dyn Arena,
+    ) -> Result<Option<RecoveryImage<'s>>, Error> {
+        let slot = match self.boot.active_slot() {
+            Some(slot) => slot,
+            None => return Ok(None),
+        };
+        RecoveryImage::parse(&*self.slots[slot], arena).map(Some)
+    }
+
+    /// Starts an update to an image of `len` bytes, discarding any update
+    /// already in progress.
+    pub fn prepare(&mut self, len: u32) -> Result<(), Error> {
+        let slot = &mut self.slots[self.boot.pending_slot()];
+        if len > slot.size()? {
+            return Err(Error::TooLarge);
+        }
+        slot.erase_covering(Region::new(0, len))?;
+
+        self.update = Some(Update { len, written: 0 });
+        Ok(())
+    }
+
+    /// Appends `data` to the image being updated.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        let slot = self.boot.pending_slot();
+        let update = self.update.as_mut().ok_or(Error::NotPrepared)?;
+        let end = update
+            .written
+            .checked_add(data.len() as u32)
+            .filter(|&end| end <= update.len)
+            .ok_or(Error::TooLarge)?;
+
+        self.slots[slot].program(update.written, data)?;
+        update.written = end;
+        Ok(())
+    }
+
+    /// Validates the image being updated and, if it is valid, makes it the
+    /// active recovery image.
+    ///
+    /// Whether or not validation succeeds, the update is over once this
+    /// function is called; a new one must be prepared to try again.
+    pub fn activate(
+        &mut self,
+        hasher: &mut dyn hash::Engine,
+        arena: &dyn Arena,
+    ) -> Result<(), Error> {
+        let len = match self.update.take() {
+            Some(u) if u.written == u.len => u.len,
+            _ => return Err(Error::NotPrepared),
+        };
+        let pending_slot = self.boot.pending_slot();
+        self.slots[pending_slot].flush()?;
+
+        {
+            let image =
+                RecoveryImage::parse(&*self.slots[pending_slot], arena)?;
+            if image.total_len() != len {
+                return Err(Error::Malformed);
+            }
+            image.verify_signature(hasher, self.verifier, arena)?;
+
+            let pfm = Container::<Pfm>::parse_and_verify(
+                self.pfm,
+                hasher,
+                self.verifier,
+                arena,
+                arena,
+            )?;
+            image.check_pfm(&ParsedPfm::new(pfm), hasher, arena)?;
+        }
+
+        // Recovery images are accepted based on the active PFM rather than
+        // a version ID, so there is none to record.
+        self.boot.activate(pending_slot, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::from_str;
+    use testutil::data::keys;
+
+    use crate::crypto::ring;
+    use crate::hardware::flash::Nor;
+    use crate::hardware::flash::Ram;
+    use crate::hardware::flash::RamMut;
+    use crate::manifest::owned;
+    use crate::manifest::testdata;
+    use crate::mem::BumpArena;
+
+    const VERSION: &[u8; 32] = b"recovery image version 1.0.0....";
+
+    /// Returns a signed PFM allowing images with `ver-1.2.2` at `0x10`.
+    fn pfm(signer: &mut ring::rsa::Sign256) -> Vec<u8> {
+        #[rustfmt::skip]
+        let pfm: owned::Pfm = from_str(r#"{
+            "version_id": 1,
+            "elements": [
+                { "platform_id": "my platform" },
+                {
+                    "version_count": 1,
+                    "firmware_id": "host firmware",
+                    "flags": 0,
+                    "hashed": true,
+                    "children": [{
+                        "version_addr": "0x10",
+                        "version_str": "ver-1.2.2",
+                        "rw_regions": [],
+                        "image_regions": []
+                    }]
+                }
+            ]
+        }"#).unwrap();
+        pfm.sign(
+            0x0,
+            testdata::RSA_SHA256,
+            &mut ring::hash::Engine::new(),
+            signer,
+        )
+        .unwrap()
+    }
+
+    /// Returns a host flash image with `version` at `0x10`.
+    fn host_image(version: &[u8]) -> Vec<u8> {
+        let mut image = vec![0xaa; 64];
+        image[0x10..0x10 + version.len()].copy_from_slice(version);
+        image
+    }
+
+    fn update(
+        store: &mut Store,
+        image: &[u8],
+        arena: &dyn Arena,
+    ) -> Result<(), Error> {
+        store.prepare(image.len() as u32)?;
+        for chunk in image.chunks(100) {
+            store.write(chunk)?;
+        }
+        store.activate(&mut ring::hash::Engine::new(), arena)
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn update_and_activate() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let pfm = Ram(pfm(&mut signer));
+        let image = sign(
+            VERSION,
+            b"my platform",
+            &host_image(b"ver-1.2.2"),
+            testdata::RSA_SHA256,
+            &mut hasher,
+            &mut signer,
+        )
+        .unwrap();
+
+        let mut a = Nor::new(vec![0xff; 1024], 256);
+        let mut b = Nor::new(vec![0xff; 1024], 256);
+        let mut records = Nor::new(vec![0xff; 512], 256);
+        let mut store =
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut records).unwrap();
+
+        let mut arena = vec![0; 1024];
+        let arena = BumpArena::new(&mut arena);
+        assert!(store.active_image(&arena).unwrap().is_none());
+
+        update(&mut store, &image, &arena).unwrap();
+        let active = store.active_image(&arena).unwrap().unwrap();
+        assert_eq!(active.version(), VERSION);
+        assert_eq!(active.platform_id(&arena).unwrap(), b"my platform");
+        assert_eq!(active.image_region().len, 64);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn interrupted_update() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let pfm = Ram(pfm(&mut signer));
+        let image = sign(
+            VERSION,
+            b"my platform",
+            &host_image(b"ver-1.2.2"),
+            testdata::RSA_SHA256,
+            &mut hasher,
+            &mut signer,
+        )
+        .unwrap();
+
+        let mut a = Nor::new(vec![0xff; 1024], 256);
+        let mut b = Nor::new(vec![0xff; 1024], 256);
+        let mut records = Nor::new(vec![0xff; 512], 256);
+        let mut arena = vec![0; 1024];
+        let arena = BumpArena::new(&mut arena);
+
+        {
+            let mut store =
+                Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut records)
+                    .unwrap();
+            update(&mut store, &image, &arena).unwrap();
+
+            // Simulate losing power halfway through writing a second image.
+            store.prepare(image.len() as u32).unwrap();
+            store.write(&image[..image.len() / 2]).unwrap();
+        }
+
+        let (mut verifier, _) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let store =
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut records).unwrap();
+        let active = store.active_image(&arena).unwrap().unwrap();
+        assert_eq!(active.version(), VERSION);
+        active
+            .verify_signature(&mut hasher, &mut verifier, &arena)
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn incompatible() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let pfm = Ram(pfm(&mut signer));
+
+        let mut a = RamMut(vec![0; 1024]);
+        let mut b = RamMut(vec![0; 1024]);
+        let mut records = RamMut(vec![0; 40]);
+
+        let mut too_small = RamMut(vec![0; 16]);
+        assert!(matches!(
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut too_small),
+            Err(Error::BootRecordsTooSmall)
+        ));
+
+        let mut store =
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut records).unwrap();
+        let mut arena = vec![0; 1024];
+        let mut arena = BumpArena::new(&mut arena);
+
+        let bad_version = sign(
+            VERSION,
+            b"my platform",
+            &host_image(b"ver-6.6.6"),
+            testdata::RSA_SHA256,
+            &mut hasher,
+            &mut signer,
+        )
+        .unwrap();
+        assert!(matches!(
+            update(&mut store, &bad_version, &arena),
+            Err(Error::Incompatible)
+        ));
+        arena.reset();
+
+        let bad_platform = sign(
+            VERSION,
+            b"not my platform",
+            &host_image(b"ver-1.2.2"),
+            testdata::RSA_SHA256,
+            &mut hasher,
+            &mut signer,
+        )
+        .unwrap();
+        assert!(matches!(
+            update(&mut store, &bad_platform, &arena),
+            Err(Error::Incompatible)
+        ));
+        arena.reset();
+
+        // Nothing should have been activated.
+        assert!(store.active_image(&arena).unwrap().is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn bad_signature() {
+        let mut hasher = ring::hash::Engine::new();
+        let (mut rsa, mut signer) =
+            ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let (_, mut other_signer) =
+            ring::rsa::from_keypair(keys::KEY2_RSA_KEYPAIR);
+        let pfm = Ram(pfm(&mut signer));
+
+        let mut a = RamMut(vec![0; 1024]);
+        let mut b = RamMut(vec![0; 1024]);
+        let mut records = RamMut(vec![0; 40]);
+
+        let mut too_small = RamMut(vec![0; 16]);
+        assert!(matches!(
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut too_small),
+            Err(Error::BootRecordsTooSmall)
+        ));
+
+        let mut store =
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut records).unwrap();
+        let mut arena = vec![0; 1024];
+        let arena = BumpArena::new(&mut arena);
+
+        let image = sign(
+            VERSION,
+            b"my platform",
+            &host_image(b"ver-1.2.2"),
+            testdata::RSA_SHA256,
+            &mut hasher,
+            &mut other_signer,
+        )
+        .unwrap();
+        assert!(matches!(
+            update(&mut store, &image, &arena),
+            Err(Error::Signature(_))
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn sig_types() {
+        let mut hasher = ring::hash::Engine::new();
+        let mut p256 = ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(
+            keys::KEY1_ECDSA_P256_KEYPAIR,
+        )
+        .unwrap();
+        let sig_type = manifest::SigType {
+            key_type: manifest::KeyType::EcdsaP256,
+            hash_type: hash::Algo::Sha256,
+        };
+
+        let image = sign(
+            VERSION,
+            b"my platform",
+            &host_image(b"ver-1.2.2"),
+            sig_type,
+            &mut hasher,
+            &mut p256,
+        )
+        .unwrap();
+        let flash = Ram(image);
+        let arena = BumpArena::new([0; 256]);
+        let image = RecoveryImage::parse(&flash, &arena).unwrap();
+        assert_eq!(image.sig_type(), sig_type);
+        image
+            .verify_signature(&mut hasher, &mut p256.verifier(), &arena)
+            .unwrap();
+
+        let unsupported = manifest::SigType {
+            key_type: manifest::KeyType::Rsa2048,
+            hash_type: hash::Algo::Sha384,
+        };
+        assert!(matches!(
+            sign(
+                VERSION,
+                b"my platform",
+                &host_image(b"ver-1.2.2"),
+                unsupported,
+                &mut hasher,
+                &mut p256,
+            ),
+            Err(Error::UnsupportedSigType(_))
+        ));
+    }
+
+    #[test]
+    fn out_of_order() {
+        let (mut rsa, _) = ring::rsa::from_keypair(keys::KEY1_RSA_KEYPAIR);
+        let pfm = Ram(vec![]);
+        let mut a = RamMut(vec![0; 16]);
+        let mut b = RamMut(vec![0; 16]);
+        let mut records = RamMut(vec![0; 40]);
+
+        let mut too_small = RamMut(vec![0; 16]);
+        assert!(matches!(
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut too_small),
+            Err(Error::BootRecordsTooSmall)
+        ));
+
+        let mut store =
+            Store::new(&pfm, &mut rsa, [&mut a, &mut b], &mut records).unwrap();
+        let mut hasher = ring::hash::Engine::new();
+        let mut arena = vec![0; 64];
+        let arena = BumpArena::new(&mut arena);
+
+        assert!(matches!(store.write(&[0; 4]), Err(Error::NotPrepared)));
+        assert!(matches!(
+            store.activate(&mut hasher, &arena),
+            Err(Error::NotPrepared)
+        ));
+        assert!(matches!(store.prepare(17), Err(Error::TooLarge)));
+
+        store.prepare(8).unwrap();
+        store.write(&[0; 4]).unwrap();
+        assert!(matches!(store.write(&[0; 5]), Err(Error::TooLarge)));
+        assert!(matches!(
+            store.activate(&mut hasher, &arena),
+            Err(Error::NotPrepared)
+        ));
+
+        // A failed activation ends the update.
+        assert!(matches!(store.write(&[0; 4]), Err(Error::NotPrepared)));
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Unsealing of secrets bound to PMR values.
+//!
+//! A host can seal a secret (such as a disk encryption key) such that the
+//! device will only release it if its [PMRs][crate::pmr] have particular
+//! values. Sealing works as follows:
+//!
+//! 1. The host performs an ECDH agreement between a fresh ephemeral key and
+//!    the device's sealing key, producing a shared secret. Optionally, this
+//!    secret is hashed with SHA-256 to form the "seed".
+//! 2. Two keys are derived from the seed, using the KDF described in the
+//!    [`session`] module:
+//!    ```text
+//!    signing_key    := KDF(seed, "signing key", "")
+//!    encryption_key := KDF(seed, "encryption key", "")
+//!    ```
+//! 3. The secret is encrypted by XOR-ing it with `encryption_key`; thus, it
+//!    may be at most 32 bytes long.
+//! 4. The host chooses a sealing policy: a value for each of the first
+//!    [`POLICY_PMRS`] PMRs, zero-padded to 64 bytes. An all-zero value
+//!    indicates that the corresponding PMR is not part of the policy.
+//! 5. The host computes `HMAC(signing_key, ciphertext || policy)`.
+//!
+//! The device then performs the same agreement to recover the seed, checks
+//! the HMAC, and compares the policy against its live PMRs before decrypting
+//! the secret.
+//!
+//! See [`protocol::Unseal`] and [`protocol::UnsealResult`].
+
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::pmr;
+use crate::session;
+
+#[cfg(doc)]
+use crate::protocol;
+
+#[cfg(all(feature = "ring", feature = "std"))]
+pub mod ring;
+
+/// The number of PMRs covered by a sealing policy.
+pub const POLICY_PMRS: usize = 5;
+
+/// The sealed value for a single PMR in a sealing policy.
+pub type PolicyValue = [u8; 64];
+
+/// The KDF label for the key used to authenticate a [`Sealed`] secret.
+const SIGNING_LABEL: &[u8] = b"signing key";
+
+/// The KDF label for the key used to encrypt a [`Sealed`] secret.
+const ENCRYPTION_LABEL: &[u8] = b"encryption key";
+
+/// An error returned by an unsealing operation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// Indicates that the sealed secret was malformed, such as by having a
+    /// policy of the wrong length or a ciphertext that is too long.
+    Malformed,
+    /// Indicates that the HMAC over the sealed secret did not match.
+    BadHmac,
+    /// Indicates that the device's PMRs did not satisfy the sealing policy.
+    PolicyMismatch,
+    /// Indicates that the key agreement failed, such as because the seed
+    /// was not a valid public key.
+    Agreement,
+    /// Indicates an error while hashing.
+    Hash(hash::Error),
+}
+
+impl From<hash::Error> for Error {
+    fn from(e: hash::Error) -> Self {
+        Self::Hash(e)
+    }
+}
+
+/// A device's long-term sealing key.
+///
+/// Implementations must use the P-256 curve, and need a backend capable of
+/// static ECDH, i.e., an agreement with a fixed private scalar.
+pub trait KeyAgreement {
+    /// Performs an ECDH agreement between this key and `their_key`, which
+    /// is an uncompressed SEC1 point, writing the shared secret to `secret`.
+    fn agree(
+        &mut self,
+        their_key: &[u8],
+        secret: &mut session::Key,
+    ) -> Result<(), Error>;
+}
+
+/// A sealed secret, as received from a host.
+#[derive(Copy, Clone, Debug)]
+pub struct Sealed<'a> {
+    /// The host's public key for the ECDH agreement.
+    pub seed: &'a [u8],
+    /// Whether the ECDH shared secret should be hashed to form the seed.
+    pub hash_seed: bool,
+    /// The encrypted secret.
+    pub ciphertext: &'a [u8],
+    /// The HMAC over `ciphertext` and `policy`.
+    pub hmac: &'a [u8],
+    /// The sealing policy, which must have exactly [`POLICY_PMRS`] entries.
+    pub policy: &'a [PolicyValue],
+}
+
+/// Unseals `sealed`, writing the secret to `out`.
+///
+/// `pmrs` are the device's live PMRs, which the policy in `sealed` is
+/// checked against.
+///
+/// Returns the length of the secret.
+pub fn unseal(
+    sealed: &Sealed,
+    key: &mut dyn KeyAgreement,
+    pmrs: &dyn pmr::Pmrs,
+    hasher: &mut dyn hash::Engine,
+    out: &mut session::Key,
+) -> Result<usize, Error> {
+    if sealed.policy.len() != POLICY_PMRS || sealed.ciphertext.len() > out.len()
+    {
+        return Err(Error::Malformed);
+    }
+
+    let mut seed = session::Key::default();
+    key.agree(sealed.seed, &mut seed)?;
+    if sealed.hash_seed {
+        let secret = seed;
+        hasher.contiguous_hash(hash::Algo::Sha256, &secret, &mut seed)?;
+    }
+
+    let signing_key = session::kdf(hasher, &seed, SIGNING_LABEL, &[])?;
+    let mut h = hasher.new_hmac(hash::Algo::Sha256, &signing_key)?;
+    h.write(sealed.ciphertext)?;
+    for value in sealed.policy {
+        h.write(value)?;
+    }
+    h.expect(sealed.hmac).map_err(|_| Error::BadHmac)?;
+
+    for (i, value) in sealed.policy.iter().enumerate() {
+        if value.iter().all(|&b| b == 0) {
+            continue;
+        }
+
+        let pmr = pmrs.value(i).ok_or(Error::PolicyMismatch)?;
+        let (expected, padding) = value.split_at(pmr.len());
+        if expected != pmr || padding.iter().any(|&b| b != 0) {
+            return Err(Error::PolicyMismatch);
+        }
+    }
+
+    let encryption_key = session::kdf(hasher, &seed, ENCRYPTION_LABEL, &[])?;
+    let len = sealed.ciphertext.len();
+    for (i, c) in sealed.ciphertext.iter().enumerate() {
+        out[i] = c ^ encryption_key[i];
+    }
+    Ok(len)
+}
+
+#[cfg(all(test, feature = "ring", feature = "std"))]
+mod test {
+    use super::*;
+
+    use ::ring::agreement as ecdh;
+
+    use crate::crypto::ring::hash::Engine;
+    use crate::pmr::Pmrs as _;
+    use crate::pmr::SimplePmrs;
+
+    const SECRET: &[u8] = b"super secret disk encryption key";
+
+    /// A fixed-scalar sealing key, for tests only.
+    ///
+    /// `ring` has no static ECDH, so the scalar is replayed through its
+    /// test RNG to rebuild an ephemeral key for every agreement.
+    struct TestKey {
+        scalar: [u8; 32],
+        public: Vec<u8>,
+    }
+
+    impl TestKey {
+        fn new() -> Self {
+            let scalar = [0x42; 32];
+            let public = Self::private_key(&scalar)
+                .compute_public_key()
+                .unwrap()
+                .as_ref()
+                .to_vec();
+            Self { scalar, public }
+        }
+
+        fn private_key(scalar: &[u8; 32]) -> ecdh::EphemeralPrivateKey {
+            let rand = ::ring::test::rand::FixedSliceRandom { bytes: scalar };
+            ecdh::EphemeralPrivateKey::generate(&ecdh::ECDH_P256, &rand)
+                .unwrap()
+        }
+
+        fn public_key(&self) -> &[u8] {
+            &self.public
+        }
+    }
+
+    impl KeyAgreement for TestKey {
+        fn agree(
+            &mut self,
+            their_key: &[u8],
+            secret: &mut session::Key,
+        ) -> Result<(), Error> {
+            ecdh::agree_ephemeral(
+                Self::private_key(&self.scalar),
+                &ecdh::UnparsedPublicKey::new(&ecdh::ECDH_P256, their_key),
+                Error::Agreement,
+                |material| {
+                    secret.copy_from_slice(material);
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    /// Performs the host side of sealing `SECRET` to `policy`.
+    fn seal(
+        device_key: &[u8],
+        hash_seed: bool,
+        policy: Vec<PolicyValue>,
+    ) -> ring::Sealing {
+        ring::seal(device_key, SECRET, hash_seed, policy, &mut Engine::new())
+            .unwrap()
+    }
+
+    fn policy_for(pmrs: &dyn pmr::Pmrs, indices: &[usize]) -> Vec<PolicyValue> {
+        let mut policy = vec![[0; 64]; POLICY_PMRS];
+        for &i in indices {
+            policy[i][..32].copy_from_slice(pmrs.value(i).unwrap());
+        }
+        policy
+    }
+
+    fn try_unseal(
+        sealing: &ring::Sealing,
+        hash_seed: bool,
+        key: &mut TestKey,
+        pmrs: &dyn pmr::Pmrs,
+    ) -> Result<Vec<u8>, Error> {
+        let mut out = session::Key::default();
+        let len = unseal(
+            &Sealed {
+                hash_seed,
+                ..sealing.as_sealed()
+            },
+            key,
+            pmrs,
+            &mut Engine::new(),
+            &mut out,
+        )?;
+        Ok(out[..len].to_vec())
+    }
+
+    #[test]
+    fn unseal_ok() {
+        let mut h = Engine::new();
+        let mut key = TestKey::new();
+        let mut pmrs = SimplePmrs::<4, 4>::new();
+        pmrs.extend(0, 0, &[0xaa; 32], &mut h).unwrap();
+        pmrs.extend(2, 0, &[0xbb; 32], &mut h).unwrap();
+
+        for &hash_seed in &[false, true] {
+            let policy = policy_for(&pmrs, &[0, 2]);
+            let sealing = seal(key.public_key(), hash_seed, policy);
+            let secret =
+                try_unseal(&sealing, hash_seed, &mut key, &pmrs).unwrap();
+            assert_eq!(secret, SECRET);
+
+            // The sealing key is long-term, so the same secret can be
+            // unsealed again.
+            let secret =
+                try_unseal(&sealing, hash_seed, &mut key, &pmrs).unwrap();
+            assert_eq!(secret, SECRET);
+        }
+    }
+
+    #[test]
+    fn policy_mismatch() {
+        let mut h = Engine::new();
+        let mut key = TestKey::new();
+        let mut pmrs = SimplePmrs::<4, 4>::new();
+        pmrs.extend(1, 0, &[0xaa; 32], &mut h).unwrap();
+
+        let sealing = seal(key.public_key(), false, policy_for(&pmrs, &[1]));
+        pmrs.extend(1, 0, &[0xbb; 32], &mut h).unwrap();
+        assert_eq!(
+            try_unseal(&sealing, false, &mut key, &pmrs),
+            Err(Error::PolicyMismatch)
+        );
+
+        // A policy that refers to PMRs the device doesn't have can never be
+        // satisfied.
+        let mut policy = policy_for(&pmrs, &[]);
+        policy[4] = [0xff; 64];
+        let sealing = seal(key.public_key(), false, policy);
+        assert_eq!(
+            try_unseal(&sealing, false, &mut key, &pmrs),
+            Err(Error::PolicyMismatch)
+        );
+    }
+
+    #[test]
+    fn bad_hmac() {
+        let mut key = TestKey::new();
+        let pmrs = SimplePmrs::<4, 4>::new();
+
+        let mut sealing =
+            seal(key.public_key(), false, policy_for(&pmrs, &[0]));
+        sealing.ciphertext[0] ^= 1;
+        assert_eq!(
+            try_unseal(&sealing, false, &mut key, &pmrs),
+            Err(Error::BadHmac)
+        );
+
+        // Disagreeing about the seed hashing also breaks the HMAC.
+        let sealing = seal(key.public_key(), false, policy_for(&pmrs, &[0]));
+        assert_eq!(
+            try_unseal(&sealing, true, &mut key, &pmrs),
+            Err(Error::BadHmac)
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let mut key = TestKey::new();
+        let pmrs = SimplePmrs::<4, 4>::new();
+
+        let mut sealing = seal(key.public_key(), false, policy_for(&pmrs, &[]));
+        sealing.policy.pop();
+        assert_eq!(
+            try_unseal(&sealing, false, &mut key, &pmrs),
+            Err(Error::Malformed)
+        );
+
+        let mut sealing = seal(key.public_key(), false, policy_for(&pmrs, &[]));
+        sealing.seed[0] = 0xff;
+        assert_eq!(
+            try_unseal(&sealing, false, &mut key, &pmrs),
+            Err(Error::Agreement)
+        );
+    }
+}
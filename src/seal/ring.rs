@@ -0,0 +1,118 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Host-side sealing based on [`ring`].
+//!
+//! No [`seal::KeyAgreement`] is provided here: `ring` only supports ephemeral
+//! ECDH keys, and cannot perform an agreement with a long-term scalar.
+//!
+//! Requires the `std` feature flag to be enabled.
+
+use ring::agreement as ecdh;
+use ring::rand::SystemRandom;
+
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::seal;
+use crate::session;
+
+/// A secret sealed with [`seal()`].
+#[derive(Clone, Debug)]
+pub struct Sealing {
+    /// The host's ephemeral public key.
+    pub seed: Vec<u8>,
+    /// Whether the ECDH shared secret was hashed to form the seed.
+    pub hash_seed: bool,
+    /// The encrypted secret.
+    pub ciphertext: Vec<u8>,
+    /// The HMAC over `ciphertext` and `policy`.
+    pub hmac: Vec<u8>,
+    /// The sealing policy.
+    pub policy: Vec<seal::PolicyValue>,
+}
+
+impl Sealing {
+    /// Returns a [`seal::Sealed`] that borrows from this `Sealing`.
+    pub fn as_sealed(&self) -> seal::Sealed {
+        seal::Sealed {
+            seed: &self.seed,
+            hash_seed: self.hash_seed,
+            ciphertext: &self.ciphertext,
+            hmac: &self.hmac,
+            policy: &self.policy,
+        }
+    }
+}
+
+/// Performs the host side of sealing: seals `secret` to `policy`, such that
+/// it can only be unsealed by the holder of `device_key`.
+///
+/// `device_key` is the device's public sealing key, as an uncompressed SEC1
+/// point.
+pub fn seal(
+    device_key: &[u8],
+    secret: &[u8],
+    hash_seed: bool,
+    policy: Vec<seal::PolicyValue>,
+    hasher: &mut dyn hash::Engine,
+) -> Result<Sealing, seal::Error> {
+    let mut encryption_key = session::Key::default();
+    if secret.len() > encryption_key.len() || policy.len() != seal::POLICY_PMRS
+    {
+        return Err(seal::Error::Malformed);
+    }
+
+    let rand = SystemRandom::new();
+    let ephemeral =
+        ecdh::EphemeralPrivateKey::generate(&ecdh::ECDH_P256, &rand)
+            .map_err(|_| seal::Error::Agreement)?;
+    let seed = ephemeral
+        .compute_public_key()
+        .map_err(|_| seal::Error::Agreement)?
+        .as_ref()
+        .to_vec();
+
+    let mut shared = session::Key::default();
+    ecdh::agree_ephemeral(
+        ephemeral,
+        &ecdh::UnparsedPublicKey::new(&ecdh::ECDH_P256, device_key),
+        seal::Error::Agreement,
+        |material| {
+            if material.len() != shared.len() {
+                return Err(seal::Error::Agreement);
+            }
+            shared.copy_from_slice(material);
+            Ok(())
+        },
+    )?;
+    if hash_seed {
+        let material = shared;
+        hasher.contiguous_hash(hash::Algo::Sha256, &material, &mut shared)?;
+    }
+
+    encryption_key =
+        session::kdf(hasher, &shared, seal::ENCRYPTION_LABEL, &[])?;
+    let ciphertext = secret
+        .iter()
+        .zip(&encryption_key)
+        .map(|(a, b)| a ^ b)
+        .collect::<Vec<_>>();
+
+    let signing_key = session::kdf(hasher, &shared, seal::SIGNING_LABEL, &[])?;
+    let mut hmac = vec![0; 32];
+    let mut h = hasher.new_hmac(hash::Algo::Sha256, &signing_key)?;
+    h.write(&ciphertext)?;
+    for value in &policy {
+        h.write(value)?;
+    }
+    h.finish(&mut hmac)?;
+
+    Ok(Sealing {
+        seed,
+        hash_seed,
+        ciphertext,
+        hmac,
+        policy,
+    })
+}
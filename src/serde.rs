@@ -348,6 +348,27 @@ pub mod hexstring {
     pub use super::se_hexstring as serialize;
 }
 
+/// Like `se_hexstrings` but for use with `#[serde(with)]` on a
+/// `Vec<Vec<u8>>`.
+#[cfg(feature = "std")]
+pub mod hexstrings {
+    use super::*;
+
+    pub fn serialize<S>(bytes: &[Vec<u8>], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        se_hexstrings(bytes, s)
+    }
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        de_hexstrings(d).map(Vec::from)
+    }
+}
+
 /// Helper for `de_radix`.
 pub struct Radix<T>(PhantomData<T>);
 
@@ -548,3 +569,51 @@ pub mod bitflags {
     pub use super::de_bitflags as deserialize;
     pub use super::se_bitflags as serialize;
 }
+
+/// Helper for `de_flags`.
+struct FlagsVisitor<F>(PhantomData<F>);
+impl<'de, F> de::Visitor<'de> for FlagsVisitor<F>
+where
+    F: Deserialize<'de> + From<u8>,
+{
+    type Value = F;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "map of fields or integer representation of {}",
+            type_name::<F>()
+        )
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<F, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        F::deserialize(de::value::MapAccessDeserializer::new(map))
+    }
+
+    fn visit_borrowed_str<E>(self, s: &'de str) -> Result<F, E>
+    where
+        E: de::Error,
+    {
+        Radix::<u8>(PhantomData).visit_borrowed_str(s).map(F::from)
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<F, E>
+    where
+        E: de::Error,
+    {
+        Radix::<u8>(PhantomData).visit_u64(n).map(F::from)
+    }
+}
+
+/// Deserializes a struct of flags from a map of its fields *or* from the
+/// integer value of its encoding.
+pub fn de_flags<'de, D, F>(d: D) -> Result<F, D::Error>
+where
+    D: Deserializer<'de>,
+    F: Deserialize<'de> + From<u8>,
+{
+    d.deserialize_any(FlagsVisitor::<F>(PhantomData))
+}
@@ -0,0 +1,421 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A `manticore` "server" for an AC-RoT.
+//!
+//! This module provides structures for serving responses to a PA-RoT making
+//! requests to an AC-RoT, or "Active Component Root of Trust": a device that
+//! is attested by a PA-RoT. Unlike [`PaRot`], an [`AcRot`] only answers the
+//! handful of requests needed for attestation: identity and capabilities
+//! queries, certificate retrieval, challenges, and signed measurements. It
+//! does not support sessions, host protection, or manifests, which keeps it
+//! small enough for tiny microcontrollers.
+//!
+//! [`PaRot`]: crate::server::pa_rot::PaRot
+
+use crate::cert;
+use crate::crypto::csrng;
+use crate::crypto::hash;
+use crate::crypto::sig;
+use crate::hardware;
+use crate::mem::Arena;
+use crate::net;
+use crate::net::CerberusHeader;
+use crate::pmr;
+use crate::protocol;
+use crate::protocol::capabilities;
+use crate::protocol::device_id;
+use crate::protocol::Req;
+use crate::protocol::Resp;
+use crate::server::attest;
+use crate::server::Error;
+
+use crate::server::handler::prelude::*;
+
+/// Options struct for initializing an [`AcRot`].
+pub struct Options<'a> {
+    /// A handle to the "hardware identity" of the device.
+    pub identity: &'a dyn hardware::Identity,
+
+    /// A handle to a hashing engine.
+    pub hasher: &'a mut dyn hash::Engine,
+    /// A handle to the device's supported ciphers, used only for capabilities
+    /// negotiation.
+    pub ciphers: &'a dyn sig::Ciphers,
+    /// A random number generator for creating nonces.
+    pub csrng: &'a mut dyn csrng::Csrng,
+    /// The trust chain to use for the challenge.
+    pub trust_chain: &'a mut dyn cert::TrustChain,
+
+    /// The device's Platform Measurement Registers.
+    ///
+    /// PMR #0 is reported in challenge responses. The host can read, but not
+    /// modify, any of them.
+    pub pmrs: &'a dyn pmr::Pmrs,
+
+    /// This device's silicon identifier.
+    pub device_id: device_id::DeviceIdentifier,
+    /// Integration-provided description of the device's networking
+    /// capabilities.
+    pub networking: capabilities::Networking,
+    /// Integration-provided "acceptable timeout" lengths.
+    pub timeouts: capabilities::Timeouts,
+}
+
+/// An AC-RoT, or "Active Component Root of Trust", server.
+///
+/// This type implements the request -> response "business logic" of the
+/// PA-RoT <-> AC-RoT interaction.
+pub struct AcRot<'a> {
+    opts: Options<'a>,
+}
+
+impl<'a> AcRot<'a> {
+    /// Create a new `AcRot` with the given `Options`.
+    pub fn new(opts: Options<'a>) -> Self {
+        Self { opts }
+    }
+
+    /// Process a single incoming request.
+    pub fn process_request<'req>(
+        &mut self,
+        host_port: &mut dyn net::host::HostPort<'req, CerberusHeader>,
+        arena: &'req dyn Arena,
+    ) -> Result<(), Error<CerberusHeader>> {
+        // Style note: when defining a new handler, if it is more than a
+        // handful of lines long, define it out-of-line instead.
+        Handler::<&mut Self, CerberusHeader>::new()
+            .handle::<protocol::FirmwareVersion, _>(|ctx| {
+                let identity = ctx.server.opts.identity;
+                let version = match ctx.req.index {
+                    0 => identity.firmware_version(),
+                    i => identity
+                        .vendor_firmware_version(i)
+                        .ok_or(protocol::error::Error::OutOfRange)?,
+                };
+                Ok(Resp::<protocol::FirmwareVersion> { version })
+            })
+            .handle::<protocol::DeviceCapabilities, _>(|ctx| {
+                ctx.server.handle_capabilities(&ctx.req)
+            })
+            .handle::<protocol::DeviceId, _>(|ctx| {
+                Ok(Resp::<protocol::DeviceId> {
+                    id: ctx.server.opts.device_id,
+                })
+            })
+            .handle::<protocol::DeviceInfo, _>(|ctx| {
+                use protocol::device_info::InfoIndex;
+                // Failure counters are only meaningful for a PA-RoT.
+                let identity = ctx.server.opts.identity;
+                let info = match ctx.req.index {
+                    InfoIndex::UniqueChipIndex => {
+                        identity.unique_device_identity()
+                    }
+                    InfoIndex::VendorChipIdentifier => identity
+                        .vendor_chip_identifier()
+                        .ok_or(protocol::error::Error::OutOfRange)?,
+                    _ => return Err(protocol::error::Error::OutOfRange),
+                };
+                Ok(Resp::<protocol::DeviceInfo> { info })
+            })
+            .handle::<protocol::GetDigests, _>(|ctx| {
+                // NOTE: AC-RoTs do not support sessions, so
+                // `req.key_exchange` is ignored.
+                let opts = &mut ctx.server.opts;
+                attest::get_digests(
+                    &*opts.trust_chain,
+                    opts.hasher,
+                    ctx.arena,
+                    &ctx.req,
+                )
+            })
+            .handle::<protocol::GetCert, _>(|ctx| {
+                attest::get_cert(&*ctx.server.opts.trust_chain, &ctx.req)
+            })
+            .handle_buffered::<protocol::Challenge, _>(|ctx| {
+                let opts = &mut ctx.server.opts;
+                attest::challenge(
+                    opts.trust_chain,
+                    opts.csrng,
+                    opts.pmrs,
+                    ctx.arena,
+                    &ctx.req,
+                    ctx.req_buf,
+                )
+            })
+            .handle_buffered::<protocol::GetPmr, _>(|ctx| {
+                let opts = &mut ctx.server.opts;
+                attest::get_pmr(
+                    opts.trust_chain,
+                    opts.csrng,
+                    opts.pmrs,
+                    ctx.arena,
+                    &ctx.req,
+                    ctx.req_buf,
+                )
+            })
+            .run(self, host_port, arena)
+    }
+
+    fn handle_capabilities(
+        &mut self,
+        req: &Req<protocol::DeviceCapabilities>,
+    ) -> Result<
+        Resp<protocol::DeviceCapabilities>,
+        protocol::Error<protocol::DeviceCapabilities>,
+    > {
+        use enumflags2::BitFlags;
+        use protocol::capabilities::*;
+        let mut crypto = req.capabilities.crypto;
+
+        self.opts.ciphers.negotiate(&mut crypto);
+        crypto.has_aes = false;
+        crypto.aes_strength = BitFlags::<AesKeyStrength>::empty();
+
+        // AC-RoTs do not support sessions, so there is no key exchange to
+        // derive keys with, and no secured channel; only attestation is
+        // offered.
+        let capabilities = Capabilities {
+            networking: self.opts.networking,
+            security: Security::Authentication.into(),
+
+            has_pfm_support: false,
+            has_policy_support: false,
+            has_firmware_protection: false,
+
+            crypto,
+        };
+
+        Ok(Resp::<protocol::DeviceCapabilities> {
+            capabilities,
+            timeouts: self.opts.timeouts,
+        })
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    use super::*;
+
+    use core::time::Duration;
+
+    use testutil::data::keys;
+    use testutil::data::x509;
+
+    use crate::cert::CertFormat;
+    use crate::cert::TrustChain as _;
+    use crate::crypto::ring;
+    use crate::crypto::sig::Ciphers as _;
+    use crate::io::Cursor;
+    use crate::mem::BumpArena;
+    use crate::pmr::Pmrs as _;
+    use crate::protocol::wire::FromWire as _;
+    use crate::protocol::wire::ToWire;
+
+    struct Identity;
+    impl hardware::Identity for Identity {
+        fn firmware_version(&self) -> &[u8; 32] {
+            b"ac-rot firmware, version 1.0.0!!"
+        }
+        fn unique_device_identity(&self) -> &[u8] {
+            b"ac-rot"
+        }
+    }
+
+    /// Sends `req` to `rot`, returning the raw response.
+    fn send<'a, C>(
+        rot: &mut AcRot,
+        req: C::Req,
+        buf: &'a mut [u8],
+    ) -> (CerberusHeader, &'a [u8])
+    where
+        C: protocol::Command<'a, CommandType = protocol::CommandType>,
+    {
+        let (req_buf, port_buf) = buf.split_at_mut(1024);
+        let mut cursor = Cursor::new(req_buf);
+        req.to_wire(&mut cursor).unwrap();
+        let req_bytes = cursor.take_consumed_bytes();
+
+        let mut port = net::host::InMemHost::new(port_buf);
+        port.request(
+            CerberusHeader {
+                command: <C::Req as protocol::Message>::TYPE,
+                crypt: false,
+            },
+            req_bytes,
+        );
+        let mut arena = [0; 1024];
+        rot.process_request(&mut port, &BumpArena::new(&mut arena))
+            .unwrap();
+
+        let (header, resp) = port.response().unwrap();
+        let len = resp.len();
+        let port_buf = &*port_buf;
+        (header, &port_buf[..len])
+    }
+
+    #[test]
+    fn capabilities() {
+        use protocol::capabilities::*;
+
+        let mut hasher = ring::hash::Engine::new();
+        let mut csrng = ring::csrng::Csrng::new();
+        let ciphers = ring::sig::Ciphers::new();
+        let mut chain = cert::SimpleChain::<0>::parse(
+            &[],
+            CertFormat::RiotX509,
+            &mut ring::sig::Ciphers::new(),
+            None,
+        )
+        .unwrap();
+        let pmrs = pmr::SimplePmrs::<1, 4>::new();
+        let networking = Networking {
+            max_message_size: 1024,
+            max_packet_size: 256,
+            mode: RotMode::Active,
+            roles: BusRole::Target.into(),
+        };
+
+        let mut rot = AcRot::new(Options {
+            identity: &Identity,
+            hasher: &mut hasher,
+            ciphers: &ciphers,
+            csrng: &mut csrng,
+            trust_chain: &mut chain,
+            pmrs: &pmrs,
+            device_id: device_id::DeviceIdentifier {
+                vendor_id: 1,
+                device_id: 2,
+                subsys_vendor_id: 3,
+                subsys_id: 4,
+            },
+            networking,
+            timeouts: Timeouts {
+                regular: Duration::from_millis(30),
+                crypto: Duration::from_millis(200),
+            },
+        });
+
+        let mut buf = [0; 2048];
+        let (header, mut resp) = send::<DeviceCapabilities>(
+            &mut rot,
+            DeviceCapabilitiesRequest {
+                capabilities: Capabilities {
+                    networking,
+                    security: Security::HashAndKdf
+                        | Security::Authentication
+                        | Security::Confidentiality,
+                    has_pfm_support: false,
+                    has_policy_support: false,
+                    has_firmware_protection: false,
+                    crypto: Crypto {
+                        has_ecdsa: false,
+                        has_ecc: false,
+                        has_rsa: true,
+                        has_aes: true,
+                        ecc_strength: Default::default(),
+                        rsa_strength: RsaKeyStrength::Bits2048.into(),
+                        aes_strength: AesKeyStrength::Bits256.into(),
+                    },
+                },
+            },
+            &mut buf,
+        );
+        assert_eq!(header.command, protocol::CommandType::DeviceCapabilities);
+        let arena = BumpArena::new([0; 256]);
+        let resp =
+            DeviceCapabilitiesResponse::from_wire(&mut resp, &arena).unwrap();
+
+        // There are no sessions, so neither key derivation nor encryption is
+        // advertised.
+        assert_eq!(resp.capabilities.security, Security::Authentication);
+        assert!(resp.capabilities.crypto.aes_strength.is_empty());
+    }
+
+    #[test]
+    fn challenge() {
+        use protocol::challenge::*;
+        use protocol::get_digests::*;
+
+        let mut hasher = ring::hash::Engine::new();
+        let mut csrng = ring::csrng::Csrng::new();
+        let mut ciphers = ring::sig::Ciphers::new();
+        let mut signer =
+            ring::rsa::Sign256::from_pkcs8(keys::KEY3_RSA_KEYPAIR).unwrap();
+        let mut chain = cert::SimpleChain::<3>::parse(
+            &[x509::CHAIN1, x509::CHAIN2, x509::CHAIN3],
+            CertFormat::RiotX509,
+            &mut ciphers,
+            Some(&mut signer),
+        )
+        .unwrap();
+        let mut pmrs = pmr::SimplePmrs::<1, 4>::new();
+        pmrs.extend(0, 0, &[0x55; 32], &mut hasher).unwrap();
+        let pmr0 = *pmrs.value(0).unwrap();
+
+        let mut rot = AcRot::new(Options {
+            identity: &Identity,
+            hasher: &mut hasher,
+            ciphers: &ciphers,
+            csrng: &mut csrng,
+            trust_chain: &mut chain,
+            pmrs: &pmrs,
+            device_id: device_id::DeviceIdentifier {
+                vendor_id: 1,
+                device_id: 2,
+                subsys_vendor_id: 3,
+                subsys_id: 4,
+            },
+            networking: capabilities::Networking {
+                max_message_size: 1024,
+                max_packet_size: 256,
+                mode: capabilities::RotMode::Active,
+                roles: capabilities::BusRole::Target.into(),
+            },
+            timeouts: capabilities::Timeouts {
+                regular: Duration::from_millis(30),
+                crypto: Duration::from_millis(200),
+            },
+        });
+
+        let mut buf = [0; 2048];
+        let (header, mut resp) = send::<GetDigests>(
+            &mut rot,
+            GetDigestsRequest {
+                slot: 0,
+                key_exchange: KeyExchangeAlgo::Ecdh,
+            },
+            &mut buf,
+        );
+        assert_eq!(header.command, protocol::CommandType::GetDigests);
+        let arena = BumpArena::new([0; 256]);
+        let digests = GetDigestsResponse::from_wire(&mut resp, &arena).unwrap();
+        assert_eq!(digests.digests.len(), 3);
+
+        let req = ChallengeRequest {
+            slot: 0,
+            nonce: &[99; 32],
+        };
+        let mut buf = [0; 2048];
+        let (header, mut resp) = send::<Challenge>(&mut rot, req, &mut buf);
+        assert_eq!(header.command, protocol::CommandType::Challenge);
+        let arena = BumpArena::new([0; 1024]);
+        let resp = ChallengeResponse::from_wire(&mut resp, &arena).unwrap();
+        assert_eq!(resp.tbs.pmr0, pmr0);
+        assert_eq!(resp.tbs.pmr0_components, 1);
+
+        let mut signee = [0; 1024];
+        let mut cursor = Cursor::new(&mut signee);
+        ToWire::to_wire(&req, &mut cursor).unwrap();
+        ToWire::to_wire(&resp.tbs, &mut cursor).unwrap();
+
+        let mut ciphers = ring::sig::Ciphers::new();
+        let alias = chain.cert(0, 2).unwrap();
+        ciphers
+            .verifier(sig::Algo::RsaPkcs1Sha256, alias.subject_key())
+            .unwrap()
+            .verify(&[cursor.consumed_bytes()], resp.signature)
+            .unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright lowRISC contributors.
+// Licensed under the Apache License, Version 2.0, see LICENSE for details.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Attestation request handlers shared by all servers.
+//!
+//! A PA-RoT and an AC-RoT answer `GetDigests`, `GetCert`, `Challenge`, and
+//! `GetPmr` in exactly the same way. These functions take only the pieces of
+//! server state they need, so that each server can layer its own bookkeeping,
+//! such as session setup, on top.
+
+use core::convert::TryInto as _;
+
+use crate::cert;
+use crate::crypto::csrng;
+use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
+use crate::mem::Arena;
+use crate::mem::ArenaExt as _;
+use crate::pmr;
+use crate::protocol;
+use crate::protocol::Req;
+use crate::protocol::Resp;
+
+/// Handles a `GetDigests` request, hashing each certificate in the
+/// requested slot.
+pub fn get_digests<'req>(
+    trust_chain: &dyn cert::TrustChain,
+    hasher: &mut dyn hash::Engine,
+    arena: &'req dyn Arena,
+    req: &Req<protocol::GetDigests>,
+) -> Result<
+    Resp<'req, protocol::GetDigests>,
+    protocol::Error<'req, protocol::GetDigests>,
+> {
+    let digests_len = trust_chain
+        .chain_len(req.slot)
+        .ok_or(protocol::error::ChallengeError::UnknownChain)?
+        .get();
+    let digests =
+        arena.alloc_slice::<[u8; hash::Algo::Sha256.bytes()]>(digests_len)?;
+    for (i, digest) in digests.iter_mut().enumerate() {
+        let cert = trust_chain
+            .cert(req.slot, i)
+            .ok_or(protocol::error::ChallengeError::UnknownChain)?;
+        hasher.contiguous_hash(hash::Algo::Sha256, cert.raw(), digest)?;
+    }
+
+    Ok(Resp::<protocol::GetDigests> { digests })
+}
+
+/// Handles a `GetCert` request, returning the requested chunk of a
+/// certificate.
+pub fn get_cert<'a>(
+    trust_chain: &'a dyn cert::TrustChain,
+    req: &Req<protocol::GetCert>,
+) -> Result<Resp<'a, protocol::GetCert>, protocol::Error<'a, protocol::GetCert>>
+{
+    let cert = trust_chain
+        .cert(req.slot, req.cert_number as usize)
+        .ok_or(protocol::error::ChallengeError::UnknownChain)?;
+
+    let start = cert.raw().len().min(req.offset as usize);
+    let end = cert
+        .raw()
+        .len()
+        .min((req.len as usize).saturating_add(start));
+    Ok(Resp::<protocol::GetCert> {
+        slot: req.slot,
+        cert_number: req.cert_number,
+        data: &cert.raw()[start..end],
+    })
+}
+
+/// Handles a `Challenge` request, signing a fresh nonce and PMR #0 with the
+/// key for the requested slot.
+///
+/// `req_buf` is the raw request, which is included in the signature.
+pub fn challenge<'req>(
+    trust_chain: &mut dyn cert::TrustChain,
+    csrng: &mut dyn csrng::Csrng,
+    pmrs: &'req dyn pmr::Pmrs,
+    arena: &'req dyn Arena,
+    req: &Req<protocol::Challenge>,
+    req_buf: &[u8],
+) -> Result<
+    Resp<'req, protocol::Challenge>,
+    protocol::Error<'req, protocol::Challenge>,
+> {
+    use protocol::challenge::ChallengeResponseTbs;
+    let signer = trust_chain
+        .signer(req.slot)
+        .ok_or(protocol::error::ChallengeError::UnknownChain)?;
+    let nonce = arena.alloc::<[u8; 32]>()?;
+    csrng.fill(nonce)?;
+
+    let pmr0 = pmrs.value(0).map_or(&[][..], |v| &v[..]);
+    let pmr0_components = pmrs
+        .measurements(0)
+        .map_or(0, <[_]>::len)
+        .try_into()
+        .map_err(|_| protocol::error::Error::Internal)?;
+
+    let tbs = ChallengeResponseTbs {
+        slot: req.slot,
+        slot_mask: 0, // Currently unspecified?
+        protocol_range: (0, 0),
+        nonce,
+        pmr0_components,
+        pmr0,
+    };
+
+    let signature = arena.alloc_slice::<u8>(signer.sig_bytes())?;
+    let sig_len = tbs.as_iovec_with(|[a, b, c, d]| {
+        signer.sign(&[req_buf, a, b, c, d], signature)
+    })?;
+    let signature = &signature[..sig_len];
+
+    Ok(Resp::<protocol::Challenge> { tbs, signature })
+}
+
+/// Handles a `GetPmr` request, signing the requested PMR's value along with
+/// a fresh nonce.
+///
+/// `req_buf` is the raw request, which is included in the signature.
+pub fn get_pmr<'req>(
+    trust_chain: &mut dyn cert::TrustChain,
+    csrng: &mut dyn csrng::Csrng,
+    pmrs: &'req dyn pmr::Pmrs,
+    arena: &'req dyn Arena,
+    req: &Req<protocol::GetPmr>,
+    req_buf: &[u8],
+) -> Result<Resp<'req, protocol::GetPmr>, protocol::Error<'req, protocol::GetPmr>>
+{
+    let value = pmrs
+        .value(req.index as usize)
+        .ok_or(protocol::error::Error::OutOfRange)?;
+    let signer = trust_chain
+        .signer(0)
+        .ok_or(protocol::error::Error::Internal)?;
+    let nonce = arena.alloc::<[u8; 32]>()?;
+    csrng.fill(nonce)?;
+
+    let signature = arena.alloc_slice::<u8>(signer.sig_bytes())?;
+    let sig_len = signer
+        .sign(&[req_buf, nonce, &[value.len() as u8], value], signature)?;
+    Ok(Resp::<protocol::GetPmr> {
+        nonce,
+        value,
+        signature: &signature[..sig_len],
+    })
+}
@@ -464,6 +464,7 @@ mod test {
         port.request(
             net::CerberusHeader {
                 command: <C::Req as protocol::Message<'a>>::TYPE,
+                crypt: false,
             },
             request_bytes,
         );
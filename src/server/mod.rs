@@ -9,7 +9,9 @@
 //!
 //! TODO: description of how to use a server.
 
+mod attest;
 mod handler;
 pub use handler::Error;
 
+pub mod ac_rot;
 pub mod pa_rot;
@@ -7,25 +7,40 @@
 //! This module provides structures for serving responses to a host making
 //! requests to a PA-RoT.
 
+use core::convert::TryFrom as _;
+use core::convert::TryInto as _;
+
+use crate::auth;
 use crate::cert;
+use crate::cert::CertRole;
+use crate::cert::ProvisionState;
 use crate::crypto::csrng;
 use crate::crypto::hash;
 use crate::crypto::hash::EngineExt as _;
 use crate::crypto::sig;
 use crate::hardware;
+use crate::manifest::pfm::Pfm;
+use crate::manifest::store;
 use crate::mem::Arena;
 use crate::mem::ArenaExt as _;
 use crate::net;
 use crate::net::CerberusHeader;
 use crate::net::SpdmHeader;
+use crate::pmr;
 use crate::protocol;
 use crate::protocol::capabilities;
 use crate::protocol::device_id;
+use crate::protocol::error::Ack;
 use crate::protocol::get_digests::KeyExchangeAlgo;
 use crate::protocol::spdm;
+use crate::protocol::wire::WireEnum as _;
 use crate::protocol::Req;
 use crate::protocol::Resp;
+use crate::recovery;
+use crate::seal;
+use crate::server::attest;
 use crate::server::Error;
+use crate::session;
 use crate::session::Session;
 
 use crate::server::handler::prelude::*;
@@ -35,7 +50,7 @@ pub struct Options<'a> {
     /// A handle to the "hardware identity" of the device.
     pub identity: &'a dyn hardware::Identity,
     /// A handle for looking up reset-related information for the current
-    /// device.
+    /// device and the devices on its ports.
     pub reset: &'a dyn hardware::Reset,
 
     /// A handle to a hashing engine.
@@ -49,12 +64,49 @@ pub struct Options<'a> {
 
     /// The session manager.
     pub session: &'a mut dyn Session,
+    /// Whether the integration decrypts session-encrypted requests, such as
+    /// by wrapping its host port in a [`net::encrypted::EncryptedHost`].
+    ///
+    /// If set, AES-GCM session encryption is advertised during capabilities
+    /// negotiation, to requesters that support 256-bit AES keys.
+    pub session_encryption: bool,
+
+    /// The device's Platform Measurement Registers.
+    ///
+    /// PMR #0 is reported in challenge responses, and cannot be modified
+    /// by the host.
+    pub pmrs: &'a mut dyn pmr::Pmrs,
+    /// The device's sealing key, for unsealing secrets bound to PMR values.
+    ///
+    /// If `None`, `Unseal` requests are rejected.
+    pub sealing_key: Option<&'a mut dyn seal::KeyAgreement>,
+
+    /// Hooks for wiping device configuration with `ResetConfig`.
+    ///
+    /// If `None`, `ResetConfig` requests are rejected.
+    pub config_reset: Option<&'a mut dyn hardware::ConfigReset>,
+    /// The authority whose signature is required on `ResetConfig`
+    /// authorization tokens.
+    ///
+    /// If `None`, `ResetConfig` requests are rejected.
+    pub reset_authority: Option<&'a mut dyn sig::Verify>,
 
-    /// The value of PMR0.
+    /// Recovery image storage for each host port, indexed by port ID.
     ///
-    /// Eventually this should be replaced with a general "PMRs"
-    /// trait.
-    pub pmr0: &'a [u8],
+    /// Recovery image requests for ports without storage are rejected.
+    pub recovery: &'a mut [recovery::Store<'a>],
+    /// Storage for the host's PFM.
+    ///
+    /// If set, PFM support is advertised during capabilities negotiation.
+    pub pfm: Option<&'a store::Store<'a, Pfm>>,
+    /// Whether the integration protects host firmware, such as by enforcing
+    /// the active PFM with [`ParsedPfm::protect()`].
+    ///
+    /// If set, firmware protection is advertised during capabilities
+    /// negotiation.
+    ///
+    /// [`ParsedPfm::protect()`]: crate::manifest::pfm::ParsedPfm::protect
+    pub firmware_protection: bool,
 
     /// This device's silicon identifier.
     pub device_id: device_id::DeviceIdentifier,
@@ -88,6 +140,13 @@ pub struct PaRot<'a> {
     /// Note that this is *only* changed when the most recent `GetDigests`
     /// indicated a forthcoming key exchange.
     current_cert_slot: Option<u8>,
+
+    /// The outcome of the most recent `Unseal`, which has not yet been
+    /// retrieved with `UnsealResult`.
+    unseal_result: Option<Result<(session::Key, usize), seal::Error>>,
+
+    /// The outstanding `ResetConfig` authorization token, if any.
+    reset_auth: auth::Authorizer,
 }
 
 impl<'a> PaRot<'a> {
@@ -99,9 +158,19 @@ impl<'a> PaRot<'a> {
             err_count: 0,
             key_exchange: None,
             current_cert_slot: None,
+            unseal_result: None,
+            reset_auth: auth::Authorizer::new(),
         }
     }
 
+    /// Returns the AES key for the currently active session, if any.
+    ///
+    /// This key should be passed to a [`net::encrypted::EncryptedHost`] to
+    /// process encrypted requests.
+    pub fn session_key(&self) -> Option<session::Key> {
+        self.opts.session.aes_key().copied()
+    }
+
     /// Process a single incoming request.
     pub fn process_request<'req>(
         &mut self,
@@ -123,15 +192,38 @@ impl<'a> PaRot<'a> {
                 })
             })
             .handle::<protocol::DeviceInfo, _>(|ctx| {
-                Ok(Resp::<protocol::DeviceInfo> {
-                    info: ctx.server.opts.identity.unique_device_identity(),
-                })
+                use protocol::device_info::InfoIndex;
+                let identity = ctx.server.opts.identity;
+                let count = match ctx.req.index {
+                    InfoIndex::UniqueChipIndex => {
+                        return Ok(Resp::<protocol::DeviceInfo> {
+                            info: identity.unique_device_identity(),
+                        })
+                    }
+                    InfoIndex::VendorChipIdentifier => {
+                        let info = identity
+                            .vendor_chip_identifier()
+                            .ok_or(protocol::error::Error::OutOfRange)?;
+                        return Ok(Resp::<protocol::DeviceInfo> { info });
+                    }
+                    InfoIndex::CertificateFailures => {
+                        identity.certificate_failures()
+                    }
+                    InfoIndex::AttestationFailures => {
+                        identity.attestation_failures()
+                    }
+                };
+
+                let count = count.ok_or(protocol::error::Error::OutOfRange)?;
+                let info = ctx.arena.alloc::<[u8; 4]>()?;
+                *info = count.to_le_bytes();
+                Ok(Resp::<protocol::DeviceInfo> { info })
             })
             .handle::<protocol::GetDigests, _>(|ctx| {
                 ctx.server.handle_digests(ctx.arena, &ctx.req)
             })
             .handle::<protocol::GetCert, _>(|ctx| {
-                ctx.server.handle_cert(&ctx.req)
+                attest::get_cert(&*ctx.server.opts.trust_chain, &ctx.req)
             })
             .handle_buffered::<protocol::Challenge, _>(|ctx| {
                 ctx.server
@@ -140,30 +232,171 @@ impl<'a> PaRot<'a> {
             .handle::<protocol::KeyExchange, _>(|ctx| {
                 ctx.server.handle_key_xchg(ctx.arena, &ctx.req)
             })
-            .handle::<protocol::ResetCounter, _>(|ctx| {
-                use protocol::reset_counter::ResetType;
-                // NOTE: Currently, we only handle "local resets" for port 0,
-                // the "self" port.
-                if ctx.req.reset_type != ResetType::Local
-                    || ctx.req.port_id != 0
-                {
+            .handle::<protocol::ExportCsr, _>(|ctx| {
+                ctx.server.handle_export_csr(ctx.arena, &ctx.req)
+            })
+            .handle::<protocol::ImportCertificate, _>(|ctx| {
+                ctx.server.handle_import_cert(&ctx.req)
+            })
+            .handle::<protocol::GetCertificateState, _>(|ctx| {
+                use protocol::get_certificate_state::CertificateState;
+                let state = match ctx.server.opts.trust_chain.provisioner() {
+                    Some(p) => p.state(),
+                    None => return Err(protocol::error::Error::OutOfRange),
+                };
+                let state = match state {
+                    ProvisionState::Provisioned => CertificateState::Valid,
+                    ProvisionState::NotProvisioned => {
+                        CertificateState::NotProvisioned
+                    }
+                };
+                Ok(Resp::<protocol::GetCertificateState> {
+                    state,
+                    error_details: [0; 3],
+                })
+            })
+            .handle_buffered::<protocol::GetPmr, _>(|ctx| {
+                let opts = &mut ctx.server.opts;
+                attest::get_pmr(
+                    opts.trust_chain,
+                    opts.csrng,
+                    &*opts.pmrs,
+                    ctx.arena,
+                    &ctx.req,
+                    ctx.req_buf,
+                )
+            })
+            .handle::<protocol::UpdatePmr, _>(|ctx| {
+                // PMR #0 may only be extended by the RoT itself.
+                if ctx.req.index == 0 {
                     return Err(protocol::error::Error::OutOfRange);
                 }
-
-                Ok(Resp::<protocol::ResetCounter> {
-                    count: ctx.server.opts.reset.resets_since_power_on() as u16,
-                })
+                let measurement = ctx
+                    .req
+                    .measurement
+                    .try_into()
+                    .map_err(|_| protocol::error::Error::Malformed)?;
+                ctx.server.opts.pmrs.extend(
+                    ctx.req.index as usize,
+                    pmr::HOST_EVENT_TYPE,
+                    measurement,
+                    ctx.server.opts.hasher,
+                )?;
+                Ok(Ack)
             })
-            .handle::<protocol::DeviceUptime, _>(|ctx| {
-                // NOTE: Currently, we only handle port 0, the "self" port.
-                if ctx.req.port_id != 0 {
+            .handle::<protocol::ResetPmr, _>(|ctx| {
+                if ctx.req.index == 0 {
                     return Err(protocol::error::Error::OutOfRange);
                 }
+                ctx.server.opts.pmrs.reset(ctx.req.index as usize)?;
+                Ok(Ack)
+            })
+            .handle::<protocol::GetAttestationData, _>(|ctx| {
+                let entries = ctx
+                    .server
+                    .opts
+                    .pmrs
+                    .measurements(ctx.req.index as usize)
+                    .ok_or(protocol::error::Error::OutOfRange)?;
+                Ok(Resp::<protocol::GetAttestationData> { entries })
+            })
+            .handle::<protocol::Unseal, _>(|ctx| {
+                ctx.server.handle_unseal(&ctx.req)
+            })
+            .handle::<protocol::UnsealResult, _>(|ctx| {
+                ctx.server.handle_unseal_result(ctx.arena)
+            })
+            .handle::<protocol::ResetConfig, _>(|ctx| {
+                ctx.server.handle_reset_config(ctx.arena, &ctx.req)
+            })
+            .handle::<protocol::PrepareRecoveryImage, _>(|ctx| {
+                ctx.server
+                    .opts
+                    .recovery
+                    .get_mut(ctx.req.port_id as usize)
+                    .ok_or(protocol::error::Error::OutOfRange)?
+                    .prepare(ctx.req.size)?;
+                Ok(Ack)
+            })
+            .handle::<protocol::UpdateRecoveryImage, _>(|ctx| {
+                ctx.server
+                    .opts
+                    .recovery
+                    .get_mut(ctx.req.port_id as usize)
+                    .ok_or(protocol::error::Error::OutOfRange)?
+                    .write(ctx.req.data)?;
+                Ok(Ack)
+            })
+            .handle::<protocol::ActivateRecoveryImage, _>(|ctx| {
+                let opts = &mut ctx.server.opts;
+                opts.recovery
+                    .get_mut(ctx.req.port_id as usize)
+                    .ok_or(protocol::error::Error::OutOfRange)?
+                    .activate(opts.hasher, ctx.arena)?;
+                Ok(Ack)
+            })
+            .handle::<protocol::GetRecoveryImageVersion, _>(|ctx| {
+                let store = ctx
+                    .server
+                    .opts
+                    .recovery
+                    .get(ctx.req.port_id as usize)
+                    .ok_or(protocol::error::Error::OutOfRange)?;
+                let version = ctx.arena.alloc::<[u8; 32]>()?;
+                *version = *store
+                    .active_image(ctx.arena)?
+                    .ok_or(recovery::Error::NoImage)?
+                    .version();
+                Ok(Resp::<protocol::GetRecoveryImageVersion> { version })
+            })
+            .handle::<protocol::ResetCounter, _>(|ctx| {
+                use protocol::reset_counter::ResetType;
+                let reset = ctx.server.opts.reset;
+                let kind = match ctx.req.reset_type {
+                    ResetType::Local => hardware::ResetKind::Local,
+                    ResetType::External => hardware::ResetKind::External,
+                };
+                let count = match (kind, ctx.req.port_id) {
+                    (hardware::ResetKind::Local, 0) => {
+                        reset.resets_since_power_on()
+                    }
+                    (kind, port) => reset
+                        .port_resets_since_power_on(port, kind)
+                        .ok_or(protocol::error::Error::OutOfRange)?,
+                };
 
-                Ok(Resp::<protocol::DeviceUptime> {
-                    uptime: ctx.server.opts.reset.uptime(),
+                Ok(Resp::<protocol::ResetCounter> {
+                    count: u16::try_from(count).unwrap_or(u16::MAX),
                 })
             })
+            .handle::<protocol::DeviceUptime, _>(|ctx| {
+                let reset = ctx.server.opts.reset;
+                let uptime = match ctx.req.port_id {
+                    0 => reset.uptime(),
+                    port => reset
+                        .port_uptime(port)
+                        .ok_or(protocol::error::Error::OutOfRange)?,
+                };
+
+                Ok(Resp::<protocol::DeviceUptime> { uptime })
+            })
+            .handle::<protocol::GetHostState, _>(|ctx| {
+                use protocol::get_host_state::HostResetState;
+                let host_reset_state = match ctx
+                    .server
+                    .opts
+                    .reset
+                    .host_state(ctx.req.port_id)
+                    .ok_or(protocol::error::Error::OutOfRange)?
+                {
+                    hardware::HostState::Running => HostResetState::HostRunning,
+                    hardware::HostState::InReset => HostResetState::HostInReset,
+                    hardware::HostState::NotRunning => {
+                        HostResetState::HostNotRunning
+                    }
+                };
+                Ok(Resp::<protocol::GetHostState> { host_reset_state })
+            })
             .handle::<protocol::RequestCounter, _>(|ctx| {
                 Ok(Resp::<protocol::RequestCounter> {
                     ok_count: ctx.server.ok_count,
@@ -212,16 +445,31 @@ impl<'a> PaRot<'a> {
         let mut crypto = req.capabilities.crypto;
 
         self.opts.ciphers.negotiate(&mut crypto);
-        crypto.has_aes = false;
-        crypto.aes_strength = BitFlags::<AesKeyStrength>::empty();
+
+        // Session keys are always 256 bits; see `session::Key`. Only offer
+        // that key size, and only if the requester also supports it.
+        if self.opts.session_encryption {
+            crypto.aes_strength &= AesKeyStrength::Bits256;
+        } else {
+            crypto.aes_strength = BitFlags::<AesKeyStrength>::empty();
+        }
+        // `has_aes` is not part of the wire format, so the key strengths are
+        // the only indication of AES support.
+        crypto.has_aes = !crypto.aes_strength.is_empty();
+
+        let mut security = Security::HashAndKdf | Security::Authentication;
+        if crypto.has_aes {
+            security |= Security::Confidentiality;
+        }
 
         let capabilities = Capabilities {
             networking: self.opts.networking,
-            security: BitFlags::<Security>::empty(),
+            security,
 
-            has_pfm_support: false,
-            has_policy_support: false,
-            has_firmware_protection: false,
+            has_pfm_support: self.opts.pfm.is_some(),
+            // Sealing policies are the only policies this device enforces.
+            has_policy_support: self.opts.sealing_key.is_some(),
+            has_firmware_protection: self.opts.firmware_protection,
 
             crypto,
         };
@@ -232,60 +480,67 @@ impl<'a> PaRot<'a> {
         })
     }
 
-    fn handle_digests<'req>(
+    fn handle_export_csr<'req>(
         &mut self,
         arena: &'req dyn Arena,
-        req: &Req<protocol::GetDigests>,
+        req: &Req<protocol::ExportCsr>,
     ) -> Result<
-        Resp<'req, protocol::GetDigests>,
-        protocol::Error<protocol::GetDigests>,
+        Resp<'req, protocol::ExportCsr>,
+        protocol::Error<protocol::ExportCsr>,
     > {
-        let digests_len = self
+        let provisioner = self
             .opts
             .trust_chain
-            .chain_len(req.slot)
-            .ok_or(protocol::error::ChallengeError::UnknownChain)?
-            .get();
-        let digests = arena
-            .alloc_slice::<[u8; hash::Algo::Sha256.bytes()]>(digests_len)?;
-        for (i, digest) in digests.iter_mut().enumerate() {
-            let cert = self
-                .opts
-                .trust_chain
-                .cert(req.slot, i)
-                .ok_or(protocol::error::ChallengeError::UnknownChain)?;
-            self.opts.hasher.contiguous_hash(
-                hash::Algo::Sha256,
-                cert.raw(),
-                digest,
-            )?;
-        }
+            .provisioner()
+            .ok_or(protocol::error::Error::OutOfRange)?;
+        let len = provisioner
+            .csr_len(req.index)
+            .ok_or(protocol::error::Error::OutOfRange)?;
 
-        self.key_exchange = Some(req.key_exchange);
-        Ok(Resp::<protocol::GetDigests> { digests })
+        let csr = arena.alloc_slice::<u8>(len)?;
+        let len = provisioner.export_csr(req.index, csr)?;
+        Ok(Resp::<protocol::ExportCsr> { csr: &csr[..len] })
     }
 
-    fn handle_cert(
+    fn handle_import_cert(
         &mut self,
-        req: &Req<protocol::GetCert>,
-    ) -> Result<Resp<protocol::GetCert>, protocol::Error<protocol::GetCert>>
-    {
-        let cert = self
+        req: &Req<protocol::ImportCertificate>,
+    ) -> Result<
+        Resp<protocol::ImportCertificate>,
+        protocol::Error<protocol::ImportCertificate>,
+    > {
+        use protocol::import_certificate::CertificateType;
+        let provisioner = self
             .opts
             .trust_chain
-            .cert(req.slot, req.cert_number as usize)
-            .ok_or(protocol::error::ChallengeError::UnknownChain)?;
-
-        let start = cert.raw().len().min(req.offset as usize);
-        let end = cert
-            .raw()
-            .len()
-            .min((req.len as usize).saturating_add(start));
-        Ok(Resp::<protocol::GetCert> {
-            slot: req.slot,
-            cert_number: req.cert_number,
-            data: &cert.raw()[start..end],
-        })
+            .provisioner()
+            .ok_or(protocol::error::Error::OutOfRange)?;
+        let role = match req.cert_type {
+            CertificateType::RootCa => CertRole::Root,
+            CertificateType::IntermediateCa => CertRole::Intermediate,
+            CertificateType::DeviceId => CertRole::DeviceId,
+        };
+
+        provisioner.import(role, req.cert, self.opts.ciphers)?;
+        Ok(Ack)
+    }
+
+    fn handle_digests<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<protocol::GetDigests>,
+    ) -> Result<
+        Resp<'req, protocol::GetDigests>,
+        protocol::Error<protocol::GetDigests>,
+    > {
+        let resp = attest::get_digests(
+            &*self.opts.trust_chain,
+            self.opts.hasher,
+            arena,
+            req,
+        )?;
+        self.key_exchange = Some(req.key_exchange);
+        Ok(resp)
     }
 
     fn handle_challenge<'req>(
@@ -297,36 +552,137 @@ impl<'a> PaRot<'a> {
         Resp<'req, protocol::Challenge>,
         protocol::Error<protocol::Challenge>,
     > {
-        use protocol::challenge::ChallengeResponseTbs;
-        let signer = self
+        let resp = attest::challenge(
+            self.opts.trust_chain,
+            self.opts.csrng,
+            &*self.opts.pmrs,
+            arena,
+            req,
+            req_buf,
+        )?;
+
+        if let Some(KeyExchangeAlgo::Ecdh) = self.key_exchange {
+            self.opts
+                .session
+                .create_session(req.nonce, resp.tbs.nonce)?;
+            self.current_cert_slot = Some(resp.tbs.slot);
+        }
+
+        Ok(resp)
+    }
+
+    fn handle_unseal(
+        &mut self,
+        req: &Req<protocol::Unseal>,
+    ) -> Result<Resp<protocol::Unseal>, protocol::Error<protocol::Unseal>> {
+        use protocol::unseal::*;
+        let key = self
             .opts
-            .trust_chain
-            .signer(req.slot)
-            .ok_or(protocol::error::ChallengeError::UnknownChain)?;
-        let nonce = arena.alloc::<[u8; 32]>()?;
-        self.opts.csrng.fill(nonce)?;
-
-        let tbs = ChallengeResponseTbs {
-            slot: req.slot,
-            slot_mask: 0, // Currently unspecified?
-            protocol_range: (0, 0),
-            nonce,
-            pmr0_components: 0,
-            pmr0: self.opts.pmr0,
+            .sealing_key
+            .as_deref_mut()
+            .ok_or(protocol::error::Error::OutOfRange)?;
+        if req.hmac_type != HmacType::Sha256 || req.seed_type != SeedType::Ecdh
+        {
+            return Err(protocol::error::Error::OutOfRange);
+        }
+        let hash_seed = match req.seed_params {
+            0 => false,
+            1 => true,
+            _ => return Err(protocol::error::Error::OutOfRange),
         };
 
-        let signature = arena.alloc_slice::<u8>(signer.sig_bytes())?;
-        let sig_len = tbs.as_iovec_with(|[a, b, c, d]| {
-            signer.sign(&[req_buf, a, b, c, d], signature)
-        })?;
-        let signature = &signature[..sig_len];
+        let sealed = seal::Sealed {
+            seed: req.seed,
+            hash_seed,
+            ciphertext: req.ciphertext,
+            hmac: req.hmac,
+            policy: req.policy,
+        };
+        let mut secret = session::Key::default();
+        let result = seal::unseal(
+            &sealed,
+            key,
+            &*self.opts.pmrs,
+            self.opts.hasher,
+            &mut secret,
+        );
+        self.unseal_result = Some(result.map(|len| (secret, len)));
+        Ok(Ack)
+    }
 
-        if let Some(KeyExchangeAlgo::Ecdh) = self.key_exchange {
-            self.opts.session.create_session(req.nonce, tbs.nonce)?;
-            self.current_cert_slot = Some(tbs.slot);
+    fn handle_unseal_result<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+    ) -> Result<
+        Resp<'req, protocol::UnsealResult>,
+        protocol::Error<protocol::UnsealResult>,
+    > {
+        use protocol::unseal_result::UnsealStatus;
+        // The secret is only ever handed out once.
+        let (status, key) = match self.unseal_result.take() {
+            None => (UnsealStatus::NotStarted, &[][..]),
+            Some(Ok((secret, len))) => {
+                let key = arena.alloc_slice::<u8>(len)?;
+                key.copy_from_slice(&secret[..len]);
+                (UnsealStatus::Success, &*key)
+            }
+            Some(Err(seal::Error::BadHmac)) => (UnsealStatus::BadHmac, &[][..]),
+            Some(Err(seal::Error::PolicyMismatch)) => {
+                (UnsealStatus::PolicyMismatch, &[][..])
+            }
+            Some(Err(_)) => (UnsealStatus::Failed, &[][..]),
+        };
+        Ok(Resp::<protocol::UnsealResult> { status, key })
+    }
+
+    fn handle_reset_config<'req>(
+        &mut self,
+        arena: &'req dyn Arena,
+        req: &Req<protocol::ResetConfig>,
+    ) -> Result<
+        Resp<'req, protocol::ResetConfig>,
+        protocol::Error<protocol::ResetConfig>,
+    > {
+        let config_reset = self
+            .opts
+            .config_reset
+            .as_mut()
+            .ok_or(protocol::error::Error::OutOfRange)?;
+        let authority = self
+            .opts
+            .reset_authority
+            .as_mut()
+            .ok_or(protocol::error::Error::OutOfRange)?;
+        use protocol::reset_config::ResetType;
+        let kind = match req.reset_type {
+            ResetType::RestoreBypass => {
+                hardware::ConfigResetKind::RestoreBypass
+            }
+            ResetType::RestoreDefaults => {
+                hardware::ConfigResetKind::RestoreDefaults
+            }
+            ResetType::ClearPlatformConfig => {
+                hardware::ConfigResetKind::ClearPlatformConfig
+            }
+            ResetType::ResetIntrusion => {
+                hardware::ConfigResetKind::ResetIntrusion
+            }
+        };
+        if !config_reset.supports(kind) {
+            return Err(protocol::error::Error::OutOfRange);
+        }
+
+        let op = req.reset_type.to_wire_value();
+        if req.authorization.is_empty() {
+            let token = self.reset_auth.issue(op, self.opts.csrng)?;
+            let authorization = arena.alloc_slice::<u8>(token.len())?;
+            authorization.copy_from_slice(token);
+            return Ok(Resp::<protocol::ResetConfig> { authorization });
         }
 
-        Ok(Resp::<protocol::Challenge> { tbs, signature })
+        self.reset_auth.redeem(op, req.authorization, *authority)?;
+        config_reset.reset(kind)?;
+        Ok(Resp::<protocol::ResetConfig> { authorization: &[] })
     }
 
     fn handle_key_xchg<'req>(
@@ -25,6 +25,7 @@
 //! [SP 800-108]: https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-108.pdf
 
 use crate::crypto::hash;
+use crate::crypto::hash::EngineExt as _;
 
 #[cfg(doc)]
 use crate::protocol;
@@ -48,6 +49,27 @@ pub enum Error {
 /// A secret key returned by a [`Session`].
 pub type Key = [u8; 256 / 8];
 
+/// Computes the SP 800-108 KDF described in the [module documentation][self],
+/// using `hasher` to compute the HMAC.
+///
+/// `label` and `context` correspond to `L` and `C` in the formula above.
+pub fn kdf(
+    hasher: &mut (impl hash::Engine + ?Sized),
+    key: &[u8],
+    label: &[u8],
+    context: &[u8],
+) -> Result<Key, hash::Error> {
+    let mut out = Key::default();
+    let mut h = hasher.new_hmac(hash::Algo::Sha256, key)?;
+    h.write(&1u32.to_be_bytes())?;
+    h.write(label)?;
+    h.write(&[0])?;
+    h.write(context)?;
+    h.write(&256u16.to_be_bytes())?;
+    h.finish(&mut out)?;
+    Ok(out)
+}
+
 /// A manager for a Cerberus session, usable by either the host (the client)
 /// or the device (the server).
 ///
@@ -9,7 +9,6 @@
 use core::mem;
 
 use ring::agreement as ecdh;
-use ring::hmac;
 
 use crate::crypto::hash;
 use crate::session;
@@ -122,16 +121,21 @@ impl session::Session for Session {
             &their_key,
             session::Error::Unspecified,
             |material| {
-                let aes_key = sp800_108_hmac256(
+                let mut hasher = crate::crypto::ring::hash::Engine::new();
+                let aes_key = session::kdf(
+                    &mut hasher,
                     material,
                     &conn.req_nonce,
                     &conn.resp_nonce,
-                );
-                let hmac_key = sp800_108_hmac256(
+                )
+                .map_err(|_| session::Error::Unspecified)?;
+                let hmac_key = session::kdf(
+                    &mut hasher,
                     material,
                     &conn.resp_nonce,
                     &conn.req_nonce,
-                );
+                )
+                .map_err(|_| session::Error::Unspecified)?;
                 Ok(Keys::Session {
                     aes_key,
                     hmac_key,
@@ -163,26 +167,11 @@ impl session::Session for Session {
     }
 }
 
-/// Computes an SP 800-108 KDF with the Cerberus parametrization.
-fn sp800_108_hmac256(key: &[u8], label: &[u8], context: &[u8]) -> session::Key {
-    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
-    let mut ctx = hmac::Context::with_key(&key);
-    ctx.update(&1u32.to_be_bytes());
-    ctx.update(label);
-    ctx.update(&[0]);
-    ctx.update(context);
-    ctx.update(&256u16.to_be_bytes());
-    let tag = ctx.sign();
-
-    let mut key = session::Key::default();
-    key.copy_from_slice(tag.as_ref());
-    key
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::session::Session as _;
+    use ring::hmac;
 
     #[test]
     #[cfg_attr(miri, ignore)]
@@ -210,4 +199,21 @@ mod test {
         assert_eq!(host.aes_key(), device.aes_key());
         assert_eq!(host.hmac_key(), device.hmac_key());
     }
+
+    #[test]
+    fn generic_kdf() {
+        let mut hasher = crate::crypto::ring::hash::Engine::new();
+        let key = session::kdf(&mut hasher, b"material", b"label", b"context")
+            .unwrap();
+
+        // Compute the expected key with `ring` directly.
+        let hmac_key = hmac::Key::new(hmac::HMAC_SHA256, b"material");
+        let mut ctx = hmac::Context::with_key(&hmac_key);
+        ctx.update(&1u32.to_be_bytes());
+        ctx.update(b"label");
+        ctx.update(&[0]);
+        ctx.update(b"context");
+        ctx.update(&256u16.to_be_bytes());
+        assert_eq!(&key[..], ctx.sign().as_ref());
+    }
 }
@@ -95,7 +95,7 @@ for key in $(find "$DATA_DIR/keys" -name '*.rsa.pk8' -type f | sort); do
   push_const \
     "$KEYS_RS" "$key" \
     "$(basename "$base")_RSA_KEYPAIR" '&[u8]' \
-    "Test-only RSA keypair \`$(basename "key")\`."
+    "Test-only RSA keypair \`$(basename "$key")\`."
   push_const \
     "$KEYS_RS" "$pub" \
     "$(basename "$base")_RSA_PUBLIC" '&[u8]' \
@@ -148,7 +148,7 @@ for key in $(find "$DATA_DIR/keys" -name '*.ecdsa-*.pk8' -type f | sort); do
   push_const \
     "$KEYS_RS" "$pub" \
     "$(basename "$base")_ECDSA_${curve}_PUBLIC" '&[u8]' \
-    "Test-only ECDAS public key generated from \`$(basename "$key")\`."
+    "Test-only ECDSA public key generated from \`$(basename "$key")\`."
   push_const \
     "$KEYS_RS" "$x" \
     "$(basename "$base")_ECDSA_${curve}_X" "&[u8; $(($coord_len_hex / 2))]" \
@@ -6,7 +6,7 @@
 
 /* GENERATED START */
 
-/// Test-only RSA keypair `key`.
+/// Test-only RSA keypair `key1.rsa.pk8`.
 #[rustfmt::skip]
 pub const KEY1_RSA_KEYPAIR: &[u8] = include_bytes!("keys/key1.rsa.pk8");
 /// Test-only RSA public key generated from `key1.rsa.pk8`.
@@ -19,7 +19,7 @@ pub const KEY1_RSA_MOD: &[u8] = include_bytes!("keys/generated/key1.rsa.pub.mod"
 #[rustfmt::skip]
 pub const KEY1_RSA_EXP: &[u8] = include_bytes!("keys/generated/key1.rsa.pub.exp");
 
-/// Test-only RSA keypair `key`.
+/// Test-only RSA keypair `key2.rsa.pk8`.
 #[rustfmt::skip]
 pub const KEY2_RSA_KEYPAIR: &[u8] = include_bytes!("keys/key2.rsa.pk8");
 /// Test-only RSA public key generated from `key2.rsa.pk8`.
@@ -32,7 +32,7 @@ pub const KEY2_RSA_MOD: &[u8] = include_bytes!("keys/generated/key2.rsa.pub.mod"
 #[rustfmt::skip]
 pub const KEY2_RSA_EXP: &[u8] = include_bytes!("keys/generated/key2.rsa.pub.exp");
 
-/// Test-only RSA keypair `key`.
+/// Test-only RSA keypair `key3.rsa.pk8`.
 #[rustfmt::skip]
 pub const KEY3_RSA_KEYPAIR: &[u8] = include_bytes!("keys/key3.rsa.pk8");
 /// Test-only RSA public key generated from `key3.rsa.pk8`.
@@ -48,7 +48,7 @@ pub const KEY3_RSA_EXP: &[u8] = include_bytes!("keys/generated/key3.rsa.pub.exp"
 /// Test-only ECDSA keypair `key1.ecdsa-p256.pk8`.
 #[rustfmt::skip]
 pub const KEY1_ECDSA_P256_KEYPAIR: &[u8] = include_bytes!("keys/key1.ecdsa-p256.pk8");
-/// Test-only ECDAS public key generated from `key1.ecdsa-p256.pk8`.
+/// Test-only ECDSA public key generated from `key1.ecdsa-p256.pk8`.
 #[rustfmt::skip]
 pub const KEY1_ECDSA_P256_PUBLIC: &[u8] = include_bytes!("keys/key1.ecdsa-p256.pub.pk8");
 /// X coordinate of `key1.ecdsa-p256.pk8`.
@@ -57,3 +57,16 @@ pub const KEY1_ECDSA_P256_X: &[u8; 32] = include_bytes!("keys/generated/key1.ecd
 /// Y coordinate of `key1.ecdsa-p256.pk8`.
 #[rustfmt::skip]
 pub const KEY1_ECDSA_P256_Y: &[u8; 32] = include_bytes!("keys/generated/key1.ecdsa-p256.pub.y");
+
+/// Test-only ECDSA keypair `key1.ecdsa-p384.pk8`.
+#[rustfmt::skip]
+pub const KEY1_ECDSA_P384_KEYPAIR: &[u8] = include_bytes!("keys/key1.ecdsa-p384.pk8");
+/// Test-only ECDSA public key generated from `key1.ecdsa-p384.pk8`.
+#[rustfmt::skip]
+pub const KEY1_ECDSA_P384_PUBLIC: &[u8] = include_bytes!("keys/key1.ecdsa-p384.pub.pk8");
+/// X coordinate of `key1.ecdsa-p384.pk8`.
+#[rustfmt::skip]
+pub const KEY1_ECDSA_P384_X: &[u8; 48] = include_bytes!("keys/generated/key1.ecdsa-p384.pub.x");
+/// Y coordinate of `key1.ecdsa-p384.pk8`.
+#[rustfmt::skip]
+pub const KEY1_ECDSA_P384_Y: &[u8; 48] = include_bytes!("keys/generated/key1.ecdsa-p384.pub.y");
@@ -0,0 +1 @@
+e.ZDd)ـV(6#bɳ0!{6=bi'8&Y
\ No newline at end of file
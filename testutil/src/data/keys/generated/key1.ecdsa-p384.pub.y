@@ -0,0 +1 @@
+kO8~nD~M9-aWYbD{zQSh8Κ[Vt
\ No newline at end of file
@@ -87,6 +87,10 @@ pub const CHAIN2: &[u8] = include_bytes!("x509/generated/chain2.tbs.bin");
 #[rustfmt::skip]
 pub const CHAIN3: &[u8] = include_bytes!("x509/generated/chain3.tbs.bin");
 
+/// X509 certificate generated from `chain3_ecdsa_p256.tbs`.
+#[rustfmt::skip]
+pub const CHAIN3_ECDSA_P256: &[u8] = include_bytes!("x509/generated/chain3_ecdsa_p256.tbs.bin");
+
 /// X509 certificate generated from `ok_no_ca_without_bc.tbs`.
 #[rustfmt::skip]
 pub const OK_NO_CA_WITHOUT_BC: &[u8] = include_bytes!("x509/generated/ok_no_ca_without_bc.tbs.bin");
@@ -11,10 +11,24 @@ use std::path::PathBuf;
 
 use manticore::crypto::hash;
 use manticore::crypto::ring;
+use manticore::crypto::sig;
+use manticore::crypto::sig::Sign as _;
+use manticore::hardware::flash::FileFlash;
+use manticore::hardware::flash::Ram;
 use manticore::io::ReadInt as _;
 use manticore::manifest::owned;
+use manticore::manifest::pfm::ParsedPfm;
+use manticore::manifest::pfm::Pfm;
+use manticore::manifest::Container;
+use manticore::manifest::KeyType;
 use manticore::manifest::ManifestType;
+use manticore::manifest::Parse as _;
+use manticore::manifest::SigType;
+use manticore::manifest::ValidationTime;
+use manticore::mem::BumpArena;
 use manticore::protocol::wire::WireEnum;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 /// A CLI interface for Manticore's parser infrastructure.
 #[derive(structopt::StructOpt)]
@@ -22,8 +36,8 @@ use manticore::protocol::wire::WireEnum;
 pub enum Manifest {
     /// Create a new signed manifest.
     SignManifest {
-        /// PKCS#8-encoded RSA signing key to sign with.
-        // TODO: Support other key types.
+        /// PKCS#8-encoded RSA or ECDSA (P-256 or P-384) signing key to sign
+        /// with.
         #[structopt(long, parse(from_os_str))]
         key: PathBuf,
 
@@ -42,8 +56,8 @@ pub enum Manifest {
 
     /// Inspect an existing manifest.
     ShowManifest {
-        /// PKCS#8-encoded RSA public key to optionally verify the signature.
-        // TODO: Support other key types.
+        /// PKCS#8-encoded RSA or ECDSA (P-256 or P-384) keypair to optionally
+        /// verify the signature with.
         #[structopt(long, parse(from_os_str))]
         key: Option<PathBuf>,
 
@@ -59,6 +73,23 @@ pub enum Manifest {
         #[structopt(short = "o", long, parse(from_os_str))]
         output: Option<PathBuf>,
     },
+
+    /// Validate a host firmware image against a PFM.
+    ValidateFirmware {
+        /// PKCS#8-encoded RSA or ECDSA (P-256 or P-384) keypair to verify the
+        /// PFM's signature with.
+        #[structopt(long, parse(from_os_str))]
+        key: PathBuf,
+
+        /// The signed PFM describing the allowed firmware.
+        #[structopt(long, parse(from_os_str))]
+        pfm: PathBuf,
+
+        /// The host flash image to validate; it is read in place, rather than
+        /// loaded into memory.
+        #[structopt(long, parse(from_os_str))]
+        image: PathBuf,
+    },
 }
 
 impl Manifest {
@@ -74,30 +105,28 @@ impl Manifest {
                     crate::util::stdio(input.as_deref(), output.as_deref());
 
                 let key = check!(fs::read(key), "failed to open file");
-                let mut signer = check!(
-                    ring::rsa::Sign256::from_pkcs8(&key),
-                    "failed to parse key"
-                );
+                let mut key =
+                    check!(Key::from_pkcs8(&key), "failed to parse key");
                 let mut hasher = ring::hash::Engine::new();
 
                 let mut read_buf = Vec::new();
                 check!(r.read_to_end(&mut read_buf), "failed to read file");
                 let manifest = match manifest {
-                    ManifestType::Pfm => {
-                        let pfm: owned::Pfm = check!(
-                            serde_json::from_slice(&read_buf),
-                            "failed to parse PFM"
-                        );
-                        check!(
-                            pfm.sign(
-                                0x00,
-                                hash::Algo::Sha256,
-                                &mut hasher,
-                                &mut signer
-                            ),
-                            "failed to sign PFM"
-                        )
-                    }
+                    ManifestType::Pfm => sign::<owned::pfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        &mut key,
+                    ),
+                    ManifestType::Cfm => sign::<owned::cfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        &mut key,
+                    ),
+                    ManifestType::Pcd => sign::<owned::pcd::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        &mut key,
+                    ),
                 };
 
                 check!(w.write_all(&manifest), "failed to write manifest");
@@ -114,12 +143,11 @@ impl Manifest {
 
                 let mut engine = key.map(|key| {
                     let key = check!(fs::read(key), "failed to open file");
-                    let signer = check!(
-                        ring::rsa::Sign256::from_pkcs8(&key),
-                        "failed to parse key",
-                    );
-                    signer.verifier()
+                    let key =
+                        check!(Key::from_pkcs8(&key), "failed to parse key");
+                    key.verifier()
                 });
+                let verify = engine.as_mut().map(|v| &mut **v as _);
                 let mut hasher = ring::hash::Engine::new();
 
                 let mut read_buf = Vec::new();
@@ -133,38 +161,27 @@ impl Manifest {
                 let manifest_type = check!(r.read_le::<u16>(), "input len < 4");
 
                 match ManifestType::from_wire_value(manifest_type) {
-                    Some(ManifestType::Pfm) => {
-                        let parse = check!(
-                            owned::Pfm::parse(
-                                &read_buf,
-                                &mut hasher,
-                                engine.as_mut()
-                            ),
-                            "failed to parse PFM"
-                        );
-
-                        if parse.bad_signature {
-                            eprintln!("warning: signature verification failed");
-                        }
-                        if parse.bad_toc_hash {
-                            eprintln!("warning: TOC hash verification failed");
-                        }
-                        for idx in parse.bad_hashes {
-                            eprintln!(
-                                "warning: bad hash for toc entry {}",
-                                idx
-                            );
-                        }
-
-                        let r = match pretty {
-                            true => serde_json::to_writer_pretty(
-                                w,
-                                &parse.container,
-                            ),
-                            false => serde_json::to_writer(w, &parse.container),
-                        };
-                        check!(r, "failed to serialize PFM");
-                    }
+                    Some(ManifestType::Pfm) => show::<owned::pfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        verify,
+                        pretty,
+                        w,
+                    ),
+                    Some(ManifestType::Cfm) => show::<owned::cfm::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        verify,
+                        pretty,
+                        w,
+                    ),
+                    Some(ManifestType::Pcd) => show::<owned::pcd::Element>(
+                        &read_buf,
+                        &mut hasher,
+                        verify,
+                        pretty,
+                        w,
+                    ),
                     None => {
                         check!(
                             Err(format!(
@@ -176,6 +193,166 @@ impl Manifest {
                     }
                 }
             }
+
+            Self::ValidateFirmware { key, pfm, image } => {
+                let key = check!(fs::read(key), "failed to open file");
+                let key = check!(Key::from_pkcs8(&key), "failed to parse key");
+                let mut verifier = key.verifier();
+                let mut hasher = ring::hash::Engine::new();
+
+                let pfm = Ram(check!(fs::read(pfm), "failed to open file"));
+                let image = check!(
+                    FileFlash::open_read_only(image),
+                    "failed to open file"
+                );
+
+                let arena = BumpArena::new(vec![0; 4096]);
+                let container = check!(
+                    Container::parse_and_verify(
+                        &pfm,
+                        &mut hasher,
+                        &mut *verifier,
+                        &arena,
+                        &arena,
+                    ),
+                    "failed to parse {}",
+                    ManifestType::Pfm
+                );
+                let pfm = ParsedPfm::new(container);
+                check!(
+                    Pfm::validate(
+                        &pfm,
+                        ValidationTime::Activation,
+                        &image,
+                        &mut hasher,
+                        &arena,
+                    ),
+                    "firmware does not match {}",
+                    ManifestType::Pfm
+                );
+            }
         }
     }
 }
+
+/// A manifest signing key.
+enum Key {
+    Rsa(ring::rsa::Sign256),
+    P256(ring::ecdsa::SignP256),
+    P384(ring::ecdsa::SignP384),
+}
+
+impl Key {
+    /// Parses a PKCS#8-encoded RSA or ECDSA keypair.
+    fn from_pkcs8(pkcs8: &[u8]) -> Result<Self, String> {
+        if let Ok(k) = ring::rsa::Sign256::from_pkcs8(pkcs8) {
+            return Ok(Self::Rsa(k));
+        }
+        if let Ok(k) =
+            ring::ecdsa::SignP256::with_der_encoding_from_pkcs8(pkcs8)
+        {
+            return Ok(Self::P256(k));
+        }
+        if let Ok(k) =
+            ring::ecdsa::SignP384::with_der_encoding_from_pkcs8(pkcs8)
+        {
+            return Ok(Self::P384(k));
+        }
+        Err("expected an RSA, P-256, or P-384 keypair".into())
+    }
+
+    /// Returns the manifest signature type for signatures made with this key.
+    fn sig_type(&self) -> Result<SigType, String> {
+        let (key_type, hash_type) = match self {
+            Self::Rsa(k) => match k.sig_bytes() {
+                256 => (KeyType::Rsa2048, hash::Algo::Sha256),
+                384 => (KeyType::Rsa3072, hash::Algo::Sha256),
+                512 => (KeyType::Rsa4096, hash::Algo::Sha256),
+                n => {
+                    return Err(format!(
+                        "unsupported RSA modulus length: {} bytes",
+                        n
+                    ))
+                }
+            },
+            Self::P256(_) => (KeyType::EcdsaP256, hash::Algo::Sha256),
+            Self::P384(_) => (KeyType::EcdsaP384, hash::Algo::Sha384),
+        };
+        Ok(SigType {
+            key_type,
+            hash_type,
+        })
+    }
+
+    /// Returns a signer for this key.
+    fn signer(&mut self) -> &mut dyn sig::Sign {
+        match self {
+            Self::Rsa(k) => k,
+            Self::P256(k) => k,
+            Self::P384(k) => k,
+        }
+    }
+
+    /// Returns a verifier for this key's public half.
+    fn verifier(&self) -> Box<dyn sig::Verify> {
+        match self {
+            Self::Rsa(k) => Box::new(k.verifier()),
+            Self::P256(k) => Box::new(k.verifier()),
+            Self::P384(k) => Box::new(k.verifier()),
+        }
+    }
+}
+
+/// Parses a JSON manifest with elements of type `E` out of `json` and signs
+/// it.
+fn sign<E>(
+    json: &[u8],
+    hasher: &mut ring::hash::Engine,
+    key: &mut Key,
+) -> Vec<u8>
+where
+    E: owned::Element + DeserializeOwned,
+{
+    let manifest: owned::Container<E> =
+        check!(serde_json::from_slice(json), "failed to parse {}", E::TYPE);
+    let sig_type = check!(key.sig_type(), "failed to sign {}", E::TYPE);
+    check!(
+        manifest.sign(0x00, sig_type, hasher, key.signer()),
+        "failed to sign {}",
+        E::TYPE
+    )
+}
+
+/// Parses a manifest with elements of type `E` out of `bytes` and writes it
+/// to `w` as JSON, warning about any failed integrity checks.
+fn show<E>(
+    bytes: &[u8],
+    hasher: &mut ring::hash::Engine,
+    verify: Option<&mut dyn sig::Verify>,
+    pretty: bool,
+    w: impl Write,
+) where
+    E: owned::FromUnowned + Serialize,
+{
+    let parse = check!(
+        owned::Container::<E>::parse(bytes, hasher, verify),
+        "failed to parse {}",
+        E::TYPE
+    );
+
+    if parse.bad_signature {
+        eprintln!("warning: signature verification failed");
+    }
+    if parse.bad_toc_hash {
+        eprintln!("warning: TOC hash verification failed");
+    }
+    for idx in parse.bad_hashes {
+        eprintln!("warning: bad hash for toc entry {}", idx);
+    }
+
+    let r = match pretty {
+        true => serde_json::to_writer_pretty(w, &parse.container),
+        false => serde_json::to_writer(w, &parse.container),
+    };
+    check!(r, "failed to serialize {}", E::TYPE);
+}